@@ -0,0 +1,24 @@
+use std::env;
+use std::process::Command;
+
+// Record the commit being built as GIT_SHA, for the `/api/version` endpoint.
+// Heroku builds from a tarball rather than a checkout, but says which commit
+// it's building in SOURCE_VERSION.
+fn main() {
+    println!("cargo:rerun-if-env-changed=SOURCE_VERSION");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+
+    let sha = env::var("SOURCE_VERSION").ok().or_else(|| {
+        let output = Command::new("git")
+            .args(["rev-parse", "HEAD"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())?;
+        String::from_utf8(output.stdout).ok()
+    });
+
+    if let Some(sha) = sha {
+        println!("cargo:rustc-env=GIT_SHA={}", sha.trim());
+    }
+}
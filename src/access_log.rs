@@ -0,0 +1,403 @@
+//! Access logs, with a line for each request served.
+//!
+//! Wrap an App in an `AccessLog` to log every request it serves, in the
+//! `combined` format most log tools read, or as JSON, to stdout or to a file:
+//!
+//! ```no_run
+//! # use things_to_check::{access_log, view};
+//! # #[actix_web::main]
+//! # async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+//! use actix_web::{App, HttpServer};
+//! use access_log::{AccessLog, Format};
+//!
+//! let service = view::make_service()?;
+//! let log = AccessLog::to_file("access.log", Format::Json)?;
+//! let app_factory = move ||
+//!     App::new()
+//!         .wrap(log.clone())
+//!         .configure(|cfg| service(cfg));
+//!
+//! HttpServer::new(app_factory);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Lines are written as responses start, so the size of a response whose body
+//! is streamed, such as `/stream`'s, isn't known, and is logged as `-` (or
+//! `null`). Times are in UTC.
+//!
+//! Requests that were served a suggestion — pages under `/` and `/check/…`,
+//! `/api/v1/suggestion`, and Slack commands — also log the list it was chosen
+//! from, and its `item` (its `id`, if it has one, or else its number). In the
+//! `combined` format, these follow the usual fields, in quotes, as in:
+//!
+//! ```text
+//! 127.0.0.1 - - [16/Oct/2026:09:30:00 +0000] "GET / HTTP/1.1" 200 5120 "-" "curl/8.5.0" "general" "dns"
+//! ```
+//!
+//! JSON lines carry them as `list` and `item`, with the request's `time`,
+//! `remote` address, `method`, `path`, `status`, `bytes`, `duration_ms`,
+//! `referer`, `user_agent`, and, on Apps wrapped in `RequestIds`, its
+//! `request_id`. Fields a request doesn't have are `null`.
+
+use actix_web::body::{BodySize, MessageBody};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header;
+use actix_web::{HttpMessage, HttpRequest};
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use serde_json::json;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+use crate::request_id;
+use crate::schedule::civil;
+
+/// The layout of access log lines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// The combined log format, as written by Apache and nginx, followed by
+    /// the suggestion served, if any.
+    Combined,
+    /// One JSON object per line.
+    Json,
+}
+
+/// Indicates that a log format wasn't `combined` or `json`.
+#[derive(Error, Debug)]
+#[error("Invalid access log format {0:?}: expected combined or json")]
+pub struct InvalidFormat(String);
+
+impl FromStr for Format {
+    type Err = InvalidFormat;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "combined" => Ok(Format::Combined),
+            "json" => Ok(Format::Json),
+            _ => Err(InvalidFormat(format.to_owned())),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Target {
+    Stdout,
+    File(Arc<Mutex<File>>),
+}
+
+/// Logs each request an App serves. Wrap an App in it to log every request
+/// the App serves.
+///
+/// Access logs are cheap to clone; every clone writes to the same file.
+#[derive(Clone, Debug)]
+pub struct AccessLog {
+    format: Format,
+    target: Target,
+}
+
+impl AccessLog {
+    /// Log in `format` to stdout.
+    pub fn to_stdout(format: Format) -> Self {
+        AccessLog {
+            format,
+            target: Target::Stdout,
+        }
+    }
+
+    /// Log in `format` to the file at `path`, adding to the end of it if it
+    /// already exists.
+    pub fn to_file(path: impl AsRef<Path>, format: Format) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(AccessLog {
+            format,
+            target: Target::File(Arc::new(Mutex::new(file))),
+        })
+    }
+
+    fn write(&self, line: &str) {
+        let written = match &self.target {
+            Target::Stdout => writeln!(io::stdout().lock(), "{}", line),
+            Target::File(file) => {
+                let mut file = file.lock().unwrap_or_else(|err| err.into_inner());
+                writeln!(file, "{}", line)
+            }
+        };
+        if let Err(err) = written {
+            eprintln!("Unable to write to the access log: {}", err);
+        }
+    }
+}
+
+impl Default for AccessLog {
+    /// Log in the combined format to stdout.
+    fn default() -> Self {
+        AccessLog::to_stdout(Format::Combined)
+    }
+}
+
+/// The suggestion served in response to a request.
+struct Chosen {
+    list: String,
+    item: String,
+}
+
+/// Note that `req` was served `item` from `list`, for its access log line.
+pub(crate) fn chose(req: &HttpRequest, list: &str, item: &str) {
+    req.extensions_mut().insert(Chosen {
+        list: list.to_owned(),
+        item: item.to_owned(),
+    });
+}
+
+impl<S, B> Transform<S, ServiceRequest> for AccessLog
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = AccessLogService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AccessLogService {
+            service,
+            log: self.clone(),
+        }))
+    }
+}
+
+/// The service `AccessLog` wraps an App's service in.
+pub struct AccessLogService<S> {
+    service: S,
+    log: AccessLog,
+}
+
+impl<S, B> Service<ServiceRequest> for AccessLogService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let received = SystemTime::now();
+        let started = Instant::now();
+        let log = self.log.clone();
+        let response = self.service.call(req);
+
+        Box::pin(async move {
+            let response = response.await?;
+            let bytes = match response.response().body().size() {
+                BodySize::None => Some(0),
+                BodySize::Sized(size) => Some(size),
+                BodySize::Stream => None,
+            };
+            let line = Line {
+                req: response.request(),
+                received,
+                duration_ms: started.elapsed().as_secs_f64() * 1000.0,
+                status: response.status().as_u16(),
+                bytes,
+            };
+            log.write(&match log.format {
+                Format::Combined => line.combined(),
+                Format::Json => line.json(),
+            });
+
+            Ok(response)
+        })
+    }
+}
+
+// What's logged about one request.
+struct Line<'a> {
+    req: &'a HttpRequest,
+    received: SystemTime,
+    duration_ms: f64,
+    status: u16,
+    bytes: Option<u64>,
+}
+
+impl Line<'_> {
+    fn header(&self, name: header::HeaderName) -> Option<&str> {
+        self.req
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    }
+
+    fn remote(&self) -> Option<String> {
+        self.req.peer_addr().map(|addr| addr.ip().to_string())
+    }
+
+    fn combined(&self) -> String {
+        let (year, month, day, time) = utc(self.received);
+        let request = format!(
+            "{} {} {:?}",
+            self.req.method(),
+            self.req.uri(),
+            self.req.version()
+        );
+        let extensions = self.req.extensions();
+        let chosen = extensions.get::<Chosen>();
+
+        format!(
+            "{} - - [{:02}/{}/{}:{} +0000] {} {} {} {} {} {} {}",
+            self.remote().as_deref().unwrap_or("-"),
+            day,
+            MONTHS[month as usize - 1],
+            year,
+            time,
+            quoted(Some(&request)),
+            self.status,
+            self.bytes.map_or("-".to_owned(), |bytes| bytes.to_string()),
+            quoted(self.header(header::REFERER)),
+            quoted(self.header(header::USER_AGENT)),
+            quoted(chosen.map(|chosen| chosen.list.as_str())),
+            quoted(chosen.map(|chosen| chosen.item.as_str())),
+        )
+    }
+
+    fn json(&self) -> String {
+        let (year, month, day, time) = utc(self.received);
+        let extensions = self.req.extensions();
+        let chosen = extensions.get::<Chosen>();
+
+        json!({
+            "time": format!("{}-{:02}-{:02}T{}Z", year, month, day, time),
+            "remote": self.remote(),
+            "method": self.req.method().as_str(),
+            "path": self.req.uri().to_string(),
+            "status": self.status,
+            "bytes": self.bytes,
+            "duration_ms": self.duration_ms,
+            "referer": self.header(header::REFERER),
+            "user_agent": self.header(header::USER_AGENT),
+            "request_id": request_id::of(self.req).map(|id| id.to_string()),
+            "list": chosen.map(|chosen| &chosen.list),
+            "item": chosen.map(|chosen| &chosen.item),
+        })
+        .to_string()
+    }
+}
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+// The year, month, and day of `time`, in UTC, and the time of day, as
+// `HH:MM:SS`.
+fn utc(time: SystemTime) -> (u64, u64, u64, String) {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or(0);
+    let (year, month, day) = civil(seconds / 86_400);
+    let of_day = seconds % 86_400;
+    let time = format!(
+        "{:02}:{:02}:{:02}",
+        of_day / 3600,
+        of_day / 60 % 60,
+        of_day % 60
+    );
+
+    (year, month, day, time)
+}
+
+// `value` in double quotes, escaped as nginx does, or `"-"` if there is none.
+fn quoted(value: Option<&str>) -> String {
+    let value = match value {
+        Some(value) => value,
+        None => return "\"-\"".to_owned(),
+    };
+
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for byte in value.bytes() {
+        match byte {
+            b'"' | b'\\' => {
+                quoted.push('\\');
+                quoted.push(char::from(byte));
+            }
+            b' '..=b'~' => quoted.push(char::from(byte)),
+            _ => quoted.push_str(&format!("\\x{:02X}", byte)),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, App};
+    use std::env;
+    use std::fs;
+    use std::process;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::view;
+
+    #[actix_web::test]
+    async fn requests_are_logged() {
+        let path = env::temp_dir().join(format!("things-to-check-access-{}.log", process::id()));
+        let service = view::make_service().unwrap();
+        let log = AccessLog::to_file(&path, Format::Json).unwrap();
+        let app = test::init_service(App::new().wrap(log).configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get()
+            .uri("/?item=0")
+            .insert_header((header::USER_AGENT, "curl/8.5.0"))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 302);
+        let req = test::TestRequest::get()
+            .uri("/check/have-you-checked-permissions")
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+
+        let logged = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let lines: Vec<serde_json::Value> = logged
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["status"], 302);
+        assert_eq!(lines[0]["user_agent"], "curl/8.5.0");
+        assert_eq!(lines[0]["item"], serde_json::Value::Null);
+        assert_eq!(lines[1]["path"], "/check/have-you-checked-permissions");
+        assert_eq!(lines[1]["list"], "general");
+        assert!(lines[1]["item"].is_string());
+    }
+
+    #[actix_web::test]
+    async fn combined_fields_are_quoted() {
+        assert_eq!(quoted(None), r#""-""#);
+        assert_eq!(quoted(Some("say \"hi\"\n")), r#""say \"hi\"\x0A""#);
+    }
+
+    #[actix_web::test]
+    async fn times_are_utc() {
+        // 16 October 2026, at 09:30:05.
+        let time = UNIX_EPOCH + Duration::from_secs(20_742 * 86_400 + 34_205);
+        assert_eq!(utc(time), (2026, 10, 16, "09:30:05".to_owned()));
+    }
+
+    #[actix_web::test]
+    async fn formats_are_parsed() {
+        assert_eq!("combined".parse::<Format>().unwrap(), Format::Combined);
+        assert_eq!("json".parse::<Format>().unwrap(), Format::Json);
+        assert!("common".parse::<Format>().is_err());
+    }
+}
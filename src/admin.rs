@@ -0,0 +1,213 @@
+//! A dashboard for operators, showing the state of the running service at a
+//! glance.
+//!
+//! The dashboard is part of moderation: it's only served when the App is given
+//! a `Moderators` value as app data, and only to those presenting the
+//! moderators' token. See the `moderation` module.
+//!
+//! # Endpoints
+//!
+//! * `/admin` (`GET`): an HTML page showing where the suggestions come from,
+//!   whether the last attempt to refresh them failed and why, and when they
+//!   were last loaded; the number of suggestions in each list, and of
+//!   submissions awaiting moderation; the most-served suggestions, if the App
+//!   counts them (see the `counts` module); and the last few server errors,
+//!   with their request IDs, if the App is wrapped in `RequestIds` (see the
+//!   `request_id` module).
+//!
+//!   Takes the moderators' token as for the `/admin/pending` endpoints. As
+//!   browsers can't send a bearer token, requests without the token are
+//!   answered with `401 Unauthorized` and a challenge for HTTP Basic
+//!   credentials, so that browsers prompt for it; give it as the password, with
+//!   any user name. Returns `404 Not Found` if moderation is off.
+//!
+//! Like the other pages, the dashboard's fixed text follows the reader's
+//! language, and its template can be overridden; see the `locale` and
+//! `templates` modules.
+
+use actix_web::http::header::{self, HttpDate};
+use actix_web::{error, get, web, HttpRequest, HttpResponse};
+use askama::Template;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::VecDeque;
+use std::sync::{Mutex, MutexGuard};
+use std::time::SystemTime;
+
+use crate::counts::Counts;
+use crate::locale::Locale;
+use crate::moderation::{self, Queue};
+use crate::request_id::RequestId;
+use crate::templates;
+use crate::theme::Theme;
+use crate::things::Store;
+use crate::view::{Source, Urls};
+
+/// The most server errors the dashboard shows.
+const RECENT_ERRORS: usize = 20;
+
+/// The most-served suggestions the dashboard shows.
+const MOST_SERVED: usize = 10;
+
+/// The last few server errors, newest first, for the dashboard.
+#[derive(Default)]
+pub(crate) struct Errors(Mutex<VecDeque<Failure>>);
+
+#[derive(Serialize)]
+struct Failure {
+    at: String,
+    request_id: String,
+    request: String,
+    message: String,
+}
+
+impl Errors {
+    /// Remember that `req`, whose ID is `id`, failed with `err`, forgetting
+    /// the oldest error remembered if there are too many.
+    pub(crate) fn record(&self, id: &RequestId, req: &HttpRequest, err: &error::Error) {
+        let mut errors = self.lock();
+        errors.push_front(Failure {
+            at: HttpDate::from(SystemTime::now()).to_string(),
+            request_id: id.to_string(),
+            request: format!("{} {}", req.method(), req.path()),
+            message: err.to_string(),
+        });
+        errors.truncate(RECENT_ERRORS);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, VecDeque<Failure>> {
+        self.0.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+#[derive(Serialize)]
+struct List {
+    name: String,
+    live: usize,
+    retired: usize,
+}
+
+#[derive(Serialize)]
+struct Served {
+    list: String,
+    item: String,
+    text: Option<String>,
+    web: u64,
+    slack: u64,
+}
+
+#[derive(Template)]
+#[template(path = "admin.html")]
+struct Dashboard<'a> {
+    req: HttpRequest,
+    theme: web::Data<Theme>,
+    source: &'a str,
+    failure: Option<String>,
+    loaded: String,
+    lists: Vec<List>,
+    pending: usize,
+    most_served: Option<Vec<Served>>,
+    errors: &'a VecDeque<Failure>,
+    locale: Locale,
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin",
+    tag = "html",
+    security(("moderator" = [])),
+    responses(
+        (status = 200, description = "The dashboard", content_type = "text/html"),
+        (status = 401, description = "Missing or incorrect moderator token"),
+        (status = 404, description = "Moderation is off"),
+    ),
+)]
+#[get("/admin")]
+pub(crate) async fn dashboard(
+    req: HttpRequest,
+    store: web::Data<Store>,
+    source: web::Data<Source>,
+    queue: web::Data<Queue>,
+    errors: web::Data<Errors>,
+    theme: web::Data<Theme>,
+) -> error::Result<HttpResponse> {
+    moderation::moderators(&req)?
+        .authenticate(&req)
+        .map_err(|err| {
+            let challenge = HttpResponse::Unauthorized()
+                .insert_header((header::WWW_AUTHENTICATE, r#"Basic realm="things-to-check""#))
+                .finish();
+            error::InternalError::from_response(err, challenge)
+        })?;
+
+    let catalog = store.current();
+    let lists = catalog
+        .0
+        .iter()
+        .map(|(name, things)| {
+            let retired = things.0.iter().filter(|(_, thing)| thing.retired).count();
+            List {
+                name: name.clone(),
+                live: things.0.len() - retired,
+                retired,
+            }
+        })
+        .collect();
+
+    let most_served = req.app_data::<web::Data<Counts>>().map(|counts| {
+        counts
+            .most_served(MOST_SERVED)
+            .into_iter()
+            .map(|(list, item, count)| Served {
+                text: catalog
+                    .list(Some(&list))
+                    .and_then(|things| things.find(&item))
+                    .map(|(_, thing)| thing.text()),
+                list,
+                item,
+                web: count.web,
+                slack: count.slack,
+            })
+            .collect()
+    });
+
+    let errors = errors.lock();
+    let page = Dashboard {
+        req: req.clone(),
+        theme,
+        source: &source.0,
+        failure: store.failure(),
+        loaded: HttpDate::from(store.loaded()).to_string(),
+        lists,
+        pending: queue.len(),
+        most_served,
+        errors: &errors,
+        locale: Locale::negotiate(&req, None),
+    };
+    let body = templates::render(&req, "admin.html", &page, || {
+        Ok(json!({
+            "source": page.source,
+            "failure": page.failure,
+            "loaded": page.loaded,
+            "lists": page.lists,
+            "pending": page.pending,
+            "most_served": page.most_served,
+            "errors": page.errors,
+            "locale": page.locale.lang(),
+            "theme": page.theme.get_ref(),
+            "urls": {
+                "stylesheet": req.asset("search.css")?.as_str(),
+            },
+        }))
+    })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(header::ContentType::html())
+        .insert_header(("Cache-Control", "no-store"))
+        .insert_header((header::VARY, "Accept-Language"))
+        .body(body))
+}
+
+pub(crate) fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(dashboard);
+}
@@ -0,0 +1,2680 @@
+//! JSON resources for bots, dashboards, and scripts.
+//!
+//! These endpoints serve the same suggestions as the HTML pages in the `view`
+//! module, and take the same parameters, but return JSON, so that other
+//! programs can use suggestions without scraping HTML. They are mounted by
+//! every `make_*service(…)` function.
+//!
+//! # Endpoints
+//!
+//! * `/api/v1/suggestion` (`GET`): one suggestion, as a JSON object.
+//!
+//!   Takes the same `item`, `list`, `tag`, and `lang` URL parameters as `/`,
+//!   and chooses a suggestion the same way. Requests for an alias return the
+//!   suggestion it refers to. The object has the following fields:
+//!
+//!   * `id`: the value of the `item` parameter that refers to this suggestion;
+//!     its `id`, if it has one, or its index otherwise.
+//!   * `list`: the name of the list the suggestion is in.
+//!   * `markdown` and `html`: the suggestion's text, as Markdown and as HTML.
+//!   * `url`: the suggestion's permalink.
+//!   * `lang`: the language of `markdown` and `html`.
+//!   * `details`: an object with the `markdown` and `html` of the suggestion's
+//!     longer explanation, or `null`.
+//!   * `tags`, `links`: lists of strings, possibly empty.
+//!   * `author`, `added`, `context`: strings, or `null`.
+//!   * `retired`: whether the suggestion has been retired.
+//!
+//!   Unknown lists, items, and tags return `404 Not Found`. Responses for a
+//!   fixed `item` carry an `ETag` and honour `If-None-Match`, as for
+//!   `/check/{slug}`.
+//!
+//!   With a `count` URL parameter instead of an `item`, returns a JSON list of
+//!   that many different suggestions, chosen at random from those that
+//!   haven't been retired, in the same format; for instance, to fill in a
+//!   checklist in one request. At most 20 are returned, and fewer if there
+//!   aren't enough suggestions. A `count` of 0, or given with an `item`,
+//!   returns `400 Bad Request`.
+//!
+//! * `/api/v1/suggestions` (`GET`): every suggestion in a list, a page at a
+//!   time, as a JSON object.
+//!
+//!   Takes the same `list`, `tag`, and `lang` URL parameters as `/`, and
+//!   optional `page` (counting from 1, by default 1) and `per_page` (by
+//!   default 50, and at most 100) parameters. The object has the following
+//!   fields:
+//!
+//!   * `list`: the name of the list.
+//!   * `page`, `per_page`: the page returned, and the number of suggestions
+//!     per page.
+//!   * `total`: the number of suggestions in the list, on every page.
+//!   * `suggestions`: the suggestions on this page, in order, as objects in
+//!     the format returned by `/api/v1/suggestion`. Retired suggestions are
+//!     included.
+//!
+//!   Pages past the end of the list are empty. Unknown lists return `404 Not
+//!   Found`, and a `page` of 0 returns `400 Bad Request`.
+//!
+//! * `/api/v1/search` (`GET`): suggestions matching a search, as a JSON
+//!   object.
+//!
+//!   Takes a `q` URL parameter holding the words to search for, and the same
+//!   `list` and `lang` URL parameters as `/`. Every word must match a word of
+//!   a suggestion's text or details, ignoring case; words found within the
+//!   text rank above near misses, which allow for a single typo. Retired
+//!   suggestions are never found. The object has the following fields:
+//!
+//!   * `q`: the search.
+//!   * `list`: the name of the list searched.
+//!   * `results`: at most 20 matching suggestions, best first, as objects in
+//!     the format returned by `/api/v1/suggestion`.
+//!
+//!   Unknown lists return `404 Not Found`.
+//!
+//! * `/api/version` (`GET`): which build of this service is running, and what
+//!   it's serving, as a JSON object with the following fields:
+//!
+//!   * `version`: the version of this crate.
+//!   * `git_sha`: the commit the service was built from, or `null` if it
+//!     wasn't built from a git checkout.
+//!   * `items`: the number of suggestions being served, across every list.
+//!   * `source`: where the suggestions come from: `built-in`, a file path or
+//!     URL, `github:owner/repository/path@branch`, `sqlite:` and a file path,
+//!     or `provider:` and the name of a `DataProvider` type.
+//!
+//! * `/api/openapi.json` (`GET`): an OpenAPI 3 document describing every
+//!   endpoint this service provides, for generating clients and exploring the
+//!   API.
+//!
+//! # CORS
+//!
+//! Browsers only let pages call these endpoints from other origins if the App
+//! is given a `Cors` value as app data, naming the origins to allow:
+//!
+//! ```
+//! # use things_to_check::{api, view};
+//! # #[actix_web::main]
+//! # async fn main() -> std::result::Result<(), things_to_check::view::Error> {
+//! use actix_web::{web, App, HttpServer};
+//!
+//! let service = view::make_service()?;
+//! let cors = web::Data::new(api::Cors::new(["https://status.example.com"]));
+//! let app_factory = move ||
+//!     App::new()
+//!         .app_data(cors.clone())
+//!         .configure(|cfg| service(cfg));
+//!
+//! HttpServer::new(app_factory);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Requests from allowed origins get an `Access-Control-Allow-Origin` header,
+//! and `OPTIONS` preflight requests are answered. The HTML pages and the
+//! moderation endpoints never allow cross-origin requests.
+
+use actix_web::dev::{Service, ServiceRequest};
+use actix_web::http::header::{self, ContentType, HeaderValue};
+use actix_web::{error, get, guard, web, HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+use utoipa::openapi::security::{Http, HttpAuthScheme, SecurityScheme};
+use utoipa::{IntoParams, Modify, OpenApi, ToSchema};
+
+use crate::access_log;
+use crate::admin;
+use crate::crawlers;
+use crate::discord;
+use crate::embed;
+use crate::feed;
+use crate::github;
+use crate::limit::Limited;
+use crate::mattermost;
+use crate::moderation;
+use crate::notify;
+use crate::poster;
+use crate::rocketchat;
+use crate::slack;
+use crate::socket;
+use crate::stream;
+use crate::teams;
+use crate::telegram;
+use crate::things::{Position, Rendered, Store, Thing};
+use crate::view;
+use crate::view::{ItemQuery, SearchQuery, Source, Urls, MAX_RESULTS};
+use crate::webex;
+use crate::zulip;
+
+#[derive(Serialize, ToSchema)]
+pub(crate) struct Suggestion {
+    id: String,
+    list: String,
+    markdown: String,
+    html: String,
+    url: String,
+    lang: String,
+    details: Option<Rendered>,
+    tags: Vec<String>,
+    links: Vec<String>,
+    author: Option<String>,
+    added: Option<String>,
+    context: Option<String>,
+    retired: bool,
+    number: usize,
+    total: usize,
+}
+
+impl Suggestion {
+    pub(crate) fn new(
+        list: &str,
+        id: String,
+        url: String,
+        thing: Thing,
+        position: Position,
+    ) -> Self {
+        Suggestion {
+            id,
+            list: list.to_owned(),
+            markdown: thing.markdown,
+            html: thing.html,
+            url,
+            lang: thing.lang,
+            details: thing.details,
+            tags: thing.tags,
+            links: thing.links,
+            author: thing.author,
+            added: thing.added,
+            context: thing.context,
+            retired: thing.retired,
+            number: position.number,
+            total: position.total,
+        }
+    }
+}
+
+/// The most suggestions returned for one request with a `count`.
+const MAX_COUNT: usize = 20;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct CountQuery {
+    count: Option<usize>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/suggestion",
+    tag = "api",
+    params(ItemQuery, CountQuery),
+    responses(
+        (status = 200, description = "A suggestion, or a list of suggestions if a count was given", body = Suggestion),
+        (status = 304, description = "The item matches the If-None-Match header"),
+        (status = 400, description = "A count was given with an item, or was 0"),
+        (status = 404, description = "No such list, item, or tagged suggestion"),
+    ),
+)]
+#[get("/v1/suggestion")]
+pub(crate) async fn suggestion(
+    _: Limited,
+    req: HttpRequest,
+    store: web::Data<Store>,
+    query: web::Query<ItemQuery>,
+    count: web::Query<CountQuery>,
+) -> error::Result<HttpResponse> {
+    let catalog = store.current();
+    let (list, things) = catalog
+        .named_list(query.list.as_deref())
+        .ok_or_else(|| view::not_found(&req))?;
+
+    let item = query.item.as_deref();
+    let tag = query.tag.as_deref();
+
+    if let Some(count) = count.count {
+        if item.is_some() {
+            return Err(error::ErrorBadRequest(
+                "A count can't be given with an item",
+            ));
+        }
+        if count == 0 {
+            return Err(error::ErrorBadRequest("The count must be at least 1"));
+        }
+
+        let chosen = things.sample(count.min(MAX_COUNT), tag);
+        if chosen.is_empty() {
+            return Err(view::not_found(&req));
+        }
+        let batch = chosen
+            .into_iter()
+            .map(|(index, thing)| {
+                let id = thing.item(*index);
+                let url = req.suggestion(&query, thing)?;
+                let thing = thing.translated(query.lang.as_deref());
+                let position = things.position(*index);
+                Ok(Suggestion::new(list, id, url.into(), thing, position))
+            })
+            .collect::<error::Result<Vec<_>>>()?;
+
+        return Ok(HttpResponse::Ok()
+            .insert_header(("Cache-Control", "no-store"))
+            .json(batch));
+    }
+
+    let chosen = match item.and_then(|item| things.aliased(item)) {
+        Some(aliased) => Some(aliased).filter(|(_, thing)| thing.has_tag(tag)),
+        None => things.choose(item, tag),
+    };
+    let (index, thing) = chosen.ok_or_else(|| view::not_found(&req))?;
+
+    let id = thing.item(*index);
+    access_log::chose(&req, list, &id);
+    let url = req.suggestion(&query, thing)?;
+    let thing = thing.translated(query.lang.as_deref());
+    let suggestion = Suggestion::new(list, id, url.into(), thing, things.position(*index));
+
+    if item.is_some() {
+        let body = serde_json::to_string(&suggestion).map_err(error::ErrorInternalServerError)?;
+        return Ok(view::fixed(&req, ContentType::json(), body));
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header(("Cache-Control", "no-store"))
+        .json(suggestion))
+}
+
+/// The number of suggestions per page when `per_page` isn't given.
+const DEFAULT_PER_PAGE: usize = 50;
+
+/// The most suggestions returned on one page.
+const MAX_PER_PAGE: usize = 100;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct PageQuery {
+    list: Option<String>,
+    tag: Option<String>,
+    lang: Option<String>,
+    page: Option<usize>,
+    per_page: Option<usize>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct Page {
+    list: String,
+    page: usize,
+    per_page: usize,
+    total: usize,
+    suggestions: Vec<Suggestion>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/suggestions",
+    tag = "api",
+    params(PageQuery),
+    responses(
+        (status = 200, description = "A page of suggestions", body = Page),
+        (status = 400, description = "The page number was 0"),
+        (status = 404, description = "No such list"),
+    ),
+)]
+#[get("/v1/suggestions")]
+pub(crate) async fn suggestions(
+    _: Limited,
+    req: HttpRequest,
+    store: web::Data<Store>,
+    query: web::Query<PageQuery>,
+) -> error::Result<HttpResponse> {
+    let page = query.page.unwrap_or(1);
+    if page == 0 {
+        return Err(error::ErrorBadRequest("Pages are counted from 1"));
+    }
+    let per_page = query
+        .per_page
+        .unwrap_or(DEFAULT_PER_PAGE)
+        .clamp(1, MAX_PER_PAGE);
+
+    let catalog = store.current();
+    let (list, things) = catalog
+        .named_list(query.list.as_deref())
+        .ok_or_else(|| view::not_found(&req))?;
+
+    let tag = query.tag.as_deref();
+    let lang = query.lang.as_deref();
+    let tagged: Vec<_> = things
+        .0
+        .iter()
+        .filter(|(_, thing)| thing.has_tag(tag))
+        .collect();
+    let item_query = ItemQuery {
+        item: None,
+        list: query.list.clone(),
+        tag: query.tag.clone(),
+        lang: query.lang.clone(),
+    };
+
+    let suggestions = tagged
+        .iter()
+        .skip((page - 1).saturating_mul(per_page))
+        .take(per_page)
+        .map(|(index, thing)| {
+            let id = thing.item(*index);
+            let url = req.suggestion(&item_query, thing)?;
+            Ok(Suggestion::new(
+                list,
+                id,
+                url.into(),
+                thing.translated(lang),
+                things.position(*index),
+            ))
+        })
+        .collect::<error::Result<_>>()?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(("Cache-Control", "no-store"))
+        .json(Page {
+            list: list.to_owned(),
+            page,
+            per_page,
+            total: tagged.len(),
+            suggestions,
+        }))
+}
+
+#[derive(Serialize, ToSchema)]
+struct SearchResults {
+    q: String,
+    list: String,
+    results: Vec<Suggestion>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/search",
+    tag = "api",
+    params(SearchQuery),
+    responses(
+        (status = 200, description = "The suggestions matching the search", body = SearchResults),
+        (status = 404, description = "No such list"),
+    ),
+)]
+#[get("/v1/search", name = "api_search")]
+pub(crate) async fn search(
+    _: Limited,
+    req: HttpRequest,
+    store: web::Data<Store>,
+    query: web::Query<SearchQuery>,
+) -> error::Result<HttpResponse> {
+    let catalog = store.current();
+    let (list, things) = catalog
+        .named_list(query.list.as_deref())
+        .ok_or_else(|| view::not_found(&req))?;
+
+    let item_query = query.item_query();
+    let results = things
+        .search(&query.q)
+        .into_iter()
+        .take(MAX_RESULTS)
+        .map(|(index, thing)| {
+            let id = thing.item(*index);
+            let url = req.suggestion(&item_query, thing)?;
+            let thing = thing.translated(query.lang.as_deref());
+            let position = things.position(*index);
+            Ok(Suggestion::new(list, id, url.into(), thing, position))
+        })
+        .collect::<error::Result<_>>()?;
+
+    Ok(HttpResponse::Ok().json(SearchResults {
+        q: query.q.clone(),
+        list: list.to_owned(),
+        results,
+    }))
+}
+
+#[derive(Serialize, ToSchema)]
+struct Version {
+    version: &'static str,
+    git_sha: Option<&'static str>,
+    items: usize,
+    source: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/version",
+    tag = "api",
+    responses((status = 200, description = "The running build and its suggestions", body = Version)),
+)]
+#[get("/version")]
+pub(crate) async fn version(store: web::Data<Store>, source: web::Data<Source>) -> HttpResponse {
+    let catalog = store.current();
+    let items = catalog.0.values().map(|things| things.0.len()).sum();
+
+    HttpResponse::Ok()
+        .insert_header(("Cache-Control", "no-store"))
+        .json(Version {
+            version: env!("CARGO_PKG_VERSION"),
+            git_sha: option_env!("GIT_SHA"),
+            items,
+            source: source.0.clone(),
+        })
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Things To Check"),
+    paths(
+        view::index,
+        view::check,
+        view::search,
+        view::all,
+        view::category_index,
+        view::category_page,
+        view::healthz,
+        view::readyz,
+        feed::atom,
+        feed::json,
+        poster::poster,
+        crawlers::sitemap,
+        crawlers::robots,
+        embed::oembed,
+        embed::widget,
+        embed::badge,
+        embed::item_badge,
+        embed::qr,
+        embed::og_image,
+        stream::suggestions,
+        socket::socket,
+        suggestion,
+        suggestions,
+        search,
+        version,
+        openapi_json,
+        moderation::suggest,
+        moderation::list_pending,
+        moderation::approve,
+        moderation::reject,
+        admin::dashboard,
+        notify::notify,
+        slack::command,
+        slack::interact,
+        slack::events,
+        slack::oauth_start,
+        slack::oauth_callback,
+        discord::interactions,
+        discord::notify,
+        teams::troubleshoot,
+        mattermost::troubleshoot,
+        telegram::webhook,
+        zulip::troubleshoot,
+        rocketchat::troubleshoot,
+        webex::webhook,
+        github::push,
+    ),
+    modifiers(&Moderators),
+)]
+struct ApiDoc;
+
+// The moderation endpoints take the moderators' token as a bearer token.
+struct Moderators;
+
+impl Modify for Moderators {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "moderator",
+            SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)),
+        );
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/openapi.json",
+    tag = "api",
+    responses((status = 200, description = "This document", content_type = "application/json")),
+)]
+#[get("/openapi.json")]
+pub(crate) async fn openapi_json() -> HttpResponse {
+    HttpResponse::Ok().json(ApiDoc::openapi())
+}
+
+/// Enables CORS on these endpoints, so that pages served from other origins,
+/// such as dashboards and status pages, can call them.
+///
+/// CORS is off unless the App is given a `Cors` value as app data, in the same
+/// way as `moderation::Moderators`.
+pub struct Cors {
+    origins: Vec<String>,
+}
+
+impl Cors {
+    /// Allow pages served from any of `origins`, such as
+    /// `https://status.example.com`, to call these endpoints. An origin of `*`
+    /// allows pages from anywhere.
+    pub fn new(origins: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Cors {
+            origins: origins.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    // The Access-Control-Allow-Origin header for a request, if its origin is
+    // allowed.
+    fn allow(&self, req: &ServiceRequest) -> Option<HeaderValue> {
+        if self.origins.iter().any(|origin| origin == "*") {
+            return Some(HeaderValue::from_static("*"));
+        }
+
+        let origin = req.headers().get(header::ORIGIN)?;
+        self.origins
+            .iter()
+            .any(|allowed| allowed.as_bytes() == origin.as_bytes())
+            .then(|| origin.clone())
+    }
+}
+
+// Answers CORS preflight requests for any of these endpoints. The
+// Access-Control-Allow-Origin header is added by the middleware in
+// `configure`, along with every other response's.
+async fn preflight(req: HttpRequest) -> HttpResponse {
+    let enabled = req.app_data::<web::Data<Cors>>().is_some();
+    if !enabled
+        || !req
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_REQUEST_METHOD)
+    {
+        return HttpResponse::NotFound().finish();
+    }
+
+    HttpResponse::NoContent()
+        .insert_header((header::ACCESS_CONTROL_ALLOW_METHODS, "GET"))
+        .insert_header((header::ACCESS_CONTROL_ALLOW_HEADERS, "If-None-Match"))
+        .insert_header((header::ACCESS_CONTROL_MAX_AGE, "86400"))
+        .finish()
+}
+
+pub(crate) fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/api")
+            .wrap_fn(|req, srv| {
+                let cors = req
+                    .app_data::<web::Data<Cors>>()
+                    .map(|cors| cors.allow(&req));
+                let response = srv.call(req);
+                async move {
+                    let mut response = response.await?;
+                    if let Some(origin) = cors {
+                        let headers = response.headers_mut();
+                        headers.append(header::VARY, HeaderValue::from_static("Origin"));
+                        if let Some(origin) = origin {
+                            headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+                            headers.insert(
+                                header::ACCESS_CONTROL_EXPOSE_HEADERS,
+                                HeaderValue::from_static("ETag"),
+                            );
+                        }
+                    }
+                    Ok(response)
+                }
+            })
+            .service(suggestion)
+            .service(suggestions)
+            .service(search)
+            .service(version)
+            .service(openapi_json)
+            .service(
+                web::resource("/{path:.*}")
+                    .guard(guard::Options())
+                    .to(preflight),
+            ),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_codec::{Decoder, Encoder};
+    use actix_http::ws::{Codec, Frame, Message};
+    use actix_web::body::MessageBody;
+    use actix_web::cookie::Cookie;
+    use actix_web::http::header::ContentType;
+    use actix_web::http::{header, Method};
+    use actix_web::{error, test, web, App, HttpResponse};
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use bytes::BytesMut;
+    use hmac::{Hmac, Mac};
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+    use sha2::Sha256;
+    use std::collections::HashMap;
+    use std::pin::Pin;
+    use std::time::{SystemTime, UNIX_EPOCH};
+    use std::{env, fs, future, process};
+
+    use super::Cors;
+    use crate::counts::{Channel, Counts};
+    use crate::discord::{Discord, Webhook};
+    use crate::mattermost::Mattermost;
+    use crate::notify::Notifier;
+    use crate::request_id::{self, RequestIds};
+    use crate::rocketchat::RocketChat;
+    use crate::seen::Unseen;
+    use crate::shuffle::Shuffle;
+    use crate::slack::{Slack, Tokens};
+    use crate::teams::Teams;
+    use crate::telegram::Telegram;
+    use crate::templates::Templates;
+    use crate::webex::Webex;
+    use crate::zulip::Zulip;
+    use crate::{crawlers, limit, moderation, theme, view};
+
+    #[actix_web::test]
+    async fn suggestion_is_json() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/v1/suggestion?item=0")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(body["id"], "0");
+        assert_eq!(body["list"], "general");
+        assert!(body["url"]
+            .as_str()
+            .unwrap()
+            .ends_with("/check/have-you-checked-permissions"));
+    }
+
+    #[actix_web::test]
+    async fn suggestions_come_in_batches() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/v1/suggestion?count=5")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+        let mut ids: Vec<_> = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|suggestion| suggestion["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(ids.len(), 5);
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids.len(), 5);
+
+        let req = test::TestRequest::get()
+            .uri("/api/v1/suggestion?count=1000")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body.as_array().unwrap().len(), 20);
+
+        for uri in [
+            "/api/v1/suggestion?count=0",
+            "/api/v1/suggestion?count=2&item=0",
+        ] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), 400);
+        }
+    }
+
+    #[actix_web::test]
+    async fn fixed_items_are_cacheable() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        for uri in [
+            "/check/have-you-checked-permissions",
+            "/api/v1/suggestion?item=0",
+        ] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), 200);
+            let etag = resp.headers().get(header::ETAG).unwrap().clone();
+
+            let req = test::TestRequest::get()
+                .uri(uri)
+                .insert_header((header::IF_NONE_MATCH, etag))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), 304);
+        }
+    }
+
+    #[actix_web::test]
+    async fn random_items_are_not_cacheable() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        for uri in ["/", "/api/v1/suggestion"] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let resp = test::call_service(&app, req).await;
+
+            assert!(resp.headers().get(header::ETAG).is_none());
+            assert_eq!(
+                resp.headers().get(header::CACHE_CONTROL).unwrap(),
+                "no-store"
+            );
+        }
+    }
+
+    #[actix_web::test]
+    async fn suggestions_are_paginated() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/v1/suggestions?page=2&per_page=3")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(body["page"], 2);
+        assert_eq!(body["per_page"], 3);
+        assert!(body["total"].as_u64().unwrap() > 6);
+        let ids: Vec<_> = body["suggestions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|suggestion| suggestion["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(ids, ["3", "4", "5"]);
+    }
+
+    #[actix_web::test]
+    async fn search_finds_suggestions() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/v1/search?q=DNS")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+        let results = body["results"].as_array().unwrap();
+        assert!(!results.is_empty());
+        assert!(results
+            .iter()
+            .all(|result| result["markdown"].as_str().unwrap().contains("DNS")));
+    }
+
+    #[actix_web::test]
+    async fn all_lists_every_suggestion() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get().uri("/all").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        assert!(resp.headers().contains_key(header::ETAG));
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+
+        let req = test::TestRequest::get()
+            .uri("/api/v1/suggestions?per_page=1")
+            .to_request();
+        let page: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(
+            body.matches("<li id=").count() as u64,
+            page["total"].as_u64().unwrap()
+        );
+        assert!(body.contains(r#"<li id="have-you-checked-permissions""#));
+        assert!(body.contains(r##"<a href="#have-you-checked-permissions">#1</a>"##));
+        assert!(
+            body.contains(r#"<a href="http://localhost:8080/check/have-you-checked-permissions">"#)
+        );
+
+        for uri in ["/all?list=missing", "/all?tag=no-such-tag"] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), 404);
+        }
+    }
+
+    #[actix_web::test]
+    async fn suggestions_are_read_from_a_file() {
+        let path = env::temp_dir().join(format!("things-to-check-path-{}.yml", process::id()));
+        fs::write(&path, "- Have you checked the fuses?\n").unwrap();
+        let service = view::make_service_from_path(&path, &view::Options::default()).unwrap();
+        fs::remove_file(&path).unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/v1/suggestion")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["markdown"], "Have you checked the fuses?");
+        assert_eq!(body["total"], 1);
+    }
+
+    #[actix_web::test]
+    async fn missing_files_are_errors() {
+        let path = env::temp_dir().join(format!("things-to-check-missing-{}.yml", process::id()));
+        let result = view::make_service_from_path(&path, &view::Options::default());
+        assert!(matches!(result, Err(view::Error::ReadError(_))));
+    }
+
+    #[actix_web::test]
+    async fn categories_list_tagged_suggestions() {
+        let path =
+            env::temp_dir().join(format!("things-to-check-categories-{}.yml", process::id()));
+        fs::write(
+            &path,
+            "
+- Have you checked permissions?
+- text: Have you checked DNS?
+  tags: [dns, networking]
+- text: Have you checked the MTU?
+  tags: [networking]
+",
+        )
+        .unwrap();
+        let service = view::make_service_from_path(&path, &view::Options::default()).unwrap();
+        fs::remove_file(&path).unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get().uri("/categories").to_request();
+        let body = String::from_utf8(test::call_and_read_body(&app, req).await.to_vec()).unwrap();
+        assert!(body.contains(r#"<a href="http://localhost:8080/category/dns">dns</a> (1)"#));
+        assert!(body
+            .contains(r#"<a href="http://localhost:8080/category/networking">networking</a> (2)"#));
+
+        let req = test::TestRequest::get()
+            .uri("/category/networking")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert!(body.contains("<h1>Category: networking</h1>"));
+        assert_eq!(body.matches("<li id=").count(), 2);
+        assert!(!body.contains("permissions"));
+
+        for uri in ["/category/hardware", "/categories?list=missing"] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), 404);
+        }
+    }
+
+    #[cfg(feature = "graphql")]
+    #[actix_web::test]
+    async fn graphql_finds_suggestions() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::post()
+            .uri("/graphql")
+            .set_json(serde_json::json!({
+                "query": r#"{
+                    suggestion(item: "0") { id list url }
+                    search(q: "DNS") { markdown }
+                    lists
+                }"#,
+            }))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+        let data = &body["data"];
+        assert_eq!(data["suggestion"]["id"], "0");
+        assert_eq!(data["suggestion"]["list"], "general");
+        assert!(data["suggestion"]["url"]
+            .as_str()
+            .unwrap()
+            .ends_with("/check/have-you-checked-permissions"));
+        assert!(!data["search"].as_array().unwrap().is_empty());
+        assert_eq!(data["lists"], serde_json::json!(["general"]));
+    }
+
+    #[actix_web::test]
+    async fn openapi_describes_every_endpoint() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/openapi.json")
+            .to_request();
+        let doc: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+        for path in [
+            "/",
+            "/search",
+            "/all",
+            "/categories",
+            "/category/{name}",
+            "/healthz",
+            "/readyz",
+            "/feed.xml",
+            "/feed.json",
+            "/poster.pdf",
+            "/sitemap.xml",
+            "/robots.txt",
+            "/oembed",
+            "/badge.svg",
+            "/badge/{item}.svg",
+            "/qr/{item}.png",
+            "/og/{item}.png",
+            "/stream",
+            "/ws",
+            "/api/v1/suggestion",
+            "/api/v1/suggestions",
+            "/api/v1/search",
+            "/api/version",
+            "/api/openapi.json",
+            "/suggest",
+            "/admin/pending",
+            "/admin/pending/{id}/approve",
+            "/admin/pending/{id}/reject",
+            "/admin",
+            "/notify/webhooks",
+            "/slack/command",
+            "/slack/interact",
+            "/slack/events",
+            "/slack/oauth/start",
+            "/slack/oauth/callback",
+            "/discord/interactions",
+            "/notify/discord",
+            "/teams/troubleshoot",
+            "/mattermost/troubleshoot",
+            "/telegram/webhook",
+            "/zulip/troubleshoot",
+            "/rocketchat/troubleshoot",
+            "/webex/webhook",
+            "/hooks/github",
+        ] {
+            assert!(doc["paths"].get(path).is_some(), "{} is missing", path);
+        }
+    }
+
+    #[actix_web::test]
+    async fn healthz_is_ok() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get().uri("/healthz").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(body["status"], "ok");
+    }
+
+    #[actix_web::test]
+    async fn readyz_follows_the_source() {
+        let path = env::temp_dir().join(format!("things-to-check-readyz-{}.yml", process::id()));
+        fs::write(&path, "- Have you checked DNS?\n").unwrap();
+        let (service, reloader) =
+            view::make_reloadable_service(&path, &view::Options::default()).unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get().uri("/readyz").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+
+        fs::write(&path, "{{ not yaml").unwrap();
+        assert!(reloader.reload().is_err());
+        let req = test::TestRequest::get().uri("/readyz").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 503);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["reason"], "source-failed");
+
+        // Liveness doesn't depend on the source.
+        let req = test::TestRequest::get().uri("/healthz").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+
+        fs::write(&path, "- Have you checked DNS?\n").unwrap();
+        reloader.reload().unwrap();
+        fs::remove_file(&path).unwrap();
+        let req = test::TestRequest::get().uri("/readyz").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["status"], "ready");
+    }
+
+    #[actix_web::test]
+    async fn version_describes_the_suggestions() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get().uri("/api/version").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(body["source"], "built-in");
+        assert!(body["items"].as_u64().unwrap() > 0);
+    }
+
+    #[actix_web::test]
+    async fn cors_allows_configured_origins() {
+        let service = view::make_service().unwrap();
+        let cors = web::Data::new(Cors::new(["https://status.example.com"]));
+        let app = test::init_service(App::new().app_data(cors).configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/v1/suggestion")
+            .insert_header((header::ORIGIN, "https://status.example.com"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .unwrap(),
+            "https://status.example.com"
+        );
+
+        let req = test::TestRequest::default()
+            .method(Method::OPTIONS)
+            .uri("/api/v1/suggestion")
+            .insert_header((header::ORIGIN, "https://status.example.com"))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "GET"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 204);
+        assert!(resp
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+
+        let req = test::TestRequest::get()
+            .uri("/api/v1/suggestion")
+            .insert_header((header::ORIGIN, "https://elsewhere.example.com"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(!resp
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+
+    #[actix_web::test]
+    async fn cors_is_off_by_default() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/v1/suggestion")
+            .insert_header((header::ORIGIN, "https://status.example.com"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert!(!resp
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+
+    #[actix_web::test]
+    async fn limited_requests_are_refused() {
+        let options = view::Options {
+            rate_limit: Some(limit::RateLimit {
+                burst: 1,
+                per_second: 0.1,
+                forwarded: false,
+            }),
+            ..Default::default()
+        };
+        let service = view::make_service_with(&options).unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+        let peer = "192.0.2.1:4000".parse().unwrap();
+
+        let req = test::TestRequest::get()
+            .uri("/check/have-you-checked-permissions")
+            .peer_addr(peer)
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+
+        let req = test::TestRequest::get()
+            .uri("/check/have-you-checked-permissions")
+            .peer_addr(peer)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 429);
+        assert_eq!(resp.headers().get(header::RETRY_AFTER).unwrap(), "10");
+
+        let req = test::TestRequest::get()
+            .uri("/healthz")
+            .peer_addr(peer)
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+    }
+
+    #[actix_web::test]
+    async fn feed_lists_suggestions() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get().uri("/feed.xml").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/atom+xml"
+        );
+
+        let body = test::read_body(resp).await;
+        let body = std::str::from_utf8(&body).unwrap();
+        assert!(body.contains("<feed xmlns=\"http://www.w3.org/2005/Atom\">"));
+        assert!(body.contains("<id>http://localhost:8080/check/have-you-checked-permissions</id>"));
+    }
+
+    #[actix_web::test]
+    async fn json_feed_lists_suggestions() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get().uri("/feed.json").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(body["version"], "https://jsonfeed.org/version/1.1");
+        assert_eq!(body["feed_url"], "http://localhost:8080/feed.json");
+        let items = body["items"].as_array().unwrap();
+        assert!(!items.is_empty());
+        assert!(items.iter().all(|item| item["id"] == item["url"]));
+    }
+
+    #[actix_web::test]
+    async fn poster_lists_suggestions() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get().uri("/poster.pdf").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/pdf"
+        );
+
+        let body = test::read_body(resp).await;
+        assert!(body.starts_with(b"%PDF-"));
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains("(Things To Check) Tj"));
+        assert!(body.contains("(1.) Tj"));
+
+        let req = test::TestRequest::get()
+            .uri("/poster.pdf?list=missing")
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn sitemap_lists_permalinks() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get().uri("/sitemap.xml").to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        let body = std::str::from_utf8(&body).unwrap();
+
+        assert!(body.contains("<loc>http://localhost:8080/</loc>"));
+        assert!(
+            body.contains("<loc>http://localhost:8080/check/have-you-checked-permissions</loc>")
+        );
+    }
+
+    #[actix_web::test]
+    async fn robots_follow_options() {
+        let options = view::Options {
+            robots: crawlers::Robots {
+                disallow: vec!["/".to_owned()],
+                sitemap: false,
+            },
+            ..Default::default()
+        };
+        let service = view::make_service_with(&options).unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get().uri("/robots.txt").to_request();
+        let body = test::call_and_read_body(&app, req).await;
+
+        assert_eq!(body, "User-agent: *\nDisallow: /\n");
+    }
+
+    #[actix_web::test]
+    async fn robots_point_at_the_sitemap() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get().uri("/robots.txt").to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        let body = std::str::from_utf8(&body).unwrap();
+
+        assert!(body.contains("Disallow: /api/\n"));
+        assert!(body.contains("Allow: /\n"));
+        assert!(body.ends_with("Sitemap: http://localhost:8080/sitemap.xml\n"));
+    }
+
+    #[actix_web::test]
+    async fn oembed_describes_permalinks() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get()
+            .uri("/oembed?url=http%3A%2F%2Flocalhost%3A8080%2F%3Fitem%3D0&maxwidth=400")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(body["type"], "rich");
+        assert_eq!(body["width"], 400);
+        assert!(body["html"]
+            .as_str()
+            .unwrap()
+            .contains("href=\"http://localhost:8080/check/have-you-checked-permissions\""));
+
+        // Titles are plain text, not Markdown.
+        let req = test::TestRequest::get()
+            .uri("/oembed?url=http%3A%2F%2Flocalhost%3A8080%2F%3Fitem%3D7")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["title"], "Have you checked resolv.conf?");
+
+        for uri in [
+            "/oembed?url=http%3A%2F%2Flocalhost%3A8080%2F",
+            "/oembed?url=http%3A%2F%2Fexample.com%2F%3Fitem%3D0",
+        ] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), 404);
+        }
+    }
+
+    #[actix_web::test]
+    async fn items_redirect_to_slug_permalinks() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get()
+            .uri("/?item=3&lang=fr")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 302);
+        assert_eq!(
+            resp.headers().get(header::LOCATION).unwrap(),
+            "http://localhost:8080/check/have-you-checked-the-cache?lang=fr"
+        );
+
+        let req = test::TestRequest::get()
+            .uri("/check/have-you-checked-the-cache")
+            .to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("Have you checked the cache?"));
+
+        for uri in ["/?item=9999", "/check/have-you-checked-the-kettle"] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), 404, "{}", uri);
+        }
+
+        let req = test::TestRequest::get()
+            .uri("/oembed?url=http%3A%2F%2Flocalhost%3A8080%2Fcheck%2Fhave-you-checked-the-cache")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["title"], "Have you checked the cache?");
+    }
+
+    #[actix_web::test]
+    async fn badges_show_suggestions() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get().uri("/badge/7.svg").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "image/svg+xml"
+        );
+        let body = test::read_body(resp).await;
+        let body = std::str::from_utf8(&body).unwrap();
+        assert!(body.contains("<title>check: Have you checked resolv.conf?</title>"));
+
+        let req = test::TestRequest::get().uri("/badge.svg").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(
+            resp.headers().get(header::CACHE_CONTROL).unwrap(),
+            "no-store"
+        );
+    }
+
+    #[actix_web::test]
+    async fn qr_codes_are_png() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get().uri("/qr/0.png").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "image/png"
+        );
+
+        let body = test::read_body(resp).await;
+        assert!(body.starts_with(b"\x89PNG\r\n\x1a\n"));
+    }
+
+    #[actix_web::test]
+    async fn pages_link_to_preview_cards() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get()
+            .uri("/check/have-you-checked-permissions?lang=fr")
+            .to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        let body = std::str::from_utf8(&body).unwrap();
+        assert!(body.contains("content=\"http://localhost:8080/og/0.png?lang=fr\""));
+
+        let req = test::TestRequest::get()
+            .uri("/og/0.png?lang=fr")
+            .to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        assert!(body.starts_with(b"\x89PNG\r\n\x1a\n"));
+    }
+
+    #[actix_web::test]
+    async fn streams_send_suggestions() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get().uri("/stream").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/event-stream"
+        );
+
+        // The stream never ends, so only its first event can be read.
+        let mut body = resp.into_body();
+        let event = future::poll_fn(|cx| Pin::new(&mut body).poll_next(cx))
+            .await
+            .unwrap()
+            .unwrap();
+        let event = std::str::from_utf8(&event).unwrap();
+        let data = event
+            .strip_prefix("event: suggestion\ndata: ")
+            .and_then(|event| event.strip_suffix("\n\n"))
+            .unwrap();
+        let suggestion: serde_json::Value = serde_json::from_str(data).unwrap();
+        assert_eq!(suggestion["list"], "general");
+
+        let req = test::TestRequest::get()
+            .uri("/stream?list=no-such-list")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn sockets_answer_with_suggestions() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let mut client = Codec::new().client_mode();
+        let mut frames = BytesMut::new();
+        for message in ["next", r#"{"item": "0"}"#, r#"{"list": "no-such-list"}"#] {
+            client
+                .encode(Message::Text(message.into()), &mut frames)
+                .unwrap();
+        }
+        client.encode(Message::Close(None), &mut frames).unwrap();
+
+        let req = test::TestRequest::get()
+            .uri("/ws")
+            .insert_header((header::UPGRADE, "websocket"))
+            .insert_header((header::CONNECTION, "upgrade"))
+            .insert_header((header::SEC_WEBSOCKET_VERSION, "13"))
+            .insert_header((header::SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ=="))
+            .set_payload(frames.freeze())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 101);
+
+        let mut body = BytesMut::from(&test::read_body(resp).await[..]);
+        let mut answers = vec![];
+        while let Some(frame) = client.decode(&mut body).unwrap() {
+            match frame {
+                Frame::Text(text) => {
+                    answers.push(serde_json::from_slice::<serde_json::Value>(&text).unwrap())
+                }
+                Frame::Close(_) => break,
+                frame => panic!("unexpected frame {:?}", frame),
+            }
+        }
+
+        assert_eq!(answers.len(), 3);
+        assert_eq!(answers[0]["list"], "general");
+        assert_eq!(answers[1]["id"], "0");
+        assert!(answers[2]["error"].is_string());
+    }
+
+    #[actix_web::test]
+    async fn unseen_suggestions_are_remembered() {
+        let service = view::make_service().unwrap();
+        let unseen = web::Data::new(Unseen::new("s3kr1t"));
+        let app = test::init_service(
+            App::new()
+                .app_data(unseen.clone())
+                .configure(|cfg| service(cfg)),
+        )
+        .await;
+
+        let mut cookie = None;
+        for seen in 1..=5 {
+            let mut req = test::TestRequest::get().uri("/");
+            if let Some(cookie) = cookie {
+                req = req.cookie(cookie);
+            }
+            let resp = test::call_service(&app, req.to_request()).await;
+            let next = resp.response().cookies().next().unwrap().into_owned();
+
+            // The cookie holds a bitmap, in hex, with a bit for each
+            // suggestion seen, and a signature.
+            let (bitmap, _) = next.value().split_once('.').unwrap();
+            let bits: u32 = hex::decode(bitmap)
+                .unwrap()
+                .iter()
+                .map(|byte| byte.count_ones())
+                .sum();
+            assert_eq!(bits, seen);
+            cookie = Some(next);
+        }
+
+        let forged = Cookie::new("seen", "ff.00");
+        let req = test::TestRequest::get()
+            .uri("/")
+            .cookie(forged)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let cookie = resp.response().cookies().next().unwrap();
+        assert!(!cookie.value().starts_with("ff."));
+    }
+
+    #[actix_web::test]
+    async fn shuffled_suggestions_do_not_repeat() {
+        let service = view::make_service().unwrap();
+        let shuffle = web::Data::new(Shuffle::new("s3kr1t"));
+        let app = test::init_service(
+            App::new()
+                .app_data(shuffle.clone())
+                .configure(|cfg| service(cfg)),
+        )
+        .await;
+
+        let mut cookie = None;
+        let mut shown = std::collections::BTreeSet::new();
+        for dealt in 1..=5 {
+            let mut req = test::TestRequest::get().uri("/");
+            if let Some(cookie) = cookie {
+                req = req.cookie(cookie);
+            }
+            let resp = test::call_service(&app, req.to_request()).await;
+            let next = resp.response().cookies().next().unwrap().into_owned();
+
+            // The cookie holds the shuffle's seed, how far through it the
+            // browser has got, and a signature.
+            let mut parts = next.value().split('.');
+            assert_eq!(parts.nth(1).unwrap(), dealt.to_string());
+            cookie = Some(next);
+
+            let body = test::read_body(resp).await;
+            let body = std::str::from_utf8(&body).unwrap();
+            let (_, canonical) = body.split_once("rel=\"canonical\" href=\"").unwrap();
+            let (canonical, _) = canonical.split_once('"').unwrap();
+            assert!(shown.insert(canonical.to_owned()), "{} repeated", canonical);
+        }
+    }
+
+    // A request to `uri` signed as Slack signs its requests.
+    fn slack_request(uri: &str, secret: &str, body: &str) -> test::TestRequest {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            .to_string();
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(format!("v0:{}:{}", timestamp, body).as_bytes());
+        let signature = hex::encode(mac.finalize().into_bytes());
+
+        test::TestRequest::post()
+            .uri(uri)
+            .insert_header(("X-Slack-Request-Timestamp", timestamp))
+            .insert_header(("X-Slack-Signature", format!("v0={}", signature)))
+            .insert_header(ContentType::form_url_encoded())
+            .set_payload(body.to_owned())
+    }
+
+    #[actix_web::test]
+    async fn slack_commands_offer_another() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Slack::new("s3kr1t")))
+                .configure(|cfg| service(cfg)),
+        )
+        .await;
+
+        let req =
+            slack_request("/slack/command", "s3kr1t", "command=%2Ftroubleshoot&text=").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["response_type"], "in_channel");
+        let button = &body["blocks"][1]["elements"][1];
+        assert_eq!(button["action_id"], "another");
+
+        let req = slack_request("/slack/command", "s3kr1t", "text=quietly").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["response_type"], "ephemeral");
+
+        let req = slack_request("/slack/command", "s3kr1t", "text=0").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let permalink = body["blocks"][1]["elements"][0]["url"].as_str().unwrap();
+        assert!(
+            permalink.contains("check/have-you-checked-permissions"),
+            "{}",
+            permalink
+        );
+
+        let req = slack_request("/slack/command", "s3kr1t", "text=no+such+words").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["response_type"], "ephemeral");
+
+        let req =
+            slack_request("/slack/command", "wrong", "command=%2Ftroubleshoot&text=").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 401);
+
+        // Presses can only be answered at Slack's own response URLs.
+        let payload = serde_json::json!({
+            "type": "block_actions",
+            "response_url": "https://example.com/",
+            "actions": [{ "action_id": "another", "value": button["value"] }],
+        });
+        let body = serde_urlencoded::to_string([("payload", payload.to_string())]).unwrap();
+        let req = slack_request("/slack/interact", "s3kr1t", &body).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn slack_events_verify_their_url() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Slack::new("s3kr1t")))
+                .configure(|cfg| service(cfg)),
+        )
+        .await;
+
+        let body = r#"{"type": "url_verification", "token": "t", "challenge": "c4llenge"}"#;
+        let req = slack_request("/slack/events", "s3kr1t", body)
+            .insert_header(ContentType::json())
+            .to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["challenge"], "c4llenge");
+
+        let req = slack_request("/slack/events", "wrong", body).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 401);
+
+        // Without a bot token, mentions can't be answered, but are received.
+        let body = r#"{
+            "type": "event_callback",
+            "team_id": "T0123",
+            "event": {"type": "app_mention", "text": "<@U0123> dns", "channel": "C0123", "ts": "1.2"}
+        }"#;
+        let req = slack_request("/slack/events", "s3kr1t", body).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    #[actix_web::test]
+    async fn slack_installs_start_with_state() {
+        let service = view::make_service().unwrap();
+        let slack = Slack::new("s3kr1t").installable("1.2", "client-s3kr1t", Tokens::in_memory());
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(slack))
+                .configure(|cfg| service(cfg)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/slack/oauth/start")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 302);
+        let location = resp.headers().get("Location").unwrap().to_str().unwrap();
+        let location = url::Url::parse(location).unwrap();
+        assert_eq!(location.host_str(), Some("slack.com"));
+        let params: HashMap<_, _> = location.query_pairs().collect();
+        assert_eq!(params["client_id"], "1.2");
+        assert!(params["redirect_uri"].ends_with("/slack/oauth/callback"));
+        let state = resp.response().cookies().next().unwrap().into_owned();
+        assert_eq!(params["state"], state.value());
+
+        // Callbacks must come back to the browser that started installing.
+        let req = test::TestRequest::get()
+            .uri("/slack/oauth/callback?code=abc&state=forged")
+            .cookie(state.clone())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/slack/oauth/callback?error=access_denied&state={}",
+                state.value()
+            ))
+            .cookie(state)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    // An interaction signed with `key` as Discord signs its requests.
+    fn discord_request(key: &Ed25519KeyPair, body: &str) -> test::TestRequest {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            .to_string();
+        let signature = key.sign(format!("{}{}", timestamp, body).as_bytes());
+
+        test::TestRequest::post()
+            .uri("/discord/interactions")
+            .insert_header(("X-Signature-Timestamp", timestamp))
+            .insert_header(("X-Signature-Ed25519", hex::encode(signature)))
+            .insert_header(ContentType::json())
+            .set_payload(body.to_owned())
+    }
+
+    #[actix_web::test]
+    async fn discord_commands_post_suggestions() {
+        let key = Ed25519KeyPair::from_seed_unchecked(&[7; 32]).unwrap();
+        let discord = Discord::new(&hex::encode(key.public_key())).unwrap();
+        let service = view::make_service().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(discord))
+                .configure(|cfg| service(cfg)),
+        )
+        .await;
+
+        let req = discord_request(&key, r#"{"type": 1}"#).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["type"], 1);
+
+        let command = r#"{
+            "type": 2,
+            "data": {"name": "troubleshoot", "options": [{"name": "item", "type": 3, "value": "0"}]}
+        }"#;
+        let req = discord_request(&key, command).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["type"], 4);
+        let content = body["data"]["content"].as_str().unwrap();
+        assert!(
+            content.contains("check/have-you-checked-permissions"),
+            "{}",
+            content
+        );
+
+        let press = r#"{"type": 3, "data": {"custom_id": "another", "component_type": 2}}"#;
+        let req = discord_request(&key, press).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["type"], 7);
+
+        // Discord checks that forged requests are refused.
+        let forger = Ed25519KeyPair::from_seed_unchecked(&[8; 32]).unwrap();
+        let req = discord_request(&forger, r#"{"type": 1}"#).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 401);
+    }
+
+    #[actix_web::test]
+    async fn discord_notifications_need_moderators() {
+        // Nothing listens on the discard port, so posts there fail quickly.
+        let webhook = Webhook::new(url::Url::parse("http://127.0.0.1:9/api/webhooks/1/x").unwrap());
+        let service = view::make_service().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(webhook))
+                .app_data(web::Data::new(moderation::Moderators::new("s3kr1t")))
+                .configure(|cfg| service(cfg)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/notify/discord?item=0")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 401);
+
+        let req = test::TestRequest::post()
+            .uri("/notify/discord?item=0")
+            .insert_header(("Authorization", "Bearer s3kr1t"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 502);
+
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+        let req = test::TestRequest::post()
+            .uri("/notify/discord")
+            .insert_header(("Authorization", "Bearer s3kr1t"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn webhook_notifications_need_moderators() {
+        let service = view::make_service().unwrap();
+        // Nothing listens on the discard port, so posts to it fail.
+        let webhook = url::Url::parse("http://127.0.0.1:9/hook").unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(moderation::Moderators::new("s3kr1t")))
+                .app_data(web::Data::new(Notifier::new(vec![webhook])))
+                .configure(|cfg| service(cfg)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/notify/webhooks")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 401);
+
+        let req = test::TestRequest::post()
+            .uri("/notify/webhooks?item=0")
+            .insert_header(("Authorization", "Bearer s3kr1t"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 502);
+
+        let req = test::TestRequest::post()
+            .uri("/notify/webhooks?item=no-such-thing")
+            .insert_header(("Authorization", "Bearer s3kr1t"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn approved_suggestions_keep_their_indices() {
+        let path = env::temp_dir().join(format!("things-to-check-approved-{}.yml", process::id()));
+        fs::write(&path, "- Have you checked DNS?\n").unwrap();
+        let options = view::Options {
+            markdown: view::Markdown {
+                strikethrough: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let (service, reloader) = view::make_reloadable_service(&path, &options).unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(moderation::Moderators::new("s3kr1t")))
+                .configure(|cfg| service(cfg)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/suggest")
+            .set_form([("text", "Have you [checked](javascript:alert(1)) this?")])
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+
+        let req = test::TestRequest::post()
+            .uri("/suggest")
+            .set_form([("text", "Have you checked the ~~old~~ new backups?")])
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let req = test::TestRequest::post()
+            .uri(&format!("/admin/pending/{}/approve", body["id"]))
+            .insert_header(("Authorization", "Bearer s3kr1t"))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["item"], 1_000_000);
+
+        // The source grows, and is reloaded.
+        fs::write(&path, "- Have you checked DNS?\n- Have you checked NTP?\n").unwrap();
+        reloader.reload().unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let req = test::TestRequest::get()
+            .uri("/api/v1/suggestion?item=1")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["markdown"], "Have you checked NTP?");
+
+        let req = test::TestRequest::get()
+            .uri("/api/v1/suggestion?item=1000000")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let html = body["html"].as_str().unwrap();
+        assert!(html.contains("~~old~~"), "{}", html);
+    }
+
+    #[actix_web::test]
+    async fn dashboard_needs_moderators() {
+        let counts = Counts::in_memory();
+        counts.served("general", "0", Channel::Slack);
+        let service = view::make_service().unwrap();
+        let app = test::init_service(
+            App::new()
+                .wrap(RequestIds)
+                .app_data(web::Data::new(moderation::Moderators::new("s3kr1t")))
+                .app_data(web::Data::new(counts))
+                .route(
+                    "/fail",
+                    web::get().to(|| async {
+                        Err::<HttpResponse, _>(error::ErrorInternalServerError("disk on fire"))
+                    }),
+                )
+                .configure(|cfg| service(cfg)),
+        )
+        .await;
+
+        // Browsers are asked for the token.
+        let req = test::TestRequest::get().uri("/admin").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 401);
+        assert!(resp.headers().contains_key(header::WWW_AUTHENTICATE));
+
+        let req = test::TestRequest::get()
+            .uri("/fail")
+            .insert_header((request_id::HEADER, "abc-123"))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 500);
+
+        let req = test::TestRequest::get()
+            .uri("/admin")
+            .insert_header((
+                header::AUTHORIZATION,
+                format!("Basic {}", STANDARD.encode("admin:s3kr1t")),
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert!(body.contains("built-in"));
+        assert!(body.contains("general"));
+        assert!(body.contains("Have you checked"));
+        assert!(body.contains("abc-123"));
+        assert!(body.contains("disk on fire"));
+
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+        let req = test::TestRequest::get()
+            .uri("/admin")
+            .insert_header((header::AUTHORIZATION, "Bearer s3kr1t"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn teams_replies_with_cards() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Teams::new("czNrcjF0").unwrap()))
+                .configure(|cfg| service(cfg)),
+        )
+        .await;
+
+        let body = r#"{"type": "message", "text": "<at>troubleshoot</at> 0"}"#;
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"s3kr1t").unwrap();
+        mac.update(body.as_bytes());
+        let signature = STANDARD.encode(mac.finalize().into_bytes());
+
+        let req = test::TestRequest::post()
+            .uri("/teams/troubleshoot")
+            .insert_header(("Authorization", format!("HMAC {}", signature)))
+            .insert_header(ContentType::json())
+            .set_payload(body)
+            .to_request();
+        let reply: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let attachment = &reply["attachments"][0];
+        assert_eq!(
+            attachment["contentType"],
+            "application/vnd.microsoft.card.adaptive"
+        );
+        let permalink = attachment["content"]["actions"][0]["url"].as_str().unwrap();
+        assert!(
+            permalink.contains("check/have-you-checked-permissions"),
+            "{}",
+            permalink
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/teams/troubleshoot")
+            .insert_header(("Authorization", "HMAC Zm9yZ2Vk"))
+            .insert_header(ContentType::json())
+            .set_payload(body)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 401);
+    }
+
+    #[actix_web::test]
+    async fn mattermost_commands_check_tokens() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Mattermost::new("s3kr1t")))
+                .configure(|cfg| service(cfg)),
+        )
+        .await;
+
+        let command = |body: &'static str| {
+            test::TestRequest::post()
+                .uri("/mattermost/troubleshoot")
+                .insert_header(ContentType::form_url_encoded())
+                .set_payload(body)
+                .to_request()
+        };
+
+        let req = command("token=s3kr1t&command=%2Ftroubleshoot&text=0");
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["response_type"], "in_channel");
+        let text = body["text"].as_str().unwrap();
+        assert!(
+            text.contains("check/have-you-checked-permissions"),
+            "{}",
+            text
+        );
+
+        let req = command("token=s3kr1t&text=quietly");
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["response_type"], "ephemeral");
+
+        let req = command("token=wrong&text=");
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 401);
+    }
+
+    #[actix_web::test]
+    async fn telegram_commands_check_tokens() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Telegram::new("s3kr1t")))
+                .configure(|cfg| service(cfg)),
+        )
+        .await;
+
+        let update = |secret: &str, text: &str| {
+            test::TestRequest::post()
+                .uri("/telegram/webhook")
+                .insert_header(("X-Telegram-Bot-Api-Secret-Token", secret))
+                .set_json(serde_json::json!({
+                    "update_id": 1,
+                    "message": { "message_id": 1, "chat": { "id": 42 }, "text": text },
+                }))
+                .to_request()
+        };
+
+        let req = update("s3kr1t", "/troubleshoot 0");
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["method"], "sendMessage");
+        assert_eq!(body["chat_id"], 42);
+        assert_eq!(body["parse_mode"], "MarkdownV2");
+        let text = body["text"].as_str().unwrap();
+        assert!(
+            text.contains("check/have-you-checked-permissions"),
+            "{}",
+            text
+        );
+
+        let req = update("s3kr1t", "hello");
+        let body = test::call_and_read_body(&app, req).await;
+        assert!(body.is_empty());
+
+        let req = update("wrong", "/troubleshoot");
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 401);
+
+        let req = test::TestRequest::post()
+            .uri("/telegram/webhook")
+            .set_json(serde_json::json!({ "update_id": 1 }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 401);
+    }
+
+    #[actix_web::test]
+    async fn zulip_replies_check_tokens() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Zulip::new("s3kr1t")))
+                .configure(|cfg| service(cfg)),
+        )
+        .await;
+
+        let outgoing = |token: &str, data: &str| {
+            test::TestRequest::post()
+                .uri("/zulip/troubleshoot")
+                .set_json(serde_json::json!({
+                    "bot_email": "troubleshoot-bot@example.zulipchat.com",
+                    "data": data,
+                    "token": token,
+                    "trigger": "mention",
+                }))
+                .to_request()
+        };
+
+        let req = outgoing("s3kr1t", "@**troubleshoot** 0");
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let content = body["content"].as_str().unwrap();
+        assert!(
+            content.contains("check/have-you-checked-permissions"),
+            "{}",
+            content
+        );
+
+        let req = outgoing("wrong", "@**troubleshoot**");
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 401);
+    }
+
+    #[actix_web::test]
+    async fn rocketchat_replies_with_attachments() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(RocketChat::new("s3kr1t")))
+                .configure(|cfg| service(cfg)),
+        )
+        .await;
+
+        let outgoing = |token: &str, text: &str| {
+            test::TestRequest::post()
+                .uri("/rocketchat/troubleshoot")
+                .set_json(serde_json::json!({
+                    "token": token,
+                    "channel_name": "ops",
+                    "user_name": "alice",
+                    "text": text,
+                    "trigger_word": "!troubleshoot",
+                }))
+                .to_request()
+        };
+
+        let req = outgoing("s3kr1t", "!troubleshoot 0");
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let attachment = &body["attachments"][0];
+        let permalink = attachment["title_link"].as_str().unwrap();
+        assert!(
+            permalink.contains("check/have-you-checked-permissions"),
+            "{}",
+            permalink
+        );
+
+        let req = outgoing("wrong", "!troubleshoot");
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 401);
+    }
+
+    #[actix_web::test]
+    async fn webex_webhooks_check_signatures() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Webex::new("bot-token", "s3kr1t")))
+                .configure(|cfg| service(cfg)),
+        )
+        .await;
+
+        // Bots' own messages are ignored, so this makes no calls to Webex.
+        let body = r#"{
+            "resource": "messages",
+            "event": "created",
+            "data": { "id": "m1", "roomId": "r1", "personEmail": "troubleshoot@webex.bot" }
+        }"#;
+        let mut mac = Hmac::<sha1::Sha1>::new_from_slice(b"s3kr1t").unwrap();
+        mac.update(body.as_bytes());
+        let signature = hex::encode(mac.finalize().into_bytes());
+
+        let req = test::TestRequest::post()
+            .uri("/webex/webhook")
+            .insert_header(("X-Spark-Signature", signature))
+            .insert_header(ContentType::json())
+            .set_payload(body)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+
+        let req = test::TestRequest::post()
+            .uri("/webex/webhook")
+            .insert_header(("X-Spark-Signature", "00"))
+            .insert_header(ContentType::json())
+            .set_payload(body)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 401);
+    }
+
+    #[actix_web::test]
+    async fn slack_links_to_the_public_url() {
+        let service = view::make_service().unwrap();
+        let index = url::Url::parse("https://troubleshoot.example.com/").unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Slack::new("s3kr1t").linking_to(index)))
+                .configure(|cfg| service(cfg)),
+        )
+        .await;
+
+        let req = slack_request("/slack/command", "s3kr1t", "text=0").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let button = &body["blocks"][1]["elements"][0];
+        assert_eq!(button["action_id"], "permalink");
+        assert_eq!(
+            button["url"],
+            "https://troubleshoot.example.com/check/have-you-checked-permissions"
+        );
+    }
+
+    #[actix_web::test]
+    async fn slack_chooses_lists_by_workspace() {
+        let service = view::make_service().unwrap();
+        let team_lists = [
+            ("T0GENERAL".to_owned(), "general".to_owned()),
+            ("T0MISSING".to_owned(), "no-such-list".to_owned()),
+        ];
+        let slack = Slack::new("s3kr1t").with_team_lists(team_lists.into_iter().collect());
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(slack))
+                .configure(|cfg| service(cfg)),
+        )
+        .await;
+
+        let req =
+            slack_request("/slack/command", "s3kr1t", "text=0&team_id=T0GENERAL").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let button = &body["blocks"][1]["elements"][0];
+        assert_eq!(
+            button["url"],
+            "http://localhost:8080/check/have-you-checked-permissions?list=general"
+        );
+
+        let req = slack_request("/slack/command", "s3kr1t", "text=0&team_id=T0OTHER").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let button = &body["blocks"][1]["elements"][0];
+        assert_eq!(
+            button["url"],
+            "http://localhost:8080/check/have-you-checked-permissions"
+        );
+
+        let req =
+            slack_request("/slack/command", "s3kr1t", "text=0&team_id=T0MISSING").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["response_type"], "ephemeral");
+    }
+
+    #[actix_web::test]
+    async fn dashboard_is_localized_and_overridable() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(moderation::Moderators::new("s3kr1t")))
+                .configure(|cfg| service(cfg)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/admin")
+            .insert_header((header::AUTHORIZATION, "Bearer s3kr1t"))
+            .insert_header((header::ACCEPT_LANGUAGE, "fr"))
+            .to_request();
+        let body = String::from_utf8(test::call_and_read_body(&app, req).await.to_vec()).unwrap();
+        assert!(body.contains(r#"<html lang="fr">"#));
+        assert!(body.contains("<h2>Erreurs récentes</h2>"));
+
+        let dir = env::temp_dir().join(format!("things-to-check-admin-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("admin.html"),
+            "{{ source }}: {% for list in lists %}{{ list.name }}={{ list.live }}{% endfor %}",
+        )
+        .unwrap();
+        let templates = Templates::open(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let service = view::make_service().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(moderation::Moderators::new("s3kr1t")))
+                .app_data(web::Data::new(templates))
+                .configure(|cfg| service(cfg)),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri("/admin")
+            .insert_header((header::AUTHORIZATION, "Bearer s3kr1t"))
+            .to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        assert_eq!(body, "built-in: general=41");
+    }
+
+    #[actix_web::test]
+    async fn templates_override_pages() {
+        let dir = env::temp_dir().join(format!("things-to-check-templates-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("index.html"),
+            "<h1>{{ suggestion.html|safe }}</h1><a href=\"{{ urls.another }}\">{{ suggestion.list }}</a>",
+        )
+        .unwrap();
+
+        let service = view::make_service().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Templates::open(&dir).unwrap()))
+                .configure(|cfg| service(cfg)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/check/have-you-checked-permissions")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/html; charset=utf-8"
+        );
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert!(body.starts_with("<h1><p>"));
+        // Overrides escape their output, slashes included.
+        assert!(
+            body.ends_with(r#"</h1><a href="http:&#x2f;&#x2f;localhost:8080&#x2f;">general</a>"#)
+        );
+
+        // Pages without an override keep the built-in template.
+        let req = test::TestRequest::get().uri("/search?q=dns").to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("Search suggestions"));
+
+        fs::write(dir.join("search.html"), "{% if %}").unwrap();
+        assert!(Templates::open(&dir).is_err());
+        assert!(Templates::open(dir.join("missing")).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[actix_web::test]
+    async fn missing_pages_link_to_suggestions() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(
+            App::new()
+                .configure(|cfg| service(cfg))
+                .default_service(web::to(view::missing)),
+        )
+        .await;
+
+        for uri in ["/check/no-such-check", "/?list=missing", "/no/such/page"] {
+            let req = test::TestRequest::get()
+                .uri(uri)
+                .insert_header((header::ACCEPT_LANGUAGE, "de"))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), 404);
+            assert_eq!(
+                resp.headers().get(header::CONTENT_TYPE).unwrap(),
+                "text/html; charset=utf-8"
+            );
+            let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+            assert!(body.contains("<title>Nicht gefunden</title>"));
+            assert!(body.contains(r#"<a href="http://localhost:8080/">"#));
+        }
+
+        let req = test::TestRequest::get()
+            .uri("/api/v1/suggestion?item=9999")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"], "Not found");
+        assert_eq!(body["another"], "http://localhost:8080/");
+    }
+
+    #[actix_web::test]
+    async fn pages_show_their_permalinks() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get()
+            .uri("/check/have-you-checked-permissions?lang=fr")
+            .to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        // Readers without scripts can still copy the link by hand.
+        assert!(body
+            .contains(r#"<input type="text" value="http://localhost:8080/check/have-you-checked-permissions?lang=fr""#));
+        assert!(body.contains(">Copier le lien</button>"));
+    }
+
+    #[actix_web::test]
+    async fn suggestions_are_numbered() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/v1/suggestion?item=2")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["number"], 3);
+        let total = body["total"].as_u64().unwrap();
+        assert!(total >= 3);
+
+        let req = test::TestRequest::get()
+            .uri("/check/have-you-checked-for-a-full-disk")
+            .to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains(&format!(">#3 of {}</a>", total)));
+    }
+
+    #[actix_web::test]
+    async fn pages_link_related_suggestions() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        // Both are about expired certificates.
+        let req = test::TestRequest::get()
+            .uri("/check/have-you-checked-for-an-expired-certificate")
+            .to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains(r#"<nav class="related" aria-label="Related checks">"#));
+        assert!(body.contains(
+            r#"<a href="http://localhost:8080/check/have-you-checked-for-an-expired-root-certificate">Have you checked for an expired root certificate?</a>"#
+        ));
+
+        let req = test::TestRequest::get()
+            .uri("/check/have-you-checked-permissions")
+            .to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(!body.contains(r#"<nav class="related""#));
+    }
+
+    #[actix_web::test]
+    async fn icons_are_served_where_browsers_look() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        for (uri, content_type) in [
+            ("/favicon.ico", "image/x-icon"),
+            ("/apple-touch-icon.png", "image/png"),
+            ("/icon.svg", "image/svg+xml"),
+            ("/mask-icon.svg", "image/svg+xml"),
+        ] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), 200, "{}", uri);
+            assert_eq!(
+                resp.headers().get(header::CONTENT_TYPE).unwrap(),
+                content_type
+            );
+        }
+
+        let req = test::TestRequest::get()
+            .uri("/check/have-you-checked-permissions")
+            .to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains(r#"<link rel="icon" href="http://localhost:8080/favicon.ico""#));
+    }
+
+    #[actix_web::test]
+    async fn themes_replace_icons() {
+        let options = view::Options {
+            theme: theme::Theme {
+                icon: Some("https://example.com/icon.png".parse().unwrap()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let service = view::make_service_with(&options).unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        for uri in ["/favicon.ico", "/apple-touch-icon.png", "/icon.svg"] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), 302, "{}", uri);
+            assert_eq!(
+                resp.headers().get(header::LOCATION).unwrap(),
+                "https://example.com/icon.png"
+            );
+        }
+
+        let req = test::TestRequest::get().uri("/mask-icon.svg").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    #[actix_web::test]
+    async fn pages_link_cacheable_assets() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get()
+            .uri("/check/have-you-checked-permissions")
+            .to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        let (_, stylesheet) = body
+            .split_once(r#"<link rel="stylesheet" href="http://localhost:8080"#)
+            .unwrap();
+        let (stylesheet, _) = stylesheet.split_once('"').unwrap();
+        assert!(stylesheet.starts_with("/static/index."));
+        assert!(!body.contains("<style>"));
+
+        let req = test::TestRequest::get().uri(stylesheet).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/css"
+        );
+        assert_eq!(
+            resp.headers().get("Cache-Control").unwrap(),
+            "public, max-age=31536000, immutable"
+        );
+
+        let req = test::TestRequest::get()
+            .uri("/static/index.css")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn pages_can_be_installed() {
+        let options = view::Options {
+            theme: theme::Theme {
+                title: Some("Example Corp".to_owned()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let service = view::make_service_with(&options).unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get()
+            .uri("/check/have-you-checked-permissions")
+            .to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains(
+            r#"<link rel="manifest" href="http://localhost:8080/manifest.webmanifest">"#
+        ));
+        assert!(body.contains(r#"data-service-worker="http://localhost:8080/sw.js""#));
+
+        let req = test::TestRequest::get()
+            .uri("/manifest.webmanifest")
+            .to_request();
+        let manifest: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(manifest["name"], "Example Corp");
+        assert_eq!(manifest["start_url"], "http://localhost:8080/");
+        assert_eq!(manifest["display"], "standalone");
+        assert_eq!(
+            manifest["icons"][1]["src"],
+            "http://localhost:8080/icon-512.png"
+        );
+
+        let req = test::TestRequest::get().uri("/icon-512.png").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "image/png"
+        );
+
+        let req = test::TestRequest::get().uri("/icon-100.png").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+
+        let req = test::TestRequest::get().uri("/sw.js").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/javascript"
+        );
+    }
+
+    #[actix_web::test]
+    async fn kiosk_pages_refresh() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get()
+            .uri("/?lang=fr&refresh=30")
+            .to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains(
+            r#"<meta http-equiv="refresh" content="30; url=http://localhost:8080/?lang=fr&amp;refresh=30">"#
+        ));
+
+        // Too-frequent refreshes slow down to the minimum.
+        let req = test::TestRequest::get().uri("/?refresh=0").to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains(r#"content="5; url=http://localhost:8080/?refresh=5""#));
+
+        let req = test::TestRequest::get().uri("/").to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(!body.contains(r#"http-equiv="refresh""#));
+    }
+
+    #[actix_web::test]
+    async fn widgets_can_be_framed() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get()
+            .uri("/embed?item=0&lang=fr")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_SECURITY_POLICY).unwrap(),
+            "frame-ancestors *"
+        );
+        assert!(resp.headers().contains_key(header::ETAG));
+        let body = test::read_body(resp).await;
+        let body = std::str::from_utf8(&body).unwrap();
+        assert!(body.contains("<p>Have you checked permissions?</p>"));
+        assert!(body.contains(r#"<a class="another" href="http://localhost:8080/embed?lang=fr">"#));
+        assert!(!body.contains("<header>"));
+
+        let req = test::TestRequest::get().uri("/embed").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get("Cache-Control").unwrap(), "no-store");
+        assert_eq!(
+            resp.headers().get(header::CONTENT_SECURITY_POLICY).unwrap(),
+            "frame-ancestors *"
+        );
+
+        let req = test::TestRequest::get()
+            .uri("/embed?item=nonexistent")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn pages_share_suggestions() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get()
+            .uri("/check/have-you-checked-permissions")
+            .to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(
+            body.contains(r#"<meta property="og:url" content="http://localhost:8080/check/have-you-checked-permissions">"#)
+        );
+        assert!(body.contains(r#"<meta name="twitter:description" content="Have you checked"#));
+        assert!(body.contains("https://twitter.com/intent/tweet?text=Have+you+checked"));
+        assert!(body.contains(
+            "mailto:?subject=Troubleshooting%20suggestion&amp;body=Have%20you%20checked"
+        ));
+    }
+
+    #[actix_web::test]
+    async fn themes_brand_pages() {
+        let options = view::Options {
+            theme: theme::Theme {
+                title: Some("Example Corp".to_owned()),
+                accent: Some("#c0392b".to_owned()),
+                footer: Some("Ask in #ops & #sre.".to_owned()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let service = view::make_service_with(&options).unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        for uri in ["/check/have-you-checked-permissions", "/search?q=dns"] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let body = test::call_and_read_body(&app, req).await;
+            let body = String::from_utf8(body.to_vec()).unwrap();
+            assert!(body.contains(" · Example Corp</title>"));
+            assert!(body.contains("<span>Example Corp</span>"));
+            assert!(body.contains("#c0392b;"));
+            assert!(body.contains("<footer>Ask in #ops &amp; #sre.</footer>"));
+        }
+    }
+
+    #[actix_web::test]
+    async fn slack_is_off_by_default() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = slack_request("/slack/command", "s3kr1t", "text=").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+
+        let req = test::TestRequest::get()
+            .uri("/slack/oauth/start")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn unknown_suggestions_are_not_found() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/v1/suggestion?item=no-such-thing")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), 404);
+    }
+}
@@ -0,0 +1,118 @@
+//! Stylesheets and scripts for the HTML pages.
+//!
+//! The pages link their styles and scripts rather than carrying them inline,
+//! so that browsers download them once rather than with every suggestion.
+//! Assets live under `static` in the source tree and are compiled into the
+//! service. Each is served under a name carrying a fingerprint of its
+//! contents, such as `/static/index.3f2a9c1b.css`, and may be cached for a
+//! year: a changed asset gets a new name, and pages link the new name. They
+//! are mounted by every `make_*service(…)` function.
+//!
+//! # Endpoints
+//!
+//! * `/static/{name}` (`GET`): the asset `name`, which must be fingerprinted
+//!   as the pages link it. Other names, including stale fingerprints, return
+//!   `404 Not Found`.
+//!
+//! Styles that depend on the `theme` stay inline in the pages, which are
+//! rendered for each request anyway.
+
+use actix_web::{error, get, web, HttpResponse};
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
+
+/// Each asset's name, content type, and contents.
+const ASSETS: [(&str, &str, &str); 4] = [
+    (
+        "all.js",
+        "text/javascript",
+        include_str!("../static/all.js"),
+    ),
+    ("index.css", "text/css", include_str!("../static/index.css")),
+    (
+        "index.js",
+        "text/javascript",
+        include_str!("../static/index.js"),
+    ),
+    (
+        "search.css",
+        "text/css",
+        include_str!("../static/search.css"),
+    ),
+];
+
+/// How long, in seconds, browsers may cache an asset: a year, the longest
+/// that caches are expected to honour.
+const MAX_AGE: u32 = 365 * 24 * 60 * 60;
+
+struct Asset {
+    name: &'static str,
+    fingerprinted: String,
+    content_type: &'static str,
+    body: &'static str,
+}
+
+fn assets() -> &'static [Asset] {
+    static FINGERPRINTED: OnceLock<Vec<Asset>> = OnceLock::new();
+    FINGERPRINTED.get_or_init(|| {
+        ASSETS
+            .iter()
+            .map(|&(name, content_type, body)| {
+                let digest = hex::encode(Sha256::digest(body));
+                let fingerprinted = match name.rsplit_once('.') {
+                    Some((stem, extension)) => format!("{}.{}.{}", stem, &digest[..8], extension),
+                    None => format!("{}.{}", name, &digest[..8]),
+                };
+                Asset {
+                    name,
+                    fingerprinted,
+                    content_type,
+                    body,
+                }
+            })
+            .collect()
+    })
+}
+
+/// The name the asset `name` is served under, fingerprinted, if there is such
+/// an asset.
+pub(crate) fn fingerprinted(name: &str) -> Option<&'static str> {
+    assets()
+        .iter()
+        .find(|asset| asset.name == name)
+        .map(|asset| asset.fingerprinted.as_str())
+}
+
+#[get("/static/{name}", name = "asset")]
+pub(crate) async fn serve(name: web::Path<String>) -> error::Result<HttpResponse> {
+    let asset = assets()
+        .iter()
+        .find(|asset| asset.fingerprinted == *name)
+        .ok_or_else(|| error::ErrorNotFound("Not found"))?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(asset.content_type)
+        .insert_header((
+            "Cache-Control",
+            format!("public, max-age={}, immutable", MAX_AGE),
+        ))
+        .body(asset.body))
+}
+
+pub(crate) fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(serve);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assets_are_fingerprinted() {
+        let name = fingerprinted("index.css").unwrap();
+        assert!(name.starts_with("index."));
+        assert!(name.ends_with(".css"));
+        assert_eq!(name.len(), "index.12345678.css".len());
+        assert!(fingerprinted("missing.css").is_none());
+    }
+}
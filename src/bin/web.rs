@@ -1,31 +1,351 @@
-use actix_web::{App, HttpServer};
+use actix_web::{rt, web};
+use clap::Parser;
 use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 
+use things_to_check::access_log;
+use things_to_check::api;
+use things_to_check::config;
+use things_to_check::counts;
+use things_to_check::discord;
+use things_to_check::mattermost;
+use things_to_check::moderation;
+use things_to_check::notify;
+use things_to_check::rocketchat;
+use things_to_check::seen;
+use things_to_check::server;
+use things_to_check::shuffle;
+use things_to_check::slack;
+use things_to_check::teams;
+use things_to_check::telegram;
+use things_to_check::templates;
+#[cfg(feature = "otel")]
+use things_to_check::trace;
 use things_to_check::twelve;
 use things_to_check::view;
+use things_to_check::webex;
+use things_to_check::zulip;
 
 #[derive(Error, Debug)]
 pub enum Error {
-    #[error("Unable to determine port number: {0}")]
-    PortError(#[from] twelve::Error),
+    #[error("Unable to load configuration: {0}")]
+    ConfigError(#[from] config::Error),
     #[error("Unable to initialize web view: {0}")]
     ViewError(#[from] view::Error),
+    #[error("Unable to enable the Discord app: {0}")]
+    DiscordError(#[from] discord::InvalidPublicKey),
+    #[error("Unable to enable the Teams webhook: {0}")]
+    TeamsError(#[from] teams::InvalidSecurityToken),
+    #[error("Unable to load templates: {0}")]
+    TemplatesError(#[from] templates::Error),
+    #[cfg(feature = "otel")]
+    #[error("Unable to enable tracing: {0}")]
+    TraceError(#[from] trace::Error),
     #[error("Unexpected IO error: {0}")]
     IOError(#[from] io::Error),
 }
 
 type Result = std::result::Result<(), Error>;
 
+/// Serve troubleshooting suggestions over HTTP.
+///
+/// Every setting can be read from a TOML or YAML file given with --config, and
+/// overridden by its environment variable; the options below override both.
+/// See the README.
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Read settings from the TOML or YAML file at PATH.
+    #[arg(long, value_name = "PATH", env = "THINGS_TO_CHECK_CONFIG")]
+    config: Option<PathBuf>,
+    /// Listen on ADDR, such as `127.0.0.1:8080`, rather than on every
+    /// interface at port $PORT, or 3000.
+    #[arg(long, value_name = "ADDR")]
+    bind: Option<String>,
+    /// Listen on the Unix socket at PATH, for a reverse proxy on the same
+    /// host, rather than on a TCP port.
+    #[arg(long, value_name = "PATH", conflicts_with = "bind")]
+    socket: Option<String>,
+    /// Serve suggestions from a file or directory, an http(s) URL, or
+    /// `github:owner/repository/path[@branch]`, rather than the built-in ones.
+    #[arg(long)]
+    source: Option<String>,
+    /// Answer Slack slash commands signed with SECRET.
+    #[arg(long, value_name = "SECRET")]
+    slack_signing_secret: Option<String>,
+    /// The URL people reach the index page at, which integrations link to.
+    #[arg(long)]
+    url: Option<String>,
+    /// Log every request, in FORMAT: `combined` or `json`.
+    #[arg(long, value_name = "FORMAT")]
+    access_log: Option<String>,
+    /// Write the access log to the end of the file at PATH, rather than to
+    /// stdout. Requires an access log format.
+    #[arg(long, value_name = "PATH")]
+    access_log_file: Option<String>,
+    /// Serve HTTPS with the PEM-encoded certificate chain in PATH. Requires
+    /// a private key.
+    #[cfg(feature = "tls")]
+    #[arg(long, value_name = "PATH")]
+    tls_certificate: Option<String>,
+    /// Serve HTTPS with the PEM-encoded private key in PATH. Requires a
+    /// certificate chain.
+    #[cfg(feature = "tls")]
+    #[arg(long, value_name = "PATH")]
+    tls_key: Option<String>,
+}
+
+impl Args {
+    // The settings given on the command line, by their keys in the
+    // configuration.
+    fn overrides(self) -> Vec<(&'static str, String)> {
+        let overrides = vec![
+            ("bind", self.bind),
+            ("socket", self.socket),
+            ("source", self.source),
+            ("slack.signing_secret", self.slack_signing_secret),
+            ("url", self.url),
+            ("access_log.format", self.access_log),
+            ("access_log.file", self.access_log_file),
+        ];
+        #[cfg(feature = "tls")]
+        let overrides = [
+            ("tls.certificate", self.tls_certificate),
+            ("tls.key", self.tls_key),
+        ]
+        .into_iter()
+        .chain(overrides)
+        .collect::<Vec<_>>();
+
+        overrides
+            .into_iter()
+            .filter_map(|(key, value)| Some((key, value?)))
+            .collect()
+    }
+}
+
+type Service = Arc<dyn Fn(&mut web::ServiceConfig) + Send + Sync>;
+
 #[actix_web::main]
 async fn main() -> Result {
-    let port = twelve::port(3000)?;
+    let args = Args::parse();
+    let file = args.config.clone();
+    let config = config::Config::load(file.as_deref(), args.overrides())?;
+
+    let server = match (config.bind.as_deref(), &config.socket) {
+        (Some(addr), _) => server::Server::new(addr)?,
+        (None, Some(path)) => server::Server::on_socket(path.clone()),
+        (None, None) => server::Server::new(twelve::PortAddr::from(config.port.unwrap_or(3000)))?,
+    };
+    #[cfg(feature = "tls")]
+    let server = match (&config.tls.certificate, &config.tls.key) {
+        (Some(certificate), Some(key)) => server.with_tls(certificate.clone(), key.clone()),
+        _ => server,
+    };
+    let server = match (config.access_log.format, &config.access_log.file) {
+        (Some(format), Some(path)) => {
+            server.with_access_log(access_log::AccessLog::to_file(path, format)?)
+        }
+        (Some(format), None) => server.with_access_log(access_log::AccessLog::to_stdout(format)),
+        (None, _) => server,
+    };
+
+    let index = config.url.clone();
+    let options = config.options();
+    let service: Service = match config.source.clone() {
+        Some(twelve::Source::Path(path)) => {
+            let (service, reloader) = view::make_reloadable_service(path, &options)?;
+            rt::spawn(reloader.on_sighup());
+            Arc::new(service)
+        }
+        Some(twelve::Source::Url(url)) => {
+            let (service, poller) = view::make_polling_service(&url, &options)?;
+            rt::spawn(poller.every(config.refresh.unwrap_or(Duration::from_secs(300))));
+            Arc::new(service)
+        }
+        Some(twelve::Source::GitHub(repository)) => Arc::new(view::make_github_service(
+            &repository,
+            config.github_webhook_secret.as_deref(),
+            &options,
+        )?),
+        None => Arc::new(view::make_service_with(&options)?),
+    };
+    let moderators = config
+        .moderator_token
+        .clone()
+        .map(|token| web::Data::new(moderation::Moderators::new(token)));
+    let unseen = config
+        .unseen_cookie_key
+        .clone()
+        .map(|key| web::Data::new(seen::Unseen::new(key)));
+    let shuffle = config
+        .shuffle_cookie_key
+        .clone()
+        .map(|key| web::Data::new(shuffle::Shuffle::new(key)));
+    let templates = match &config.templates {
+        Some(dir) => Some(web::Data::new(templates::Templates::open(dir)?)),
+        None => None,
+    };
+    let settings = &config.slack;
+    let slack = match settings.signing_secret.clone() {
+        Some(secret) => {
+            let slack = slack::Slack::new(secret);
+            let quiet = settings.response_type.as_deref() == Some("ephemeral");
+            let slack = if quiet { slack.quietly() } else { slack };
+            let slack = match &index {
+                Some(index) => slack.linking_to(index.clone()),
+                None => slack,
+            };
+            let slack = slack.with_team_lists(settings.team_lists.clone());
+            let slack = match settings.bot_token.clone() {
+                Some(bot_token) => slack.with_bot_token(bot_token),
+                None => slack,
+            };
+            let slack = match (settings.client_id.clone(), settings.client_secret.clone()) {
+                (Some(client_id), Some(client_secret)) => {
+                    let tokens = match &settings.tokens_path {
+                        Some(path) => slack::Tokens::open(path)?,
+                        None => slack::Tokens::in_memory(),
+                    };
+                    slack.installable(client_id, client_secret, tokens)
+                }
+                _ => slack,
+            };
+            Some(web::Data::new(slack))
+        }
+        None => None,
+    };
+    let discord = match &config.discord.public_key {
+        Some(public_key) => Some(web::Data::new(discord::Discord::new(public_key)?)),
+        None => None,
+    };
+    let discord_webhook = config
+        .discord
+        .webhook_url
+        .clone()
+        .map(|url| web::Data::new(discord::Webhook::new(url)));
+    let teams = match &config.teams.security_token {
+        Some(token) => Some(web::Data::new(teams::Teams::new(token)?)),
+        None => None,
+    };
+    let mattermost = config.mattermost.token.clone().map(|token| {
+        let mattermost = mattermost::Mattermost::new(token);
+        let quiet = config.mattermost.response_type.as_deref() == Some("ephemeral");
+        web::Data::new(if quiet {
+            mattermost.quietly()
+        } else {
+            mattermost
+        })
+    });
+    let telegram = config
+        .telegram
+        .secret_token
+        .clone()
+        .map(|secret| web::Data::new(telegram::Telegram::new(secret)));
+    let zulip = config
+        .zulip
+        .token
+        .clone()
+        .map(|token| web::Data::new(zulip::Zulip::new(token)));
+    let rocketchat = config
+        .rocketchat
+        .token
+        .clone()
+        .map(|token| web::Data::new(rocketchat::RocketChat::new(token)));
+    let webex = match (
+        config.webex.bot_token.clone(),
+        config.webex.webhook_secret.clone(),
+    ) {
+        (Some(token), Some(secret)) => Some(web::Data::new(webex::Webex::new(token, secret))),
+        _ => None,
+    };
+    let webhooks = config.notify_urls.clone();
+    let notifier = (!webhooks.is_empty()).then(|| web::Data::new(notify::Notifier::new(webhooks)));
+    let counts = match &config.counts {
+        Some(path) => counts::Counts::open(path)?,
+        None => counts::Counts::in_memory(),
+    };
+    rt::spawn(counts.clone().every(Duration::from_secs(60)));
+    let counted = web::Data::new(counts.clone());
+    let origins = config.cors_origins.clone();
+    let cors = (!origins.is_empty()).then(|| web::Data::new(api::Cors::new(origins)));
+    #[cfg(feature = "otel")]
+    let tracer_provider = match &config.otel.endpoint {
+        Some(endpoint) => Some(trace::install(endpoint)?),
+        None => None,
+    };
+    let configure = move |cfg: &mut web::ServiceConfig| {
+        if let Some(moderators) = &moderators {
+            cfg.app_data(moderators.clone());
+        }
+        if let Some(unseen) = &unseen {
+            cfg.app_data(unseen.clone());
+        }
+        if let Some(shuffle) = &shuffle {
+            cfg.app_data(shuffle.clone());
+        }
+        if let Some(templates) = &templates {
+            cfg.app_data(templates.clone());
+        }
+        if let Some(slack) = &slack {
+            cfg.app_data(slack.clone());
+        }
+        if let Some(discord) = &discord {
+            cfg.app_data(discord.clone());
+        }
+        if let Some(discord_webhook) = &discord_webhook {
+            cfg.app_data(discord_webhook.clone());
+        }
+        if let Some(teams) = &teams {
+            cfg.app_data(teams.clone());
+        }
+        if let Some(mattermost) = &mattermost {
+            cfg.app_data(mattermost.clone());
+        }
+        if let Some(telegram) = &telegram {
+            cfg.app_data(telegram.clone());
+        }
+        if let Some(zulip) = &zulip {
+            cfg.app_data(zulip.clone());
+        }
+        if let Some(rocketchat) = &rocketchat {
+            cfg.app_data(rocketchat.clone());
+        }
+        if let Some(webex) = &webex {
+            cfg.app_data(webex.clone());
+        }
+        if let Some(notifier) = &notifier {
+            cfg.app_data(notifier.clone());
+        }
+        if let Some(cors) = &cors {
+            cfg.app_data(cors.clone());
+        }
+        cfg.app_data(counted.clone());
+        service(cfg);
+    };
 
-    let service = view::make_service()?;
+    #[cfg(feature = "otel")]
+    let server = if tracer_provider.is_some() {
+        server.traced()
+    } else {
+        server
+    };
+    server.run(configure).await?;
 
-    let app_factory = move || App::new().configure(|cfg| service(cfg));
+    if let Err(err) = counts.save() {
+        eprintln!("Unable to save counts: {}", err);
+    }
 
-    HttpServer::new(app_factory).bind(port)?.run().await?;
+    // Send the spans recorded since the last batch.
+    #[cfg(feature = "otel")]
+    if let Some(provider) = tracer_provider {
+        if let Err(err) = provider.shutdown() {
+            eprintln!("Unable to send the last traces: {}", err);
+        }
+    }
 
     Ok(())
 }
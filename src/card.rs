@@ -0,0 +1,173 @@
+//! Preview cards: images of a suggestion's text, for link previews.
+//!
+//! Text is drawn in an 8×8 pixel font, scaled up, so that no font files need
+//! to be shipped or found at runtime. Characters the font lacks are drawn as
+//! `?`.
+
+use font8x8::{UnicodeFonts, BASIC_FONTS, GREEK_FONTS, LATIN_FONTS};
+
+/// The width of a card, in pixels, as Open Graph consumers prefer.
+pub(crate) const WIDTH: usize = 1200;
+
+/// The height of a card, in pixels, as Open Graph consumers prefer.
+pub(crate) const HEIGHT: usize = 630;
+
+/// The space around a card's text.
+const MARGIN: usize = 80;
+
+/// The scale of the suggestion's text.
+const TEXT_SCALE: usize = 5;
+
+/// The scale of the footer naming the service.
+const FOOTER_SCALE: usize = 3;
+
+/// The space between lines of text, as a fraction of the glyph height.
+const LEADING: usize = 3;
+
+pub(crate) const BACKGROUND: [u8; 3] = [0xdd, 0xdd, 0xe7];
+pub(crate) const FOREGROUND: [u8; 3] = [0x22, 0x22, 0x33];
+pub(crate) const ACCENT: [u8; 3] = [0x5b, 0x6e, 0xe1];
+
+/// The height of the accent stripe along the bottom of the card.
+const STRIPE: usize = 16;
+
+const FOOTER: &str = "Things To Check";
+
+struct Canvas {
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new() -> Self {
+        Canvas {
+            pixels: BACKGROUND.repeat(WIDTH * HEIGHT),
+        }
+    }
+
+    fn fill(&mut self, x: usize, y: usize, width: usize, height: usize, color: [u8; 3]) {
+        for row in y..(y + height).min(HEIGHT) {
+            for column in x..(x + width).min(WIDTH) {
+                let offset = (row * WIDTH + column) * 3;
+                self.pixels[offset..offset + 3].copy_from_slice(&color);
+            }
+        }
+    }
+
+    fn text(&mut self, x: usize, y: usize, scale: usize, text: &str) {
+        for (position, c) in text.chars().enumerate() {
+            let glyph = glyph(c);
+            let left = x + position * 8 * scale;
+            for (row, bits) in glyph.iter().enumerate() {
+                for column in 0..8 {
+                    if bits & (1 << column) != 0 {
+                        let (px, py) = (left + column * scale, y + row * scale);
+                        self.fill(px, py, scale, scale, FOREGROUND);
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn glyph(c: char) -> [u8; 8] {
+    BASIC_FONTS
+        .get(c)
+        .or_else(|| LATIN_FONTS.get(c))
+        .or_else(|| GREEK_FONTS.get(c))
+        .or_else(|| BASIC_FONTS.get('?'))
+        .expect("the basic font has a question mark")
+}
+
+// Break `text` into lines of at most `width` characters, at spaces where
+// possible, keeping at most `max_lines` and marking any cut with an ellipsis.
+fn wrap(text: &str, width: usize, max_lines: usize) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        loop {
+            let used = line.chars().count();
+            let space = usize::from(used > 0);
+            if used + space + word.len() <= width {
+                if space > 0 {
+                    line.push(' ');
+                }
+                line.extend(word.drain(..));
+                break;
+            }
+            if used > 0 {
+                lines.push(std::mem::take(&mut line));
+            } else {
+                // The word is too long for any line, so it has to be split.
+                lines.push(word.drain(..width).collect());
+            }
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    if max_lines > 0 && lines.len() > max_lines {
+        lines.truncate(max_lines);
+        let last = &mut lines[max_lines - 1];
+        let kept: String = last.chars().take(width - 3).collect();
+        *last = kept + "...";
+    }
+
+    lines
+}
+
+/// A card showing `text`, as a PNG image.
+pub(crate) fn render(text: &str) -> Result<Vec<u8>, png::EncodingError> {
+    let mut canvas = Canvas::new();
+
+    let glyph = 8 * TEXT_SCALE;
+    let line_height = glyph + glyph / LEADING;
+    let footer_height = 8 * FOOTER_SCALE;
+    let text_height = HEIGHT - 2 * MARGIN - 2 * footer_height;
+    let lines = wrap(
+        text,
+        (WIDTH - 2 * MARGIN) / glyph,
+        (text_height + glyph / LEADING) / line_height,
+    );
+    for (number, line) in lines.iter().enumerate() {
+        canvas.text(MARGIN, MARGIN + number * line_height, TEXT_SCALE, line);
+    }
+
+    canvas.text(
+        MARGIN,
+        HEIGHT - MARGIN - footer_height,
+        FOOTER_SCALE,
+        FOOTER,
+    );
+    canvas.fill(0, HEIGHT - STRIPE, WIDTH, STRIPE, ACCENT);
+
+    let mut png = vec![];
+    let mut encoder = png::Encoder::new(&mut png, WIDTH as u32, HEIGHT as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&canvas.pixels)?;
+    writer.finish()?;
+
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_breaks_at_spaces() {
+        assert_eq!(
+            wrap("Have you checked the cache?", 12, 5),
+            ["Have you", "checked the", "cache?"]
+        );
+    }
+
+    #[test]
+    fn wrap_splits_long_words_and_truncates() {
+        assert_eq!(wrap("abcdefghij", 4, 5), ["abcd", "efgh", "ij"]);
+        assert_eq!(wrap("one two three four", 6, 2), ["one", "two..."]);
+    }
+}
@@ -0,0 +1,1219 @@
+//! Configuration for a whole service, from one file.
+//!
+//! The `twelve` module reads each setting from an environment variable of its
+//! own. As the settings pile up, a `Config` gathers them into one place: a
+//! TOML or YAML file, with every environment variable the `twelve` module and
+//! the `web` binary read still overriding the setting it names.
+//!
+//! ```no_run
+//! # use things_to_check::{config, view};
+//! # fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+//! use std::path::Path;
+//!
+//! let config = config::Config::load(Some(Path::new("things-to-check.toml")), [])?;
+//! let service = view::make_service_with(&config.options())?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A file for an instance with its own suggestions, branding, and a Slack
+//! app might read:
+//!
+//! ```toml
+//! source = "https://example.com/things.yml"
+//! refresh = 600
+//! url = "https://troubleshoot.example.com/"
+//! exclude = ["9", "general:12"]
+//! strictness = "fail"
+//! moderator_token = "s3kr1t"
+//!
+//! [rate_limit]
+//! burst = 20
+//! per_second = 0.5
+//!
+//! [theme]
+//! title = "Example Corp troubleshooting"
+//! accent = "#c0392b"
+//!
+//! [slack]
+//! signing_secret = "…"
+//! team_lists = { T0123ABCD = "ops" }
+//! ```
+//!
+//! Files ending in `.toml` are read as TOML, and files ending in `.yml` or
+//! `.yaml` as YAML, with the same keys. Each setting's key is listed with the
+//! environment variable that overrides it in `VARIABLES`, and its meaning is
+//! described where the `twelve` module, or the README, describes the variable.
+//! Lists and maps can be given as they are in the environment, as
+//! comma-separated strings such as `"T0123ABCD=ops, T0456EFGH=support"`, as
+//! well as as arrays and tables. Settings for integrations left out of the
+//! build, such as `[matrix]` without the `matrix` feature, are ignored.
+//!
+//! `Config::load` takes further overrides, such as the `web` binary's
+//! command-line flags, as pairs of keys and values. These take precedence over
+//! both the file and the environment. Invalid settings are reported with the
+//! key, variable, or file they came from.
+
+use figment::providers::{Format, Toml, Yaml};
+use figment::value::{Dict, Map, Value};
+use figment::{Figment, Metadata, Profile, Provider};
+use serde::{de, Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use thiserror::Error;
+use url::Url;
+
+use crate::access_log;
+use crate::crawlers;
+#[cfg(feature = "irc")]
+use crate::irc;
+use crate::limit;
+#[cfg(feature = "matrix")]
+use crate::matrix;
+use crate::schedule::{self, Cron};
+use crate::theme::Theme;
+use crate::twelve::{self, Source};
+use crate::view::{Markdown, Options, Strictness};
+
+/// Each environment variable that overrides a setting, and the setting's key.
+pub const VARIABLES: &[(&str, &str)] = &[
+    ("PORT", "port"),
+    ("THINGS_TO_CHECK_BIND", "bind"),
+    ("THINGS_TO_CHECK_SOCKET", "socket"),
+    ("THINGS_TO_CHECK_SOURCE", "source"),
+    ("THINGS_TO_CHECK_REFRESH", "refresh"),
+    ("THINGS_TO_CHECK_URL", "url"),
+    ("THINGS_TO_CHECK_EXCLUDE", "exclude"),
+    ("THINGS_TO_CHECK_MARKDOWN", "markdown"),
+    ("THINGS_TO_CHECK_STRICTNESS", "strictness"),
+    ("THINGS_TO_CHECK_TEMPLATES", "templates"),
+    ("THINGS_TO_CHECK_COUNTS", "counts"),
+    ("THINGS_TO_CHECK_CORS_ORIGINS", "cors_origins"),
+    ("THINGS_TO_CHECK_NOTIFY_URLS", "notify_urls"),
+    ("MODERATOR_TOKEN", "moderator_token"),
+    ("UNSEEN_COOKIE_KEY", "unseen_cookie_key"),
+    ("SHUFFLE_COOKIE_KEY", "shuffle_cookie_key"),
+    ("GITHUB_WEBHOOK_SECRET", "github_webhook_secret"),
+    ("THINGS_TO_CHECK_RATE_LIMIT_BURST", "rate_limit.burst"),
+    (
+        "THINGS_TO_CHECK_RATE_LIMIT_PER_SECOND",
+        "rate_limit.per_second",
+    ),
+    (
+        "THINGS_TO_CHECK_RATE_LIMIT_FORWARDED",
+        "rate_limit.forwarded",
+    ),
+    ("THINGS_TO_CHECK_ROBOTS_DISALLOW", "robots.disallow"),
+    ("THINGS_TO_CHECK_ROBOTS_SITEMAP", "robots.sitemap"),
+    ("THINGS_TO_CHECK_TITLE", "theme.title"),
+    ("THINGS_TO_CHECK_ACCENT", "theme.accent"),
+    ("THINGS_TO_CHECK_DARK_ACCENT", "theme.dark_accent"),
+    ("THINGS_TO_CHECK_LOGO_URL", "theme.logo"),
+    ("THINGS_TO_CHECK_ICON_URL", "theme.icon"),
+    ("THINGS_TO_CHECK_FOOTER", "theme.footer"),
+    ("THINGS_TO_CHECK_ACCESS_LOG", "access_log.format"),
+    ("THINGS_TO_CHECK_ACCESS_LOG_FILE", "access_log.file"),
+    ("THINGS_TO_CHECK_SCHEDULE", "schedule.cron"),
+    ("THINGS_TO_CHECK_WEBHOOKS", "schedule.webhooks"),
+    ("SLACK_SIGNING_SECRET", "slack.signing_secret"),
+    ("SLACK_RESPONSE_TYPE", "slack.response_type"),
+    ("SLACK_TEAM_LISTS", "slack.team_lists"),
+    ("SLACK_BOT_TOKEN", "slack.bot_token"),
+    ("SLACK_CLIENT_ID", "slack.client_id"),
+    ("SLACK_CLIENT_SECRET", "slack.client_secret"),
+    ("SLACK_TOKENS_PATH", "slack.tokens_path"),
+    ("DISCORD_PUBLIC_KEY", "discord.public_key"),
+    ("DISCORD_WEBHOOK_URL", "discord.webhook_url"),
+    ("TEAMS_SECURITY_TOKEN", "teams.security_token"),
+    ("MATTERMOST_TOKEN", "mattermost.token"),
+    ("MATTERMOST_RESPONSE_TYPE", "mattermost.response_type"),
+    ("TELEGRAM_SECRET_TOKEN", "telegram.secret_token"),
+    ("ZULIP_TOKEN", "zulip.token"),
+    ("ROCKETCHAT_TOKEN", "rocketchat.token"),
+    ("WEBEX_BOT_TOKEN", "webex.bot_token"),
+    ("WEBEX_WEBHOOK_SECRET", "webex.webhook_secret"),
+    ("MATRIX_HOMESERVER", "matrix.homeserver"),
+    ("MATRIX_USER", "matrix.user"),
+    ("MATRIX_PASSWORD", "matrix.password"),
+    ("MATRIX_ROOMS", "matrix.rooms"),
+    ("IRC_SERVERS", "irc.servers"),
+    ("IRC_NICK", "irc.nick"),
+    ("IRC_PASSWORD", "irc.password"),
+    ("IRC_CHANNELS", "irc.channels"),
+    ("TWITCH_OAUTH_TOKEN", "twitch.oauth_token"),
+    ("TWITCH_NICK", "twitch.nick"),
+    ("TWITCH_CHANNELS", "twitch.channels"),
+    ("TWITCH_COOLDOWN", "twitch.cooldown"),
+    ("THINGS_TO_CHECK_TLS_CERTIFICATE", "tls.certificate"),
+    ("THINGS_TO_CHECK_TLS_KEY", "tls.key"),
+    ("OTEL_EXPORTER_OTLP_ENDPOINT", "otel.endpoint"),
+];
+
+/// Errors that can arise loading a configuration.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// Indicates that the file couldn't be read, or that a setting, from the
+    /// file, the environment, or the overrides, was invalid.
+    #[error("Invalid configuration: {0}")]
+    Invalid(Box<figment::Error>),
+    /// Indicates that the file's name didn't say whether it's TOML or YAML.
+    #[error("Configuration files must end in .toml, .yml, or .yaml, not {0:?}")]
+    UnknownFormat(PathBuf),
+    /// Indicates that a setting that must be given along with another wasn't.
+    #[error("{} must be set along with {}", described(name), described(with))]
+    Missing {
+        name: &'static str,
+        with: &'static str,
+    },
+    /// Indicates that two settings that exclude one another were both given.
+    #[error("{} can't be set along with {}", described(name), described(with))]
+    Conflict {
+        name: &'static str,
+        with: &'static str,
+    },
+}
+
+impl From<figment::Error> for Error {
+    fn from(err: figment::Error) -> Self {
+        Error::Invalid(Box::new(err))
+    }
+}
+
+// A setting's key, and the environment variable that overrides it.
+fn described(key: &str) -> String {
+    match variable(key) {
+        Some(variable) => format!("{} ({})", key, variable),
+        None => key.to_owned(),
+    }
+}
+
+// The environment variable that overrides the setting `key`.
+fn variable(key: &str) -> Option<&'static str> {
+    VARIABLES
+        .iter()
+        .find(|(_, named)| *named == key)
+        .map(|(variable, _)| *variable)
+}
+
+/// Every setting for a service.
+///
+/// Settings left out of the file, and not overridden, are left unset, or
+/// empty, and the service behaves as it does when their environment variables
+/// are unset.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// The port to listen on, on every interface.
+    #[serde(deserialize_with = "parsed")]
+    pub port: Option<u16>,
+    /// The address to listen on, such as `127.0.0.1:8080`, instead.
+    pub bind: Option<String>,
+    /// The Unix socket to listen on, instead.
+    pub socket: Option<PathBuf>,
+    /// Where the suggestions come from, as for `twelve::source`.
+    #[serde(deserialize_with = "parsed")]
+    pub source: Option<Source>,
+    /// How often to check a URL source for changes.
+    #[serde(deserialize_with = "seconds")]
+    pub refresh: Option<Duration>,
+    /// The URL people reach the index page at, which integrations link to.
+    pub url: Option<Url>,
+    /// Suggestions to hide, as for `view::Options::exclude`.
+    #[serde(deserialize_with = "list")]
+    pub exclude: Vec<String>,
+    /// The Markdown extensions suggestions may use.
+    #[serde(deserialize_with = "markdown")]
+    pub markdown: Markdown,
+    /// How to treat suggestions with problems: `warn`, or `fail`.
+    #[serde(deserialize_with = "strictness")]
+    pub strictness: Strictness,
+    /// The directory of templates overriding the pages.
+    pub templates: Option<PathBuf>,
+    /// The file to keep counts of suggestions served in.
+    pub counts: Option<PathBuf>,
+    /// The origins allowed to call the API from a browser.
+    #[serde(deserialize_with = "list")]
+    pub cors_origins: Vec<String>,
+    /// Webhooks to post suggestions to as JSON.
+    #[serde(deserialize_with = "list")]
+    pub notify_urls: Vec<Url>,
+    /// The token moderators authenticate with. Moderation is off without one.
+    pub moderator_token: Option<String>,
+    /// The key to sign the cookie tracking suggestions seen with.
+    pub unseen_cookie_key: Option<String>,
+    /// The key to sign the cookie holding a shuffled order with.
+    pub shuffle_cookie_key: Option<String>,
+    /// The secret GitHub signs push webhooks with, for GitHub sources.
+    pub github_webhook_secret: Option<String>,
+    /// How the pages are branded.
+    pub theme: Theme,
+    pub rate_limit: RateLimit,
+    pub robots: Robots,
+    pub access_log: AccessLog,
+    pub schedule: Schedule,
+    pub slack: Slack,
+    pub discord: Discord,
+    pub teams: Teams,
+    pub mattermost: Mattermost,
+    pub telegram: Telegram,
+    pub zulip: Zulip,
+    pub rocketchat: RocketChat,
+    pub webex: Webex,
+    #[cfg(feature = "matrix")]
+    pub matrix: Matrix,
+    #[cfg(feature = "irc")]
+    pub irc: Irc,
+    #[cfg(feature = "irc")]
+    pub twitch: Twitch,
+    #[cfg(feature = "tls")]
+    pub tls: Tls,
+    #[cfg(feature = "otel")]
+    pub otel: Otel,
+}
+
+/// The `[rate_limit]` section. Clients aren't limited unless `burst` and
+/// `per_second` are set; see `limit::RateLimit`.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct RateLimit {
+    #[serde(deserialize_with = "parsed")]
+    pub burst: Option<u32>,
+    #[serde(deserialize_with = "rate")]
+    pub per_second: Option<f64>,
+    #[serde(deserialize_with = "parsed")]
+    pub forwarded: Option<bool>,
+}
+
+/// The `[robots]` section. Unset settings are as for `crawlers::Robots`'s
+/// defaults.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Robots {
+    #[serde(deserialize_with = "some_list")]
+    pub disallow: Option<Vec<String>>,
+    #[serde(deserialize_with = "parsed")]
+    pub sitemap: Option<bool>,
+}
+
+/// The `[access_log]` section.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct AccessLog {
+    /// The format to log requests in. Requests aren't logged without one.
+    #[serde(deserialize_with = "parsed")]
+    pub format: Option<access_log::Format>,
+    /// The file to log to, rather than stdout.
+    pub file: Option<PathBuf>,
+}
+
+/// The `[schedule]` section.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Schedule {
+    /// When to post, or `twelve::DEFAULT_SCHEDULE` if unset.
+    #[serde(deserialize_with = "parsed")]
+    pub cron: Option<Cron>,
+    /// The chat webhooks to post to.
+    #[serde(deserialize_with = "list")]
+    pub webhooks: Vec<Url>,
+}
+
+/// The `[slack]` section.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Slack {
+    pub signing_secret: Option<String>,
+    pub response_type: Option<String>,
+    #[serde(deserialize_with = "pairs")]
+    pub team_lists: BTreeMap<String, String>,
+    pub bot_token: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    pub tokens_path: Option<PathBuf>,
+}
+
+/// The `[discord]` section.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Discord {
+    pub public_key: Option<String>,
+    pub webhook_url: Option<Url>,
+}
+
+/// The `[teams]` section.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Teams {
+    pub security_token: Option<String>,
+}
+
+/// The `[mattermost]` section.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Mattermost {
+    pub token: Option<String>,
+    pub response_type: Option<String>,
+}
+
+/// The `[telegram]` section.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Telegram {
+    pub secret_token: Option<String>,
+}
+
+/// The `[zulip]` section.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Zulip {
+    pub token: Option<String>,
+}
+
+/// The `[rocketchat]` section.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct RocketChat {
+    pub token: Option<String>,
+}
+
+/// The `[webex]` section.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Webex {
+    pub bot_token: Option<String>,
+    pub webhook_secret: Option<String>,
+}
+
+/// The `[matrix]` section.
+///
+/// This is only available with the `matrix` feature.
+#[cfg(feature = "matrix")]
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Matrix {
+    pub homeserver: Option<Url>,
+    pub user: Option<String>,
+    pub password: Option<String>,
+    #[serde(deserialize_with = "list")]
+    pub rooms: Vec<String>,
+}
+
+/// The `[irc]` section.
+///
+/// This is only available with the `irc` feature.
+#[cfg(feature = "irc")]
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Irc {
+    #[serde(deserialize_with = "list")]
+    pub servers: Vec<IrcServer>,
+    pub nick: Option<String>,
+    pub password: Option<String>,
+    #[serde(deserialize_with = "list")]
+    pub channels: Vec<String>,
+}
+
+/// An IRC server's host name, and its port, if it's not the usual one.
+///
+/// This is only available with the `irc` feature.
+#[cfg(feature = "irc")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IrcServer {
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+/// Indicates that an IRC server wasn't a host name with an optional port.
+#[cfg(feature = "irc")]
+#[derive(Error, Debug)]
+#[error("IRC servers must be host names, with optional ports, not {0:?}")]
+pub struct InvalidServer(String);
+
+#[cfg(feature = "irc")]
+impl FromStr for IrcServer {
+    type Err = InvalidServer;
+
+    fn from_str(server: &str) -> Result<Self, Self::Err> {
+        match server.rsplit_once(':') {
+            Some((host, port)) => match port.parse() {
+                Ok(port) if !host.is_empty() => Ok(IrcServer {
+                    host: host.to_owned(),
+                    port: Some(port),
+                }),
+                _ => Err(InvalidServer(server.to_owned())),
+            },
+            None => Ok(IrcServer {
+                host: server.to_owned(),
+                port: None,
+            }),
+        }
+    }
+}
+
+/// The `[twitch]` section.
+///
+/// This is only available with the `irc` feature.
+#[cfg(feature = "irc")]
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Twitch {
+    pub oauth_token: Option<String>,
+    pub nick: Option<String>,
+    #[serde(deserialize_with = "list")]
+    pub channels: Vec<String>,
+    #[serde(deserialize_with = "seconds")]
+    pub cooldown: Option<Duration>,
+}
+
+/// The `[tls]` section.
+///
+/// This is only available with the `tls` feature.
+#[cfg(feature = "tls")]
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Tls {
+    pub certificate: Option<PathBuf>,
+    pub key: Option<PathBuf>,
+}
+
+/// The `[otel]` section.
+///
+/// This is only available with the `otel` feature.
+#[cfg(feature = "otel")]
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Otel {
+    pub endpoint: Option<Url>,
+}
+
+impl Config {
+    /// Load the settings in `file`, if one is given, overridden by those in
+    /// the environment, overridden in turn by `overrides`, pairs of keys and
+    /// values such as `("bind", "127.0.0.1:8080")`.
+    ///
+    /// An override of `bind` or `socket` replaces the other, as well, if it's
+    /// set in the file or the environment: the service listens where the
+    /// override says.
+    pub fn load<'a>(
+        file: Option<&Path>,
+        overrides: impl IntoIterator<Item = (&'a str, String)>,
+    ) -> Result<Self, Error> {
+        let environment = environment(|name| env::var(name).ok());
+        let overrides = Overrides::named("override", |keys| keys.join(".")).with(overrides);
+
+        Self::from_sources(file, environment, overrides)
+    }
+
+    fn from_sources(
+        file: Option<&Path>,
+        environment: Overrides,
+        overrides: Overrides,
+    ) -> Result<Self, Error> {
+        let replaced = match (overrides.has("bind"), overrides.has("socket")) {
+            (true, false) => Some("socket"),
+            (false, true) => Some("bind"),
+            _ => None,
+        };
+
+        let config: Config = file_figment(file, replaced)?
+            .merge(Without(environment, replaced))
+            .merge(overrides)
+            .extract()?;
+        config.check()?;
+        Ok(config)
+    }
+
+    // Check for settings that need, or rule out, others.
+    fn check(&self) -> Result<(), Error> {
+        let missing = |name, with| Err(Error::Missing { name, with });
+
+        if self.bind.is_some() && self.socket.is_some() {
+            return Err(Error::Conflict {
+                name: "socket",
+                with: "bind",
+            });
+        }
+        match (self.rate_limit.burst, self.rate_limit.per_second) {
+            (Some(_), None) => return missing("rate_limit.per_second", "rate_limit.burst"),
+            (None, Some(_)) => return missing("rate_limit.burst", "rate_limit.per_second"),
+            _ => {}
+        }
+        if self.access_log.file.is_some() && self.access_log.format.is_none() {
+            return missing("access_log.format", "access_log.file");
+        }
+        #[cfg(feature = "matrix")]
+        if self.matrix.homeserver.is_some() {
+            if self.matrix.user.is_none() {
+                return missing("matrix.user", "matrix.homeserver");
+            }
+            if self.matrix.password.is_none() {
+                return missing("matrix.password", "matrix.homeserver");
+            }
+        }
+        #[cfg(feature = "irc")]
+        if self.twitch.oauth_token.is_some() && self.twitch.nick.is_none() {
+            return missing("twitch.nick", "twitch.oauth_token");
+        }
+        #[cfg(feature = "tls")]
+        match (&self.tls.certificate, &self.tls.key) {
+            (Some(_), None) => return missing("tls.key", "tls.certificate"),
+            (None, Some(_)) => return missing("tls.certificate", "tls.key"),
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// The options to make a service with, from the settings for suggestions,
+    /// the pages, rate limits, crawlers, the posting schedule, and, with the
+    /// `matrix` and `irc`
+    /// features, the chat bots. As for the `twelve` module, scheduled posts
+    /// and the bots link to suggestions under `url`, if it's set.
+    pub fn options(&self) -> Options {
+        let schedule =
+            (!self.schedule.webhooks.is_empty() || !self.notify_urls.is_empty()).then(|| {
+                let when = self.schedule.cron.clone().unwrap_or_else(|| {
+                    twelve::DEFAULT_SCHEDULE
+                        .parse()
+                        .expect("the default schedule is valid")
+                });
+                let schedule = schedule::Schedule::new(when, self.schedule.webhooks.clone())
+                    .notifying(self.notify_urls.clone());
+                match &self.url {
+                    Some(index) => schedule.linking_to(index.clone()),
+                    None => schedule,
+                }
+            });
+
+        Options {
+            strictness: self.strictness,
+            exclude: self.exclude.clone(),
+            rate_limit: self.rate_limit(),
+            robots: self.robots(),
+            theme: self.theme.clone(),
+            markdown: self.markdown,
+            schedule,
+            #[cfg(feature = "matrix")]
+            matrix: self.matrix(),
+            #[cfg(feature = "irc")]
+            irc: self.irc(),
+        }
+    }
+
+    fn rate_limit(&self) -> Option<limit::RateLimit> {
+        let settings = &self.rate_limit;
+        Some(limit::RateLimit {
+            burst: settings.burst?,
+            // Checked when the configuration is loaded.
+            per_second: settings.per_second.unwrap_or_default(),
+            forwarded: settings.forwarded.unwrap_or_default(),
+        })
+    }
+
+    fn robots(&self) -> crawlers::Robots {
+        let settings = &self.robots;
+        let robots = crawlers::Robots::default();
+        crawlers::Robots {
+            disallow: settings.disallow.clone().unwrap_or(robots.disallow),
+            sitemap: settings.sitemap.unwrap_or(robots.sitemap),
+        }
+    }
+
+    #[cfg(feature = "matrix")]
+    fn matrix(&self) -> Option<matrix::Matrix> {
+        let settings = &self.matrix;
+        let homeserver = settings.homeserver.clone()?;
+        // Checked when the configuration is loaded.
+        let user = settings.user.clone().unwrap_or_default();
+        let password = settings.password.clone().unwrap_or_default();
+
+        let matrix =
+            matrix::Matrix::new(homeserver, user, password).joining(settings.rooms.clone());
+        Some(match &self.url {
+            Some(index) => matrix.linking_to(index.clone()),
+            None => matrix,
+        })
+    }
+
+    #[cfg(feature = "irc")]
+    fn irc(&self) -> Vec<irc::Irc> {
+        let settings = &self.irc;
+        let nickname = settings.nick.as_deref().unwrap_or(twelve::DEFAULT_IRC_NICK);
+        let servers = settings.servers.iter().map(|server| {
+            let irc = irc::Irc::new(&server.host, nickname);
+            let irc = match server.port {
+                Some(port) => irc.on_port(port),
+                None => irc,
+            };
+            let irc = irc.joining(settings.channels.clone());
+            match &settings.password {
+                Some(password) => irc.with_password(password),
+                None => irc,
+            }
+        });
+
+        let twitch = self.twitch.oauth_token.as_ref().map(|token| {
+            // Checked when the configuration is loaded.
+            let nickname = self.twitch.nick.clone().unwrap_or_default();
+            let channels = self
+                .twitch
+                .channels
+                .iter()
+                .map(|channel| format!("#{}", channel.trim_start_matches('#').to_lowercase()))
+                .collect();
+            irc::Irc::twitch(nickname, token)
+                .joining(channels)
+                .cooling_down(
+                    self.twitch
+                        .cooldown
+                        .unwrap_or(twelve::DEFAULT_TWITCH_COOLDOWN),
+                )
+        });
+
+        servers
+            .chain(twitch)
+            .map(|irc| match &self.url {
+                Some(index) => irc.linking_to(index.clone()),
+                None => irc,
+            })
+            .collect()
+    }
+}
+
+// The settings in the environment, with each variable's value looked up by
+// `lookup`.
+fn environment(lookup: impl Fn(&str) -> Option<String>) -> Overrides {
+    Overrides::named("environment variable", |keys| {
+        variable(&keys.join("."))
+            .map(str::to_owned)
+            .unwrap_or_else(|| keys.join("."))
+    })
+    .with(
+        VARIABLES
+            .iter()
+            .filter_map(|(name, key)| Some((*key, lookup(name)?))),
+    )
+}
+
+// The settings in `file`, read as TOML or YAML by its extension, or none,
+// without the setting `replaced`.
+fn file_figment(file: Option<&Path>, replaced: Option<&'static str>) -> Result<Figment, Error> {
+    let file = match file {
+        Some(file) => file,
+        None => return Ok(Figment::new()),
+    };
+
+    match file.extension().and_then(|extension| extension.to_str()) {
+        Some("toml") => Ok(Figment::from(Without(Toml::file_exact(file), replaced))),
+        Some("yml" | "yaml") => Ok(Figment::from(Without(Yaml::file_exact(file), replaced))),
+        _ => Err(Error::UnknownFormat(file.to_owned())),
+    }
+}
+
+// The settings from a provider, without the top-level setting named, if any,
+// as it's replaced by an override.
+struct Without<P>(P, Option<&'static str>);
+
+impl<P: Provider> Provider for Without<P> {
+    fn metadata(&self) -> Metadata {
+        self.0.metadata()
+    }
+
+    fn data(&self) -> Result<Map<Profile, Dict>, figment::Error> {
+        let Without(provider, replaced) = self;
+        let mut data = provider.data()?;
+        if let Some(replaced) = replaced {
+            for settings in data.values_mut() {
+                settings.remove(*replaced);
+            }
+        }
+
+        Ok(data)
+    }
+}
+
+// Settings given as strings, by key, from outside the file.
+struct Overrides {
+    name: &'static str,
+    describe: fn(&[&str]) -> String,
+    settings: Vec<(String, String)>,
+}
+
+impl Overrides {
+    // Overrides from the source called `name`, whose settings' keys are
+    // described, in errors, by `describe`.
+    fn named(name: &'static str, describe: fn(&[&str]) -> String) -> Self {
+        Overrides {
+            name,
+            describe,
+            settings: vec![],
+        }
+    }
+
+    fn with<'a>(self, settings: impl IntoIterator<Item = (&'a str, String)>) -> Self {
+        Overrides {
+            settings: settings
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+            ..self
+        }
+    }
+}
+
+impl Overrides {
+    fn has(&self, key: &str) -> bool {
+        self.settings.iter().any(|(named, _)| named == key)
+    }
+}
+
+impl Provider for Overrides {
+    fn metadata(&self) -> Metadata {
+        let describe = self.describe;
+        Metadata::named(self.name).interpolater(move |_, keys| describe(keys))
+    }
+
+    fn data(&self) -> Result<Map<Profile, Dict>, figment::Error> {
+        let mut settings = Dict::new();
+        for (key, value) in &self.settings {
+            let value = Value::from(value.clone());
+            match key.split_once('.') {
+                Some((section, key)) => {
+                    let section = settings
+                        .entry(section.to_owned())
+                        .or_insert_with(|| Value::from(Dict::new()));
+                    if let Value::Dict(_, section) = section {
+                        section.insert(key.to_owned(), value);
+                    }
+                }
+                None => {
+                    settings.insert(key.clone(), value);
+                }
+            }
+        }
+
+        Ok(Profile::Default.collect(settings))
+    }
+}
+
+// A setting read from the environment, which is always a string, or from a
+// file, which may hold a number, a flag, or a list, instead.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Given {
+    Number(u64),
+    Fraction(f64),
+    Flag(bool),
+    Text(String),
+    List(Vec<String>),
+    Table(BTreeMap<String, String>),
+}
+
+impl fmt::Display for Given {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Given::Number(number) => write!(f, "{}", number),
+            Given::Fraction(number) => write!(f, "{}", number),
+            Given::Flag(flag) => write!(f, "{}", flag),
+            Given::Text(text) => write!(f, "{:?}", text),
+            Given::List(_) => f.write_str("a list"),
+            Given::Table(_) => f.write_str("a table"),
+        }
+    }
+}
+
+impl Given {
+    // The entries of a list, given as a list or as a comma-separated string.
+    fn entries(self) -> Option<Vec<String>> {
+        match self {
+            Given::Text(text) => Some(
+                text.split(',')
+                    .map(str::trim)
+                    .filter(|entry| !entry.is_empty())
+                    .map(str::to_owned)
+                    .collect(),
+            ),
+            Given::List(entries) => Some(entries),
+            Given::Number(_) | Given::Fraction(_) | Given::Flag(_) | Given::Table(_) => None,
+        }
+    }
+}
+
+// A value parsed from a string, or from a number or flag given as one.
+fn parsed<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    let given = match Given::deserialize(deserializer)? {
+        Given::Number(number) => number.to_string(),
+        Given::Fraction(number) => number.to_string(),
+        Given::Flag(flag) => flag.to_string(),
+        Given::Text(text) => text,
+        other => {
+            return Err(de::Error::custom(format!(
+                "expected a value, not {}",
+                other
+            )))
+        }
+    };
+    given.parse().map(Some).map_err(de::Error::custom)
+}
+
+// A number of times per second, which must be positive.
+fn rate<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
+    match parsed::<D, f64>(deserializer)? {
+        Some(rate) if rate <= 0.0 || !rate.is_finite() => Err(de::Error::custom(format!(
+            "expected a positive number, not {}",
+            rate
+        ))),
+        rate => Ok(rate),
+    }
+}
+
+// A whole number of seconds.
+fn seconds<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+    Ok(parsed::<D, u64>(deserializer)?.map(Duration::from_secs))
+}
+
+// A list of values, each parsed from a string.
+fn list<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    let given = Given::deserialize(deserializer)?;
+    let description = given.to_string();
+    given
+        .entries()
+        .ok_or_else(|| de::Error::custom(format!("expected a list, not {}", description)))?
+        .iter()
+        .map(|entry| entry.parse().map_err(de::Error::custom))
+        .collect()
+}
+
+// A list, as for `list`, that's set even if it's empty.
+fn some_list<'de, D, T>(deserializer: D) -> Result<Option<Vec<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    list(deserializer).map(Some)
+}
+
+// Keys paired with values, given as a table or as a comma-separated string of
+// `key=value` entries.
+fn pairs<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BTreeMap<String, String>, D::Error> {
+    let entries = match Given::deserialize(deserializer)? {
+        Given::Table(table) => return Ok(table),
+        given => {
+            let description = given.to_string();
+            given.entries().ok_or_else(|| {
+                de::Error::custom(format!("expected key=value pairs, not {}", description))
+            })?
+        }
+    };
+
+    entries
+        .into_iter()
+        .map(|entry| match entry.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() && !value.trim().is_empty() => {
+                Ok((key.trim().to_owned(), value.trim().to_owned()))
+            }
+            _ => Err(de::Error::custom(format!(
+                "expected key=value pairs, not {:?}",
+                entry
+            ))),
+        })
+        .collect()
+}
+
+// The Markdown extensions to allow, as for `twelve::markdown`: a list of
+// `strikethrough`, `tables`, `footnotes`, and `smart-punctuation`.
+fn markdown<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Markdown, D::Error> {
+    let mut markdown = Markdown {
+        strikethrough: false,
+        tables: false,
+        footnotes: false,
+        smart_punctuation: false,
+    };
+    for extension in list::<D, String>(deserializer)? {
+        match extension.as_str() {
+            "strikethrough" => markdown.strikethrough = true,
+            "tables" => markdown.tables = true,
+            "footnotes" => markdown.footnotes = true,
+            "smart-punctuation" => markdown.smart_punctuation = true,
+            _ => {
+                return Err(de::Error::custom(format!(
+                    "not a Markdown extension: {:?}",
+                    extension
+                )))
+            }
+        }
+    }
+
+    Ok(markdown)
+}
+
+// How to treat suggestions with problems: `warn`, or `fail`.
+fn strictness<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Strictness, D::Error> {
+    match parsed::<D, String>(deserializer)?.as_deref() {
+        Some("warn") => Ok(Strictness::Warn),
+        Some("fail") => Ok(Strictness::Fail),
+        other => Err(de::Error::custom(format!(
+            "expected warn or fail, not {:?}",
+            other.unwrap_or_default()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::process;
+
+    use super::*;
+
+    // A figment reading `contents` as the file `name`, overridden by
+    // `environment`, which stands in for the process's environment.
+    fn load(name: &str, contents: &str, variables: &[(&str, &str)]) -> Result<Config, Error> {
+        overriding(name, contents, variables, &[])
+    }
+
+    // As for `load`, with the settings in `overrides` overriding both.
+    fn overriding(
+        name: &str,
+        contents: &str,
+        variables: &[(&str, &str)],
+        overrides: &[(&str, &str)],
+    ) -> Result<Config, Error> {
+        let path = env::temp_dir().join(format!("things-to-check-{}-{}", process::id(), name));
+        fs::write(&path, contents).unwrap();
+        let environment = environment(|name| {
+            variables
+                .iter()
+                .find(|(variable, _)| *variable == name)
+                .map(|(_, value)| value.to_string())
+        });
+        let overrides = Overrides::named("override", |keys| keys.join(".")).with(
+            overrides
+                .iter()
+                .map(|(key, value)| (*key, value.to_string())),
+        );
+        let config = Config::from_sources(Some(&path), environment, overrides);
+        fs::remove_file(&path).unwrap();
+        config
+    }
+
+    #[test]
+    fn files_are_read_as_toml_or_yaml() {
+        let toml = load(
+            "config.toml",
+            r##"
+                port = 8080
+                source = "github:example/things/things.yml"
+                exclude = ["dns", "ntp"]
+                markdown = ["tables"]
+
+                [theme]
+                title = "Example Corp"
+                accent = "#c0392b"
+
+                [slack]
+                signing_secret = "s3kr1t"
+                team_lists = { T0123ABCD = "ops" }
+            "##,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(toml.port, Some(8080));
+        assert!(matches!(toml.source, Some(Source::GitHub(_))));
+        assert_eq!(toml.exclude, ["dns", "ntp"]);
+        assert!(toml.markdown.tables && !toml.markdown.footnotes);
+        assert_eq!(toml.theme.title.as_deref(), Some("Example Corp"));
+        assert_eq!(toml.slack.signing_secret.as_deref(), Some("s3kr1t"));
+        assert_eq!(toml.slack.team_lists["T0123ABCD"], "ops");
+        // Unset, rather than an empty list of extensions.
+        assert!(Config::default().markdown.tables);
+
+        let yaml = load(
+            "config.yaml",
+            "refresh: 60\ntheme:\n  footer: \"Ask in #ops\"\n",
+            &[],
+        )
+        .unwrap();
+        assert_eq!(yaml.refresh, Some(Duration::from_secs(60)));
+        assert_eq!(yaml.theme.footer.as_deref(), Some("Ask in #ops"));
+
+        assert!(matches!(
+            load("config.ini", "", &[]),
+            Err(Error::UnknownFormat(_))
+        ));
+    }
+
+    #[test]
+    fn the_environment_overrides_the_file() {
+        let config = load(
+            "override.toml",
+            "port = 8080\nexclude = [\"dns\"]\n[theme]\ntitle = \"File\"\n",
+            &[
+                ("PORT", "9090"),
+                ("THINGS_TO_CHECK_EXCLUDE", "ntp, , tls"),
+                ("THINGS_TO_CHECK_TITLE", "Environment"),
+                ("SLACK_TEAM_LISTS", "T0123ABCD=ops, T0456EFGH=support"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(config.port, Some(9090));
+        assert_eq!(config.exclude, ["ntp", "tls"]);
+        assert_eq!(config.theme.title.as_deref(), Some("Environment"));
+        assert_eq!(config.slack.team_lists.len(), 2);
+    }
+
+    #[test]
+    fn invalid_settings_are_reported() {
+        let err = load("color.toml", "[theme]\naccent = \"</style>\"\n", &[])
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("accent"), "{}", err);
+        let err = load("empty.toml", "", &[("THINGS_TO_CHECK_ACCENT", "red;")])
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string().contains("THINGS_TO_CHECK_ACCENT"),
+            "{}",
+            err
+        );
+
+        assert!(load("list.toml", "notify_urls = [\"not a url\"]\n", &[]).is_err());
+        assert!(load("cron.toml", "[schedule]\ncron = \"daily\"\n", &[]).is_err());
+
+        let err = load(
+            "both.toml",
+            "bind = \"127.0.0.1:8080\"\nsocket = \"app.sock\"\n",
+            &[],
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "socket (THINGS_TO_CHECK_SOCKET) can't be set along with bind (THINGS_TO_CHECK_BIND)"
+        );
+    }
+
+    #[test]
+    fn overrides_replace_other_addresses() {
+        let config = overriding(
+            "socket.toml",
+            "bind = \"127.0.0.1:8080\"\n",
+            &[],
+            &[("socket", "app.sock")],
+        )
+        .unwrap();
+        assert_eq!(config.bind, None);
+        assert_eq!(config.socket, Some(PathBuf::from("app.sock")));
+
+        let config = overriding(
+            "bind.toml",
+            "",
+            &[("THINGS_TO_CHECK_SOCKET", "app.sock")],
+            &[("bind", "127.0.0.1:8080")],
+        )
+        .unwrap();
+        assert_eq!(config.bind.as_deref(), Some("127.0.0.1:8080"));
+        assert_eq!(config.socket, None);
+
+        assert!(matches!(
+            overriding(
+                "both.toml",
+                "",
+                &[],
+                &[("bind", "127.0.0.1:8080"), ("socket", "app.sock")],
+            ),
+            Err(Error::Conflict { .. })
+        ));
+    }
+
+    #[test]
+    fn options_come_from_the_settings() {
+        let config = load(
+            "options.toml",
+            "url = \"https://troubleshoot.example.com/\"\nnotify_urls = \"https://example.com/hook\"\n",
+            &[],
+        )
+        .unwrap();
+        let options = config.options();
+        assert!(options.schedule.is_some());
+
+        let options = Config::default().options();
+        assert!(options.schedule.is_none());
+        assert_eq!(options.rate_limit, None);
+        assert_eq!(options.robots, crawlers::Robots::default());
+    }
+
+    #[test]
+    fn limits_and_crawlers_come_from_the_settings() {
+        let config = load(
+            "limits.toml",
+            "strictness = \"fail\"\n[rate_limit]\nburst = 20\nper_second = 0.5\n[robots]\nsitemap = false\n",
+            &[
+                ("THINGS_TO_CHECK_RATE_LIMIT_FORWARDED", "true"),
+                ("THINGS_TO_CHECK_ROBOTS_DISALLOW", ""),
+            ],
+        )
+        .unwrap();
+        let options = config.options();
+        assert_eq!(options.strictness, Strictness::Fail);
+        assert_eq!(
+            options.rate_limit,
+            Some(limit::RateLimit {
+                burst: 20,
+                per_second: 0.5,
+                forwarded: true,
+            })
+        );
+        assert_eq!(
+            options.robots,
+            crawlers::Robots {
+                disallow: vec![],
+                sitemap: false,
+            }
+        );
+
+        let config = load(
+            "limits.yml",
+            "",
+            &[
+                ("THINGS_TO_CHECK_STRICTNESS", "warn"),
+                ("THINGS_TO_CHECK_RATE_LIMIT_BURST", "5"),
+                ("THINGS_TO_CHECK_RATE_LIMIT_PER_SECOND", "2"),
+            ],
+        )
+        .unwrap();
+        let options = config.options();
+        assert_eq!(options.strictness, Strictness::Warn);
+        assert_eq!(options.rate_limit.map(|limit| limit.per_second), Some(2.0));
+
+        let err = load("burst.toml", "[rate_limit]\nburst = 20\n", &[])
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "rate_limit.per_second (THINGS_TO_CHECK_RATE_LIMIT_PER_SECOND) must be set along with rate_limit.burst (THINGS_TO_CHECK_RATE_LIMIT_BURST)"
+        );
+        assert!(load(
+            "rate.toml",
+            "[rate_limit]\nburst = 20\nper_second = -1\n",
+            &[]
+        )
+        .is_err());
+        assert!(load("strict.toml", "strictness = \"very\"\n", &[]).is_err());
+    }
+}
@@ -0,0 +1,270 @@
+//! Count how often each suggestion is served.
+//!
+//! An App with a `Counts` in its app data counts each suggestion shown on the
+//! HTML pages, under `/` and `/check/…`, and in Slack messages, separately,
+//! by list and by the suggestion's `item` (its `id`, if it has one, or else its
+//! number), so that counts follow suggestions when others are added around
+//! them.
+//!
+//! Counts are kept in memory, or in a JSON file that carries them across
+//! restarts. Counting doesn't touch the file; call `Counts::save`, or spawn
+//! `Counts::every` on the actix runtime, to write it.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # use things_to_check::{counts, view};
+//! # #[actix_web::main]
+//! # async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+//! use actix_web::{rt, web, App, HttpServer};
+//! use std::time::Duration;
+//!
+//! let service = view::make_service()?;
+//! let counts = counts::Counts::open("counts.json")?;
+//! rt::spawn(counts.clone().every(Duration::from_secs(60)));
+//!
+//! let app_factory = move ||
+//!     App::new()
+//!         .app_data(web::Data::new(counts.clone()))
+//!         .configure(|cfg| service(cfg));
+//!
+//! HttpServer::new(app_factory);
+//! # Ok(())
+//! # }
+//! ```
+
+use actix_web::rt::time;
+use actix_web::{web, HttpRequest};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// Where a suggestion was served.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Channel {
+    /// On the HTML pages.
+    Web,
+    /// In a Slack message.
+    Slack,
+}
+
+/// How many times a suggestion has been served, by channel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Count {
+    pub web: u64,
+    pub slack: u64,
+}
+
+impl Count {
+    /// The number of times the suggestion has been served, everywhere.
+    pub fn total(&self) -> u64 {
+        self.web + self.slack
+    }
+}
+
+// Counts by list name, then by item.
+type Lists = BTreeMap<String, BTreeMap<String, Count>>;
+
+/// The number of times each suggestion has been served.
+///
+/// Counts are cheap to clone; every clone counts into, and saves, the same
+/// counts.
+#[derive(Clone)]
+pub struct Counts {
+    path: Option<PathBuf>,
+    lists: Arc<Mutex<Lists>>,
+    changed: Arc<AtomicBool>,
+}
+
+impl Counts {
+    /// Keep counts in memory only. They start again from zero when the app
+    /// restarts.
+    pub fn in_memory() -> Self {
+        Counts {
+            path: None,
+            lists: Arc::default(),
+            changed: Arc::default(),
+        }
+    }
+
+    /// Keep counts in the JSON file at `path`, picking up from the counts
+    /// already saved there, if it exists. It's created on the first save if
+    /// it doesn't.
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let lists = match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Lists::new(),
+            Err(err) => return Err(err),
+        };
+
+        Ok(Counts {
+            path: Some(path),
+            lists: Arc::new(Mutex::new(lists)),
+            changed: Arc::default(),
+        })
+    }
+
+    /// Count one serving of `item` from the list `list` on `channel`.
+    pub fn served(&self, list: &str, item: &str, channel: Channel) {
+        let mut lists = self.lock();
+        let count = lists
+            .entry(list.to_owned())
+            .or_default()
+            .entry(item.to_owned())
+            .or_default();
+        match channel {
+            Channel::Web => count.web += 1,
+            Channel::Slack => count.slack += 1,
+        }
+        self.changed.store(true, Ordering::Relaxed);
+    }
+
+    /// The number of times `item` from the list `list` has been served.
+    pub fn get(&self, list: &str, item: &str) -> Count {
+        self.lock()
+            .get(list)
+            .and_then(|items| items.get(item))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Up to `limit` of the most-served suggestions, as their list names and
+    /// items, with their counts, most-served first.
+    pub fn most_served(&self, limit: usize) -> Vec<(String, String, Count)> {
+        let lists = self.lock();
+        let mut served: Vec<_> = lists
+            .iter()
+            .flat_map(|(list, items)| {
+                items
+                    .iter()
+                    .map(move |(item, count)| (list.clone(), item.clone(), *count))
+            })
+            .collect();
+        // Stable, so ties stay in order by list and item.
+        served.sort_by_key(|(_, _, count)| std::cmp::Reverse(count.total()));
+        served.truncate(limit);
+        served
+    }
+
+    /// Write the counts to their file, if they're kept in one and have changed
+    /// since they were last saved.
+    pub fn save(&self) -> io::Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if !self.changed.swap(false, Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        let json = serde_json::to_string_pretty(&*self.lock())?;
+        // Write the whole file aside and then move it into place, so that a
+        // failed write can't lose the counts already saved.
+        let saving = path.with_extension("tmp");
+        let saved = fs::write(&saving, json).and_then(|_| fs::rename(&saving, path));
+        if saved.is_err() {
+            self.changed.store(true, Ordering::Relaxed);
+        }
+        saved
+    }
+
+    /// Save once every `period`.
+    ///
+    /// This runs until the runtime shuts down. Failed saves are reported on
+    /// stderr and tried again next time.
+    pub async fn every(self, period: Duration) {
+        let mut ticks = time::interval(period);
+
+        loop {
+            ticks.tick().await;
+
+            let counts = self.clone();
+            match web::block(move || counts.save()).await {
+                Ok(Ok(())) => {}
+                Ok(Err(err)) => eprintln!("Unable to save counts: {}", err),
+                Err(err) => eprintln!("Unable to save counts: {}", err),
+            }
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Lists> {
+        self.lists.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// Count one serving of `item` from `list` on `channel`, if the App counts.
+pub(crate) fn served(req: &HttpRequest, list: &str, item: &str, channel: Channel) {
+    if let Some(counts) = req.app_data::<web::Data<Counts>>() {
+        counts.served(list, item, channel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::header;
+    use actix_web::{test, App};
+    use std::env;
+    use std::process;
+
+    use super::*;
+    use crate::view;
+
+    #[actix_web::test]
+    async fn pages_are_counted() {
+        let counts = Counts::in_memory();
+        let service = view::make_service().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(counts.clone()))
+                .configure(|cfg| service(cfg)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/check/have-you-checked-permissions")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        let etag = resp.headers().get(header::ETAG).unwrap().clone();
+
+        // Revalidating the page doesn't count as serving it again.
+        let req = test::TestRequest::get()
+            .uri("/check/have-you-checked-permissions")
+            .insert_header((header::IF_NONE_MATCH, etag))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 304);
+
+        let served = counts.most_served(10);
+        assert_eq!(served.len(), 1);
+        assert_eq!(served[0].2, Count { web: 1, slack: 0 });
+    }
+
+    #[actix_web::test]
+    async fn counts_survive_reopening() {
+        let path = env::temp_dir().join(format!("things-to-check-counts-{}.json", process::id()));
+        let counts = Counts::open(&path).unwrap();
+        counts.served("general", "dns", Channel::Web);
+        counts.served("general", "dns", Channel::Slack);
+        counts.served("general", "dns", Channel::Web);
+        counts.served("general", "4", Channel::Slack);
+        counts.save().unwrap();
+
+        let reopened = Counts::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(reopened.get("general", "dns"), Count { web: 2, slack: 1 });
+        assert_eq!(reopened.get("general", "missing"), Count::default());
+        let served: Vec<_> = reopened
+            .most_served(1)
+            .into_iter()
+            .map(|(_, item, _)| item)
+            .collect();
+        assert_eq!(served, ["dns"]);
+    }
+}
@@ -0,0 +1,139 @@
+//! Resources for search engines and other crawlers.
+//!
+//! The front page shows a random suggestion, so crawlers following links from
+//! it see a different page every time. These endpoints list every
+//! suggestion's permalink instead, so that each can be indexed. They are
+//! mounted by every `make_*service(…)` function.
+//!
+//! # Endpoints
+//!
+//! * `/sitemap.xml` (`GET`): a [sitemap][1] listing the front page of every
+//!   list, and the permalink of every suggestion in it, including retired
+//!   suggestions. Suggestions with an `added` date give it as their
+//!   `lastmod`.
+//!
+//!   The sitemap carries an `ETag`, and honours `If-None-Match`, as for
+//!   `/check/{slug}`.
+//!
+//! * `/robots.txt` (`GET`): [crawler rules][2], generated from the `Robots`
+//!   in the service's `view::Options`. By default, crawlers are asked to stay
+//!   out of the API, search results, and the submission, moderation, and
+//!   webhook endpoints, and are pointed at the sitemap.
+//!
+//! [1]: https://www.sitemaps.org/protocol.html
+//! [2]: https://www.rfc-editor.org/rfc/rfc9309.html
+
+use actix_web::{error, get, web, HttpRequest, HttpResponse};
+use askama::Template;
+use std::fmt::Write;
+use std::iter;
+
+use crate::things::Store;
+use crate::view::{self, ItemQuery, Urls};
+
+struct Page {
+    url: String,
+    date: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "sitemap.xml")]
+struct Sitemap {
+    pages: Vec<Page>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/sitemap.xml",
+    tag = "crawlers",
+    responses(
+        (status = 200, description = "Every suggestion's permalink", content_type = "application/xml"),
+        (status = 304, description = "The sitemap matches the If-None-Match header"),
+    ),
+)]
+#[get("/sitemap.xml", name = "sitemap")]
+pub(crate) async fn sitemap(
+    req: HttpRequest,
+    store: web::Data<Store>,
+) -> error::Result<HttpResponse> {
+    let catalog = store.current();
+
+    let mut pages = vec![];
+    for (position, (list, things)) in catalog.0.iter().enumerate() {
+        // The first list is the default, and needs no `list` parameter.
+        let query = ItemQuery {
+            list: Some(list.clone()).filter(|_| position > 0),
+            ..Default::default()
+        };
+
+        pages.push(Page {
+            url: req.new_suggestion(&query)?.into(),
+            date: None,
+        });
+        for (_, thing) in &things.0 {
+            pages.push(Page {
+                url: req.suggestion(&query, thing)?.into(),
+                date: thing.date().map(str::to_owned),
+            });
+        }
+    }
+
+    let body = Sitemap { pages }
+        .render()
+        .map_err(error::ErrorInternalServerError)?;
+
+    Ok(view::fixed(&req, "application/xml", body))
+}
+
+/// What `/robots.txt` asks of crawlers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Robots {
+    /// Path prefixes crawlers are asked not to visit, such as `/api/`. A
+    /// prefix of `/` asks them to stay away entirely.
+    pub disallow: Vec<String>,
+    /// Whether to point crawlers at `/sitemap.xml`.
+    pub sitemap: bool,
+}
+
+impl Default for Robots {
+    fn default() -> Self {
+        Robots {
+            disallow: ["/api/", "/search", "/suggest", "/admin/", "/hooks/"]
+                .map(str::to_owned)
+                .to_vec(),
+            sitemap: true,
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/robots.txt",
+    tag = "crawlers",
+    responses((status = 200, description = "Rules for crawlers", content_type = "text/plain")),
+)]
+#[get("/robots.txt")]
+pub(crate) async fn robots(
+    req: HttpRequest,
+    robots: web::Data<Robots>,
+) -> error::Result<HttpResponse> {
+    let mut body = String::from("User-agent: *\n");
+    for path in &robots.disallow {
+        writeln!(body, "Disallow: {}", path).expect("writing to a String succeeds");
+    }
+    if !robots.disallow.iter().any(|path| path == "/") {
+        body.push_str("Allow: /\n");
+    }
+    if robots.sitemap {
+        let url = req.url_for("sitemap", iter::empty::<&str>())?;
+        writeln!(body, "\nSitemap: {}", url).expect("writing to a String succeeds");
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; charset=utf-8")
+        .body(body))
+}
+
+pub(crate) fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(sitemap).service(robots);
+}
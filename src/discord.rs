@@ -0,0 +1,374 @@
+//! A Discord app, for asking for suggestions from Discord.
+//!
+//! The app is off unless the App is given a `Discord` value as app data, which
+//! holds the public key Discord signs its requests with:
+//!
+//! ```
+//! # use things_to_check::{discord, view};
+//! # #[actix_web::main]
+//! # async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+//! use actix_web::{web, App, HttpServer};
+//!
+//! let service = view::make_service()?;
+//! let key = "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c";
+//! let discord = web::Data::new(discord::Discord::new(key)?);
+//! let app_factory = move ||
+//!     App::new()
+//!         .app_data(discord.clone())
+//!         .configure(|cfg| service(cfg));
+//!
+//! HttpServer::new(app_factory);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Endpoints
+//!
+//! * `/discord/interactions` (`POST`): interactions, such as `/troubleshoot`.
+//!
+//!   Point the app's interactions endpoint URL here, and register a chat
+//!   input command, such as `troubleshoot`, with an optional string option
+//!   named `item`. The command posts a random suggestion to the channel, or
+//!   the one `item` names, as for the `item` parameter to `/`, with a link to
+//!   its permalink and a "Give me another" button that swaps in a fresh one.
+//!   Discord's `PING`s, sent to check the endpoint, are answered with a
+//!   `PONG`.
+//!
+//! * `/notify/discord` (`POST`): posts a suggestion to a Discord webhook.
+//!
+//!   Only available if the App is also given a `Webhook` value as app data,
+//!   holding the webhook's URL, and a `moderation::Moderators` value. Takes
+//!   the same URL parameters as `/api/v1/suggestion`, and posts the
+//!   suggestion a request there would return, with a link to its permalink.
+//!   Returns `204 No Content` once Discord has accepted the post, or `502 Bad
+//!   Gateway` if it didn't. Requires an `Authorization: Bearer …` header
+//!   carrying the moderators' token.
+//!
+//! Interactions must carry a valid `X-Signature-Ed25519` for the public key,
+//! and an `X-Signature-Timestamp` within five minutes of the current time, or
+//! are rejected with `401 Unauthorized`, as Discord requires. The endpoints
+//! return `404 Not Found` if they're off. Every interaction comes from
+//! Discord, on behalf of everyone in the server, so these endpoints aren't
+//! rate limited.
+//!
+//! `Webhook` can also be used directly, to post suggestions to Discord from
+//! elsewhere; the `schedule` module posts with it.
+
+use actix_web::{error, post, web, HttpRequest, HttpResponse};
+use ring::signature::{UnparsedPublicKey, ED25519};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use url::Url;
+
+use crate::moderation;
+use crate::things::Store;
+use crate::view::{self, ItemQuery, Urls};
+
+/// How far, in seconds, a request's timestamp may be from the current time.
+/// Older requests are refused, so that captured requests can't be replayed.
+const MAX_SKEW: u64 = 5 * 60;
+
+/// The `custom_id` of the "Give me another" button.
+const ANOTHER: &str = "another";
+
+// Interaction types.
+const PING: u8 = 1;
+const APPLICATION_COMMAND: u8 = 2;
+const MESSAGE_COMPONENT: u8 = 3;
+
+// Interaction response types.
+const PONG: u8 = 1;
+const CHANNEL_MESSAGE_WITH_SOURCE: u8 = 4;
+const UPDATE_MESSAGE: u8 = 7;
+
+/// The message flag that shows a message only to the person who asked.
+const EPHEMERAL: u32 = 1 << 6;
+
+/// A public key was not 32 bytes, in hex.
+#[derive(Error, Debug)]
+#[error("Invalid Discord public key: expected 64 hexadecimal digits")]
+pub struct InvalidPublicKey;
+
+/// Enables the Discord app, and holds the key Discord signs requests with.
+pub struct Discord {
+    public_key: Vec<u8>,
+}
+
+impl Discord {
+    /// Enable the Discord app, for requests signed with the key whose public
+    /// half is `public_key`, in hex, from the app's General Information page.
+    pub fn new(public_key: &str) -> Result<Self, InvalidPublicKey> {
+        let public_key = hex::decode(public_key.trim()).map_err(|_| InvalidPublicKey)?;
+        if public_key.len() != 32 {
+            return Err(InvalidPublicKey);
+        }
+
+        Ok(Discord { public_key })
+    }
+
+    // Discord signs the timestamp followed by the body with Ed25519, in the
+    // X-Signature-Ed25519 header.
+    fn verify(&self, req: &HttpRequest, body: &[u8]) -> error::Result<()> {
+        let header = |name| {
+            req.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+        };
+        let timestamp = header("X-Signature-Timestamp");
+        let signature = header("X-Signature-Ed25519").and_then(|value| hex::decode(value).ok());
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs());
+        let fresh = timestamp
+            .and_then(|timestamp| timestamp.parse::<u64>().ok())
+            .is_some_and(|timestamp| now.abs_diff(timestamp) <= MAX_SKEW);
+
+        match (timestamp, signature) {
+            (Some(timestamp), Some(signature)) if fresh => {
+                let signed = [timestamp.as_bytes(), body].concat();
+                UnparsedPublicKey::new(&ED25519, &self.public_key)
+                    .verify(&signed, &signature)
+                    .map_err(|_| error::ErrorUnauthorized("Unauthorized"))
+            }
+            _ => Err(error::ErrorUnauthorized("Unauthorized")),
+        }
+    }
+}
+
+/// A Discord webhook, to post suggestions to without anyone asking.
+#[derive(Clone, Debug)]
+pub struct Webhook {
+    url: Url,
+}
+
+impl Webhook {
+    /// Post to the webhook at `url`, from a channel's Integrations settings.
+    pub fn new(url: Url) -> Self {
+        Webhook { url }
+    }
+
+    /// Post `text`, linking to `permalink` if given. This blocks until
+    /// Discord answers.
+    pub fn post(&self, text: &str, permalink: Option<&Url>) -> Result<(), Box<ureq::Error>> {
+        let body = json!({
+            "content": content(text, permalink.map(Url::as_str)),
+            "allowed_mentions": { "parse": [] },
+        });
+
+        ureq::post(self.url.as_str())
+            .set("Content-Type", "application/json")
+            .send_string(&body.to_string())
+            .map_err(Box::new)?;
+
+        Ok(())
+    }
+}
+
+// A message's content, showing `text` and linking to `permalink`.
+fn content(text: &str, permalink: Option<&str>) -> String {
+    match permalink {
+        // Angle brackets keep Discord from embedding a preview.
+        Some(permalink) => format!("{}\n<{}>", text, permalink),
+        None => text.to_owned(),
+    }
+}
+
+fn discord(req: &HttpRequest) -> error::Result<&Discord> {
+    req.app_data::<web::Data<Discord>>()
+        .map(|discord| discord.get_ref())
+        .ok_or_else(|| view::not_found(req))
+}
+
+// A message showing a suggestion, the one named `item` if given, with a
+// button for another.
+fn message(req: &HttpRequest, store: &Store, item: Option<&str>) -> error::Result<Value> {
+    let catalog = store.current();
+    let chosen = catalog.list(None).and_then(|things| match item {
+        Some(item) => things.aliased(item).or_else(|| things.find(item)),
+        None => things.choose(None, None),
+    });
+    let (_, thing) = match chosen {
+        Some(chosen) => chosen,
+        None => {
+            return Ok(json!({
+                "content": "Sorry, there are no suggestions to show.",
+                "flags": EPHEMERAL,
+            }))
+        }
+    };
+
+    let url = req.suggestion(&ItemQuery::default(), thing)?;
+
+    Ok(json!({
+        "content": content(&thing.text(), Some(url.as_str())),
+        "allowed_mentions": { "parse": [] },
+        "components": [
+            {
+                "type": 1,
+                "components": [
+                    {
+                        "type": 2,
+                        "style": 2,
+                        "label": "Give me another",
+                        "custom_id": ANOTHER,
+                    },
+                ],
+            },
+        ],
+    }))
+}
+
+#[derive(Deserialize)]
+struct Interaction {
+    #[serde(rename = "type")]
+    kind: u8,
+    data: Option<InteractionData>,
+}
+
+#[derive(Deserialize)]
+struct InteractionData {
+    custom_id: Option<String>,
+    #[serde(default)]
+    options: Vec<CommandOption>,
+}
+
+#[derive(Deserialize)]
+struct CommandOption {
+    name: String,
+    value: Value,
+}
+
+impl InteractionData {
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .find(|option| option.name == name)
+            .and_then(|option| option.value.as_str())
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/discord/interactions",
+    tag = "discord",
+    request_body(content = String, description = "A Discord interaction", content_type = "application/json"),
+    responses(
+        (status = 200, description = "The response to the interaction, as JSON"),
+        (status = 400, description = "The interaction was malformed or unsupported"),
+        (status = 401, description = "The request's signature was missing, stale, or incorrect"),
+        (status = 404, description = "The Discord app is off"),
+    ),
+)]
+#[post("/discord/interactions")]
+pub(crate) async fn interactions(
+    req: HttpRequest,
+    store: web::Data<Store>,
+    body: web::Bytes,
+) -> error::Result<HttpResponse> {
+    discord(&req)?.verify(&req, &body)?;
+
+    let interaction: Interaction = serde_json::from_slice(&body).map_err(error::ErrorBadRequest)?;
+    let data = interaction.data.as_ref();
+
+    let response = match interaction.kind {
+        PING => json!({ "type": PONG }),
+        APPLICATION_COMMAND => {
+            let item = data.and_then(|data| data.option("item"));
+            json!({
+                "type": CHANNEL_MESSAGE_WITH_SOURCE,
+                "data": message(&req, &store, item)?,
+            })
+        }
+        MESSAGE_COMPONENT if data.and_then(|data| data.custom_id.as_deref()) == Some(ANOTHER) => {
+            json!({
+                "type": UPDATE_MESSAGE,
+                "data": message(&req, &store, None)?,
+            })
+        }
+        _ => return Err(error::ErrorBadRequest("Unsupported interaction")),
+    };
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/notify/discord",
+    tag = "discord",
+    security(("moderator" = [])),
+    params(ItemQuery),
+    responses(
+        (status = 204, description = "The suggestion was posted"),
+        (status = 401, description = "Missing or incorrect moderator token"),
+        (status = 404, description = "No webhook is configured, or no such list or suggestion"),
+        (status = 502, description = "Discord did not accept the post"),
+    ),
+)]
+#[post("/notify/discord")]
+pub(crate) async fn notify(
+    req: HttpRequest,
+    store: web::Data<Store>,
+    query: web::Query<ItemQuery>,
+) -> error::Result<HttpResponse> {
+    let webhook = req
+        .app_data::<web::Data<Webhook>>()
+        .ok_or_else(|| view::not_found(&req))?
+        .clone();
+    moderation::moderators(&req)?.authenticate(&req)?;
+
+    let (text, url) = {
+        let catalog = store.current();
+        let things = catalog
+            .list(query.list.as_deref())
+            .ok_or_else(|| view::not_found(&req))?;
+        let item = query.item.as_deref();
+        let tag = query.tag.as_deref();
+        let chosen = match item.and_then(|item| things.aliased(item)) {
+            Some(aliased) => Some(aliased).filter(|(_, thing)| thing.has_tag(tag)),
+            None => things.choose(item, tag),
+        };
+        let (_, thing) = chosen.ok_or_else(|| view::not_found(&req))?;
+
+        let url = req.suggestion(&query, thing)?;
+        let text = thing.translated(query.lang.as_deref()).text();
+        (text, url)
+    };
+
+    // Posting blocks, so keep it off the thread serving requests.
+    web::block(move || webhook.post(&text, Some(&url)))
+        .await?
+        .map_err(|err| {
+            eprintln!("Unable to post a suggestion to Discord: {}", err);
+            error::ErrorBadGateway(err)
+        })?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub(crate) fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(interactions).service(notify);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permalinks_are_not_embedded() {
+        assert_eq!(
+            content("Is it DNS?", Some("https://example.com/?item=0")),
+            "Is it DNS?\n<https://example.com/?item=0>"
+        );
+        assert_eq!(content("Is it DNS?", None), "Is it DNS?");
+    }
+
+    #[test]
+    fn public_keys_are_checked() {
+        assert!(Discord::new(&"ab".repeat(32)).is_ok());
+        assert!(Discord::new(&"ab".repeat(31)).is_err());
+        assert!(Discord::new("not hex").is_err());
+    }
+}
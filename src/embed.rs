@@ -0,0 +1,481 @@
+//! Resources for showing suggestions on other sites.
+//!
+//! These endpoints let chat tools, wikis, and other sites show a suggestion
+//! inline when someone pastes its permalink. They are mounted by every
+//! `make_*service(…)` function.
+//!
+//! # Endpoints
+//!
+//! * `/oembed` (`GET`): an [oEmbed][1] `rich` response for a suggestion.
+//!
+//!   Takes a `url` URL parameter holding the permalink of a suggestion served
+//!   by this service, as linked from its page, and optional `maxwidth`,
+//!   `maxheight`, and `format` parameters, as described by the oEmbed
+//!   specification. Only the `json` format is supported; others return `501
+//!   Not Implemented`. URLs that aren't a permalink served by this service,
+//!   including links to a random suggestion, return `404 Not Found`.
+//!
+//!   Every suggestion page advertises this endpoint with a `<link>` element,
+//!   for oEmbed discovery. Responses carry an `ETag`, and honour
+//!   `If-None-Match`, as for `/check/{slug}`.
+//!
+//! * `/badge.svg` (`GET`): a [shields.io][2]-style SVG badge showing a
+//!   randomly-selected suggestion, for READMEs and dashboards.
+//!
+//!   Takes the same `list`, `tag`, and `lang` URL parameters as `/`. Retired
+//!   suggestions are never shown, and responses are never cached, so that the
+//!   badge changes on every view.
+//!
+//! * `/badge/{item}.svg` (`GET`): a badge showing a fixed suggestion, where
+//!   `item` is as for the `item` parameter to `/`. Takes the same `list`,
+//!   `tag`, and `lang` parameters as `/`, and carries an `ETag`, as for
+//!   `/check/{slug}`.
+//!
+//! * `/qr/{item}.png` (`GET`): a PNG image of a QR code encoding the permalink
+//!   of a suggestion, for printing. Takes the same `list`, `tag`, and `lang`
+//!   parameters as `/`, which are carried over into the permalink, and
+//!   carries an `ETag`, as for `/check/{slug}`.
+//!
+//! * `/og/{item}.png` (`GET`): a PNG preview card showing a suggestion's
+//!   text, for link previews. Takes the same `list`, `tag`, and `lang`
+//!   parameters as `/`, and carries an `ETag`, as for `/check/{slug}`. Every
+//!   suggestion page names its card in its Open Graph `og:image` tag.
+//!
+//! * `/embed` (`GET`): a small HTML page showing a suggestion and a link to
+//!   another, for framing in wikis, dashboards, and internal portals, such as
+//!   with `<iframe src="https://example.com/embed">`.
+//!
+//!   Takes the same `item`, `list`, `tag`, and `lang` URL parameters as `/`,
+//!   and shows a random suggestion unless given an `item`. The page has no
+//!   header, footer, or background of its own, and any site may frame it.
+//!   Fixed suggestions carry an `ETag`, as for `/check/{slug}`.
+//!
+//! Unknown lists, items, and tags return `404 Not Found`.
+//!
+//! [1]: https://oembed.com/
+//! [2]: https://shields.io/
+
+use actix_web::http::header::{self, ContentType, HeaderValue};
+use actix_web::{error, get, web, HttpRequest, HttpResponse};
+use askama::Template;
+use qrcode::{Color, QrCode};
+use serde::{Deserialize, Serialize};
+use std::iter;
+use url::Url;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::card;
+use crate::limit::Limited;
+use crate::locale::Locale;
+use crate::theme::Theme;
+use crate::things::{Store, Thing, Things};
+use crate::view::{self, ItemQuery, Urls};
+
+/// The approximate width, in pixels, of a character of badge text.
+const BADGE_CHAR_WIDTH: u32 = 7;
+
+/// The space, in pixels, on either side of each half of a badge.
+const BADGE_PADDING: u32 = 10;
+
+/// The size, in pixels, of each module (the squares a QR code is made of).
+const QR_SCALE: usize = 8;
+
+/// The blank modules around a QR code, which scanners need to find it.
+const QR_QUIET_ZONE: usize = 4;
+
+/// The width of embedded suggestions, unless the consumer asks for narrower.
+const WIDTH: u32 = 600;
+
+/// The height of embedded suggestions, unless the consumer asks for shorter.
+const HEIGHT: u32 = 150;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct OEmbedQuery {
+    url: String,
+    maxwidth: Option<u32>,
+    maxheight: Option<u32>,
+    format: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "embed.html")]
+struct Embed {
+    thing: Thing,
+    url: String,
+}
+
+#[derive(Serialize, ToSchema)]
+struct OEmbed {
+    version: &'static str,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    provider_name: &'static str,
+    provider_url: String,
+    title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author_name: Option<String>,
+    html: String,
+    width: u32,
+    height: u32,
+}
+
+// The thing an `item` parameter refers to, either directly or as an alias,
+// if it carries `tag`.
+fn lookup<'a>(things: &'a Things, item: &str, tag: Option<&str>) -> Option<&'a (usize, Thing)> {
+    things
+        .aliased(item)
+        .or_else(|| things.find(item))
+        .filter(|(_, thing)| thing.has_tag(tag))
+}
+
+// `url`, if it links to a page of the service handling `req`, and that
+// service's index page. The scheme isn't compared, as proxies in front of the
+// service may change it.
+fn on_service(req: &HttpRequest, url: &str) -> error::Result<Option<(Url, Url)>> {
+    let index = req.url_for("index", iter::empty::<&str>())?;
+    let url = match Url::parse(url) {
+        Ok(url) => url,
+        Err(_) => return Ok(None),
+    };
+
+    let same_service = url.host_str() == index.host_str()
+        && url.port_or_known_default() == index.port_or_known_default();
+
+    Ok(Some((url, index)).filter(|_| same_service))
+}
+
+#[utoipa::path(
+    get,
+    path = "/oembed",
+    tag = "embedding",
+    params(OEmbedQuery),
+    responses(
+        (status = 200, description = "The suggestion, for embedding", body = OEmbed),
+        (status = 304, description = "The response matches the If-None-Match header"),
+        (status = 404, description = "The URL isn't a suggestion's permalink"),
+        (status = 501, description = "The format isn't supported"),
+    ),
+)]
+#[get("/oembed", name = "oembed")]
+pub(crate) async fn oembed(
+    req: HttpRequest,
+    store: web::Data<Store>,
+    query: web::Query<OEmbedQuery>,
+) -> error::Result<HttpResponse> {
+    if query.format.as_deref().unwrap_or("json") != "json" {
+        return Err(error::ErrorNotImplemented("Only JSON is supported"));
+    }
+
+    let not_found = || view::not_found(&req);
+    let (url, index) = on_service(&req, &query.url)?.ok_or_else(not_found)?;
+
+    let catalog = store.current();
+    let (item_query, (_, thing)) =
+        view::permalinked(&catalog, &index, &url).ok_or_else(not_found)?;
+
+    let url = req.suggestion(&item_query, thing)?;
+    let thing = thing.translated(item_query.lang.as_deref());
+    let html = Embed {
+        thing: thing.clone(),
+        url: url.into(),
+    }
+    .render()
+    .map_err(error::ErrorInternalServerError)?;
+
+    let oembed = OEmbed {
+        version: "1.0",
+        kind: "rich",
+        provider_name: "Things To Check",
+        provider_url: req.new_suggestion(&ItemQuery::default())?.into(),
+        title: thing.text(),
+        author_name: thing.author,
+        html,
+        width: query.maxwidth.map_or(WIDTH, |max| max.min(WIDTH)),
+        height: query.maxheight.map_or(HEIGHT, |max| max.min(HEIGHT)),
+    };
+    let body = serde_json::to_string(&oembed).map_err(error::ErrorInternalServerError)?;
+
+    Ok(view::fixed(&req, ContentType::json(), body))
+}
+
+#[derive(Template)]
+#[template(path = "badge.svg", escape = "html")]
+struct Badge {
+    label: &'static str,
+    text: String,
+    width: u32,
+    label_width: u32,
+    text_width: u32,
+}
+
+impl Badge {
+    fn new(thing: &Thing) -> Self {
+        let label = "check";
+        let text = thing.text();
+        let label_width = badge_width(label);
+        let text_width = badge_width(&text);
+
+        Badge {
+            label,
+            text,
+            width: label_width + text_width,
+            label_width,
+            text_width,
+        }
+    }
+}
+
+fn badge_width(text: &str) -> u32 {
+    let chars = u32::try_from(text.chars().count()).unwrap_or(u32::MAX);
+    chars
+        .saturating_mul(BADGE_CHAR_WIDTH)
+        .saturating_add(2 * BADGE_PADDING)
+}
+
+#[utoipa::path(
+    get,
+    path = "/badge.svg",
+    tag = "embedding",
+    params(ItemQuery),
+    responses(
+        (status = 200, description = "A badge showing a random suggestion", content_type = "image/svg+xml"),
+        (status = 404, description = "No such list, or tagged suggestion"),
+    ),
+)]
+#[get("/badge.svg")]
+pub(crate) async fn badge(
+    _: Limited,
+    req: HttpRequest,
+    store: web::Data<Store>,
+    query: web::Query<ItemQuery>,
+) -> error::Result<HttpResponse> {
+    let catalog = store.current();
+    let (_, thing) = catalog
+        .list(query.list.as_deref())
+        .and_then(|things| things.choose(None, query.tag.as_deref()))
+        .ok_or_else(|| view::not_found(&req))?;
+
+    let body = Badge::new(&thing.translated(query.lang.as_deref()))
+        .render()
+        .map_err(error::ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(("Cache-Control", "no-store"))
+        .content_type("image/svg+xml")
+        .body(body))
+}
+
+#[utoipa::path(
+    get,
+    path = "/badge/{item}.svg",
+    tag = "embedding",
+    params(
+        ("item" = String, Path, description = "The suggestion's ID or index"),
+        ItemQuery,
+    ),
+    responses(
+        (status = 200, description = "A badge showing the suggestion", content_type = "image/svg+xml"),
+        (status = 304, description = "The badge matches the If-None-Match header"),
+        (status = 404, description = "No such list, item, or tagged suggestion"),
+    ),
+)]
+#[get("/badge/{item}.svg")]
+pub(crate) async fn item_badge(
+    _: Limited,
+    req: HttpRequest,
+    store: web::Data<Store>,
+    item: web::Path<String>,
+    query: web::Query<ItemQuery>,
+) -> error::Result<HttpResponse> {
+    let catalog = store.current();
+    let (_, thing) = catalog
+        .list(query.list.as_deref())
+        .and_then(|things| lookup(things, &item, query.tag.as_deref()))
+        .ok_or_else(|| view::not_found(&req))?;
+
+    let body = Badge::new(&thing.translated(query.lang.as_deref()))
+        .render()
+        .map_err(error::ErrorInternalServerError)?;
+
+    Ok(view::fixed(&req, "image/svg+xml", body))
+}
+
+// A black-on-white PNG of a QR code encoding `text`.
+fn qr_png(text: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let code = QrCode::new(text)?;
+    let modules = code.width();
+    let colors = code.to_colors();
+
+    let side = (modules + 2 * QR_QUIET_ZONE) * QR_SCALE;
+    let mut pixels = vec![u8::MAX; side * side];
+    for (position, color) in colors.iter().enumerate() {
+        if *color == Color::Light {
+            continue;
+        }
+        let x = (position % modules + QR_QUIET_ZONE) * QR_SCALE;
+        let y = (position / modules + QR_QUIET_ZONE) * QR_SCALE;
+        for row in y..y + QR_SCALE {
+            pixels[row * side + x..row * side + x + QR_SCALE].fill(0);
+        }
+    }
+
+    let side = u32::try_from(side)?;
+    let mut png = vec![];
+    let mut encoder = png::Encoder::new(&mut png, side, side);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&pixels)?;
+    writer.finish()?;
+
+    Ok(png)
+}
+
+#[utoipa::path(
+    get,
+    path = "/qr/{item}.png",
+    tag = "embedding",
+    params(
+        ("item" = String, Path, description = "The suggestion's ID or index"),
+        ItemQuery,
+    ),
+    responses(
+        (status = 200, description = "A QR code encoding the suggestion's permalink", content_type = "image/png"),
+        (status = 304, description = "The image matches the If-None-Match header"),
+        (status = 404, description = "No such list, item, or tagged suggestion"),
+    ),
+)]
+#[get("/qr/{item}.png")]
+pub(crate) async fn qr(
+    _: Limited,
+    req: HttpRequest,
+    store: web::Data<Store>,
+    item: web::Path<String>,
+    query: web::Query<ItemQuery>,
+) -> error::Result<HttpResponse> {
+    let catalog = store.current();
+    let (_, thing) = catalog
+        .list(query.list.as_deref())
+        .and_then(|things| lookup(things, &item, query.tag.as_deref()))
+        .ok_or_else(|| view::not_found(&req))?;
+
+    let url = req.suggestion(&query, thing)?;
+    let body = qr_png(url.as_str()).map_err(error::ErrorInternalServerError)?;
+
+    Ok(view::fixed(&req, "image/png", body))
+}
+
+#[utoipa::path(
+    get,
+    path = "/og/{item}.png",
+    tag = "embedding",
+    params(
+        ("item" = String, Path, description = "The suggestion's ID or index"),
+        ItemQuery,
+    ),
+    responses(
+        (status = 200, description = "A preview card showing the suggestion", content_type = "image/png"),
+        (status = 304, description = "The image matches the If-None-Match header"),
+        (status = 404, description = "No such list, item, or tagged suggestion"),
+    ),
+)]
+#[get("/og/{item}.png", name = "og_image")]
+pub(crate) async fn og_image(
+    _: Limited,
+    req: HttpRequest,
+    store: web::Data<Store>,
+    item: web::Path<String>,
+    query: web::Query<ItemQuery>,
+) -> error::Result<HttpResponse> {
+    let catalog = store.current();
+    let (_, thing) = catalog
+        .list(query.list.as_deref())
+        .and_then(|things| lookup(things, &item, query.tag.as_deref()))
+        .ok_or_else(|| view::not_found(&req))?;
+
+    let text = thing.translated(query.lang.as_deref()).text();
+    let body = card::render(&text).map_err(error::ErrorInternalServerError)?;
+
+    Ok(view::fixed(&req, "image/png", body))
+}
+
+#[derive(Template)]
+#[template(path = "widget.html")]
+struct Widget {
+    thing: Thing,
+    url: String,
+    another: String,
+    theme: web::Data<Theme>,
+    locale: Locale,
+}
+
+#[utoipa::path(
+    get,
+    path = "/embed",
+    tag = "embedding",
+    params(ItemQuery),
+    responses(
+        (status = 200, description = "A page showing the suggestion, for framing", content_type = "text/html"),
+        (status = 304, description = "The item matches the If-None-Match header"),
+        (status = 404, description = "No such list, item, or tagged suggestion"),
+    ),
+)]
+#[get("/embed", name = "widget")]
+pub(crate) async fn widget(
+    _: Limited,
+    req: HttpRequest,
+    store: web::Data<Store>,
+    theme: web::Data<Theme>,
+    query: web::Query<ItemQuery>,
+) -> error::Result<HttpResponse> {
+    let catalog = store.current();
+    let tag = query.tag.as_deref();
+    let (_, thing) = catalog
+        .list(query.list.as_deref())
+        .and_then(|things| match query.item.as_deref() {
+            Some(item) => lookup(things, item, tag),
+            None => things.choose(None, tag),
+        })
+        .ok_or_else(|| view::not_found(&req))?;
+
+    let body = Widget {
+        thing: thing.translated(query.lang.as_deref()),
+        url: req.suggestion(&query, thing)?.into(),
+        another: req.widget(&query)?.into(),
+        theme,
+        locale: Locale::negotiate(&req, query.lang.as_deref()),
+    }
+    .render()
+    .map_err(error::ErrorInternalServerError)?;
+
+    let mut response = match query.item {
+        Some(_) => {
+            let mut response = view::fixed(&req, ContentType::html(), body);
+            response
+                .headers_mut()
+                .insert(header::VARY, HeaderValue::from_static("Accept-Language"));
+            response
+        }
+        None => HttpResponse::Ok()
+            .content_type(ContentType::html())
+            .insert_header(("Cache-Control", "no-store"))
+            .body(body),
+    };
+    // Unlike the other pages, this one is meant to be framed anywhere.
+    response.headers_mut().insert(
+        header::CONTENT_SECURITY_POLICY,
+        HeaderValue::from_static("frame-ancestors *"),
+    );
+
+    Ok(response)
+}
+
+pub(crate) fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(oembed)
+        .service(widget)
+        .service(badge)
+        .service(item_badge)
+        .service(qr)
+        .service(og_image);
+}
@@ -0,0 +1,224 @@
+//! Emoji shortcodes, such as `:bulb:`, as Slack and GitHub write them.
+//!
+//! Suggestions written for chat often use shortcodes rather than the emoji
+//! themselves. Things replace the shortcodes they know with the emoji when
+//! they're loaded, so that they read the same everywhere; unknown shortcodes,
+//! and shortcodes in code, are left as they are.
+
+/// Shortcodes and their emoji, sorted by shortcode. Where Slack and GitHub
+/// disagree on a name, both are listed.
+const SHORTCODES: &[(&str, &str)] = &[
+    ("+1", "👍"),
+    ("-1", "👎"),
+    ("100", "💯"),
+    ("alarm_clock", "⏰"),
+    ("bangbang", "‼️"),
+    ("battery", "🔋"),
+    ("bell", "🔔"),
+    ("bomb", "💣"),
+    ("book", "📖"),
+    ("books", "📚"),
+    ("boom", "💥"),
+    ("brain", "🧠"),
+    ("bug", "🐛"),
+    ("bulb", "💡"),
+    ("calendar", "📆"),
+    ("chart_with_downwards_trend", "📉"),
+    ("chart_with_upwards_trend", "📈"),
+    ("clipboard", "📋"),
+    ("clock1", "🕐"),
+    ("closed_lock_with_key", "🔐"),
+    ("cloud", "☁️"),
+    ("coffee", "☕"),
+    ("computer", "💻"),
+    ("confused", "😕"),
+    ("construction", "🚧"),
+    ("cry", "😢"),
+    ("desktop_computer", "🖥️"),
+    ("disappointed", "😞"),
+    ("dizzy_face", "😵"),
+    ("electric_plug", "🔌"),
+    ("exclamation", "❗"),
+    ("eyes", "👀"),
+    ("facepalm", "🤦"),
+    ("file_folder", "📁"),
+    ("fire", "🔥"),
+    ("floppy_disk", "💾"),
+    ("gear", "⚙️"),
+    ("ghost", "👻"),
+    ("globe_with_meridians", "🌐"),
+    ("grey_question", "❔"),
+    ("grimacing", "😬"),
+    ("hammer", "🔨"),
+    ("hammer_and_wrench", "🛠️"),
+    ("heavy_check_mark", "✔️"),
+    ("heavy_multiplication_x", "✖️"),
+    ("hourglass", "⌛"),
+    ("hourglass_flowing_sand", "⏳"),
+    ("information_source", "ℹ️"),
+    ("joy", "😂"),
+    ("key", "🔑"),
+    ("keyboard", "⌨️"),
+    ("lady_beetle", "🐞"),
+    ("ladybug", "🐞"),
+    ("link", "🔗"),
+    ("lock", "🔒"),
+    ("mag", "🔍"),
+    ("mag_right", "🔎"),
+    ("mailbox", "📫"),
+    ("memo", "📝"),
+    ("money_with_wings", "💸"),
+    ("mute", "🔇"),
+    ("no_entry", "⛔"),
+    ("no_entry_sign", "🚫"),
+    ("ok", "🆗"),
+    ("ok_hand", "👌"),
+    ("open_file_folder", "📂"),
+    ("package", "📦"),
+    ("page_facing_up", "📄"),
+    ("pencil", "📝"),
+    ("pencil2", "✏️"),
+    ("point_right", "👉"),
+    ("point_up", "☝️"),
+    ("pray", "🙏"),
+    ("printer", "🖨️"),
+    ("pushpin", "📌"),
+    ("question", "❓"),
+    ("recycle", "♻️"),
+    ("red_circle", "🔴"),
+    ("repeat", "🔁"),
+    ("rocket", "🚀"),
+    ("rotating_light", "🚨"),
+    ("satellite", "📡"),
+    ("scream", "😱"),
+    ("scroll", "📜"),
+    ("see_no_evil", "🙈"),
+    ("shield", "🛡️"),
+    ("signal_strength", "📶"),
+    ("skull", "💀"),
+    ("sleeping", "😴"),
+    ("slightly_smiling_face", "🙂"),
+    ("smile", "😄"),
+    ("smiley", "😃"),
+    ("snail", "🐌"),
+    ("snowflake", "❄️"),
+    ("sob", "😭"),
+    ("sparkles", "✨"),
+    ("stopwatch", "⏱️"),
+    ("sunglasses", "😎"),
+    ("sweat_smile", "😅"),
+    ("tada", "🎉"),
+    ("thinking", "🤔"),
+    ("thinking_face", "🤔"),
+    ("thumbsdown", "👎"),
+    ("thumbsup", "👍"),
+    ("timer_clock", "⏲️"),
+    ("tools", "🛠️"),
+    ("trophy", "🏆"),
+    ("turtle", "🐢"),
+    ("unlock", "🔓"),
+    ("warning", "⚠️"),
+    ("wave", "👋"),
+    ("white_check_mark", "✅"),
+    ("wrench", "🔧"),
+    ("x", "❌"),
+    ("zap", "⚡"),
+    ("zzz", "💤"),
+];
+
+// The emoji for `shortcode`, without its colons, if there's one.
+fn emoji(shortcode: &str) -> Option<&'static str> {
+    SHORTCODES
+        .binary_search_by_key(&shortcode, |(shortcode, _)| shortcode)
+        .ok()
+        .map(|index| SHORTCODES[index].1)
+}
+
+fn is_shortcode(c: char) -> bool {
+    c.is_ascii_lowercase() || c.is_ascii_digit() || "_+-".contains(c)
+}
+
+/// `markdown`, with the shortcodes in it replaced by emoji. Shortcodes in
+/// code spans and fenced code blocks are left as they are.
+pub(crate) fn emojify(markdown: &str) -> String {
+    let mut emojified = String::with_capacity(markdown.len());
+    let mut fenced = false;
+    for line in markdown.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let fence = trimmed.starts_with("```") || trimmed.starts_with("~~~");
+        if fence {
+            fenced = !fenced;
+        }
+        if fence || fenced {
+            emojified.push_str(line);
+            continue;
+        }
+
+        // Code spans lie between backticks, so alternate pieces are code.
+        for (index, piece) in line.split('`').enumerate() {
+            if index > 0 {
+                emojified.push('`');
+            }
+            if index % 2 == 1 {
+                emojified.push_str(piece);
+            } else {
+                replace_shortcodes(piece, &mut emojified);
+            }
+        }
+    }
+
+    emojified
+}
+
+fn replace_shortcodes(text: &str, emojified: &mut String) {
+    let mut rest = text;
+    while let Some(start) = rest.find(':') {
+        emojified.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let length = after.find(|c| !is_shortcode(c)).unwrap_or(after.len());
+        let replacement = Some(&after[..length])
+            .filter(|_| after[length..].starts_with(':'))
+            .and_then(emoji);
+        match replacement {
+            Some(emoji) => {
+                emojified.push_str(emoji);
+                rest = &after[length + 1..];
+            }
+            None => {
+                emojified.push(':');
+                rest = after;
+            }
+        }
+    }
+    emojified.push_str(rest);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shortcodes_are_sorted() {
+        assert!(SHORTCODES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn shortcodes_become_emoji() {
+        assert_eq!(
+            emojify(":bulb: Have you checked the :electric_plug:? :+1:"),
+            "💡 Have you checked the 🔌? 👍"
+        );
+        assert_eq!(emojify("Is it 12:30:00 yet?"), "Is it 12:30:00 yet?");
+        assert_eq!(emojify(":no_such_emoji: ::"), ":no_such_emoji: ::");
+        assert_eq!(emojify("a :x::x: b"), "a ❌❌ b");
+    }
+
+    #[test]
+    fn code_keeps_shortcodes() {
+        assert_eq!(
+            emojify("Have you checked `:bulb:` :bulb:?"),
+            "Have you checked `:bulb:` 💡?"
+        );
+        assert_eq!(emojify("```\n:bulb:\n```\n:bulb:"), "```\n:bulb:\n```\n💡");
+    }
+}
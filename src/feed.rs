@@ -0,0 +1,246 @@
+//! Feeds of suggestions, for feed readers.
+//!
+//! These endpoints list the most recently added suggestions, so that people
+//! can subscribe to new troubleshooting advice. They are mounted by every
+//! `make_*service(…)` function.
+//!
+//! # Endpoints
+//!
+//! * `/feed.xml` (`GET`): an Atom feed of suggestions.
+//!
+//!   Takes the same `list`, `tag`, and `lang` URL parameters as `/`. Each
+//!   entry is a suggestion that hasn't been retired, identified by its
+//!   permalink. Entries are ordered newest first, by the date suggestions were
+//!   `added`, and then by index, as new suggestions go at the end of their
+//!   list; suggestions without a date are dated 1970-01-01. At most 50 entries
+//!   are included.
+//!
+//! * `/feed.json` (`GET`): the same suggestions, as a [JSON Feed 1.1][1].
+//!
+//!   Takes the same parameters as `/feed.xml`. Items are identified by
+//!   permalink, as Atom entries are, but suggestions without a date have no
+//!   `date_published`.
+//!
+//! Feeds carry an `ETag`, and honour `If-None-Match`, as for `/check/{slug}`.
+//! Unknown lists return `404 Not Found`.
+//!
+//! [1]: https://www.jsonfeed.org/version/1.1/
+
+use actix_web::{error, get, web, HttpRequest, HttpResponse};
+use askama::Template;
+use serde::{Deserialize, Serialize};
+use std::iter;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::things::{Store, Thing};
+use crate::view::{self, ItemQuery, Urls};
+
+/// The most suggestions included in a feed.
+const MAX_ENTRIES: usize = 50;
+
+/// The date of suggestions without an `added` date.
+const EPOCH: &str = "1970-01-01";
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct FeedQuery {
+    list: Option<String>,
+    tag: Option<String>,
+    lang: Option<String>,
+}
+
+impl FeedQuery {
+    fn item_query(&self) -> ItemQuery {
+        ItemQuery {
+            item: None,
+            list: self.list.clone(),
+            tag: self.tag.clone(),
+            lang: self.lang.clone(),
+        }
+    }
+}
+
+struct Entry {
+    url: String,
+    date: Option<String>,
+    thing: Thing,
+}
+
+impl Entry {
+    fn updated(&self) -> &str {
+        self.date.as_deref().unwrap_or(EPOCH)
+    }
+}
+
+struct Feed {
+    title: String,
+    url: String,
+    home: String,
+    entries: Vec<Entry>,
+}
+
+impl Feed {
+    // The newest suggestions for the feed at the route named `name`.
+    fn new(req: &HttpRequest, store: &Store, query: &FeedQuery, name: &str) -> error::Result<Self> {
+        let catalog = store.current();
+        let (list, things) = catalog
+            .named_list(query.list.as_deref())
+            .ok_or_else(|| view::not_found(req))?;
+
+        let item_query = query.item_query();
+        let entries = things
+            .newest(query.tag.as_deref())
+            .into_iter()
+            .take(MAX_ENTRIES)
+            .map(|(_, thing)| {
+                let url = req.suggestion(&item_query, thing)?;
+                Ok(Entry {
+                    url: url.into(),
+                    date: thing.date().map(str::to_owned),
+                    thing: thing.translated(query.lang.as_deref()),
+                })
+            })
+            .collect::<error::Result<_>>()?;
+
+        let mut url = req.url_for(name, iter::empty::<&str>())?;
+        if !req.query_string().is_empty() {
+            url.set_query(Some(req.query_string()));
+        }
+
+        Ok(Feed {
+            title: format!("Things To Check: {}", list),
+            url: url.into(),
+            home: req.new_suggestion(&item_query)?.into(),
+            entries,
+        })
+    }
+}
+
+#[derive(Template)]
+#[template(path = "feed.xml")]
+struct Atom {
+    feed: Feed,
+    updated: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/feed.xml",
+    tag = "feeds",
+    params(FeedQuery),
+    responses(
+        (status = 200, description = "The newest suggestions", content_type = "application/atom+xml"),
+        (status = 304, description = "The feed matches the If-None-Match header"),
+        (status = 404, description = "No such list"),
+    ),
+)]
+#[get("/feed.xml", name = "atom_feed")]
+pub(crate) async fn atom(
+    req: HttpRequest,
+    store: web::Data<Store>,
+    query: web::Query<FeedQuery>,
+) -> error::Result<HttpResponse> {
+    let feed = Feed::new(&req, &store, &query, "atom_feed")?;
+    let updated = feed
+        .entries
+        .iter()
+        .map(Entry::updated)
+        .max()
+        .unwrap_or(EPOCH)
+        .to_owned();
+
+    let body = Atom { feed, updated }
+        .render()
+        .map_err(error::ErrorInternalServerError)?;
+
+    Ok(view::fixed(&req, "application/atom+xml", body))
+}
+
+#[derive(Serialize, ToSchema)]
+struct JsonFeed {
+    version: &'static str,
+    title: String,
+    home_page_url: String,
+    feed_url: String,
+    items: Vec<JsonItem>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct JsonItem {
+    id: String,
+    url: String,
+    title: String,
+    content_html: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    date_published: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    authors: Vec<JsonAuthor>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    language: String,
+}
+
+#[derive(Serialize, ToSchema)]
+struct JsonAuthor {
+    name: String,
+}
+
+impl From<Entry> for JsonItem {
+    fn from(entry: Entry) -> Self {
+        let Entry { url, date, thing } = entry;
+        let content_html = match thing.details {
+            Some(details) => thing.html + &details.html,
+            None => thing.html,
+        };
+
+        JsonItem {
+            id: url.clone(),
+            url,
+            title: thing.markdown,
+            content_html,
+            date_published: date.map(|date| format!("{}T00:00:00Z", date)),
+            authors: thing
+                .author
+                .into_iter()
+                .map(|name| JsonAuthor { name })
+                .collect(),
+            tags: thing.tags,
+            language: thing.lang,
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/feed.json",
+    tag = "feeds",
+    params(FeedQuery),
+    responses(
+        (status = 200, description = "The newest suggestions", body = JsonFeed, content_type = "application/feed+json"),
+        (status = 304, description = "The feed matches the If-None-Match header"),
+        (status = 404, description = "No such list"),
+    ),
+)]
+#[get("/feed.json", name = "json_feed")]
+pub(crate) async fn json(
+    req: HttpRequest,
+    store: web::Data<Store>,
+    query: web::Query<FeedQuery>,
+) -> error::Result<HttpResponse> {
+    let feed = Feed::new(&req, &store, &query, "json_feed")?;
+    let feed = JsonFeed {
+        version: "https://jsonfeed.org/version/1.1",
+        title: feed.title,
+        home_page_url: feed.home,
+        feed_url: feed.url,
+        items: feed.entries.into_iter().map(JsonItem::from).collect(),
+    };
+
+    let body = serde_json::to_string(&feed).map_err(error::ErrorInternalServerError)?;
+
+    Ok(view::fixed(&req, "application/feed+json", body))
+}
+
+pub(crate) fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(atom).service(json);
+}
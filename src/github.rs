@@ -0,0 +1,373 @@
+//! Serve suggestions from a file in a GitHub repository, and refresh them when
+//! the repository is pushed to.
+//!
+//! A service created with `view::make_github_service(…)` fetches its
+//! suggestions from a branch of a GitHub repository when it starts, and again
+//! whenever GitHub delivers a push webhook for that branch, so that merging a
+//! change to the suggestions file updates the running service within seconds.
+//!
+//! Suggestions are fetched from `raw.githubusercontent.com`, so the
+//! repository must be public. On a signed push, the file is fetched at the
+//! pushed commit, rather than at the tip of the branch, so that caches between
+//! here and GitHub can't serve a stale copy. Without a secret, anyone can
+//! deliver a push, so the pushed commit isn't trusted, and the file is fetched
+//! at the tip of the branch instead.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # use things_to_check::{github, view};
+//! # #[actix_web::main]
+//! # async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+//! use actix_web::{App, HttpServer};
+//!
+//! let repository: github::Repository = "ojacobson/things-to-check/src/things-to-check.yml@main".parse()?;
+//! let options = view::Options::default();
+//! let service = view::make_github_service(&repository, Some("s3kr1t"), &options)?;
+//!
+//! let app_factory = move ||
+//!     App::new()
+//!         .configure(|cfg| service(cfg));
+//!
+//! HttpServer::new(app_factory);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Endpoints
+//!
+//! * `/hooks/github` (`POST`): GitHub webhook deliveries.
+//!
+//!   Configure the repository's webhook to send `push` events to this
+//!   endpoint, with the `application/json` content type. If the service was
+//!   given a secret, deliveries must be signed with it, and unsigned or
+//!   wrongly-signed deliveries are rejected with `401 Unauthorized`; set the
+//!   same secret on the webhook. Pushes whose `after` isn't a commit's SHA are
+//!   rejected with `400 Bad Request`. Pushes to the served branch refresh the
+//!   suggestions, and return `204 No Content` once the new suggestions are
+//!   being served. Pushes to other branches, and other events, are ignored. If
+//!   the suggestions can't be fetched, or fail the checks called for by the
+//!   service's `Options`, this returns an error and the service keeps serving
+//!   the suggestions it already had.
+
+use actix_web::{error, post, web, HttpRequest, HttpResponse};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+use url::Url;
+
+use crate::things::{fetch_catalog, Catalog, Store};
+use crate::view::{Error, Options};
+
+/// A suggestions file on a branch of a GitHub repository.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Repository {
+    owner: String,
+    name: String,
+    path: String,
+    branch: String,
+}
+
+impl Repository {
+    /// The file at `path` on `branch` of the repository `owner/name`.
+    pub fn new(
+        owner: impl Into<String>,
+        name: impl Into<String>,
+        path: impl Into<String>,
+        branch: impl Into<String>,
+    ) -> Self {
+        Repository {
+            owner: owner.into(),
+            name: name.into(),
+            path: path.into(),
+            branch: branch.into(),
+        }
+    }
+
+    // The file's contents as of `rev`, which may be a branch or a commit.
+    fn url(&self, rev: &str) -> Url {
+        let mut url =
+            Url::parse("https://raw.githubusercontent.com/").expect("GitHub's base URL is valid");
+        url.path_segments_mut()
+            .expect("GitHub's base URL can have a path")
+            .pop_if_empty()
+            .push(&self.owner)
+            .push(&self.name)
+            .push(rev)
+            .extend(self.path.split('/'));
+        url
+    }
+
+    pub(crate) fn fetch(&self, rev: &str) -> Result<Catalog, Error> {
+        fetch_catalog(&self.url(rev))
+    }
+
+    pub(crate) fn branch(&self) -> &str {
+        &self.branch
+    }
+}
+
+/// Indicates that a string could not be parsed as a `Repository`.
+#[derive(Error, Debug)]
+#[error("{0:?} is not of the form owner/repository/path[@branch]")]
+pub struct InvalidRepository(String);
+
+/// The branch used when a `Repository` is parsed without one.
+pub const DEFAULT_BRANCH: &str = "main";
+
+impl FromStr for Repository {
+    type Err = InvalidRepository;
+
+    /// Parse a repository from `owner/repository/path`, optionally followed
+    /// by `@branch`. The branch defaults to `main`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidRepository(s.to_owned());
+
+        let (location, branch) = match s.rsplit_once('@') {
+            Some((location, branch)) => (location, branch),
+            None => (s, DEFAULT_BRANCH),
+        };
+        let mut parts = location.splitn(3, '/');
+        let (owner, name, path) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(owner), Some(name), Some(path)) => (owner, name, path),
+            _ => return Err(invalid()),
+        };
+        if [owner, name, path, branch]
+            .iter()
+            .any(|part| part.is_empty())
+        {
+            return Err(invalid());
+        }
+
+        Ok(Repository::new(owner, name, path, branch))
+    }
+}
+
+impl fmt::Display for Repository {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{}/{}@{}",
+            self.owner, self.name, self.path, self.branch
+        )
+    }
+}
+
+/// Refreshes the suggestions served by a running service from GitHub.
+pub(crate) struct Hook {
+    repository: Repository,
+    secret: Option<String>,
+    options: Options,
+    store: web::Data<Store>,
+}
+
+impl Hook {
+    pub(crate) fn new(
+        repository: Repository,
+        secret: Option<String>,
+        options: Options,
+        store: web::Data<Store>,
+    ) -> Self {
+        Hook {
+            repository,
+            secret,
+            options,
+            store,
+        }
+    }
+
+    // GitHub signs deliveries with an HMAC of the body, keyed with the
+    // webhook's secret, in the X-Hub-Signature-256 header.
+    fn verify(&self, req: &HttpRequest, body: &[u8]) -> error::Result<()> {
+        let secret = match &self.secret {
+            Some(secret) => secret,
+            None => return Ok(()),
+        };
+        let signature = req
+            .headers()
+            .get("X-Hub-Signature-256")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("sha256="))
+            .and_then(|value| hex::decode(value).ok());
+
+        match signature {
+            Some(signature) if signed(secret, body, &signature) => Ok(()),
+            _ => Err(error::ErrorUnauthorized("Unauthorized")),
+        }
+    }
+
+    fn refresh(&self, rev: &str) -> Result<(), Error> {
+        let catalog = self.store.record(self.fetch(rev))?;
+        self.store.replace(catalog);
+
+        Ok(())
+    }
+
+    fn fetch(&self, rev: &str) -> Result<Catalog, Error> {
+        let mut catalog = self.repository.fetch(rev)?;
+        catalog.check(self.options.strictness)?;
+        catalog.exclude(&self.options.exclude);
+        catalog.render(self.options.markdown);
+
+        Ok(catalog)
+    }
+}
+
+// Checks `signature` in constant time, so that response times don't reveal
+// how much of a forged signature is right.
+fn signed(secret: &str, body: &[u8], signature: &[u8]) -> bool {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    mac.verify_slice(signature).is_ok()
+}
+
+// Whether `rev` is a full commit SHA: 40 hexadecimal digits.
+fn is_commit(rev: &str) -> bool {
+    rev.len() == 40 && rev.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+#[derive(Deserialize)]
+struct Push {
+    #[serde(rename = "ref")]
+    reference: String,
+    after: String,
+    #[serde(default)]
+    deleted: bool,
+}
+
+#[utoipa::path(
+    post,
+    path = "/hooks/github",
+    tag = "github",
+    request_body(content = String, description = "A GitHub webhook delivery", content_type = "application/json"),
+    responses(
+        (status = 204, description = "The delivery was handled"),
+        (status = 400, description = "The delivery was not a valid push event"),
+        (status = 401, description = "The delivery's signature was missing or incorrect"),
+        (status = 502, description = "The suggestions could not be refreshed"),
+    ),
+)]
+#[post("/hooks/github")]
+pub(crate) async fn push(
+    req: HttpRequest,
+    hook: web::Data<Hook>,
+    body: web::Bytes,
+) -> error::Result<HttpResponse> {
+    hook.verify(&req, &body)?;
+
+    let event = req
+        .headers()
+        .get("X-GitHub-Event")
+        .and_then(|value| value.to_str().ok());
+    if event != Some("push") {
+        return Ok(HttpResponse::NoContent().finish());
+    }
+
+    let push: Push = serde_json::from_slice(&body).map_err(error::ErrorBadRequest)?;
+    if !is_commit(&push.after) {
+        return Err(error::ErrorBadRequest("after is not a commit SHA"));
+    }
+    let branch = format!("refs/heads/{}", hook.repository.branch());
+    if push.reference != branch || push.deleted {
+        return Ok(HttpResponse::NoContent().finish());
+    }
+
+    // Unsigned deliveries could name any commit, including old ones, or ones
+    // from forks, which GitHub serves under this repository's path.
+    let rev = match hook.secret {
+        Some(_) => push.after,
+        None => hook.repository.branch().to_owned(),
+    };
+
+    // Fetching blocks, so keep it off the thread serving requests.
+    web::block(move || hook.refresh(&rev))
+        .await?
+        .map_err(|err| {
+            eprintln!("Unable to refresh suggestions from GitHub: {}", err);
+            error::ErrorBadGateway(err)
+        })?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repository_parses_with_branch() {
+        let repository: Repository = "ojacobson/things-to-check/data/things.yml@release"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            repository,
+            Repository::new("ojacobson", "things-to-check", "data/things.yml", "release")
+        );
+    }
+
+    #[test]
+    fn repository_branch_defaults_to_main() {
+        let repository: Repository = "ojacobson/things-to-check/things.yml".parse().unwrap();
+
+        assert_eq!(repository.branch(), DEFAULT_BRANCH);
+    }
+
+    #[test]
+    fn repository_requires_a_path() {
+        assert!("ojacobson/things-to-check".parse::<Repository>().is_err());
+        assert!("ojacobson/things-to-check/".parse::<Repository>().is_err());
+        assert!("ojacobson/things-to-check/things.yml@"
+            .parse::<Repository>()
+            .is_err());
+    }
+
+    #[test]
+    fn repository_url_names_the_revision() {
+        let repository = Repository::new("ojacobson", "things-to-check", "data/things.yml", "main");
+
+        assert_eq!(
+            repository.url("0123abcd").as_str(),
+            "https://raw.githubusercontent.com/ojacobson/things-to-check/0123abcd/data/things.yml"
+        );
+    }
+
+    #[test]
+    fn pushes_name_commits() {
+        assert!(is_commit("0123456789abcdef0123456789abcdef01234567"));
+        assert!(!is_commit("0123abcd"));
+        assert!(!is_commit("main"));
+        assert!(!is_commit(
+            "../../attacker/things-to-check/0123456789abcdef0"
+        ));
+        assert!(!is_commit("0123456789abcdef0123456789abcdef0123456g"));
+    }
+
+    #[test]
+    fn signatures_are_checked() {
+        // The example from GitHub's webhook documentation.
+        let signature =
+            hex::decode("757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17")
+                .unwrap();
+
+        assert!(signed(
+            "It's a Secret to Everybody",
+            b"Hello, World!",
+            &signature
+        ));
+        assert!(!signed(
+            "It's a Secret to Nobody",
+            b"Hello, World!",
+            &signature
+        ));
+        assert!(!signed(
+            "It's a Secret to Everybody",
+            b"Hello, World?",
+            &signature
+        ));
+    }
+}
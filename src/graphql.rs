@@ -0,0 +1,250 @@
+//! A GraphQL endpoint, for GraphQL gateways.
+//!
+//! This module is only available with the `graphql` feature. With it, every
+//! `make_*service(…)` function also mounts:
+//!
+//! * `/graphql` (`POST`): executes a GraphQL request, sent as JSON with
+//!   `query`, and optionally `operationName` and `variables`, fields, and
+//!   responds with the result as JSON.
+//!
+//! The schema offers these queries, whose arguments are as for the URL
+//! parameters of the same name to `/`:
+//!
+//! * `suggestion(list, item, tag, lang)`: a suggestion, chosen at random from
+//!   those that haven't been retired unless `item` is given, or `null` if there
+//!   is no such item or no suggestion carries the tag. As for `/`, an `item`
+//!   that has since been renamed finds the suggestion it now names.
+//!
+//! * `suggestions(list, tag, lang)`: every suggestion in a list, in order.
+//!
+//! * `search(q, list, lang)`: the suggestions matching a search, best matches
+//!   first, as for `/search`.
+//!
+//! * `lists`: the names of every list.
+//!
+//! Suggestions have the same fields as in the JSON API, named in camel case.
+//! Unknown lists are errors. The schema, in GraphQL's schema language, can be
+//! fetched by introspection.
+//!
+//! Requests to `/graphql` count against the service's rate limit, if any.
+
+use actix_web::{error, post, web, HttpRequest, HttpResponse};
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, Error, Object, Result, SimpleObject,
+};
+use std::iter;
+
+use crate::limit::Limited;
+use crate::things::{Rendered, Store, Thing, Things};
+use crate::view::{self, ItemQuery, MAX_RESULTS};
+
+/// The schema served at `/graphql`.
+pub(crate) type Schema = async_graphql::Schema<Query, EmptyMutation, EmptySubscription>;
+
+pub(crate) fn schema(store: web::Data<Store>) -> Schema {
+    Schema::build(Query, EmptyMutation, EmptySubscription)
+        .data(store)
+        .finish()
+}
+
+#[derive(SimpleObject)]
+struct Details {
+    markdown: String,
+    html: String,
+}
+
+impl From<Rendered> for Details {
+    fn from(rendered: Rendered) -> Self {
+        Details {
+            markdown: rendered.markdown,
+            html: rendered.html,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+struct Suggestion {
+    id: String,
+    list: String,
+    markdown: String,
+    html: String,
+    url: String,
+    lang: String,
+    details: Option<Details>,
+    tags: Vec<String>,
+    links: Vec<String>,
+    author: Option<String>,
+    added: Option<String>,
+    context: Option<String>,
+    retired: bool,
+    number: usize,
+    total: usize,
+}
+
+// The index page's URL, for building permalinks. Requests can't be shared
+// across threads, as resolvers' data must be, so the URL is worked out before
+// the query runs.
+struct Index(url::Url);
+
+impl Index {
+    fn suggestion(
+        &self,
+        query: &ItemQuery,
+        list: &str,
+        things: &Things,
+        entry: &(usize, Thing),
+    ) -> Result<Suggestion> {
+        let (index, thing) = entry;
+        let position = things.position(*index);
+        let id = thing.item(*index);
+
+        let url = view::permalink(&self.0, query, thing)?;
+
+        let thing = thing.translated(query.lang.as_deref());
+        Ok(Suggestion {
+            id,
+            list: list.to_owned(),
+            markdown: thing.markdown,
+            html: thing.html,
+            url: url.into(),
+            lang: thing.lang,
+            details: thing.details.map(Details::from),
+            tags: thing.tags,
+            links: thing.links,
+            author: thing.author,
+            added: thing.added,
+            context: thing.context,
+            retired: thing.retired,
+            number: position.number,
+            total: position.total,
+        })
+    }
+}
+
+pub(crate) struct Query;
+
+impl Query {
+    // Run `f` on the list named by `query`, or fail if there's no such list.
+    fn with_list<T>(
+        ctx: &Context<'_>,
+        query: &ItemQuery,
+        f: impl FnOnce(&Index, &str, &Things) -> Result<T>,
+    ) -> Result<T> {
+        let store = ctx.data::<web::Data<Store>>()?;
+        let index = ctx.data::<Index>()?;
+
+        let catalog = store.current();
+        let (list, things) = catalog
+            .named_list(query.list.as_deref())
+            .ok_or_else(|| Error::new("Not found"))?;
+
+        f(index, list, things)
+    }
+}
+
+#[Object]
+impl Query {
+    /// A suggestion, chosen at random unless `item` is given.
+    async fn suggestion(
+        &self,
+        ctx: &Context<'_>,
+        list: Option<String>,
+        item: Option<String>,
+        tag: Option<String>,
+        lang: Option<String>,
+    ) -> Result<Option<Suggestion>> {
+        let query = ItemQuery {
+            item,
+            list,
+            tag,
+            lang,
+        };
+        Query::with_list(ctx, &query, |index, list, things| {
+            let item = query.item.as_deref();
+            let tag = query.tag.as_deref();
+            let chosen = match item.and_then(|item| things.aliased(item)) {
+                Some(aliased) => Some(aliased).filter(|(_, thing)| thing.has_tag(tag)),
+                None => things.choose(item, tag),
+            };
+
+            chosen
+                .map(|entry| index.suggestion(&query, list, things, entry))
+                .transpose()
+        })
+    }
+
+    /// Every suggestion in a list carrying `tag`, in order.
+    async fn suggestions(
+        &self,
+        ctx: &Context<'_>,
+        list: Option<String>,
+        tag: Option<String>,
+        lang: Option<String>,
+    ) -> Result<Vec<Suggestion>> {
+        let query = ItemQuery {
+            item: None,
+            list,
+            tag,
+            lang,
+        };
+        Query::with_list(ctx, &query, |index, list, things| {
+            things
+                .0
+                .iter()
+                .filter(|(_, thing)| thing.has_tag(query.tag.as_deref()))
+                .map(|entry| index.suggestion(&query, list, things, entry))
+                .collect()
+        })
+    }
+
+    /// The suggestions matching the search `q`, best matches first.
+    async fn search(
+        &self,
+        ctx: &Context<'_>,
+        q: String,
+        list: Option<String>,
+        lang: Option<String>,
+    ) -> Result<Vec<Suggestion>> {
+        let query = ItemQuery {
+            item: None,
+            list,
+            tag: None,
+            lang,
+        };
+        Query::with_list(ctx, &query, |index, list, things| {
+            things
+                .search(&q)
+                .into_iter()
+                .take(MAX_RESULTS)
+                .map(|entry| index.suggestion(&query, list, things, entry))
+                .collect()
+        })
+    }
+
+    /// The names of every list.
+    async fn lists(&self, ctx: &Context<'_>) -> Result<Vec<String>> {
+        let store = ctx.data::<web::Data<Store>>()?;
+        Ok(store.current().0.keys().cloned().collect())
+    }
+}
+
+#[post("/graphql")]
+pub(crate) async fn graphql(
+    _: Limited,
+    req: HttpRequest,
+    schema: web::Data<Schema>,
+    request: web::Json<async_graphql::Request>,
+) -> error::Result<HttpResponse> {
+    let index = req.url_for("index", iter::empty::<&str>())?;
+    let response = schema
+        .execute(request.into_inner().data(Index(index)))
+        .await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(("Cache-Control", "no-store"))
+        .json(response))
+}
+
+pub(crate) fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(graphql);
+}
@@ -0,0 +1,291 @@
+//! Syntax highlighting for the code blocks in suggestions.
+//!
+//! Suggestions hold short snippets: commands to run, and lines of
+//! configuration to look for. Rather than a full grammar for each language,
+//! fenced code blocks tagged with a language this module knows are split into
+//! a few kinds of token, which pages color with CSS:
+//!
+//! * `hl-comment`: comments, to the end of the line.
+//! * `hl-string`: quoted strings.
+//! * `hl-number`: numbers standing alone.
+//! * `hl-literal`: words with a fixed meaning, such as `true` and `null`.
+//! * `hl-key`: the keys of configuration files, and their section headers.
+//! * `hl-variable`: shell variables, such as `$HOME` and `${PATH}`.
+//! * `hl-flag`: command-line options, such as `-v` and `--dry-run`.
+//! * `hl-prompt`: the `$ ` prompt before commands in `console` blocks.
+//!
+//! Blocks in other languages, or with no language, are left as they are.
+
+use pulldown_cmark::escape::escape_html;
+
+/// What to look for in one language.
+struct Syntax {
+    /// What starts a comment, at the start of a line or after whitespace.
+    comments: &'static [&'static str],
+    /// Whether `$NAME` and `${NAME}` are variables.
+    variables: bool,
+    /// Whether words starting with `-` are options.
+    flags: bool,
+    /// Whether lines may start with a key, followed by `:` or `=`.
+    keys: bool,
+    /// Whether lines may be `[section]` headers.
+    sections: bool,
+    /// Whether lines may start with a `$ ` prompt.
+    prompts: bool,
+    /// Words with a fixed meaning.
+    literals: &'static [&'static str],
+}
+
+const SHELL: Syntax = Syntax {
+    comments: &["#"],
+    variables: true,
+    flags: true,
+    keys: false,
+    sections: false,
+    prompts: false,
+    literals: &[],
+};
+
+const CONSOLE: Syntax = Syntax {
+    prompts: true,
+    ..SHELL
+};
+
+const YAML: Syntax = Syntax {
+    comments: &["#"],
+    variables: false,
+    flags: false,
+    keys: true,
+    sections: false,
+    prompts: false,
+    literals: &["true", "false", "null", "yes", "no", "on", "off"],
+};
+
+const INI: Syntax = Syntax {
+    comments: &["#", ";"],
+    sections: true,
+    literals: &["true", "false"],
+    ..YAML
+};
+
+const JSON: Syntax = Syntax {
+    comments: &[],
+    literals: &["true", "false", "null"],
+    ..YAML
+};
+
+fn syntax(lang: &str) -> Option<&'static Syntax> {
+    match lang.to_ascii_lowercase().as_str() {
+        "sh" | "bash" | "shell" | "zsh" => Some(&SHELL),
+        "console" | "shell-session" => Some(&CONSOLE),
+        "yaml" | "yml" => Some(&YAML),
+        "toml" | "ini" | "conf" | "cfg" | "properties" => Some(&INI),
+        "json" => Some(&JSON),
+        _ => None,
+    }
+}
+
+/// The HTML for the code block `code` in the language `lang`, escaped and
+/// with its tokens wrapped in `span`s, or `None` if there's no syntax for
+/// `lang`.
+pub(crate) fn highlight(lang: &str, code: &str) -> Option<String> {
+    let syntax = syntax(lang)?;
+    let mut html = String::new();
+    for line in code.split_inclusive('\n') {
+        highlight_line(syntax, line, &mut html);
+    }
+
+    Some(html)
+}
+
+fn push(html: &mut String, class: &str, text: &str) {
+    html.push_str("<span class=\"hl-");
+    html.push_str(class);
+    html.push_str("\">");
+    // Writing to a String never fails.
+    let _ = escape_html(&mut *html, text);
+    html.push_str("</span>");
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// The length of the quoted string at the start of `text`, which starts with
+// a quote, up to and including the closing quote or the end of the line.
+fn quoted(text: &str) -> usize {
+    let mut chars = text.char_indices();
+    let quote = match chars.next() {
+        Some((_, quote)) => quote,
+        None => return 0,
+    };
+    let mut escaped = false;
+    for (i, c) in chars {
+        match c {
+            '\n' => return i,
+            '\\' if quote == '"' && !escaped => escaped = true,
+            c if c == quote && !escaped => return i + c.len_utf8(),
+            _ => escaped = false,
+        }
+    }
+
+    text.len()
+}
+
+// The length of the key at the start of `text`, if it's followed by `:` or
+// `=`.
+fn key(text: &str) -> Option<usize> {
+    let length = if text.starts_with('"') {
+        quoted(text)
+    } else {
+        text.find(|c: char| !(is_word(c) || "-.".contains(c)))
+            .unwrap_or(text.len())
+    };
+    let rest = text[length..].trim_start_matches([' ', '\t']);
+    let separated = rest.starts_with('=') || rest.starts_with(':');
+
+    Some(length).filter(|&length| length > 0 && separated)
+}
+
+fn highlight_line(syntax: &Syntax, line: &str, html: &mut String) {
+    let indent = line.len() - line.trim_start().len();
+    let _ = escape_html(&mut *html, &line[..indent]);
+    let mut rest = &line[indent..];
+
+    if syntax.prompts {
+        if let Some(command) = rest.strip_prefix("$ ") {
+            push(html, "prompt", "$ ");
+            rest = command;
+        }
+    }
+    if syntax.keys {
+        if let Some(item) = rest.strip_prefix("- ") {
+            html.push_str("- ");
+            rest = item;
+        }
+        if let Some(length) = key(rest) {
+            push(html, "key", &rest[..length]);
+            rest = &rest[length..];
+        }
+    }
+    if syntax.sections && rest.starts_with('[') {
+        if let Some(end) = rest.find(']') {
+            push(html, "key", &rest[..=end]);
+            rest = &rest[end + 1..];
+        }
+    }
+
+    // Whether the text before `rest` ends a word, so that `rest` may start
+    // a comment, number, literal, or flag.
+    let mut boundary = true;
+    while let Some(c) = rest.chars().next() {
+        let comment = boundary
+            && syntax
+                .comments
+                .iter()
+                .any(|comment| rest.starts_with(comment));
+        let (class, length) = if comment {
+            let length = rest.find('\n').unwrap_or(rest.len());
+            (Some("comment"), length)
+        } else if c == '"' || (c == '\'' && boundary) {
+            (Some("string"), quoted(rest))
+        } else if c == '$' && syntax.variables {
+            let name = &rest[1..];
+            let length = if name.starts_with('{') {
+                name.find('}').map_or(0, |end| end + 1)
+            } else {
+                name.find(|c: char| !is_word(c)).unwrap_or(name.len())
+            };
+            match length {
+                0 => (None, 1),
+                length => (Some("variable"), 1 + length),
+            }
+        } else if boundary && c == '-' && syntax.flags {
+            let length = rest
+                .find(|c: char| c.is_whitespace() || c == '=')
+                .unwrap_or(rest.len());
+            match length {
+                1 => (None, 1),
+                length => (Some("flag"), length),
+            }
+        } else if boundary && is_word(c) {
+            let length = rest.find(|c: char| !is_word(c)).unwrap_or(rest.len());
+            let mut word = &rest[..length];
+            // Decimals, such as `1.5`, are one number.
+            let decimal = rest[length..]
+                .strip_prefix('.')
+                .map(|fraction| {
+                    fraction
+                        .find(|c: char| !is_word(c))
+                        .unwrap_or(fraction.len())
+                })
+                .filter(|&fraction| fraction > 0);
+            if let Some(fraction) = decimal {
+                word = &rest[..length + 1 + fraction];
+            }
+            let class = if word.chars().all(|c| c.is_ascii_digit() || c == '.') {
+                Some("number")
+            } else if syntax.literals.contains(&word) {
+                Some("literal")
+            } else {
+                None
+            };
+            (class, word.len())
+        } else {
+            (None, c.len_utf8())
+        };
+
+        let (token, remainder) = rest.split_at(length);
+        match class {
+            Some(class) => push(html, class, token),
+            None => {
+                let _ = escape_html(&mut *html, token);
+            }
+        }
+        boundary = !token.ends_with(is_word);
+        rest = remainder;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shell_commands_are_highlighted() {
+        assert_eq!(
+            highlight("sh", "dig +short $HOST --tries=3 # \"DNS\"\n").unwrap(),
+            "dig +short <span class=\"hl-variable\">$HOST</span> \
+             <span class=\"hl-flag\">--tries</span>=<span class=\"hl-number\">3</span> \
+             <span class=\"hl-comment\"># &quot;DNS&quot;</span>\n"
+        );
+        assert_eq!(
+            highlight("console", "$ echo 'a<b'\n").unwrap(),
+            "<span class=\"hl-prompt\">$ </span>echo <span class=\"hl-string\">'a&lt;b'</span>\n"
+        );
+    }
+
+    #[test]
+    fn configuration_keys_are_highlighted() {
+        assert_eq!(
+            highlight("yaml", "- mtu: 1500\n  enabled: yes\n").unwrap(),
+            "- <span class=\"hl-key\">mtu</span>: <span class=\"hl-number\">1500</span>\n  \
+             <span class=\"hl-key\">enabled</span>: <span class=\"hl-literal\">yes</span>\n"
+        );
+        assert_eq!(
+            highlight("toml", "[server]\nport = 8080 ; web\n").unwrap(),
+            "<span class=\"hl-key\">[server]</span>\n<span class=\"hl-key\">port</span> = \
+             <span class=\"hl-number\">8080</span> <span class=\"hl-comment\">; web</span>\n"
+        );
+        assert_eq!(
+            highlight("json", r#"{"ttl": 0.5}"#).unwrap(),
+            r#"{<span class="hl-string">&quot;ttl&quot;</span>: <span class="hl-number">0.5</span>}"#
+        );
+    }
+
+    #[test]
+    fn unknown_languages_are_not_highlighted() {
+        assert!(highlight("cobol", "DISPLAY 'HI'.").is_none());
+        assert!(highlight("", "ls").is_none());
+    }
+}
@@ -0,0 +1,327 @@
+//! An IRC bot, for asking for suggestions from IRC channels.
+//!
+//! This module is only available with the `irc` feature. A service whose
+//! `view::Options` carry `Irc` servers connects to each of them as a bot,
+//! joins the configured channels, and replies to messages starting with
+//! `!troubleshoot` with a suggestion:
+//!
+//! ```
+//! # use things_to_check::{irc, view};
+//! # fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+//! let bot = irc::Irc::new("irc.libera.chat", "troubleshoot")
+//!     .joining(vec!["#ops".to_owned()]);
+//! let options = view::Options {
+//!     irc: vec![bot],
+//!     ..Default::default()
+//! };
+//! let service = view::make_service_with(&options)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! `!troubleshoot` replies with a random suggestion, as plain text. Its text
+//! is read as for the Slack app's command: `!troubleshoot 42`, a single word
+//! naming a suggestion, replies with that suggestion, and other words reply
+//! with a random suggestion matching them. Replies end with the suggestion's
+//! permalink if the bot is given the service's public URL with
+//! `Irc::linking_to`. Suggestions are chosen from the default list, the same
+//! one the web endpoints serve, and reflect it as it's reloaded. Messages sent
+//! to the bot directly are answered directly.
+//!
+//! Connections use TLS, on port 6697 unless `Irc::on_port` says otherwise.
+//!
+//! # Twitch
+//!
+//! Twitch chat is IRC too, so streamers can let viewers ask for suggestions
+//! while debugging live. `Irc::twitch` connects to Twitch's chat server with
+//! a bot account's OAuth token; join channels as `#` and the channel's name.
+//! Busy chats can ask for suggestions faster than anyone can read them, so
+//! give the bot a cooldown with `Irc::cooling_down`, and it ignores commands
+//! in each channel until that long after it last answered there.
+//!
+//! Like a `schedule::Schedule`, each bot starts when the service is first
+//! configured into an App, and runs on that App's worker until the runtime
+//! shuts down. Failures to connect or reply are reported on stderr, and stop
+//! the bot for that server.
+
+use ::irc::client::prelude::{Client, Command, Config};
+use actix_web::web;
+use futures_util::StreamExt;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+use url::Url;
+
+use crate::schedule::permalink;
+use crate::slack::Ask;
+use crate::things::Store;
+
+/// The command the bot answers.
+const COMMAND: &str = "!troubleshoot";
+
+/// Where Twitch's chat server lives.
+const TWITCH_SERVER: &str = "irc.chat.twitch.tv";
+
+/// Which server the bot connects to, as whom, and where it answers.
+#[derive(Clone)]
+pub struct Irc {
+    server: String,
+    port: Option<u16>,
+    nickname: String,
+    password: Option<String>,
+    channels: Vec<String>,
+    index: Option<Url>,
+    cooldown: Option<Duration>,
+}
+
+impl Irc {
+    /// Connect to `server`, such as `irc.libera.chat`, as `nickname`.
+    pub fn new(server: impl Into<String>, nickname: impl Into<String>) -> Self {
+        Irc {
+            server: server.into(),
+            port: None,
+            nickname: nickname.into(),
+            password: None,
+            channels: vec![],
+            index: None,
+            cooldown: None,
+        }
+    }
+
+    /// Connect to Twitch chat as the bot account `nickname`, with the OAuth
+    /// token `token`, with or without its `oauth:` prefix.
+    pub fn twitch(nickname: impl Into<String>, token: &str) -> Self {
+        let token = token.strip_prefix("oauth:").unwrap_or(token);
+        // Twitch only knows nicknames in lowercase.
+        Irc::new(TWITCH_SERVER, nickname.into().to_lowercase())
+            .with_password(format!("oauth:{}", token))
+    }
+
+    /// Connect on `port`, rather than 6697.
+    pub fn on_port(self, port: u16) -> Self {
+        Irc {
+            port: Some(port),
+            ..self
+        }
+    }
+
+    /// Send `password` on connecting, as the server password.
+    pub fn with_password(self, password: impl Into<String>) -> Self {
+        Irc {
+            password: Some(password.into()),
+            ..self
+        }
+    }
+
+    /// Join each of `channels`, such as `#ops`, on connecting.
+    pub fn joining(self, channels: Vec<String>) -> Self {
+        Irc { channels, ..self }
+    }
+
+    /// Answer at most one command in each channel every `cooldown`.
+    pub fn cooling_down(self, cooldown: Duration) -> Self {
+        Irc {
+            cooldown: Some(cooldown),
+            ..self
+        }
+    }
+
+    /// End replies with suggestions' permalinks on the index page at `index`,
+    /// the service's public URL.
+    pub fn linking_to(self, index: Url) -> Self {
+        Irc {
+            index: Some(index),
+            ..self
+        }
+    }
+}
+
+// The password stays out of logs.
+impl fmt::Debug for Irc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Irc")
+            .field("server", &self.server)
+            .field("port", &self.port)
+            .field("nickname", &self.nickname)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .field("channels", &self.channels)
+            .field("index", &self.index)
+            .field("cooldown", &self.cooldown)
+            .finish()
+    }
+}
+
+// What a message asks of the bot, if it's a command: the rest of its text.
+fn asked(text: &str) -> Option<&str> {
+    let rest = text.trim_start().strip_prefix(COMMAND)?;
+    match rest.chars().next() {
+        None => Some(""),
+        Some(next) if next.is_whitespace() => Some(rest.trim()),
+        Some(_) => None,
+    }
+}
+
+// The text of a reply showing a suggestion chosen by the command's `text`, on
+// one line.
+fn reply(store: &Store, index: Option<&Url>, text: &str) -> String {
+    let catalog = store.current();
+    let things = catalog.list(None);
+    let ask = Ask::from_text(things, text);
+    let (_, thing) = match things.and_then(|things| ask.choose(things)) {
+        Some(chosen) => chosen,
+        None => return "Sorry, there are no suggestions to show.".to_owned(),
+    };
+
+    let text = thing
+        .text()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    match index.and_then(|index| permalink(index, thing)) {
+        Some(permalink) => format!("{} {}", text, permalink),
+        None => text,
+    }
+}
+
+// When each channel last had an answer, to hold off answering again until a
+// cooldown has passed.
+#[derive(Default)]
+struct Cooldowns {
+    answered: HashMap<String, Instant>,
+}
+
+impl Cooldowns {
+    // Whether to answer in `target` at `now`, noting the answer if so.
+    fn ready(&mut self, cooldown: Option<Duration>, target: &str, now: Instant) -> bool {
+        let cooldown = match cooldown {
+            Some(cooldown) => cooldown,
+            None => return true,
+        };
+        match self.answered.get(target) {
+            Some(&answered) if now.duration_since(answered) < cooldown => false,
+            _ => {
+                self.answered.insert(target.to_owned(), now);
+                true
+            }
+        }
+    }
+}
+
+/// Answers commands on one IRC server from a running service.
+pub(crate) struct Bot {
+    irc: Irc,
+    store: web::Data<Store>,
+}
+
+impl Bot {
+    pub(crate) fn new(irc: Irc, store: web::Data<Store>) -> Self {
+        Bot { irc, store }
+    }
+
+    /// Connect, join channels, and answer commands.
+    ///
+    /// This runs until the runtime shuts down, or until the bot can't connect
+    /// or loses touch with its server.
+    pub(crate) async fn run(self) {
+        let server = self.irc.server.clone();
+        if let Err(err) = self.answer().await {
+            eprintln!("IRC bot for {} stopped: {}", server, err);
+        }
+    }
+
+    async fn answer(self) -> Result<(), ::irc::error::Error> {
+        let Bot { irc, store } = self;
+
+        let config = Config {
+            server: Some(irc.server),
+            port: irc.port,
+            use_tls: Some(true),
+            nickname: Some(irc.nickname),
+            password: irc.password,
+            channels: irc.channels,
+            ..Default::default()
+        };
+        let mut client = Client::from_config(config).await?;
+        client.identify()?;
+
+        let mut cooldowns = Cooldowns::default();
+        let mut stream = client.stream()?;
+        while let Some(message) = stream.next().await.transpose()? {
+            let Command::PRIVMSG(_, text) = &message.command else {
+                continue;
+            };
+            let (Some(target), Some(text)) = (message.response_target(), asked(text)) else {
+                continue;
+            };
+            if !cooldowns.ready(irc.cooldown, target, Instant::now()) {
+                continue;
+            }
+
+            client.send_privmsg(target, reply(&store, irc.index.as_ref(), text))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::things::{load_catalog, Store};
+
+    #[test]
+    fn commands_start_messages() {
+        assert_eq!(asked("!troubleshoot"), Some(""));
+        assert_eq!(asked("  !troubleshoot 42\r\n"), Some("42"));
+        assert_eq!(asked("!troubleshooting"), None);
+        assert_eq!(asked("have you tried !troubleshoot"), None);
+    }
+
+    #[test]
+    fn replies_are_plain_text() {
+        let catalog = load_catalog("- Have you checked `resolv.conf`?\n").unwrap();
+        let store = Store::new(catalog);
+        let index = Url::parse("https://example.com/").unwrap();
+
+        assert_eq!(
+            reply(&store, Some(&index), "0"),
+            "Have you checked resolv.conf? https://example.com/check/have-you-checked-resolv-conf"
+        );
+        assert_eq!(reply(&store, None, ""), "Have you checked resolv.conf?");
+    }
+
+    #[test]
+    fn channels_cool_down() {
+        let mut cooldowns = Cooldowns::default();
+        let cooldown = Some(Duration::from_secs(30));
+        let start = Instant::now();
+
+        assert!(cooldowns.ready(cooldown, "#stream", start));
+        assert!(cooldowns.ready(cooldown, "#other", start));
+        assert!(!cooldowns.ready(cooldown, "#stream", start + Duration::from_secs(10)));
+        assert!(cooldowns.ready(cooldown, "#stream", start + Duration::from_secs(30)));
+        assert!(cooldowns.ready(None, "#stream", start + Duration::from_secs(31)));
+    }
+
+    #[test]
+    fn twitch_tokens_are_passwords() {
+        let irc = Irc::twitch("TroubleBot", "abc123");
+
+        assert_eq!(irc.server, TWITCH_SERVER);
+        assert_eq!(irc.nickname, "troublebot");
+        assert_eq!(irc.password.as_deref(), Some("oauth:abc123"));
+        assert_eq!(
+            Irc::twitch("troublebot", "oauth:abc123")
+                .password
+                .as_deref(),
+            Some("oauth:abc123")
+        );
+    }
+
+    #[test]
+    fn passwords_stay_out_of_logs() {
+        let irc = Irc::new("irc.example.com", "troubleshoot").with_password("s3kr1t");
+
+        assert!(!format!("{:?}", irc).contains("s3kr1t"));
+    }
+}
@@ -1,2 +1,52 @@
+pub mod access_log;
+pub mod admin;
+pub mod api;
+pub mod assets;
+mod card;
+pub mod config;
+pub mod counts;
+pub mod crawlers;
+pub mod discord;
+pub mod embed;
+mod emoji;
+pub mod feed;
+pub mod github;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+mod highlight;
+#[cfg(feature = "irc")]
+pub mod irc;
+pub mod limit;
+mod locale;
+#[cfg(feature = "matrix")]
+pub mod matrix;
+pub mod mattermost;
+pub mod moderation;
+pub mod notify;
+pub mod poster;
+pub mod provider;
+pub mod pwa;
+pub mod reload;
+pub mod remote;
+pub mod request_id;
+pub mod rocketchat;
+pub mod schedule;
+pub mod seen;
+pub mod server;
+pub mod shuffle;
+pub mod slack;
+pub mod socket;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod stream;
+pub mod teams;
+pub mod telegram;
+pub mod templates;
+pub mod theme;
+mod things;
+#[cfg(feature = "otel")]
+pub mod trace;
 pub mod twelve;
 pub mod view;
+pub mod webex;
+pub mod zulip;
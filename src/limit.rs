@@ -0,0 +1,209 @@
+//! Per-client rate limiting.
+//!
+//! Services set up with a `RateLimit` in their `view::Options` limit how often
+//! each client can ask for suggestions, so that a misbehaving integration
+//! can't hammer the instance:
+//!
+//! ```
+//! # use things_to_check::{limit, view};
+//! # fn main() -> std::result::Result<(), things_to_check::view::Error> {
+//! let options = view::Options {
+//!     rate_limit: Some(limit::RateLimit {
+//!         burst: 20,
+//!         per_second: 1.0,
+//!         forwarded: false,
+//!     }),
+//!     ..Default::default()
+//! };
+//! let service = view::make_service_with(&options)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Each client, identified by IP address, has a bucket of `burst` tokens,
+//! which refills at `per_second` tokens per second. Every limited request
+//! takes a token; requests that find the bucket empty get `429 Too Many
+//! Requests`, with a `Retry-After` header giving the number of seconds until
+//! the next token.
+//!
+//! The endpoints that pick or search for suggestions are limited, along with
+//! `/suggest` and the generated images. Health checks, version and OpenAPI
+//! documents, webhooks, the chat integrations, and the moderators' endpoints are
+//! not.
+
+use actix_web::dev::Payload;
+use actix_web::http::header;
+use actix_web::{error, web, FromRequest, HttpRequest, HttpResponse};
+use std::collections::HashMap;
+use std::future::{ready, Ready};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// The most clients tracked at once. Beyond this, clients whose buckets have
+/// refilled are forgotten.
+const MAX_CLIENTS: usize = 10_000;
+
+/// How often each client may make limited requests.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateLimit {
+    /// The most requests a client may make at once, after a quiet period.
+    pub burst: u32,
+    /// The sustained number of requests per second a client may make. Must be
+    /// positive.
+    pub per_second: f64,
+    /// Whether to identify clients by the `Forwarded` or `X-Forwarded-For`
+    /// headers, rather than by the address connecting to the service. Only
+    /// set this behind a proxy that sets these headers, such as Heroku's
+    /// router, as clients can otherwise forge them to evade the limit.
+    pub forwarded: bool,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Every client's token bucket.
+pub(crate) struct Limiter {
+    limit: RateLimit,
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+impl Limiter {
+    pub(crate) fn new(limit: RateLimit) -> Self {
+        Limiter {
+            limit,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<IpAddr, Bucket>> {
+        self.buckets.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    // The tokens in a bucket last updated at `updated`, as of `now`.
+    fn refilled(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        (bucket.tokens + elapsed * self.limit.per_second).min(f64::from(self.limit.burst))
+    }
+
+    /// Take a token from `client`'s bucket, or return how long until one is
+    /// available.
+    fn take(&self, client: IpAddr, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.lock();
+        if buckets.len() >= MAX_CLIENTS && !buckets.contains_key(&client) {
+            let burst = f64::from(self.limit.burst);
+            buckets.retain(|_, bucket| self.refilled(bucket, now) < burst);
+        }
+
+        let bucket = buckets.entry(client).or_insert(Bucket {
+            tokens: f64::from(self.limit.burst),
+            updated: now,
+        });
+        bucket.tokens = self.refilled(bucket, now);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            let wait = (1.0 - bucket.tokens) / self.limit.per_second;
+            Err(Duration::try_from_secs_f64(wait).unwrap_or(Duration::MAX))
+        }
+    }
+
+    fn client(&self, req: &HttpRequest) -> Option<IpAddr> {
+        if !self.limit.forwarded {
+            return req.peer_addr().map(|addr| addr.ip());
+        }
+
+        let info = req.connection_info();
+        let addr = info.realip_remote_addr()?;
+        addr.parse()
+            .ok()
+            .or_else(|| addr.parse().ok().map(|addr: SocketAddr| addr.ip()))
+    }
+
+    fn check(&self, req: &HttpRequest) -> error::Result<()> {
+        // Clients that can't be identified can't be limited.
+        let client = match self.client(req) {
+            Some(client) => client,
+            None => return Ok(()),
+        };
+
+        self.take(client, Instant::now()).map_err(|wait| {
+            let retry_after = wait.as_secs_f64().ceil().min(u32::MAX.into()) as u32;
+            let response = HttpResponse::TooManyRequests()
+                .insert_header((header::RETRY_AFTER, retry_after.max(1)))
+                .finish();
+            error::InternalError::from_response("Too many requests", response).into()
+        })
+    }
+}
+
+/// Takes a token from the requesting client's bucket, if the service is rate
+/// limited, and fails with `429 Too Many Requests` if there are none left.
+/// Handlers that should be limited take this as an argument.
+pub(crate) struct Limited;
+
+impl Limited {
+    /// Take a token for `req`, as extracting `Limited` does. Endpoints that
+    /// handle several requests over one connection use this to limit each
+    /// one.
+    pub(crate) fn check(req: &HttpRequest) -> error::Result<Self> {
+        match req.app_data::<web::Data<Limiter>>() {
+            Some(limiter) => limiter.check(req).map(|()| Limited),
+            None => Ok(Limited),
+        }
+    }
+}
+
+impl FromRequest for Limited {
+    type Error = error::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Limited::check(req))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    fn limiter(burst: u32, per_second: f64) -> Limiter {
+        Limiter::new(RateLimit {
+            burst,
+            per_second,
+            forwarded: false,
+        })
+    }
+
+    #[test]
+    fn buckets_empty_and_refill() {
+        let limiter = limiter(2, 0.5);
+        let client = IpAddr::from(Ipv4Addr::LOCALHOST);
+        let start = Instant::now();
+
+        assert!(limiter.take(client, start).is_ok());
+        assert!(limiter.take(client, start).is_ok());
+        assert_eq!(limiter.take(client, start), Err(Duration::from_secs(2)));
+
+        let later = start + Duration::from_secs(2);
+        assert!(limiter.take(client, later).is_ok());
+        assert!(limiter.take(client, later).is_err());
+    }
+
+    #[test]
+    fn clients_have_separate_buckets() {
+        let limiter = limiter(1, 1.0);
+        let now = Instant::now();
+
+        assert!(limiter.take(IpAddr::from([192, 0, 2, 1]), now).is_ok());
+        assert!(limiter.take(IpAddr::from([192, 0, 2, 2]), now).is_ok());
+        assert!(limiter.take(IpAddr::from([192, 0, 2, 1]), now).is_err());
+    }
+}
@@ -0,0 +1,198 @@
+//! The HTML pages' fixed text, in the reader's language.
+//!
+//! Suggestions carry their own translations; this covers everything else on
+//! the pages, such as links and labels. Messages live in [Fluent][1] files
+//! under `src/locales`, one per language, compiled into the service. English
+//! is complete, and other languages fall back to it for any message they
+//! leave out.
+//!
+//! Pages pick a language from their `lang` parameter, if they have one and
+//! it's a language there are messages for, then from the request's
+//! `Accept-Language` header, and otherwise use English.
+//!
+//! [1]: https://projectfluent.org/
+
+use actix_web::http::header::{AcceptLanguage, Header, Preference};
+use actix_web::HttpRequest;
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use std::sync::OnceLock;
+use unic_langid::LanguageIdentifier;
+
+/// Each language's messages, by language. The first is the fallback.
+const MESSAGES: [(&str, &str); 4] = [
+    ("en", include_str!("locales/en.ftl")),
+    ("de", include_str!("locales/de.ftl")),
+    ("es", include_str!("locales/es.ftl")),
+    ("fr", include_str!("locales/fr.ftl")),
+];
+
+type Bundle = FluentBundle<FluentResource>;
+
+fn bundles() -> &'static [(&'static str, Bundle)] {
+    static BUNDLES: OnceLock<Vec<(&'static str, Bundle)>> = OnceLock::new();
+    BUNDLES.get_or_init(|| {
+        MESSAGES
+            .iter()
+            .map(|&(lang, ftl)| {
+                let id: LanguageIdentifier = lang.parse().expect("built-in language is valid");
+                let resource = FluentResource::try_new(ftl.to_owned())
+                    .expect("built-in messages are valid Fluent");
+                let mut bundle = FluentBundle::new_concurrent(vec![id]);
+                // Pages escape messages themselves; Unicode isolation marks
+                // around arguments would only get in the way.
+                bundle.set_use_isolating(false);
+                bundle
+                    .add_resource(resource)
+                    .expect("built-in messages are unique");
+                (lang, bundle)
+            })
+            .collect()
+    })
+}
+
+// The language with messages that best matches `lang`, such as `fr` for
+// `fr-CA`.
+fn supported(lang: &str) -> Option<&'static str> {
+    let primary = lang.split(['-', '_']).next()?.to_lowercase();
+    MESSAGES
+        .iter()
+        .map(|&(supported, _)| supported)
+        .find(|&supported| supported == primary)
+}
+
+/// The language a page's fixed text is shown in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Locale {
+    lang: &'static str,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Locale {
+            lang: MESSAGES[0].0,
+        }
+    }
+}
+
+impl Locale {
+    /// The language for `req`, preferring the page's `lang` parameter, if any,
+    /// over the request's `Accept-Language` header.
+    pub(crate) fn negotiate(req: &HttpRequest, lang: Option<&str>) -> Self {
+        let accepted = AcceptLanguage::parse(req)
+            .map(|accepted| accepted.ranked())
+            .unwrap_or_default();
+        let accepted = accepted.iter().filter_map(|preference| match preference {
+            Preference::Specific(tag) => supported(tag.as_str()),
+            Preference::Any => None,
+        });
+
+        lang.and_then(supported)
+            .into_iter()
+            .chain(accepted)
+            .next()
+            .map(|lang| Locale { lang })
+            .unwrap_or_default()
+    }
+
+    /// The language's tag, such as `fr`.
+    pub(crate) fn lang(&self) -> &'static str {
+        self.lang
+    }
+
+    /// The message `id`.
+    pub(crate) fn text(&self, id: &str) -> String {
+        self.format(id, None)
+    }
+
+    /// The message `id`, with its argument `name` set to `value`.
+    pub(crate) fn text_with(&self, id: &str, name: &'static str, value: &str) -> String {
+        self.text_with_all(id, &[(name, value)])
+    }
+
+    /// The message `id`, with each of its arguments set to a value.
+    pub(crate) fn text_with_all(&self, id: &str, values: &[(&'static str, &str)]) -> String {
+        let mut args = FluentArgs::new();
+        for &(name, value) in values {
+            args.set(name, value.to_owned());
+        }
+        self.format(id, Some(&args))
+    }
+
+    fn format(&self, id: &str, args: Option<&FluentArgs>) -> String {
+        let bundles = bundles();
+        let found = bundles
+            .iter()
+            .filter(|(lang, _)| *lang == self.lang)
+            .chain(bundles.first())
+            .find_map(|(_, bundle)| Some((bundle, bundle.get_message(id)?.value()?)));
+
+        match found {
+            Some((bundle, pattern)) => {
+                let mut errors = vec![];
+                bundle
+                    .format_pattern(pattern, args, &mut errors)
+                    .into_owned()
+            }
+            // A missing English message is a mistake in the templates; show
+            // its ID rather than failing the page.
+            None => id.to_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn every_language_has_every_message() {
+        let ids = MESSAGES[0]
+            .1
+            .lines()
+            .filter_map(|line| line.split_once(" = "))
+            .map(|(id, _)| id);
+
+        for id in ids {
+            for (lang, bundle) in bundles() {
+                assert!(bundle.get_message(id).is_some(), "{} lacks {}", lang, id);
+            }
+        }
+    }
+
+    #[test]
+    fn languages_come_from_parameters_then_headers() {
+        let req = TestRequest::default()
+            .insert_header(("Accept-Language", "de-CH, fr;q=0.8"))
+            .to_http_request();
+
+        assert_eq!(Locale::negotiate(&req, None).lang(), "de");
+        assert_eq!(Locale::negotiate(&req, Some("fr-CA")).lang(), "fr");
+        assert_eq!(Locale::negotiate(&req, Some("ja")).lang(), "de");
+
+        let req = TestRequest::default()
+            .insert_header(("Accept-Language", "ja, *;q=0.5"))
+            .to_http_request();
+        assert_eq!(Locale::negotiate(&req, None).lang(), "en");
+    }
+
+    #[test]
+    fn messages_take_arguments() {
+        let locale = Locale { lang: "fr" };
+
+        assert_eq!(
+            locale.text_with("suggested-by", "author", "Owen"),
+            "Suggérée par Owen"
+        );
+        assert_eq!(
+            Locale::default().text("no-matches"),
+            "No suggestions match."
+        );
+        assert_eq!(
+            locale.text_with_all("position", &[("number", "42"), ("total", "217")]),
+            "nº 42 sur 217"
+        );
+        assert_eq!(locale.text("no-such-message"), "no-such-message");
+    }
+}
@@ -0,0 +1,59 @@
+suggestion-title = Vorschlag zur Fehlersuche
+retired = Dieser Vorschlag wurde zurückgezogen.
+why-check = Warum das prüfen?
+applies-to = Gilt für { $context }
+suggested-by = Vorgeschlagen von { $author }
+added = Hinzugefügt am { $added }
+related = Ähnliche Prüfungen
+suggest-another = Das war's nicht, schlag etwas anderes vor.
+shortcuts = Drück n oder die Leertaste für einen anderen Vorschlag, oder c, um seinen Link zu kopieren.
+recent = Kürzlich vorgeschlagen
+share = Diesen Vorschlag zur Fehlersuche teilen.
+position = Nr. { $number } von { $total }
+permalink = Permalink
+copy-link = Link kopieren
+copied = Kopiert!
+share-via = Teilen über
+email = E-Mail
+search-placeholder = Vorschläge durchsuchen
+search-title = Suche: { $q }
+no-matches = Keine Vorschläge gefunden.
+suggest-random = Schlag etwas Zufälliges vor.
+dark-mode = Dunkler Modus
+light-mode = Heller Modus
+not-found-title = Nicht gefunden
+not-found = Hier gibt es nichts. Vielleicht ist der Link veraltet oder vertippt.
+all-title = Alle Vorschläge
+filter-placeholder = Vorschläge filtern
+browse-all = Alle Vorschläge durchblättern.
+categories-title = Kategorien
+category-title = Kategorie: { $name }
+browse-categories = Nach Kategorie durchblättern.
+no-categories = Noch keinem Vorschlag wurde eine Kategorie gegeben.
+request-id = Anfrage-ID: { $id }
+
+admin-title = Verwaltung
+admin-source = Quelle
+admin-status = Status
+admin-refresh-failed = Aktualisierung fehlgeschlagen: { $failure }
+admin-ok = OK
+admin-loaded = Zuletzt geladen
+admin-pending = Warten auf Moderation
+admin-lists = Listen
+admin-list = Liste
+admin-suggestions = Vorschläge
+admin-retired = Zurückgezogen
+admin-most-served = Am häufigsten gezeigt
+admin-item = Eintrag
+admin-suggestion = Vorschlag
+admin-web = Web
+admin-slack = Slack
+admin-unlisted = nicht mehr aufgeführt
+admin-nothing-served = Bisher wurde nichts gezeigt.
+admin-not-counted = Vorschläge werden nicht gezählt.
+admin-errors = Letzte Fehler
+admin-time = Zeit
+admin-request-id = Anfrage-ID
+admin-request = Anfrage
+admin-error = Fehler
+admin-no-errors = Keine Serverfehler seit dem Start des Dienstes.
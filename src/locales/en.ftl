@@ -0,0 +1,63 @@
+# The fixed text of the HTML pages, in English. Every other language falls
+# back to these messages for any it leaves out.
+
+suggestion-title = Troubleshooting suggestion
+retired = This suggestion has been retired.
+why-check = Why check this?
+applies-to = Applies to { $context }
+suggested-by = Suggested by { $author }
+added = Added { $added }
+related = Related checks
+suggest-another = That wasn't it, suggest something else.
+shortcuts = Press n or space for another suggestion, or c to copy its link.
+recent = Recently suggested
+share = Share this troubleshooting suggestion.
+position = #{ $number } of { $total }
+permalink = Permalink
+copy-link = Copy link
+copied = Copied!
+share-via = Share on
+email = Email
+search-placeholder = Search suggestions
+search-title = Search: { $q }
+no-matches = No suggestions match.
+suggest-random = Suggest something at random.
+dark-mode = Dark mode
+light-mode = Light mode
+not-found-title = Not found
+not-found = There's nothing here. Perhaps the link is old, or mistyped.
+all-title = All suggestions
+filter-placeholder = Filter suggestions
+browse-all = Browse every suggestion.
+categories-title = Categories
+category-title = Category: { $name }
+browse-categories = Browse by category.
+no-categories = No suggestions have been given categories.
+request-id = Request ID: { $id }
+
+# The operators' dashboard, at /admin.
+admin-title = Admin
+admin-source = Source
+admin-status = Status
+admin-refresh-failed = Refresh failed: { $failure }
+admin-ok = OK
+admin-loaded = Last loaded
+admin-pending = Awaiting moderation
+admin-lists = Lists
+admin-list = List
+admin-suggestions = Suggestions
+admin-retired = Retired
+admin-most-served = Most served
+admin-item = Item
+admin-suggestion = Suggestion
+admin-web = Web
+admin-slack = Slack
+admin-unlisted = no longer listed
+admin-nothing-served = Nothing has been served yet.
+admin-not-counted = Suggestions aren't being counted.
+admin-errors = Recent errors
+admin-time = Time
+admin-request-id = Request ID
+admin-request = Request
+admin-error = Error
+admin-no-errors = No server errors since the service started.
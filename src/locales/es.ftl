@@ -0,0 +1,59 @@
+suggestion-title = Sugerencia para resolver problemas
+retired = Esta sugerencia se ha retirado.
+why-check = ¿Por qué comprobar esto?
+applies-to = Se aplica a { $context }
+suggested-by = Sugerida por { $author }
+added = Añadida el { $added }
+related = Comprobaciones relacionadas
+suggest-another = No era eso, sugiere otra cosa.
+shortcuts = Pulsa n o espacio para otra sugerencia, o c para copiar su enlace.
+recent = Sugerido recientemente
+share = Compartir esta sugerencia para resolver problemas.
+position = n.º { $number } de { $total }
+permalink = Enlace permanente
+copy-link = Copiar enlace
+copied = ¡Copiado!
+share-via = Compartir en
+email = Correo
+search-placeholder = Buscar sugerencias
+search-title = Búsqueda: { $q }
+no-matches = Ninguna sugerencia coincide.
+suggest-random = Sugiere algo al azar.
+dark-mode = Modo oscuro
+light-mode = Modo claro
+not-found-title = No encontrado
+not-found = Aquí no hay nada. Quizá el enlace es antiguo o está mal escrito.
+all-title = Todas las sugerencias
+filter-placeholder = Filtrar sugerencias
+browse-all = Ver todas las sugerencias.
+categories-title = Categorías
+category-title = Categoría: { $name }
+browse-categories = Ver por categoría.
+no-categories = Ninguna sugerencia tiene categoría.
+request-id = ID de la solicitud: { $id }
+
+admin-title = Administración
+admin-source = Origen
+admin-status = Estado
+admin-refresh-failed = Error al actualizar: { $failure }
+admin-ok = Correcto
+admin-loaded = Última carga
+admin-pending = Pendientes de moderación
+admin-lists = Listas
+admin-list = Lista
+admin-suggestions = Sugerencias
+admin-retired = Retiradas
+admin-most-served = Más mostradas
+admin-item = Elemento
+admin-suggestion = Sugerencia
+admin-web = Web
+admin-slack = Slack
+admin-unlisted = ya no está en la lista
+admin-nothing-served = Todavía no se ha mostrado nada.
+admin-not-counted = Las sugerencias no se están contando.
+admin-errors = Errores recientes
+admin-time = Hora
+admin-request-id = ID de la solicitud
+admin-request = Solicitud
+admin-error = Error
+admin-no-errors = No ha habido errores del servidor desde que se inició el servicio.
@@ -0,0 +1,59 @@
+suggestion-title = Suggestion de dépannage
+retired = Cette suggestion a été retirée.
+why-check = Pourquoi vérifier ceci ?
+applies-to = S'applique à { $context }
+suggested-by = Suggérée par { $author }
+added = Ajoutée le { $added }
+related = Vérifications associées
+suggest-another = Ce n'était pas ça, suggère autre chose.
+shortcuts = Appuie sur n ou espace pour une autre suggestion, ou sur c pour copier son lien.
+recent = Suggéré récemment
+share = Partager cette suggestion de dépannage.
+position = nº { $number } sur { $total }
+permalink = Lien permanent
+copy-link = Copier le lien
+copied = Copié !
+share-via = Partager sur
+email = Courriel
+search-placeholder = Rechercher des suggestions
+search-title = Recherche : { $q }
+no-matches = Aucune suggestion ne correspond.
+suggest-random = Suggère quelque chose au hasard.
+dark-mode = Mode sombre
+light-mode = Mode clair
+not-found-title = Introuvable
+not-found = Il n’y a rien ici. Le lien est peut-être ancien, ou mal saisi.
+all-title = Toutes les suggestions
+filter-placeholder = Filtrer les suggestions
+browse-all = Parcourir toutes les suggestions.
+categories-title = Catégories
+category-title = Catégorie : { $name }
+browse-categories = Parcourir par catégorie.
+no-categories = Aucune suggestion n’a de catégorie.
+request-id = Identifiant de la requête : { $id }
+
+admin-title = Administration
+admin-source = Source
+admin-status = État
+admin-refresh-failed = Échec de l’actualisation : { $failure }
+admin-ok = OK
+admin-loaded = Dernier chargement
+admin-pending = En attente de modération
+admin-lists = Listes
+admin-list = Liste
+admin-suggestions = Suggestions
+admin-retired = Retirées
+admin-most-served = Les plus affichées
+admin-item = Élément
+admin-suggestion = Suggestion
+admin-web = Web
+admin-slack = Slack
+admin-unlisted = n’est plus dans la liste
+admin-nothing-served = Rien n’a encore été affiché.
+admin-not-counted = Les suggestions ne sont pas comptées.
+admin-errors = Erreurs récentes
+admin-time = Heure
+admin-request-id = Identifiant de la requête
+admin-request = Requête
+admin-error = Erreur
+admin-no-errors = Aucune erreur serveur depuis le démarrage du service.
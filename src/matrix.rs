@@ -0,0 +1,255 @@
+//! A Matrix bot, for asking for suggestions from Matrix rooms.
+//!
+//! This module is only available with the `matrix` feature. A service whose
+//! `view::Options` carry a `Matrix` logs in to a homeserver as a bot, joins
+//! the configured rooms, and replies to messages starting with
+//! `!troubleshoot` with a suggestion:
+//!
+//! ```
+//! # use things_to_check::{matrix, view};
+//! # fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+//! use url::Url;
+//!
+//! let homeserver = Url::parse("https://matrix.example.com")?;
+//! let bot = matrix::Matrix::new(homeserver, "@troubleshoot:example.com", "s3kr1t")
+//!     .joining(vec!["#ops:example.com".to_owned()]);
+//! let options = view::Options {
+//!     matrix: Some(bot),
+//!     ..Default::default()
+//! };
+//! let service = view::make_service_with(&options)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! `!troubleshoot` replies with a random suggestion. Its text is read as for
+//! the Slack app's command: `!troubleshoot 42`, a single word naming a
+//! suggestion, replies with that suggestion, and other words reply with a
+//! random suggestion matching them. Replies link to the suggestion's
+//! permalink if the bot is given the service's public URL with
+//! `Matrix::linking_to`. Suggestions are chosen from the default list.
+//!
+//! The bot answers in the rooms it's configured to join, and in any others it
+//! has already joined, but not in encrypted rooms. Messages sent before it
+//! starts are ignored.
+//!
+//! Like a `schedule::Schedule`, the bot starts when the service is first
+//! configured into an App, and runs on that App's worker until the runtime
+//! shuts down. Failures to log in, join rooms, or reply are reported on
+//! stderr and otherwise ignored.
+
+use actix_web::web;
+use matrix_sdk::config::SyncSettings;
+use matrix_sdk::ruma::events::room::message::{
+    MessageType, OriginalSyncRoomMessageEvent, RoomMessageEventContent,
+};
+use matrix_sdk::ruma::RoomOrAliasId;
+use matrix_sdk::{Client, Room};
+use std::error::Error;
+use std::fmt;
+use url::Url;
+
+use crate::schedule::permalink;
+use crate::slack::{self, Ask};
+use crate::things::Store;
+
+/// The command the bot answers.
+const COMMAND: &str = "!troubleshoot";
+
+/// Who the bot logs in as, and where it answers.
+#[derive(Clone)]
+pub struct Matrix {
+    homeserver: Url,
+    user: String,
+    password: String,
+    rooms: Vec<String>,
+    index: Option<Url>,
+}
+
+impl Matrix {
+    /// Log in to `homeserver` as `user`, such as `@troubleshoot:example.com`
+    /// or just `troubleshoot`, with `password`.
+    pub fn new(homeserver: Url, user: impl Into<String>, password: impl Into<String>) -> Self {
+        Matrix {
+            homeserver,
+            user: user.into(),
+            password: password.into(),
+            rooms: vec![],
+            index: None,
+        }
+    }
+
+    /// Join each of `rooms`, by ID or alias, such as `#ops:example.com`, on
+    /// logging in.
+    pub fn joining(self, rooms: Vec<String>) -> Self {
+        Matrix { rooms, ..self }
+    }
+
+    /// Link replies to suggestions' permalinks on the index page at `index`,
+    /// the service's public URL.
+    pub fn linking_to(self, index: Url) -> Self {
+        Matrix {
+            index: Some(index),
+            ..self
+        }
+    }
+}
+
+// The password stays out of logs.
+impl fmt::Debug for Matrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Matrix")
+            .field("homeserver", &self.homeserver)
+            .field("user", &self.user)
+            .field("password", &"<redacted>")
+            .field("rooms", &self.rooms)
+            .field("index", &self.index)
+            .finish()
+    }
+}
+
+// What a message asks of the bot, if it's a command: the rest of its text.
+fn asked(body: &str) -> Option<&str> {
+    let rest = body.trim_start().strip_prefix(COMMAND)?;
+    match rest.chars().next() {
+        None => Some(""),
+        Some(next) if next.is_whitespace() => Some(rest.trim()),
+        Some(_) => None,
+    }
+}
+
+// A reply showing a suggestion chosen by the command's `text`.
+fn reply(store: &Store, index: Option<&Url>, text: &str) -> RoomMessageEventContent {
+    let catalog = store.current();
+    let things = catalog.list(None);
+    let ask = Ask::from_text(things, text);
+    let (_, thing) = match things.and_then(|things| ask.choose(things)) {
+        Some(chosen) => chosen,
+        None => {
+            return RoomMessageEventContent::notice_plain(
+                "Sorry, there are no suggestions to show.",
+            )
+        }
+    };
+
+    match index.and_then(|index| permalink(index, thing)) {
+        Some(permalink) => RoomMessageEventContent::notice_html(
+            format!("{}\n\n{}", thing.text(), permalink),
+            format!(
+                "{}<p><a href=\"{}\">Permalink</a></p>",
+                thing.html,
+                slack::escape(permalink.as_str())
+            ),
+        ),
+        None => RoomMessageEventContent::notice_html(thing.text(), &thing.html),
+    }
+}
+
+/// Answers commands in Matrix rooms from a running service.
+pub(crate) struct Bot {
+    matrix: Matrix,
+    store: web::Data<Store>,
+}
+
+impl Bot {
+    pub(crate) fn new(matrix: Matrix, store: web::Data<Store>) -> Self {
+        Bot { matrix, store }
+    }
+
+    /// Log in, join rooms, and answer commands.
+    ///
+    /// This runs until the runtime shuts down, or until the bot can't log in
+    /// or loses touch with its homeserver.
+    pub(crate) async fn run(self) {
+        if let Err(err) = self.answer().await {
+            eprintln!("Matrix bot stopped: {}", err);
+        }
+    }
+
+    async fn answer(self) -> Result<(), Box<dyn Error>> {
+        let Bot { matrix, store } = self;
+
+        let client = Client::builder()
+            .homeserver_url(matrix.homeserver.as_str())
+            .build()
+            .await?;
+        client
+            .matrix_auth()
+            .login_username(&matrix.user, &matrix.password)
+            .initial_device_display_name("things-to-check")
+            .send()
+            .await?;
+
+        for room in &matrix.rooms {
+            let joined: Result<_, Box<dyn Error>> = match <&RoomOrAliasId>::try_from(room.as_str())
+            {
+                Ok(id) => client
+                    .join_room_by_id_or_alias(id, &[])
+                    .await
+                    .map_err(Into::into),
+                Err(err) => Err(err.into()),
+            };
+            if let Err(err) = joined {
+                eprintln!("Unable to join Matrix room {}: {}", room, err);
+            }
+        }
+
+        // Catch up first, so that commands sent while the bot was away aren't
+        // answered all at once.
+        let response = client.sync_once(SyncSettings::default()).await?;
+
+        let index = matrix.index;
+        client.add_event_handler(
+            move |event: OriginalSyncRoomMessageEvent, room: Room, client: Client| {
+                let store = store.clone();
+                let index = index.clone();
+                async move {
+                    if Some(event.sender.as_ref()) == client.user_id() {
+                        return;
+                    }
+                    let MessageType::Text(text) = &event.content.msgtype else {
+                        return;
+                    };
+                    let Some(text) = asked(&text.body) else {
+                        return;
+                    };
+
+                    let reply = reply(&store, index.as_ref(), text);
+                    if let Err(err) = room.send(reply).await {
+                        eprintln!("Unable to reply in Matrix room {}: {}", room.room_id(), err);
+                    }
+                }
+            },
+        );
+
+        client
+            .sync(SyncSettings::default().token(response.next_batch))
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_start_messages() {
+        assert_eq!(asked("!troubleshoot"), Some(""));
+        assert_eq!(asked("  !troubleshoot 42\n"), Some("42"));
+        assert_eq!(asked("!troubleshoot is it  dns"), Some("is it  dns"));
+        assert_eq!(asked("!troubleshooting"), None);
+        assert_eq!(asked("have you tried !troubleshoot"), None);
+    }
+
+    #[test]
+    fn passwords_stay_out_of_logs() {
+        let homeserver = Url::parse("https://matrix.example.com").unwrap();
+        let matrix = Matrix::new(homeserver, "troubleshoot", "s3kr1t");
+
+        let debugged = format!("{:?}", matrix);
+        assert!(debugged.contains("troubleshoot"));
+        assert!(!debugged.contains("s3kr1t"));
+    }
+}
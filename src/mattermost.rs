@@ -0,0 +1,146 @@
+//! A Mattermost slash command, for asking for suggestions from Mattermost.
+//!
+//! The command is off unless the App is given a `Mattermost` value as app
+//! data, which holds the token Mattermost sends with the command:
+//!
+//! ```
+//! # use things_to_check::{mattermost, view};
+//! # #[actix_web::main]
+//! # async fn main() -> std::result::Result<(), things_to_check::view::Error> {
+//! use actix_web::{web, App, HttpServer};
+//!
+//! let service = view::make_service()?;
+//! let mattermost = web::Data::new(mattermost::Mattermost::new("s3kr1t"));
+//! let app_factory = move ||
+//!     App::new()
+//!         .app_data(mattermost.clone())
+//!         .configure(|cfg| service(cfg));
+//!
+//! HttpServer::new(app_factory);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Endpoints
+//!
+//! * `/mattermost/troubleshoot` (`POST`): a slash command, such as
+//!   `/troubleshoot`.
+//!
+//!   Point a custom slash command's request URL here, with the `POST` method.
+//!   The command posts a random suggestion to the channel, with a link to its
+//!   permalink. Its text is read as for the Slack app's command: `quietly`
+//!   shows the suggestion only to the person who asked, `aloud` posts it to
+//!   the channel, a single word naming a suggestion shows that suggestion, and
+//!   other words show a random suggestion matching them.
+//!
+//! Requests must carry the command's token in their `token` field, or are
+//! rejected with `401 Unauthorized`. The endpoint returns `404 Not Found` if
+//! the command is off. Every request comes from Mattermost, on behalf of
+//! everyone on the server, so it isn't rate limited.
+
+use actix_web::{error, post, web, HttpRequest, HttpResponse};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::moderation::constant_time_eq;
+use crate::slack::{Ask, Command};
+use crate::things::Store;
+use crate::view::{self, ItemQuery, Urls};
+
+/// Enables the Mattermost command, and holds the token Mattermost sends.
+pub struct Mattermost {
+    token: String,
+    quiet: bool,
+}
+
+impl Mattermost {
+    /// Enable the Mattermost command, for requests carrying `token`, shown
+    /// when the slash command is created.
+    pub fn new(token: impl Into<String>) -> Self {
+        Mattermost {
+            token: token.into(),
+            quiet: false,
+        }
+    }
+
+    /// Show suggestions only to the person who asked for them, unless the
+    /// command asks for them `aloud`.
+    pub fn quietly(self) -> Self {
+        Mattermost {
+            quiet: true,
+            ..self
+        }
+    }
+}
+
+fn mattermost(req: &HttpRequest) -> error::Result<&Mattermost> {
+    req.app_data::<web::Data<Mattermost>>()
+        .map(|mattermost| mattermost.get_ref())
+        .ok_or_else(|| view::not_found(req))
+}
+
+#[derive(Deserialize)]
+struct CommandForm {
+    #[serde(default)]
+    token: String,
+    #[serde(default)]
+    text: String,
+}
+
+// A reply showing a suggestion chosen by `ask`. Mattermost renders Markdown,
+// so suggestions keep their formatting.
+fn reply(req: &HttpRequest, store: &Store, ask: &Ask, quiet: bool) -> error::Result<Value> {
+    let catalog = store.current();
+    let chosen = catalog.list(None).and_then(|things| ask.choose(things));
+    let (_, thing) = match chosen {
+        Some(chosen) => chosen,
+        None => {
+            return Ok(json!({
+                "response_type": "ephemeral",
+                "text": "Sorry, there are no suggestions to show.",
+            }))
+        }
+    };
+
+    let url = req.suggestion(&ItemQuery::default(), thing)?;
+    let response_type = if quiet { "ephemeral" } else { "in_channel" };
+
+    Ok(json!({
+        "response_type": response_type,
+        "text": format!("{}\n\n[Permalink]({})", thing.markdown, url),
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/mattermost/troubleshoot",
+    tag = "mattermost",
+    request_body(content = String, description = "A Mattermost slash command invocation", content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 200, description = "A message to post, as JSON"),
+        (status = 400, description = "The request was malformed"),
+        (status = 401, description = "The request's token was missing or incorrect"),
+        (status = 404, description = "The Mattermost command is off"),
+    ),
+)]
+#[post("/mattermost/troubleshoot")]
+pub(crate) async fn troubleshoot(
+    req: HttpRequest,
+    store: web::Data<Store>,
+    form: web::Form<CommandForm>,
+) -> error::Result<HttpResponse> {
+    let mattermost = mattermost(&req)?;
+    if !constant_time_eq(form.token.as_bytes(), mattermost.token.as_bytes()) {
+        return Err(error::ErrorUnauthorized("Unauthorized"));
+    }
+
+    let asked = Command::parse(&form.text, mattermost.quiet);
+    let ask = Ask::from_text(store.current().list(None), &asked.text);
+    let reply = reply(&req, &store, &ask, asked.quiet)?;
+
+    Ok(HttpResponse::Ok().json(reply))
+}
+
+pub(crate) fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(troubleshoot);
+}
@@ -0,0 +1,358 @@
+//! Suggestions submitted by users, and the moderation queue that holds them
+//! until they're approved.
+//!
+//! Anyone can submit a suggestion, but submissions are held in a queue, and
+//! only enter the rotation once a moderator approves them. Moderation is off
+//! unless the App is given a `Moderators` value as app data, which holds the
+//! token moderators use to authenticate:
+//!
+//! ```
+//! # use things_to_check::{moderation, view};
+//! # #[actix_web::main]
+//! # async fn main() -> std::result::Result<(), things_to_check::view::Error> {
+//! use actix_web::{web, App, HttpServer};
+//!
+//! let service = view::make_service()?;
+//! let moderators = web::Data::new(moderation::Moderators::new("s3kr1t"));
+//! let app_factory = move ||
+//!     App::new()
+//!         .app_data(moderators.clone())
+//!         .configure(|cfg| service(cfg));
+//!
+//! HttpServer::new(app_factory);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The queue is held in memory. Approved suggestions are added to the running
+//! service, but not to the file or database the service was loaded from. They
+//! are kept when the service reloads its suggestions, but lost when it
+//! restarts; the approval response includes everything needed to add them to
+//! the source permanently. Approved suggestions are numbered from 1,000,000 in
+//! their list, so that their `item` indices stay theirs however the source
+//! grows.
+//!
+//! # Endpoints
+//!
+//! * `/suggest` (`POST`): submit a suggestion for moderation.
+//!
+//!   Takes a form-encoded body with a `text` field, holding the suggestion's
+//!   Markdown, and an optional `list` field, naming the list it belongs in.
+//!   Returns `202 Accepted` and a JSON object with the submission's `id`.
+//!   Suggestions that are empty, contain raw HTML, are too long, or link to or
+//!   embed anything but `http` and `https` URLs are rejected with
+//!   `400 Bad Request`.
+//!
+//! * `/admin/pending` (`GET`): list the submissions awaiting moderation, as
+//!   JSON.
+//!
+//! * `/admin/pending/{id}/approve` (`POST`): add a submission to the rotation.
+//!   Returns the list it was added to and its `item` index, as JSON.
+//!
+//! * `/admin/pending/{id}/reject` (`POST`): discard a submission.
+//!
+//! The `/admin` endpoints require an `Authorization: Bearer …` header carrying
+//! the moderators' token, or HTTP Basic credentials with the token as the
+//! password and any user name. All of these endpoints return `404 Not Found`
+//! if moderation is off.
+
+use actix_web::http::header;
+use actix_web::{error, get, post, web, HttpRequest, HttpResponse};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+use utoipa::ToSchema;
+
+use crate::limit::Limited;
+use crate::things::{Store, Thing};
+use crate::view::{self, Markdown};
+
+/// The most submissions that will be held for moderation at once.
+const MAX_PENDING: usize = 1000;
+
+/// Enables moderation, and holds the token moderators authenticate with.
+pub struct Moderators {
+    token: String,
+}
+
+impl Moderators {
+    /// Enable moderation for anyone presenting `token`.
+    pub fn new(token: impl Into<String>) -> Self {
+        Moderators {
+            token: token.into(),
+        }
+    }
+
+    pub(crate) fn authenticate(&self, req: &HttpRequest) -> error::Result<()> {
+        let presented = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(presented_token);
+
+        match presented {
+            Some(presented) if constant_time_eq(presented.as_bytes(), self.token.as_bytes()) => {
+                Ok(())
+            }
+            _ => Err(error::ErrorUnauthorized("Unauthorized")),
+        }
+    }
+}
+
+// The token in an `Authorization` header: a bearer token, or the password of
+// HTTP Basic credentials, so that browsers can prompt for it. The user name is
+// ignored.
+fn presented_token(authorization: &str) -> Option<String> {
+    if let Some(token) = authorization.strip_prefix("Bearer ") {
+        return Some(token.to_owned());
+    }
+
+    let credentials = authorization.strip_prefix("Basic ")?;
+    let credentials = String::from_utf8(BASE64.decode(credentials.trim()).ok()?).ok()?;
+    let (_, password) = credentials.split_once(':')?;
+    Some(password.to_owned())
+}
+
+// Compares two byte strings without short-circuiting on the first difference,
+// so that response times don't reveal how much of a guessed token is right.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+struct Submission {
+    text: String,
+    list: Option<String>,
+}
+
+/// Submissions awaiting moderation, by ID.
+#[derive(Default)]
+pub(crate) struct Queue(Mutex<Pending>);
+
+#[derive(Default)]
+struct Pending {
+    next_id: u64,
+    submissions: BTreeMap<u64, Submission>,
+}
+
+impl Queue {
+    /// The number of submissions awaiting moderation.
+    pub(crate) fn len(&self) -> usize {
+        self.lock().submissions.len()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Pending> {
+        self.0.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+pub(crate) fn moderators(req: &HttpRequest) -> error::Result<&Moderators> {
+    req.app_data::<web::Data<Moderators>>()
+        .map(|moderators| moderators.get_ref())
+        .ok_or_else(|| view::not_found(req))
+}
+
+#[derive(Serialize, ToSchema)]
+struct Submitted {
+    id: u64,
+}
+
+#[utoipa::path(
+    post,
+    path = "/suggest",
+    tag = "moderation",
+    request_body(content = Submission, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 202, description = "The suggestion is awaiting moderation", body = Submitted),
+        (status = 400, description = "The suggestion has problems, or the list doesn't exist"),
+        (status = 404, description = "Moderation is off"),
+        (status = 503, description = "Too many suggestions are awaiting moderation"),
+    ),
+)]
+#[post("/suggest")]
+pub(crate) async fn suggest(
+    _: Limited,
+    req: HttpRequest,
+    store: web::Data<Store>,
+    queue: web::Data<Queue>,
+    form: web::Form<Submission>,
+) -> error::Result<HttpResponse> {
+    moderators(&req)?;
+
+    let submission = form.into_inner();
+    let text = submission.text.trim();
+    let thing = Thing::from(text.to_owned());
+    if let Some(problem) = thing.problems().first() {
+        return Err(error::ErrorBadRequest(format!("Suggestion {}", problem)));
+    }
+    // Approved suggestions are shown as HTML, so links to anything but web
+    // pages, such as `javascript:` URLs, would run in readers' browsers.
+    if thing.links_elsewhere() {
+        return Err(error::ErrorBadRequest(
+            "Suggestion links to something other than a web page",
+        ));
+    }
+    if store.current().list(submission.list.as_deref()).is_none() {
+        return Err(error::ErrorBadRequest("No such list"));
+    }
+
+    let mut pending = queue.lock();
+    if pending.submissions.len() >= MAX_PENDING {
+        return Err(error::ErrorServiceUnavailable(
+            "Too many suggestions are awaiting moderation",
+        ));
+    }
+
+    let id = pending.next_id;
+    pending.next_id += 1;
+    pending.submissions.insert(
+        id,
+        Submission {
+            text: text.to_owned(),
+            ..submission
+        },
+    );
+
+    Ok(HttpResponse::Accepted().json(Submitted { id }))
+}
+
+#[derive(Serialize, ToSchema)]
+struct PendingSubmission {
+    id: u64,
+    #[serde(flatten)]
+    submission: Submission,
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/pending",
+    tag = "moderation",
+    security(("moderator" = [])),
+    responses(
+        (status = 200, description = "The submissions awaiting moderation", body = [PendingSubmission]),
+        (status = 401, description = "Missing or incorrect moderator token"),
+        (status = 404, description = "Moderation is off"),
+    ),
+)]
+#[get("/admin/pending")]
+pub(crate) async fn list_pending(
+    req: HttpRequest,
+    queue: web::Data<Queue>,
+) -> error::Result<HttpResponse> {
+    moderators(&req)?.authenticate(&req)?;
+
+    let pending: Vec<_> = queue
+        .lock()
+        .submissions
+        .iter()
+        .map(|(&id, submission)| PendingSubmission {
+            id,
+            submission: submission.clone(),
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(pending))
+}
+
+#[derive(Serialize, ToSchema)]
+struct Approved {
+    list: String,
+    item: usize,
+    text: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/pending/{id}/approve",
+    tag = "moderation",
+    security(("moderator" = [])),
+    params(("id" = u64, Path, description = "The submission's ID")),
+    responses(
+        (status = 200, description = "The submission was added to the rotation", body = Approved),
+        (status = 401, description = "Missing or incorrect moderator token"),
+        (status = 404, description = "Moderation is off, or no such submission"),
+    ),
+)]
+#[post("/admin/pending/{id}/approve")]
+pub(crate) async fn approve(
+    req: HttpRequest,
+    store: web::Data<Store>,
+    queue: web::Data<Queue>,
+    markdown: web::Data<Markdown>,
+    id: web::Path<u64>,
+) -> error::Result<HttpResponse> {
+    moderators(&req)?.authenticate(&req)?;
+
+    let Submission { text, list } = match queue.lock().submissions.remove(&id) {
+        Some(submission) => submission,
+        None => return Err(view::not_found(&req)),
+    };
+
+    let mut thing = Thing::from(text.clone());
+    thing.render(**markdown);
+    // The list may have been removed by a reload since the suggestion was
+    // submitted; the store puts it in the default list rather than losing it.
+    let (list, item) = store.approve(list.as_deref(), thing);
+
+    Ok(HttpResponse::Ok().json(Approved { list, item, text }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/pending/{id}/reject",
+    tag = "moderation",
+    security(("moderator" = [])),
+    params(("id" = u64, Path, description = "The submission's ID")),
+    responses(
+        (status = 204, description = "The submission was discarded"),
+        (status = 401, description = "Missing or incorrect moderator token"),
+        (status = 404, description = "Moderation is off, or no such submission"),
+    ),
+)]
+#[post("/admin/pending/{id}/reject")]
+pub(crate) async fn reject(
+    req: HttpRequest,
+    queue: web::Data<Queue>,
+    id: web::Path<u64>,
+) -> error::Result<HttpResponse> {
+    moderators(&req)?.authenticate(&req)?;
+
+    match queue.lock().submissions.remove(&id) {
+        Some(_) => Ok(HttpResponse::NoContent().finish()),
+        None => Err(view::not_found(&req)),
+    }
+}
+
+pub(crate) fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(suggest)
+        .service(list_pending)
+        .service(approve)
+        .service(reject);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constant_time_eq_compares_contents() {
+        assert!(constant_time_eq(b"s3kr1t", b"s3kr1t"));
+        assert!(!constant_time_eq(b"s3kr1t", b"s3kr1T"));
+        assert!(!constant_time_eq(b"s3kr1t", b"s3kr1"));
+        assert!(!constant_time_eq(b"", b"s3kr1t"));
+    }
+
+    #[test]
+    fn tokens_are_presented_as_bearer_or_basic() {
+        assert_eq!(presented_token("Bearer s3kr1t").as_deref(), Some("s3kr1t"));
+        // "admin:s3kr1t"
+        assert_eq!(
+            presented_token("Basic YWRtaW46czNrcjF0").as_deref(),
+            Some("s3kr1t")
+        );
+        assert_eq!(presented_token("Basic not base64!"), None);
+        assert_eq!(presented_token("s3kr1t"), None);
+    }
+}
@@ -0,0 +1,171 @@
+//! Post suggestions as JSON to any webhook, for wiring the service into tools
+//! that accept webhooks without an integration of their own.
+//!
+//! Each post is a `POST` with a JSON body holding a suggestion, with the same
+//! fields `/api/v1/suggestion` returns for one:
+//!
+//! ```json
+//! {
+//!   "id": "0",
+//!   "list": "default",
+//!   "markdown": "Have you checked DNS?",
+//!   "html": "<p>Have you checked DNS?</p>\n",
+//!   "url": "https://example.com/check/have-you-checked-dns",
+//!   "lang": "en",
+//!   …
+//! }
+//! ```
+//!
+//! Suggestions can be posted on demand, through the endpoint below, or on a
+//! schedule, with `schedule::Schedule::notifying`. The endpoint is off unless
+//! the App is given a `Notifier` value as app data, which holds the webhooks'
+//! URLs:
+//!
+//! ```
+//! # use things_to_check::{notify, view};
+//! # #[actix_web::main]
+//! # async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+//! use actix_web::{web, App, HttpServer};
+//! use url::Url;
+//!
+//! let service = view::make_service()?;
+//! let webhook = Url::parse("https://example.com/hooks/troubleshoot")?;
+//! let notifier = web::Data::new(notify::Notifier::new(vec![webhook]));
+//! let app_factory = move ||
+//!     App::new()
+//!         .app_data(notifier.clone())
+//!         .configure(|cfg| service(cfg));
+//!
+//! HttpServer::new(app_factory);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Endpoints
+//!
+//! * `/notify/webhooks` (`POST`): posts a suggestion to each webhook.
+//!
+//!   Only available if the App is also given a `moderation::Moderators`
+//!   value. Takes the same URL parameters as `/api/v1/suggestion`, and posts
+//!   the suggestion a request there would return. Returns `204 No Content`
+//!   once every webhook has accepted the post, or `502 Bad Gateway` if any
+//!   didn't; the others are posted to either way. Requires an `Authorization:
+//!   Bearer …` header carrying the moderators' token. Requests come from
+//!   moderators' own tools, so this endpoint isn't rate limited.
+
+use actix_web::{error, post, web, HttpRequest, HttpResponse};
+use url::Url;
+
+use crate::api::Suggestion;
+use crate::moderation;
+use crate::things::Store;
+use crate::view::{self, ItemQuery, Urls};
+
+/// Enables `/notify/webhooks`, and holds the webhooks it posts to.
+#[derive(Clone, Debug)]
+pub struct Notifier {
+    webhooks: Vec<Url>,
+}
+
+impl Notifier {
+    /// Post to each of `webhooks`.
+    pub fn new(webhooks: Vec<Url>) -> Self {
+        Notifier { webhooks }
+    }
+}
+
+// Post `suggestion`, as JSON, to `webhook`. This blocks until the webhook
+// answers.
+fn post(webhook: &Url, suggestion: &str) -> Result<(), Box<ureq::Error>> {
+    ureq::post(webhook.as_str())
+        .set("Content-Type", "application/json")
+        .send_string(suggestion)
+        .map_err(Box::new)?;
+
+    Ok(())
+}
+
+// Post `suggestion` to each of `webhooks`, off the thread serving requests,
+// reporting failures on stderr. Whether every post succeeded.
+pub(crate) async fn post_all(webhooks: &[Url], suggestion: &Suggestion) -> bool {
+    let suggestion = match serde_json::to_string(suggestion) {
+        Ok(suggestion) => suggestion,
+        Err(err) => {
+            eprintln!("Unable to serialize a suggestion: {}", err);
+            return false;
+        }
+    };
+
+    let mut all_posted = true;
+    for webhook in webhooks {
+        let (webhook, suggestion) = (webhook.clone(), suggestion.clone());
+        let posted =
+            web::block(move || post(&webhook, &suggestion).map_err(|err| (webhook, err))).await;
+
+        match posted {
+            Ok(Ok(())) => continue,
+            Ok(Err((webhook, err))) => {
+                eprintln!("Unable to post a suggestion to {}: {}", webhook, err)
+            }
+            Err(err) => eprintln!("Unable to post a suggestion: {}", err),
+        }
+        all_posted = false;
+    }
+
+    all_posted
+}
+
+#[utoipa::path(
+    post,
+    path = "/notify/webhooks",
+    tag = "notify",
+    security(("moderator" = [])),
+    params(ItemQuery),
+    responses(
+        (status = 204, description = "The suggestion was posted to every webhook"),
+        (status = 401, description = "Missing or incorrect moderator token"),
+        (status = 404, description = "No webhooks are configured, or no such list or suggestion"),
+        (status = 502, description = "A webhook did not accept the post"),
+    ),
+)]
+#[post("/notify/webhooks")]
+pub(crate) async fn notify(
+    req: HttpRequest,
+    store: web::Data<Store>,
+    query: web::Query<ItemQuery>,
+) -> error::Result<HttpResponse> {
+    let notifier = req
+        .app_data::<web::Data<Notifier>>()
+        .ok_or_else(|| view::not_found(&req))?
+        .clone();
+    moderation::moderators(&req)?.authenticate(&req)?;
+
+    let suggestion = {
+        let catalog = store.current();
+        let (list, things) = catalog
+            .named_list(query.list.as_deref())
+            .ok_or_else(|| view::not_found(&req))?;
+        let item = query.item.as_deref();
+        let tag = query.tag.as_deref();
+        let chosen = match item.and_then(|item| things.aliased(item)) {
+            Some(aliased) => Some(aliased).filter(|(_, thing)| thing.has_tag(tag)),
+            None => things.choose(item, tag),
+        };
+        let (index, thing) = chosen.ok_or_else(|| view::not_found(&req))?;
+
+        let id = thing.item(*index);
+        let url = req.suggestion(&query, thing)?;
+        let thing = thing.translated(query.lang.as_deref());
+        Suggestion::new(list, id, url.into(), thing, things.position(*index))
+    };
+
+    if !post_all(&notifier.webhooks, &suggestion).await {
+        return Err(error::ErrorBadGateway("A webhook did not accept the post"));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub(crate) fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(notify);
+}
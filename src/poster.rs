@@ -0,0 +1,630 @@
+//! A printable poster of the suggestions, as a PDF.
+//!
+//! Some teams would rather have the list on the wall than a page to reload.
+//! The poster sets every suggestion that hasn't been retired, numbered, in two
+//! columns on an A3 page, under the site's title. The type is as large as lets
+//! the whole list fit on one page, down to a size that can still be read from
+//! a desk away; longer lists continue onto more pages. It is mounted by every
+//! `make_*service(…)` function.
+//!
+//! The PDF is written directly, in the Helvetica fonts that every PDF reader
+//! has, so that no font files need to be shipped or found at runtime. Those
+//! fonts cover Western European text only; other characters, such as emoji,
+//! are left out.
+//!
+//! # Endpoints
+//!
+//! * `/poster.pdf` (`GET`): the poster.
+//!
+//!   Takes the same `list`, `tag`, and `lang` URL parameters as `/`.
+//!   Suggestions are in the order of the list.
+//!
+//! The poster carries an `ETag`, and honours `If-None-Match`, as for
+//! `/check/{slug}`. Unknown lists return `404 Not Found`.
+
+use actix_web::{error, get, web, HttpRequest, HttpResponse};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::card;
+use crate::limit::Limited;
+use crate::theme::Theme;
+use crate::things::Store;
+use crate::view::{self, ItemQuery, Urls};
+
+/// The width of an A3 page, in points.
+const PAGE_WIDTH: f32 = 842.0;
+
+/// The height of an A3 page, in points.
+const PAGE_HEIGHT: f32 = 1191.0;
+
+/// The space around the poster's text.
+const MARGIN: f32 = 56.0;
+
+const COLUMNS: usize = 2;
+
+/// The space between columns.
+const GUTTER: f32 = 28.0;
+
+const TITLE_SIZE: f32 = 36.0;
+
+const FOOTER_SIZE: f32 = 9.0;
+
+/// The largest and smallest sizes, in points, of the suggestions' text.
+const MAX_SIZE: u8 = 20;
+const MIN_SIZE: u8 = 9;
+
+/// The distance between lines, as a multiple of the text size.
+const LEADING: f32 = 1.3;
+
+/// The poster's title when the theme doesn't give one.
+const NAME: &str = "Things To Check";
+
+/// The widths of Helvetica's printable ASCII characters, from space to `~`,
+/// in thousandths of the text size.
+const HELVETICA: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278,
+    278, // space to /
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, // 0 to ?
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778, // @ to O
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556, // P to _
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556, // ` to o
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584, // p to ~
+];
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct PosterQuery {
+    list: Option<String>,
+    tag: Option<String>,
+    lang: Option<String>,
+}
+
+// `c` in the WinAnsi encoding the fonts use, if it's there.
+fn win_ansi(c: char) -> Option<u8> {
+    match c {
+        ' '..='~' | '\u{a0}'..='\u{ff}' => u8::try_from(c).ok(),
+        '€' => Some(0x80),
+        '…' => Some(0x85),
+        '‘' => Some(0x91),
+        '’' => Some(0x92),
+        '“' => Some(0x93),
+        '”' => Some(0x94),
+        '•' => Some(0x95),
+        '–' => Some(0x96),
+        '—' => Some(0x97),
+        '™' => Some(0x99),
+        _ => None,
+    }
+}
+
+// The words of `text`, in WinAnsi, leaving out characters the fonts lack.
+fn words(text: &str) -> Vec<Vec<u8>> {
+    text.split_whitespace()
+        .map(|word| word.chars().filter_map(win_ansi).collect::<Vec<_>>())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+// The width of `text`, in WinAnsi, set in Helvetica at `size` points. Bold is
+// a little wider, which the layout allows for where it uses bold.
+fn width(text: &[u8], size: f32) -> f32 {
+    let thousandths: u32 = text
+        .iter()
+        .map(|&byte| match byte {
+            b' '..=b'~' => u32::from(HELVETICA[usize::from(byte - b' ')]),
+            0x91 | 0x92 => 222,
+            0x85 | 0x97 => 1000,
+            0x95 => 350,
+            0x93 | 0x94 => 333,
+            _ => 556,
+        })
+        .sum();
+
+    thousandths as f32 * size / 1000.0
+}
+
+// Break `words` into lines no wider than `measure` at `size` points, splitting
+// words too long for any line.
+fn wrap(words: &[Vec<u8>], measure: f32, size: f32) -> Vec<Vec<u8>> {
+    let mut lines = vec![];
+    let mut line: Vec<u8> = vec![];
+    for word in words {
+        let mut word = &word[..];
+        while !word.is_empty() {
+            let space = if line.is_empty() {
+                0.0
+            } else {
+                width(b" ", size)
+            };
+            if width(&line, size) + space + width(word, size) <= measure {
+                if !line.is_empty() {
+                    line.push(b' ');
+                }
+                line.extend_from_slice(word);
+                break;
+            }
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+                continue;
+            }
+            // The word is too long for any line, so it has to be split.
+            let fits = (1..word.len())
+                .take_while(|&end| width(&word[..end], size) <= measure)
+                .last()
+                .unwrap_or(1);
+            lines.push(word[..fits].to_vec());
+            word = &word[fits..];
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// One suggestion, as set on the poster.
+struct Entry {
+    number: Vec<u8>,
+    lines: Vec<Vec<u8>>,
+}
+
+/// Where an entry goes: the left edge of its column, and its first baseline.
+struct Placed<'a> {
+    x: f32,
+    y: f32,
+    entry: &'a Entry,
+}
+
+/// How the suggestions are set at one size.
+struct Layout {
+    size: f32,
+    /// The space between an entry's number and its text.
+    indent: f32,
+    entries: Vec<Entry>,
+}
+
+impl Layout {
+    fn new(texts: &[Vec<Vec<u8>>], size: f32) -> Self {
+        let widest = format!("{}.", texts.len());
+        // Numbers are bold, so leave room for the wider glyphs.
+        let indent = width(widest.as_bytes(), size) * 1.1 + width(b" ", size);
+        let measure = column_width() - indent;
+        let entries = texts
+            .iter()
+            .enumerate()
+            .map(|(number, words)| Entry {
+                number: format!("{}.", number + 1).into_bytes(),
+                lines: wrap(words, measure, size),
+            })
+            .collect();
+
+        Layout {
+            size,
+            indent,
+            entries,
+        }
+    }
+
+    fn line_height(&self) -> f32 {
+        self.size * LEADING
+    }
+
+    // Place the entries in columns, top to bottom and then left to right,
+    // starting a new page when the columns are full. Entries aren't split
+    // between columns.
+    fn pages(&self) -> Vec<Vec<Placed<'_>>> {
+        let bottom = MARGIN + FOOTER_SIZE * 3.0;
+        let mut pages = vec![vec![]];
+        let mut column = 0;
+        let mut top = first_top();
+        let mut y = top;
+        for entry in &self.entries {
+            let height = entry.lines.len().max(1) as f32 * self.line_height();
+            if y - height < bottom && y < top {
+                column += 1;
+                if column == COLUMNS {
+                    column = 0;
+                    top = PAGE_HEIGHT - MARGIN;
+                    pages.push(vec![]);
+                }
+                y = top;
+            }
+
+            pages
+                .last_mut()
+                .expect("there is always a page")
+                .push(Placed {
+                    x: MARGIN + column as f32 * (column_width() + GUTTER),
+                    y: y - self.size,
+                    entry,
+                });
+            y -= height + self.size * 0.8;
+        }
+
+        pages
+    }
+}
+
+fn column_width() -> f32 {
+    (PAGE_WIDTH - 2.0 * MARGIN - GUTTER * (COLUMNS - 1) as f32) / COLUMNS as f32
+}
+
+// The top of the suggestions on the first page, below the title.
+fn first_top() -> f32 {
+    PAGE_HEIGHT - MARGIN - TITLE_SIZE * 2.0
+}
+
+// The color `color`, if it's written `#rrggbb` or `#rgb`.
+fn rgb(color: &str) -> Option<[u8; 3]> {
+    let digits = color.strip_prefix('#')?;
+    let digits = match digits.len() {
+        3 => digits.chars().flat_map(|c| [c, c]).collect(),
+        6 => digits.to_owned(),
+        _ => return None,
+    };
+    let mut rgb = [0; 3];
+    hex::decode_to_slice(digits, &mut rgb).ok()?;
+
+    Some(rgb)
+}
+
+fn fill(color: [u8; 3]) -> String {
+    let [r, g, b] = color.map(|channel| f32::from(channel) / 255.0);
+    format!("{:.3} {:.3} {:.3} rg\n", r, g, b)
+}
+
+// `text` as a PDF string, with the characters that would end it escaped.
+fn string(text: &[u8]) -> Vec<u8> {
+    let mut string = vec![b'('];
+    for &byte in text {
+        if matches!(byte, b'(' | b')' | b'\\') {
+            string.push(b'\\');
+        }
+        string.push(byte);
+    }
+    string.push(b')');
+    string
+}
+
+// Set `text` with its baseline starting at `x`, `y`.
+fn show(content: &mut Vec<u8>, font: &str, size: f32, x: f32, y: f32, text: &[u8]) {
+    content.extend(format!("BT /{} {} Tf {:.1} {:.1} Td ", font, size, x, y).into_bytes());
+    content.extend(string(text));
+    content.extend(b" Tj ET\n");
+}
+
+/// What goes on every poster, besides the suggestions.
+struct Heading {
+    title: Vec<u8>,
+    footer: Vec<u8>,
+    accent: [u8; 3],
+}
+
+// The content of each page of the poster.
+fn contents(heading: &Heading, layout: &Layout) -> Vec<Vec<u8>> {
+    let pages = layout.pages();
+    let count = pages.len();
+    pages
+        .iter()
+        .enumerate()
+        .map(|(number, placed)| {
+            let mut content = vec![];
+            if number == 0 {
+                content.extend(fill(heading.accent).into_bytes());
+                let size = TITLE_SIZE.min(
+                    TITLE_SIZE * (PAGE_WIDTH - 2.0 * MARGIN)
+                        / (width(&heading.title, TITLE_SIZE) * 1.1),
+                );
+                show(
+                    &mut content,
+                    "F2",
+                    size,
+                    MARGIN,
+                    PAGE_HEIGHT - MARGIN - TITLE_SIZE,
+                    &heading.title,
+                );
+            }
+
+            for Placed { x, y, entry } in placed {
+                content.extend(fill(heading.accent).into_bytes());
+                show(&mut content, "F2", layout.size, *x, *y, &entry.number);
+                content.extend(fill(card::FOREGROUND).into_bytes());
+                for (line, text) in entry.lines.iter().enumerate() {
+                    let baseline = y - line as f32 * layout.line_height();
+                    show(
+                        &mut content,
+                        "F1",
+                        layout.size,
+                        x + layout.indent,
+                        baseline,
+                        text,
+                    );
+                }
+            }
+
+            content.extend(fill(card::FOREGROUND).into_bytes());
+            show(
+                &mut content,
+                "F1",
+                FOOTER_SIZE,
+                MARGIN,
+                MARGIN,
+                &heading.footer,
+            );
+            if count > 1 {
+                let page = format!("{}/{}", number + 1, count).into_bytes();
+                let x = PAGE_WIDTH - MARGIN - width(&page, FOOTER_SIZE);
+                show(&mut content, "F1", FOOTER_SIZE, x, MARGIN, &page);
+            }
+            content
+        })
+        .collect()
+}
+
+/// A PDF file, as its objects are added to it.
+struct Pdf {
+    bytes: Vec<u8>,
+    offsets: Vec<usize>,
+}
+
+impl Pdf {
+    fn new() -> Self {
+        // The comment's high bytes mark the file as binary.
+        Pdf {
+            bytes: b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec(),
+            offsets: vec![],
+        }
+    }
+
+    // Add the object numbered `number`, which must be the next number.
+    fn object(&mut self, number: usize, body: &[u8]) {
+        debug_assert_eq!(number, self.offsets.len() + 1);
+        self.offsets.push(self.bytes.len());
+        self.bytes
+            .extend(format!("{} 0 obj\n", number).into_bytes());
+        self.bytes.extend(body);
+        self.bytes.extend(b"\nendobj\n");
+    }
+
+    fn stream(&mut self, number: usize, content: &[u8]) {
+        let mut body = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        body.extend(content);
+        body.extend(b"\nendstream");
+        self.object(number, &body);
+    }
+
+    fn finish(mut self, info: usize) -> Vec<u8> {
+        let xref = self.bytes.len();
+        let size = self.offsets.len() + 1;
+        self.bytes
+            .extend(format!("xref\n0 {}\n0000000000 65535 f \n", size).into_bytes());
+        for offset in &self.offsets {
+            self.bytes
+                .extend(format!("{:010} 00000 n \n", offset).into_bytes());
+        }
+        self.bytes.extend(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R /Info {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+                size, info, xref
+            )
+            .into_bytes(),
+        );
+        self.bytes
+    }
+}
+
+/// A poster of the suggestions `texts`, as a PDF file.
+fn render(heading: &Heading, texts: &[String]) -> Vec<u8> {
+    let texts: Vec<_> = texts.iter().map(|text| words(text)).collect();
+    // The largest size that fits on one page, or the smallest otherwise.
+    let layout = (MIN_SIZE..=MAX_SIZE)
+        .rev()
+        .map(|size| Layout::new(&texts, f32::from(size)))
+        .find(|layout| layout.pages().len() == 1)
+        .unwrap_or_else(|| Layout::new(&texts, f32::from(MIN_SIZE)));
+    let pages = contents(heading, &layout);
+
+    // The catalog, the page tree, the fonts, and the document's information
+    // come first, then each page and its content.
+    let mut pdf = Pdf::new();
+    let first_page = 6;
+    let kids: Vec<_> = (0..pages.len())
+        .map(|page| format!("{} 0 R", first_page + 2 * page))
+        .collect();
+    pdf.object(1, b"<< /Type /Catalog /Pages 2 0 R >>");
+    pdf.object(
+        2,
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} /MediaBox [0 0 {} {}] >>",
+            kids.join(" "),
+            pages.len(),
+            PAGE_WIDTH,
+            PAGE_HEIGHT
+        )
+        .as_bytes(),
+    );
+    for (number, font) in [(3, "Helvetica"), (4, "Helvetica-Bold")] {
+        pdf.object(
+            number,
+            format!(
+                "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+                font
+            )
+            .as_bytes(),
+        );
+    }
+    let mut info = b"<< /Title ".to_vec();
+    info.extend(string(&heading.title));
+    info.extend(b" /Producer (Things To Check) >>");
+    pdf.object(5, &info);
+    for (page, content) in pages.iter().enumerate() {
+        let number = first_page + 2 * page;
+        pdf.object(
+            number,
+            format!(
+                "<< /Type /Page /Parent 2 0 R /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> \
+                 /Contents {} 0 R >>",
+                number + 1
+            )
+            .as_bytes(),
+        );
+        pdf.stream(number + 1, content);
+    }
+
+    pdf.finish(5)
+}
+
+#[utoipa::path(
+    get,
+    path = "/poster.pdf",
+    tag = "printing",
+    params(PosterQuery),
+    responses(
+        (status = 200, description = "A printable poster of the suggestions", content_type = "application/pdf"),
+        (status = 304, description = "The poster matches the If-None-Match header"),
+        (status = 404, description = "No such list"),
+    ),
+)]
+#[get("/poster.pdf", name = "poster")]
+pub(crate) async fn poster(
+    _: Limited,
+    req: HttpRequest,
+    store: web::Data<Store>,
+    theme: web::Data<Theme>,
+    query: web::Query<PosterQuery>,
+) -> error::Result<HttpResponse> {
+    let catalog = store.current();
+    let things = catalog
+        .list(query.list.as_deref())
+        .ok_or_else(|| view::not_found(&req))?;
+
+    let texts: Vec<_> = things
+        .0
+        .iter()
+        .filter(|(_, thing)| !thing.retired && thing.has_tag(query.tag.as_deref()))
+        .map(|(_, thing)| thing.translated(query.lang.as_deref()).text())
+        .collect();
+
+    let home = req.new_suggestion(&ItemQuery {
+        item: None,
+        list: query.list.clone(),
+        tag: query.tag.clone(),
+        lang: query.lang.clone(),
+    })?;
+    let footer = match &theme.footer {
+        Some(footer) => format!("{} · {}", home, footer),
+        None => home.to_string(),
+    };
+    let heading = Heading {
+        title: words(theme.title.as_deref().unwrap_or(NAME)).join(&b' '),
+        footer: words(&footer).join(&b' '),
+        accent: theme
+            .accent
+            .as_deref()
+            .and_then(rgb)
+            .unwrap_or(card::ACCENT),
+    };
+
+    Ok(view::fixed(
+        &req,
+        "application/pdf",
+        render(&heading, &texts),
+    ))
+}
+
+pub(crate) fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(poster);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn heading() -> Heading {
+        Heading {
+            title: b"Things To Check".to_vec(),
+            footer: b"http://localhost:8080/".to_vec(),
+            accent: card::ACCENT,
+        }
+    }
+
+    #[test]
+    fn text_is_encoded_for_the_fonts() {
+        assert_eq!(
+            words("💡 Have you checked the “café”?"),
+            vec![
+                b"Have".to_vec(),
+                b"you".to_vec(),
+                b"checked".to_vec(),
+                b"the".to_vec(),
+                b"\x93caf\xe9\x94?".to_vec(),
+            ]
+        );
+    }
+
+    #[test]
+    fn wrap_fits_lines_to_the_measure() {
+        let text = words("Have you checked that the disk isn't full of old logs?");
+        let lines = wrap(&text, 100.0, 12.0);
+        assert!(lines.len() > 1);
+        assert!(lines.iter().all(|line| width(line, 12.0) <= 100.0));
+        assert_eq!(lines.join(&b' '), text.join(&b' '));
+
+        let long = words(&"x".repeat(100));
+        assert!(wrap(&long, 100.0, 12.0)
+            .iter()
+            .all(|line| width(line, 12.0) <= 100.0));
+    }
+
+    #[test]
+    fn short_lists_fit_on_one_page_in_large_type() {
+        let texts = vec!["Have you checked DNS?".to_owned(); 10];
+        let pdf = render(&heading(), &texts);
+        assert!(pdf.starts_with(b"%PDF-1.4\n"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+        let pdf = String::from_utf8_lossy(&pdf);
+        assert!(pdf.contains("/Count 1 "));
+        assert!(pdf.contains("/F1 20 Tf"));
+        assert!(pdf.contains("(Have you checked DNS?) Tj"));
+    }
+
+    #[test]
+    fn long_lists_continue_onto_more_pages() {
+        let texts = vec![
+            "Have you checked that the certificate hasn't expired, and that every \
+             intermediate certificate is served with it?"
+                .to_owned();
+            200
+        ];
+        let pdf = String::from_utf8_lossy(&render(&heading(), &texts)).into_owned();
+        assert!(!pdf.contains("/Count 1 "));
+        assert!(pdf.contains(&format!("/F1 {} Tf", MIN_SIZE)));
+        assert!(pdf.contains("(200.) Tj"));
+    }
+
+    #[test]
+    fn cross_references_point_at_objects() {
+        let pdf = render(&heading(), &["Have you checked (the) logs?".to_owned()]);
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains(r"(Have you checked \(the\) logs?) Tj"));
+
+        let xref = text.find("xref\n").unwrap();
+        let offsets = text[xref..]
+            .lines()
+            .skip(3)
+            .take_while(|line| line.ends_with(" n "))
+            .map(|line| line[..10].parse::<usize>().unwrap());
+        for (number, offset) in offsets.enumerate() {
+            assert!(pdf[offset..].starts_with(format!("{} 0 obj", number + 1).as_bytes()));
+        }
+    }
+
+    #[test]
+    fn accents_may_be_hex_colors() {
+        assert_eq!(rgb("#c0392b"), Some([0xc0, 0x39, 0x2b]));
+        assert_eq!(rgb("#fff"), Some([0xff, 0xff, 0xff]));
+        assert_eq!(rgb("teal"), None);
+    }
+}
@@ -0,0 +1,170 @@
+//! Pluggable sources of suggestions.
+//!
+//! The suggestions served by this app usually come from the built-in YAML, a
+//! file, or a URL, but applications embedding the service can supply their
+//! own by implementing `DataProvider`, and setting the service up with
+//! `view::make_service_from_provider(…)`. Providers are asynchronous, so they
+//! can read from a database, a remote API, or anywhere else, without blocking
+//! the server.
+//!
+//! A provider returns a `Document`, holding suggestions in one of the formats
+//! accepted by `view::make_service_from_path(…)`. The provider is asked once
+//! when the service is set up, and again each time the accompanying
+//! `Refresher` is triggered.
+//!
+//! # Examples
+//!
+//! ```
+//! # use things_to_check::{provider, view};
+//! # #[actix_web::main]
+//! # async fn main() -> std::result::Result<(), things_to_check::view::Error> {
+//! use actix_web::{App, HttpServer};
+//! use provider::{DataProvider, Document, Format};
+//!
+//! struct Networking;
+//!
+//! impl DataProvider for Networking {
+//!     async fn load(&self) -> Result<Document, view::Error> {
+//!         Ok(Document {
+//!             text: "- Have you checked DNS?".to_owned(),
+//!             format: Format::Yaml,
+//!         })
+//!     }
+//! }
+//!
+//! let options = view::Options::default();
+//! let (service, refresher) = view::make_service_from_provider(Networking, &options).await?;
+//!
+//! let app_factory = move ||
+//!     App::new()
+//!         .configure(|cfg| service(cfg));
+//!
+//! HttpServer::new(app_factory);
+//! # Ok(())
+//! # }
+//! ```
+
+use actix_web::web;
+use std::future::Future;
+use std::sync::Arc;
+
+pub use crate::things::Format;
+use crate::things::{parse_catalog, Catalog, Store, THINGS};
+use crate::view::{Error, Options};
+
+/// Suggestions, as provided by a `DataProvider`.
+#[derive(Clone, Debug)]
+pub struct Document {
+    /// The suggestions, as a list of Markdown strings or detailed entries, or
+    /// a mapping of list names to such lists.
+    pub text: String,
+    /// The format `text` is written in.
+    pub format: Format,
+}
+
+impl Document {
+    pub(crate) fn catalog(&self) -> Result<Catalog, Error> {
+        parse_catalog(&self.text, self.format)
+    }
+}
+
+/// A source of suggestions.
+///
+/// Implementations report their own failures as `Error::ProviderError`.
+pub trait DataProvider: Send + Sync + 'static {
+    /// Provide the current suggestions.
+    fn load(&self) -> impl Future<Output = Result<Document, Error>> + Send;
+}
+
+/// Provides the built-in suggestions, which `view::make_service()` serves.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Embedded;
+
+impl DataProvider for Embedded {
+    async fn load(&self) -> Result<Document, Error> {
+        Ok(Document {
+            text: THINGS.to_owned(),
+            format: Format::Yaml,
+        })
+    }
+}
+
+pub(crate) async fn load(
+    provider: &impl DataProvider,
+    options: &Options,
+) -> Result<Catalog, Error> {
+    let mut catalog = provider.load().await?.catalog()?;
+    catalog.check(options.strictness)?;
+    catalog.exclude(&options.exclude);
+    catalog.render(options.markdown);
+
+    Ok(catalog)
+}
+
+/// Reloads the suggestions served by a running service from its provider.
+///
+/// Refreshers are cheap to clone; every clone refreshes the same service.
+pub struct Refresher<P> {
+    provider: Arc<P>,
+    options: Options,
+    store: web::Data<Store>,
+}
+
+// Derived Clone would needlessly require P: Clone.
+impl<P> Clone for Refresher<P> {
+    fn clone(&self) -> Self {
+        Refresher {
+            provider: Arc::clone(&self.provider),
+            options: self.options.clone(),
+            store: self.store.clone(),
+        }
+    }
+}
+
+impl<P: DataProvider> Refresher<P> {
+    pub(crate) fn new(provider: P, options: Options, store: web::Data<Store>) -> Self {
+        Refresher {
+            provider: Arc::new(provider),
+            options,
+            store,
+        }
+    }
+
+    /// Ask the provider for its current suggestions, and start serving them.
+    ///
+    /// If the provider fails, or its suggestions fail the checks called for by
+    /// the service's `Options`, this returns an error and the service keeps
+    /// serving the suggestions it already had.
+    pub async fn refresh(&self) -> Result<(), Error> {
+        let catalog = self
+            .store
+            .record(load(self.provider.as_ref(), &self.options).await)?;
+        self.store.replace(catalog);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn embedded_provides_builtin_things() {
+        let document = Embedded.load().await.unwrap();
+
+        assert_eq!(document.format, Format::Yaml);
+        assert_eq!(document.catalog().unwrap().0.len(), 1);
+    }
+
+    #[test]
+    fn documents_may_use_other_formats() {
+        let document = Document {
+            text: r#"{"networking": ["Have you checked DNS?"]}"#.to_owned(),
+            format: Format::Json,
+        };
+
+        let catalog = document.catalog().unwrap();
+        assert!(catalog.list(Some("networking")).is_some());
+    }
+}
@@ -0,0 +1,311 @@
+//! The service's icons, and resources for installing it as an app.
+//!
+//! Browsers that support [progressive web apps][1] offer to install the
+//! service to a phone's home screen or a desktop's app launcher, where it opens
+//! in a window of its own. The suggestion page links these resources, and
+//! registers the service worker. They are mounted by every
+//! `make_*service(…)` function.
+//!
+//! # Endpoints
+//!
+//! * `/manifest.webmanifest` (`GET`): the app's [manifest][2], naming it after
+//!   the theme's title, if any, and coloring it with the theme's accent.
+//!
+//! * `/icon-192.png` and `/icon-512.png` (`GET`): the app's icon, at 192 and
+//!   512 pixels square.
+//!
+//! * `/favicon.ico`, `/apple-touch-icon.png`, and `/icon.svg` (`GET`): the
+//!   icon for browser tabs, bookmarks, and iOS home screens, where browsers
+//!   look for it. If the theme gives an `icon`, these redirect to it instead.
+//!
+//! * `/mask-icon.svg` (`GET`): the icon as a single-color silhouette, for
+//!   Safari's pinned tabs. The theme's `icon` doesn't replace it, as it must
+//!   be a silhouette.
+//!
+//! * `/sw.js` (`GET`): a service worker that keeps the last suggestion page
+//!   seen, and the assets from the `assets` module it uses, and shows it when
+//!   the network is unavailable.
+//!
+//! Each carries an `ETag`, and honours `If-None-Match`, as for `/check/{slug}`.
+//!
+//! [1]: https://developer.mozilla.org/en-US/docs/Web/Progressive_web_apps
+//! [2]: https://www.w3.org/TR/appmanifest/
+
+use actix_web::http::header;
+use actix_web::{error, get, web, HttpRequest, HttpResponse};
+use askama::Template;
+use serde::Serialize;
+use std::fmt::Write;
+
+use crate::card;
+use crate::theme::Theme;
+use crate::view::{self, ItemQuery, Urls};
+
+/// The sizes, in pixels, the icon is drawn at for the manifest.
+const ICON_SIZES: [u32; 2] = [192, 512];
+
+/// The size, in pixels, of the icon in `/favicon.ico`.
+const FAVICON_SIZE: u32 = 32;
+
+/// The size, in pixels, of the icon iOS puts on home screens.
+const TOUCH_ICON_SIZE: u32 = 180;
+
+/// The service worker, served as it is.
+const SERVICE_WORKER: &str = include_str!("../static/sw.js");
+
+/// The name of the app when the theme doesn't give one.
+const NAME: &str = "Things To Check";
+
+#[derive(Serialize)]
+struct Manifest {
+    name: String,
+    short_name: String,
+    start_url: String,
+    scope: String,
+    display: &'static str,
+    background_color: String,
+    theme_color: String,
+    icons: Vec<Icon>,
+}
+
+#[derive(Serialize)]
+struct Icon {
+    src: String,
+    sizes: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+}
+
+fn hex(color: [u8; 3]) -> String {
+    format!("#{}", hex::encode(color))
+}
+
+/// The color browsers paint the app's title bar with.
+pub(crate) fn theme_color(theme: &Theme) -> String {
+    theme.accent.clone().unwrap_or_else(|| hex(card::ACCENT))
+}
+
+#[get("/manifest.webmanifest", name = "manifest")]
+pub(crate) async fn manifest(
+    req: HttpRequest,
+    theme: web::Data<Theme>,
+) -> error::Result<HttpResponse> {
+    let index = req.new_suggestion(&ItemQuery::default())?;
+    let name = theme.title.clone().unwrap_or_else(|| NAME.to_owned());
+    let icons = ICON_SIZES
+        .iter()
+        .map(|size| {
+            Ok(Icon {
+                src: req.icon(*size)?.into(),
+                sizes: format!("{0}x{0}", size),
+                kind: "image/png",
+            })
+        })
+        .collect::<error::Result<_>>()?;
+
+    let manifest = Manifest {
+        short_name: name.clone(),
+        name,
+        start_url: index.to_string(),
+        scope: index.to_string(),
+        display: "standalone",
+        background_color: hex(card::BACKGROUND),
+        theme_color: theme_color(&theme),
+        icons,
+    };
+    let body = serde_json::to_string(&manifest).map_err(error::ErrorInternalServerError)?;
+
+    Ok(view::fixed(&req, "application/manifest+json", body))
+}
+
+// A square icon `size` pixels across: a question mark on the accent color.
+fn icon_png(size: u32) -> Result<Vec<u8>, png::EncodingError> {
+    let side = size as usize;
+    let mut pixels = card::ACCENT.repeat(side * side);
+
+    // Half the icon's width, centred, leaves the glyph inside the area that
+    // launchers never crop.
+    let scale = side / 16;
+    let origin = (side - 8 * scale) / 2;
+    for (row, bits) in card::glyph('?').iter().enumerate() {
+        for column in 0..8 {
+            if bits & (1 << column) == 0 {
+                continue;
+            }
+            let (x, y) = (origin + column * scale, origin + row * scale);
+            for py in y..y + scale {
+                for px in x..x + scale {
+                    let offset = (py * side + px) * 3;
+                    pixels[offset..offset + 3].copy_from_slice(&card::BACKGROUND);
+                }
+            }
+        }
+    }
+
+    let mut png = vec![];
+    let mut encoder = png::Encoder::new(&mut png, size, size);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&pixels)?;
+    writer.finish()?;
+
+    Ok(png)
+}
+
+// An ICO file holding the PNG `png`, `size` pixels square. Every browser that
+// reads `/favicon.ico` accepts PNG images in it.
+fn ico(png: &[u8], size: u32) -> Vec<u8> {
+    let length = u32::try_from(png.len()).unwrap_or(u32::MAX);
+    let mut ico = vec![];
+    // The header: reserved, an icon, one image.
+    ico.extend([0, 0, 1, 0, 1, 0]);
+    // The image's entry: size, no palette, reserved, one plane, 24 bits per
+    // pixel, then its length and offset.
+    ico.extend([size as u8, size as u8, 0, 0, 1, 0, 24, 0]);
+    ico.extend(length.to_le_bytes());
+    ico.extend(22u32.to_le_bytes());
+    ico.extend(png);
+    ico
+}
+
+// Redirect to the theme's icon, if it gives one.
+fn themed_icon(theme: &Theme) -> Option<HttpResponse> {
+    theme.icon.as_ref().map(|url| {
+        HttpResponse::Found()
+            .insert_header((header::LOCATION, url.as_str()))
+            .finish()
+    })
+}
+
+#[get("/favicon.ico", name = "favicon")]
+pub(crate) async fn favicon(
+    req: HttpRequest,
+    theme: web::Data<Theme>,
+) -> error::Result<HttpResponse> {
+    if let Some(redirect) = themed_icon(&theme) {
+        return Ok(redirect);
+    }
+
+    let png = icon_png(FAVICON_SIZE).map_err(error::ErrorInternalServerError)?;
+    Ok(view::fixed(&req, "image/x-icon", ico(&png, FAVICON_SIZE)))
+}
+
+#[get("/apple-touch-icon.png", name = "touch_icon")]
+pub(crate) async fn touch_icon(
+    req: HttpRequest,
+    theme: web::Data<Theme>,
+) -> error::Result<HttpResponse> {
+    if let Some(redirect) = themed_icon(&theme) {
+        return Ok(redirect);
+    }
+
+    let body = icon_png(TOUCH_ICON_SIZE).map_err(error::ErrorInternalServerError)?;
+    Ok(view::fixed(&req, "image/png", body))
+}
+
+#[derive(Template)]
+#[template(path = "icon.svg", escape = "html")]
+struct SvgIcon {
+    background: Option<String>,
+    foreground: String,
+    path: String,
+}
+
+impl SvgIcon {
+    // The question mark, as an SVG path of one-unit squares on a 16-unit
+    // canvas, placed as `icon_png` places it.
+    fn path() -> String {
+        let mut path = String::new();
+        for (row, bits) in card::glyph('?').iter().enumerate() {
+            for column in 0..8 {
+                if bits & (1 << column) != 0 {
+                    let _ = write!(path, "M{} {}h1v1h-1z", 4 + column, 4 + row);
+                }
+            }
+        }
+        path
+    }
+}
+
+#[get("/icon.svg", name = "svg_icon")]
+pub(crate) async fn svg_icon(
+    req: HttpRequest,
+    theme: web::Data<Theme>,
+) -> error::Result<HttpResponse> {
+    if let Some(redirect) = themed_icon(&theme) {
+        return Ok(redirect);
+    }
+
+    let body = SvgIcon {
+        background: Some(hex(card::ACCENT)),
+        foreground: hex(card::BACKGROUND),
+        path: SvgIcon::path(),
+    }
+    .render()
+    .map_err(error::ErrorInternalServerError)?;
+    Ok(view::fixed(&req, "image/svg+xml", body))
+}
+
+#[get("/mask-icon.svg", name = "mask_icon")]
+pub(crate) async fn mask_icon(req: HttpRequest) -> error::Result<HttpResponse> {
+    let body = SvgIcon {
+        background: None,
+        foreground: "#000".to_owned(),
+        path: SvgIcon::path(),
+    }
+    .render()
+    .map_err(error::ErrorInternalServerError)?;
+    Ok(view::fixed(&req, "image/svg+xml", body))
+}
+
+#[get("/icon-{size}.png", name = "icon")]
+pub(crate) async fn icon(req: HttpRequest, size: web::Path<u32>) -> error::Result<HttpResponse> {
+    let size = size.into_inner();
+    if !ICON_SIZES.contains(&size) {
+        return Err(view::not_found(&req));
+    }
+
+    let body = icon_png(size).map_err(error::ErrorInternalServerError)?;
+    Ok(view::fixed(&req, "image/png", body))
+}
+
+#[get("/sw.js", name = "service_worker")]
+pub(crate) async fn service_worker(req: HttpRequest) -> HttpResponse {
+    view::fixed(&req, "text/javascript", SERVICE_WORKER)
+}
+
+pub(crate) fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(manifest)
+        .service(icon)
+        .service(favicon)
+        .service(touch_icon)
+        .service(svg_icon)
+        .service(mask_icon)
+        .service(service_worker);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn icons_are_square_pngs() {
+        for size in ICON_SIZES {
+            let png = icon_png(size).unwrap();
+            let decoder = png::Decoder::new(std::io::Cursor::new(png));
+            let reader = decoder.read_info().unwrap();
+            assert_eq!(reader.info().width, size);
+            assert_eq!(reader.info().height, size);
+        }
+    }
+
+    #[test]
+    fn favicons_wrap_pngs() {
+        let png = icon_png(FAVICON_SIZE).unwrap();
+        let ico = ico(&png, FAVICON_SIZE);
+        assert_eq!(ico[..6], [0, 0, 1, 0, 1, 0]);
+        assert_eq!(ico[6], 32);
+        assert_eq!(ico[22..], png[..]);
+    }
+}
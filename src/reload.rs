@@ -0,0 +1,144 @@
+//! Reload suggestions into a running service.
+//!
+//! A service created with `view::make_reloadable_service(…)` comes with a
+//! `Reloader`, which can re-read the suggestions file (or directory) and swap
+//! the new suggestions in without restarting the server. Requests that are already in
+//! flight finish with the suggestions they started with.
+//!
+//! Reloads can be triggered directly, by calling `Reloader::reload`, or in the
+//! background, by spawning `Reloader::on_sighup` or `Reloader::on_change` on
+//! the actix runtime.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # use things_to_check::view;
+//! # #[actix_web::main]
+//! # async fn main() -> std::result::Result<(), things_to_check::view::Error> {
+//! use actix_web::{rt, App, HttpServer};
+//!
+//! let options = view::Options::default();
+//! let (service, reloader) = view::make_reloadable_service("things-to-check.yml", &options)?;
+//! rt::spawn(reloader.on_sighup());
+//!
+//! let app_factory = move ||
+//!     App::new()
+//!         .configure(|cfg| service(cfg));
+//!
+//! HttpServer::new(app_factory);
+//! # Ok(())
+//! # }
+//! ```
+
+use actix_web::rt::signal::unix::{signal, SignalKind};
+use actix_web::rt::time;
+use actix_web::web;
+use std::fs;
+use std::io;
+use std::iter;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::things::{catalog_files, read_catalog, Catalog, Store};
+use crate::view::{Error, Options};
+
+/// Reloads the suggestions served by a running service from disk.
+///
+/// Reloaders are cheap to clone; every clone reloads the same service.
+#[derive(Clone)]
+pub struct Reloader {
+    path: PathBuf,
+    options: Options,
+    store: web::Data<Store>,
+}
+
+impl Reloader {
+    pub(crate) fn new(path: PathBuf, options: Options, store: web::Data<Store>) -> Self {
+        Reloader {
+            path,
+            options,
+            store,
+        }
+    }
+
+    /// Re-read the suggestions file and start serving its contents.
+    ///
+    /// If the file can't be read or parsed, or fails the checks called for by
+    /// the service's `Options`, this returns an error and the service keeps
+    /// serving the suggestions it already had.
+    pub fn reload(&self) -> Result<(), Error> {
+        let catalog = self.store.record(self.read())?;
+        self.store.replace(catalog);
+
+        Ok(())
+    }
+
+    fn read(&self) -> Result<Catalog, Error> {
+        let mut catalog = read_catalog(&self.path)?;
+        catalog.check(self.options.strictness)?;
+        catalog.exclude(&self.options.exclude);
+        catalog.render(self.options.markdown);
+
+        Ok(catalog)
+    }
+
+    /// Reload every time the process receives `SIGHUP`.
+    ///
+    /// This runs until the runtime shuts down. Failed reloads are reported on
+    /// stderr and otherwise ignored. This only fails if the signal handler
+    /// can't be installed.
+    pub async fn on_sighup(self) -> io::Result<()> {
+        let mut hangups = signal(SignalKind::hangup())?;
+
+        while hangups.recv().await.is_some() {
+            self.reload_or_report();
+        }
+
+        Ok(())
+    }
+
+    /// Reload whenever the suggestions file's modification time changes,
+    /// checking once every `period`.
+    ///
+    /// This runs until the runtime shuts down. Failed reloads are reported on
+    /// stderr and otherwise ignored.
+    pub async fn on_change(self, period: Duration) {
+        let mut last_modified = self.modified();
+        let mut ticks = time::interval(period);
+
+        loop {
+            ticks.tick().await;
+
+            let modified = self.modified();
+            if modified != last_modified {
+                last_modified = modified;
+                self.reload_or_report();
+            }
+        }
+    }
+
+    // For a directory, this is the latest modification time of the directory
+    // itself (which changes when files are added or removed) or any of the
+    // files in it.
+    fn modified(&self) -> Option<SystemTime> {
+        let modified = |path: &Path| fs::metadata(path).and_then(|metadata| metadata.modified());
+
+        let path_modified = modified(&self.path).ok()?;
+        if !self.path.is_dir() {
+            return Some(path_modified);
+        }
+
+        let files = catalog_files(&self.path).ok()?;
+        files
+            .iter()
+            .filter_map(|file| modified(file).ok())
+            .chain(iter::once(path_modified))
+            .max()
+    }
+
+    fn reload_or_report(&self) {
+        if let Err(err) = self.reload() {
+            eprintln!("Unable to reload {}: {}", self.path.display(), err);
+        }
+    }
+}
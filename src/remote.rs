@@ -0,0 +1,132 @@
+//! Refresh suggestions fetched from a URL into a running service.
+//!
+//! A service created with `view::make_polling_service(…)` comes with a
+//! `Poller`, which can fetch the suggestions again and swap the new
+//! suggestions in without restarting the server.
+//!
+//! Polls are conditional: the poller sends the `ETag` and `Last-Modified`
+//! values from the last fetch as `If-None-Match` and `If-Modified-Since`, and
+//! when the server answers `304 Not Modified`, the suggestions already being
+//! served are kept as they are, without being parsed or rendered again.
+//!
+//! Polls can be triggered directly, by calling `Poller::poll`, or in the
+//! background, by spawning `Poller::every` on the actix runtime.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # use things_to_check::view;
+//! # #[actix_web::main]
+//! # async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+//! use actix_web::{rt, App, HttpServer};
+//! use std::time::Duration;
+//! use url::Url;
+//!
+//! let url = Url::parse("https://example.com/things-to-check.yml")?;
+//! let options = view::Options::default();
+//! let (service, poller) = view::make_polling_service(&url, &options)?;
+//! rt::spawn(poller.every(Duration::from_secs(300)));
+//!
+//! let app_factory = move ||
+//!     App::new()
+//!         .configure(|cfg| service(cfg));
+//!
+//! HttpServer::new(app_factory);
+//! # Ok(())
+//! # }
+//! ```
+
+use actix_web::rt::time;
+use actix_web::web;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use url::Url;
+
+use crate::things::{fetch_catalog_if_changed, Catalog, Store, Validators};
+use crate::view::{Error, Options};
+
+/// Refreshes the suggestions served by a running service from a URL.
+///
+/// Pollers are cheap to clone; every clone refreshes the same service.
+#[derive(Clone)]
+pub struct Poller {
+    url: Url,
+    options: Options,
+    store: web::Data<Store>,
+    validators: Arc<Mutex<Validators>>,
+}
+
+impl Poller {
+    pub(crate) fn new(
+        url: Url,
+        options: Options,
+        store: web::Data<Store>,
+        validators: Validators,
+    ) -> Self {
+        Poller {
+            url,
+            options,
+            store,
+            validators: Arc::new(Mutex::new(validators)),
+        }
+    }
+
+    /// Fetch the suggestions again, if they've changed, and start serving
+    /// them. Returns whether the suggestions had changed.
+    ///
+    /// If the suggestions can't be fetched or parsed, or fail the checks
+    /// called for by the service's `Options`, this returns an error and the
+    /// service keeps serving the suggestions it already had. This blocks
+    /// while the suggestions are fetched.
+    pub fn poll(&self) -> Result<bool, Error> {
+        let (catalog, validators) = match self.store.record(self.fetch())? {
+            Some(fetched) => fetched,
+            None => return Ok(false),
+        };
+        self.store.replace(catalog);
+        *self.lock() = validators;
+
+        Ok(true)
+    }
+
+    fn fetch(&self) -> Result<Option<(Catalog, Validators)>, Error> {
+        let validators = self.lock().clone();
+        let (mut catalog, validators) = match fetch_catalog_if_changed(&self.url, &validators)? {
+            Some(fetched) => fetched,
+            None => return Ok(None),
+        };
+        catalog.check(self.options.strictness)?;
+        catalog.exclude(&self.options.exclude);
+        catalog.render(self.options.markdown);
+
+        Ok(Some((catalog, validators)))
+    }
+
+    /// Poll once every `period`.
+    ///
+    /// This runs until the runtime shuts down. Failed polls are reported on
+    /// stderr and otherwise ignored.
+    pub async fn every(self, period: Duration) {
+        let mut ticks = time::interval(period);
+        // The first tick completes immediately, but the suggestions were
+        // fetched when the service was set up.
+        ticks.tick().await;
+
+        loop {
+            ticks.tick().await;
+
+            let poller = self.clone();
+            match web::block(move || poller.poll()).await {
+                Ok(Ok(_)) => {}
+                Ok(Err(err)) => eprintln!("Unable to refresh {}: {}", self.url, err),
+                Err(err) => eprintln!("Unable to refresh {}: {}", self.url, err),
+            }
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Validators> {
+        self.validators
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+    }
+}
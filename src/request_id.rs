@@ -0,0 +1,234 @@
+//! Request IDs, for matching reports of errors to the service's logs.
+//!
+//! Wrap an App in `RequestIds` to give every request an ID:
+//!
+//! ```
+//! # use things_to_check::{request_id, view};
+//! # #[actix_web::main]
+//! # async fn main() -> std::result::Result<(), things_to_check::view::Error> {
+//! use actix_web::{App, HttpServer};
+//!
+//! let service = view::make_service()?;
+//! let app_factory = move ||
+//!     App::new()
+//!         .wrap(request_id::RequestIds)
+//!         .configure(|cfg| service(cfg));
+//!
+//! HttpServer::new(app_factory);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A request's ID is taken from its `X-Request-Id` header, as set by many
+//! proxies and load balancers, if it has one of up to 128 printable ASCII
+//! characters; otherwise, a random one is made up. Every response carries the
+//! ID back in an `X-Request-Id` header.
+//!
+//! The ID is also shown on the `404 Not Found` page and in the API's JSON
+//! errors, and in place of the details of `5xx` errors, which are logged on
+//! stderr along with the ID instead, and shown on the admin dashboard (see the
+//! `admin` module). Other messages the service logs while handling a request
+//! start with its ID, in brackets.
+
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::{error, web, FromRequest, HttpMessage, HttpRequest, HttpResponse};
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use rand::{thread_rng, Rng};
+use std::fmt;
+
+use crate::admin::Errors;
+
+/// The header requests' IDs are read from and sent back in.
+pub const HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// The longest request ID taken from a request's header.
+const MAX_LENGTH: usize = 128;
+
+/// The ID of a request. Handlers can extract it, on Apps wrapped in
+/// `RequestIds`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RequestId(String);
+
+impl RequestId {
+    // The ID in `header`, if it's one to keep, or else a new, random one.
+    fn new(header: Option<&HeaderValue>) -> Self {
+        let given = header
+            .and_then(|value| value.to_str().ok())
+            .filter(|id| (1..=MAX_LENGTH).contains(&id.len()))
+            .filter(|id| id.bytes().all(|byte| byte.is_ascii_graphic()));
+
+        match given {
+            Some(id) => RequestId(id.to_owned()),
+            None => RequestId(hex::encode(thread_rng().gen::<[u8; 16]>())),
+        }
+    }
+
+    /// The ID, as a string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromRequest for RequestId {
+    type Error = error::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(of(req).ok_or_else(|| error::ErrorInternalServerError("Requests have no IDs")))
+    }
+}
+
+/// The ID of `req`, if the App is wrapped in `RequestIds`.
+pub(crate) fn of(req: &HttpRequest) -> Option<RequestId> {
+    req.extensions().get::<RequestId>().cloned()
+}
+
+/// `req`'s ID in brackets, followed by a space, for the start of messages
+/// logged while handling it; or nothing, if requests have no IDs.
+pub(crate) fn prefix(req: &HttpRequest) -> String {
+    match of(req) {
+        Some(id) => format!("[{}] ", id),
+        None => String::new(),
+    }
+}
+
+/// Gives each request an ID. Wrap an App in it to give IDs to every request
+/// the App serves.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RequestIds;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestIds
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Transform = RequestIdService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestIdService { service }))
+    }
+}
+
+/// The service `RequestIds` wraps an App's service in.
+pub struct RequestIdService<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestIdService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let id = RequestId::new(req.headers().get(HEADER));
+        req.extensions_mut().insert(id.clone());
+        let response = self.service.call(req);
+
+        Box::pin(async move {
+            let response = response.await?;
+            let value = HeaderValue::from_str(id.as_str()).expect("request IDs are ASCII");
+
+            // Details of server errors are for the log, not for the reader,
+            // who gets the ID to report instead.
+            let status = response.status();
+            let mut response = match response.response().error() {
+                Some(err) if status.is_server_error() => {
+                    let req = response.request();
+                    eprintln!("[{}] {} {} failed: {}", id, req.method(), req.path(), err);
+                    if let Some(errors) = req.app_data::<web::Data<Errors>>() {
+                        errors.record(&id, req, err);
+                    }
+                    let page = HttpResponse::build(status)
+                        .content_type("text/plain; charset=utf-8")
+                        .body(format!(
+                            "{}\n\nRequest ID: {}\n",
+                            status.canonical_reason().unwrap_or("Error"),
+                            id
+                        ));
+                    response.into_response(page).map_into_right_body()
+                }
+                _ => response.map_into_left_body(),
+            };
+            response.headers_mut().insert(HEADER, value);
+
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{get, test, App};
+
+    use super::*;
+    use crate::view;
+
+    #[get("/fail")]
+    async fn fail(id: RequestId) -> error::Result<HttpResponse> {
+        Err(error::ErrorInternalServerError(format!("secret {}", id)))
+    }
+
+    #[actix_web::test]
+    async fn requests_carry_ids() {
+        let service = view::make_service().unwrap();
+        let app = test::init_service(
+            App::new()
+                .wrap(RequestIds)
+                .service(fail)
+                .configure(|cfg| service(cfg)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/healthz")
+            .insert_header((HEADER, "abc-123"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get(HEADER).unwrap(), "abc-123");
+
+        // IDs that don't look like IDs are replaced.
+        let req = test::TestRequest::get()
+            .uri("/healthz")
+            .insert_header((HEADER, "two words"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let id = resp.headers().get(HEADER).unwrap().to_str().unwrap();
+        assert_eq!(id.len(), 32);
+
+        let req = test::TestRequest::get()
+            .uri("/check/no-such-check")
+            .insert_header((HEADER, "abc-123"))
+            .to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("abc-123"));
+
+        let req = test::TestRequest::get()
+            .uri("/fail")
+            .insert_header((HEADER, "abc-123"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 500);
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert_eq!(body, "Internal Server Error\n\nRequest ID: abc-123\n");
+    }
+}
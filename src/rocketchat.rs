@@ -0,0 +1,172 @@
+//! A Rocket.Chat outgoing webhook, for asking for suggestions from
+//! Rocket.Chat.
+//!
+//! The webhook is off unless the App is given a `RocketChat` value as app
+//! data, which holds the token Rocket.Chat sends with each request:
+//!
+//! ```
+//! # use things_to_check::{rocketchat, view};
+//! # #[actix_web::main]
+//! # async fn main() -> std::result::Result<(), things_to_check::view::Error> {
+//! use actix_web::{web, App, HttpServer};
+//!
+//! let service = view::make_service()?;
+//! let rocketchat = web::Data::new(rocketchat::RocketChat::new("s3kr1t"));
+//! let app_factory = move ||
+//!     App::new()
+//!         .app_data(rocketchat.clone())
+//!         .configure(|cfg| service(cfg));
+//!
+//! HttpServer::new(app_factory);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Endpoints
+//!
+//! * `/rocketchat/troubleshoot` (`POST`): an outgoing webhook.
+//!
+//!   Point an outgoing webhook integration's URL here, with a trigger word
+//!   such as `!troubleshoot`, or with none to answer every message in the
+//!   channel. The webhook replies with a random suggestion, as an attachment
+//!   linking to its permalink. The rest of the message is read as for the
+//!   Slack app's command: a single word naming a suggestion, as in
+//!   `!troubleshoot 42`, replies with that suggestion, and other words reply
+//!   with a random suggestion matching them.
+//!
+//! Requests must carry the integration's token in their `token` field, or
+//! are rejected with `401 Unauthorized`. The endpoint returns `404 Not Found`
+//! if the webhook is off. Every request comes from Rocket.Chat, on behalf of
+//! everyone on the server, so it isn't rate limited.
+
+use actix_web::{error, post, web, HttpRequest, HttpResponse};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::moderation::constant_time_eq;
+use crate::slack::Ask;
+use crate::things::Store;
+use crate::view::{self, ItemQuery, Urls};
+
+/// Enables the Rocket.Chat webhook, and holds the token Rocket.Chat sends.
+pub struct RocketChat {
+    token: String,
+}
+
+impl RocketChat {
+    /// Enable the Rocket.Chat webhook, for requests carrying `token`, set on
+    /// the outgoing webhook integration.
+    pub fn new(token: impl Into<String>) -> Self {
+        RocketChat {
+            token: token.into(),
+        }
+    }
+}
+
+fn rocketchat(req: &HttpRequest) -> error::Result<&RocketChat> {
+    req.app_data::<web::Data<RocketChat>>()
+        .map(|rocketchat| rocketchat.get_ref())
+        .ok_or_else(|| view::not_found(req))
+}
+
+#[derive(Deserialize)]
+struct Outgoing {
+    #[serde(default)]
+    token: String,
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    trigger_word: Option<String>,
+}
+
+impl Outgoing {
+    // What the message asks: its text, after the word that triggered the
+    // webhook.
+    fn asked(&self) -> &str {
+        let text = self.text.trim();
+        self.trigger_word
+            .as_deref()
+            .and_then(|trigger| text.strip_prefix(trigger))
+            .unwrap_or(text)
+            .trim()
+    }
+}
+
+// A reply showing a suggestion chosen by `text`, as an attachment. Rocket.Chat
+// renders Markdown in attachments, so suggestions keep their formatting.
+fn reply(req: &HttpRequest, store: &Store, text: &str) -> error::Result<Value> {
+    let catalog = store.current();
+    let things = catalog.list(None);
+    let ask = Ask::from_text(things, text);
+    let (_, thing) = match things.and_then(|things| ask.choose(things)) {
+        Some(chosen) => chosen,
+        None => return Ok(json!({ "text": "Sorry, there are no suggestions to show." })),
+    };
+
+    let url = req.suggestion(&ItemQuery::default(), thing)?;
+
+    Ok(json!({
+        "attachments": [
+            {
+                "title": "Permalink",
+                "title_link": url.as_str(),
+                "text": thing.markdown,
+            },
+        ],
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/rocketchat/troubleshoot",
+    tag = "rocketchat",
+    request_body(content = String, description = "A Rocket.Chat outgoing webhook message", content_type = "application/json"),
+    responses(
+        (status = 200, description = "A message to post, as JSON"),
+        (status = 400, description = "The message was malformed"),
+        (status = 401, description = "The request's token was missing or incorrect"),
+        (status = 404, description = "The Rocket.Chat webhook is off"),
+    ),
+)]
+#[post("/rocketchat/troubleshoot")]
+pub(crate) async fn troubleshoot(
+    req: HttpRequest,
+    store: web::Data<Store>,
+    outgoing: web::Json<Outgoing>,
+) -> error::Result<HttpResponse> {
+    let rocketchat = rocketchat(&req)?;
+    if !constant_time_eq(outgoing.token.as_bytes(), rocketchat.token.as_bytes()) {
+        return Err(error::ErrorUnauthorized("Unauthorized"));
+    }
+
+    let reply = reply(&req, &store, outgoing.asked())?;
+
+    Ok(HttpResponse::Ok().json(reply))
+}
+
+pub(crate) fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(troubleshoot);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outgoing(text: &str, trigger_word: Option<&str>) -> Outgoing {
+        Outgoing {
+            token: String::new(),
+            text: text.to_owned(),
+            trigger_word: trigger_word.map(str::to_owned),
+        }
+    }
+
+    #[test]
+    fn trigger_words_are_not_asked_for() {
+        assert_eq!(
+            outgoing("!troubleshoot 42", Some("!troubleshoot")).asked(),
+            "42"
+        );
+        assert_eq!(outgoing("!troubleshoot", Some("!troubleshoot")).asked(), "");
+        assert_eq!(outgoing(" is it dns\n", None).asked(), "is it dns");
+    }
+}
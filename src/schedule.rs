@@ -0,0 +1,437 @@
+//! Post suggestions to chat channels on a schedule.
+//!
+//! A service whose `view::Options` carry a `Schedule` posts a random
+//! suggestion to each of the schedule's webhooks whenever the schedule comes
+//! round, so that a team gets a regular troubleshooting tip without anyone
+//! asking for one:
+//!
+//! ```
+//! # use things_to_check::{schedule, view};
+//! # fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+//! use url::Url;
+//!
+//! let every_morning = "0 9 * * 1-5".parse()?;
+//! let webhook = Url::parse("https://hooks.slack.com/services/T0/B0/XXXX")?;
+//! let options = view::Options {
+//!     schedule: Some(schedule::Schedule::new(every_morning, vec![webhook])),
+//!     ..Default::default()
+//! };
+//! let service = view::make_service_with(&options)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Schedules are written as the first five fields of a crontab entry: the
+//! minute, hour, day of the month, month, and day of the week, in UTC. Each
+//! field is `*`, a number, a range such as `1-5`, or a list of these, such as
+//! `1,15`, and may be followed by a step, such as `*/15`. Days of the week run
+//! from 0, Sunday, to 6, and 7 is Sunday too. As in cron, if both the day of
+//! the month and the day of the week are restricted, either can match.
+//!
+//! Webhooks on `discord.com` are posted to as Discord webhooks; any others are
+//! posted to as Slack incoming webhooks. Schedules can also post the
+//! suggestion as JSON to other webhooks, given with `Schedule::notifying`, as
+//! described in the `notify` module. Posts link to the suggestion's
+//! permalink if the schedule is given the service's public URL with
+//! `Schedule::linking_to`. Suggestions are chosen from the default list, from
+//! those that haven't been retired. Failed posts are reported on stderr and
+//! otherwise ignored.
+//!
+//! The schedule starts when the service is first configured into an App, and
+//! runs on that App's worker until the runtime shuts down, however many
+//! workers the server runs.
+
+use actix_web::rt::time;
+use actix_web::web;
+use serde_json::{json, Value};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use url::Url;
+
+use crate::api::Suggestion;
+use crate::discord;
+use crate::notify;
+use crate::slack;
+use crate::things::{Store, Thing};
+use crate::view::{self, ItemQuery};
+
+/// How far ahead to look for a time a schedule matches, in days. Schedules
+/// that never match, such as those for 30 February, give up after this long.
+const HORIZON_DAYS: u64 = 4 * 366;
+
+const MINUTE: u64 = 60;
+const DAY: u64 = 24 * 60 * MINUTE;
+
+/// A schedule was not five valid crontab fields.
+#[derive(Error, Debug)]
+#[error("Invalid schedule {0:?}: expected a minute, hour, day, month, and day of the week")]
+pub struct InvalidCron(String);
+
+/// When to post, as the first five fields of a crontab entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cron {
+    spec: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+// The values in `min..=max` that a crontab field allows, as a bitmap.
+fn field(spec: &str, min: u32, max: u32) -> Option<u64> {
+    let mut bits = 0;
+    for part in spec.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, Some(step.parse().ok().filter(|&step| step > 0)?)),
+            None => (part, None),
+        };
+        let (low, high) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((low, high)) => (low.parse().ok()?, high.parse().ok()?),
+            // A single value with a step runs to the end of the field.
+            None if step.is_some() => (range.parse().ok()?, max),
+            None => {
+                let value = range.parse().ok()?;
+                (value, value)
+            }
+        };
+        if low < min || high > max || low > high {
+            return None;
+        }
+
+        for value in (low..=high).step_by(step.unwrap_or(1)) {
+            bits |= 1 << value;
+        }
+    }
+
+    Some(bits)
+}
+
+impl FromStr for Cron {
+    type Err = InvalidCron;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidCron(spec.to_owned());
+
+        let fields: Vec<_> = spec.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(invalid());
+        };
+
+        let sundays_twice = field(weekdays, 0, 7).ok_or_else(invalid)?;
+        Ok(Cron {
+            spec: fields.join(" "),
+            minutes: field(minutes, 0, 59).ok_or_else(invalid)?,
+            hours: field(hours, 0, 23).ok_or_else(invalid)?,
+            days: field(days, 1, 31).ok_or_else(invalid)?,
+            months: field(months, 1, 12).ok_or_else(invalid)?,
+            // Sunday is both 0 and 7.
+            weekdays: (sundays_twice | sundays_twice >> 7) & 0x7f,
+            any_day: days == "*",
+            any_weekday: weekdays == "*",
+        })
+    }
+}
+
+impl fmt::Display for Cron {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.spec)
+    }
+}
+
+// The year, month, and day `days` days after 1 January 1970. See
+// <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+pub(crate) fn civil(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    (year, month, day)
+}
+
+impl Cron {
+    // Whether the schedule allows the day `days` days after 1 January 1970.
+    fn matches_day(&self, days: u64) -> bool {
+        let (_, month, day) = civil(days);
+        // 1 January 1970 was a Thursday.
+        let weekday = (days + 4) % 7;
+
+        let allowed = |bits: u64, value: u64| bits & (1 << value) != 0;
+        let (day, weekday) = (allowed(self.days, day), allowed(self.weekdays, weekday));
+        let day = if self.any_day || self.any_weekday {
+            day && weekday
+        } else {
+            day || weekday
+        };
+
+        day && allowed(self.months, month)
+    }
+
+    /// The first time the schedule matches after `now`, both in seconds since
+    /// the Unix epoch, or `None` if it doesn't match for years.
+    pub fn next_after(&self, now: u64) -> Option<u64> {
+        let mut at = (now / MINUTE + 1) * MINUTE;
+        let horizon = at + HORIZON_DAYS * DAY;
+
+        while at < horizon {
+            if !self.matches_day(at / DAY) {
+                at = (at / DAY + 1) * DAY;
+                continue;
+            }
+
+            let hour = at % DAY / (60 * MINUTE);
+            let minute = at % (60 * MINUTE) / MINUTE;
+            if self.hours & (1 << hour) != 0 && self.minutes & (1 << minute) != 0 {
+                return Some(at);
+            }
+            at += MINUTE;
+        }
+
+        None
+    }
+}
+
+/// Where and when to post suggestions.
+#[derive(Clone, Debug)]
+pub struct Schedule {
+    when: Cron,
+    webhooks: Vec<Url>,
+    notify: Vec<Url>,
+    index: Option<Url>,
+}
+
+impl Schedule {
+    /// Post a suggestion to each of `webhooks` whenever `when` comes round.
+    pub fn new(when: Cron, webhooks: Vec<Url>) -> Self {
+        Schedule {
+            when,
+            webhooks,
+            notify: vec![],
+            index: None,
+        }
+    }
+
+    /// Also post each suggestion, as JSON, to each of `webhooks`. See the
+    /// `notify` module.
+    pub fn notifying(self, webhooks: Vec<Url>) -> Self {
+        Schedule {
+            notify: webhooks,
+            ..self
+        }
+    }
+
+    /// Link posts to suggestions' permalinks on the index page at `index`,
+    /// the service's public URL.
+    pub fn linking_to(self, index: Url) -> Self {
+        Schedule {
+            index: Some(index),
+            ..self
+        }
+    }
+}
+
+fn is_discord(webhook: &Url) -> bool {
+    webhook
+        .host_str()
+        .is_some_and(|host| host == "discord.com" || host.ends_with(".discord.com"))
+}
+
+// The body to post to a Slack incoming webhook to show `text`, linking to
+// `permalink`.
+fn slack_body(text: &str, permalink: Option<&Url>) -> Value {
+    let text = slack::escape(text);
+    let text = match permalink {
+        Some(permalink) => format!("{}\n<{}|Permalink>", text, permalink),
+        None => text,
+    };
+    json!({ "text": text })
+}
+
+// Post `text` to `webhook`, linking to `permalink`.
+fn post(webhook: &Url, text: &str, permalink: Option<&Url>) -> Result<(), Box<ureq::Error>> {
+    if is_discord(webhook) {
+        return discord::Webhook::new(webhook.clone()).post(text, permalink);
+    }
+
+    ureq::post(webhook.as_str())
+        .set("Content-Type", "application/json")
+        .send_string(&slack_body(text, permalink).to_string())
+        .map_err(Box::new)?;
+
+    Ok(())
+}
+
+// The permalink to `thing` on the service whose index page is at `index_url`.
+pub(crate) fn permalink(index_url: &Url, thing: &Thing) -> Option<Url> {
+    view::permalink(index_url, &ItemQuery::default(), thing).ok()
+}
+
+/// Posts suggestions from a running service on its schedule.
+pub(crate) struct Scheduler {
+    schedule: Schedule,
+    store: web::Data<Store>,
+}
+
+impl Scheduler {
+    pub(crate) fn new(schedule: Schedule, store: web::Data<Store>) -> Self {
+        Scheduler { schedule, store }
+    }
+
+    // The text of a suggestion to post now, its permalink, and the suggestion
+    // itself, for webhooks taking JSON.
+    fn suggestion(&self) -> Option<(String, Option<Url>, Suggestion)> {
+        let catalog = self.store.current();
+        let (list, things) = catalog.named_list(None)?;
+        let (index, thing) = things.choose(None, None)?;
+
+        let id = thing.item(*index);
+        let permalink = self
+            .schedule
+            .index
+            .as_ref()
+            .and_then(|index_url| permalink(index_url, thing));
+        let url = permalink.as_ref().map(Url::to_string).unwrap_or_default();
+        let suggestion = Suggestion::new(list, id, url, thing.clone(), things.position(*index));
+
+        Some((thing.text(), permalink, suggestion))
+    }
+
+    /// Post whenever the schedule comes round.
+    ///
+    /// This runs until the runtime shuts down, or forever if the schedule
+    /// never matches.
+    pub(crate) async fn run(self) {
+        loop {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |now| now.as_secs());
+            let next = match self.schedule.when.next_after(now) {
+                Some(next) => next,
+                None => {
+                    eprintln!(
+                        "The schedule {} never comes round; not posting suggestions",
+                        self.schedule.when
+                    );
+                    return;
+                }
+            };
+            time::sleep(Duration::from_secs(next - now)).await;
+
+            let (text, permalink, suggestion) = match self.suggestion() {
+                Some(suggestion) => suggestion,
+                None => continue,
+            };
+            for webhook in &self.schedule.webhooks {
+                let (webhook, text, permalink) = (webhook.clone(), text.clone(), permalink.clone());
+                // Posting blocks, so keep it off the thread serving requests.
+                let posted = web::block(move || {
+                    post(&webhook, &text, permalink.as_ref()).map_err(|err| (webhook, err))
+                })
+                .await;
+
+                match posted {
+                    Ok(Ok(_)) => {}
+                    Ok(Err((webhook, err))) => {
+                        eprintln!("Unable to post a suggestion to {}: {}", webhook, err)
+                    }
+                    Err(err) => eprintln!("Unable to post a suggestion: {}", err),
+                }
+            }
+            notify::post_all(&self.schedule.notify, &suggestion).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Seconds since the Unix epoch at the given UTC time.
+    fn at(days: u64, hour: u64, minute: u64) -> u64 {
+        days * DAY + hour * 60 * MINUTE + minute * MINUTE
+    }
+
+    #[test]
+    fn civil_dates() {
+        assert_eq!(civil(0), (1970, 1, 1));
+        assert_eq!(civil(59), (1970, 3, 1));
+        assert_eq!(civil(11_016), (2000, 2, 29));
+        assert_eq!(civil(20_742), (2026, 10, 16));
+    }
+
+    #[test]
+    fn crontab_fields_are_parsed() {
+        assert!("0 9 * * *".parse::<Cron>().is_ok());
+        assert!("*/15 9-17 1,15 * 1-5".parse::<Cron>().is_ok());
+        assert!("0 9 * *".parse::<Cron>().is_err());
+        assert!("0 24 * * *".parse::<Cron>().is_err());
+        assert!("0 9 0 * *".parse::<Cron>().is_err());
+        assert!("*/0 9 * * *".parse::<Cron>().is_err());
+        assert!("0 9 * * mon".parse::<Cron>().is_err());
+
+        let sundays: Cron = "0 9 * * 7".parse().unwrap();
+        assert_eq!(sundays.weekdays, 1);
+    }
+
+    #[test]
+    fn schedules_come_round() {
+        // 16 October 2026, a Friday.
+        let friday = 20_742;
+
+        let mornings: Cron = "0 9 * * *".parse().unwrap();
+        assert_eq!(
+            mornings.next_after(at(friday, 8, 30)),
+            Some(at(friday, 9, 0))
+        );
+        assert_eq!(
+            mornings.next_after(at(friday, 9, 0)),
+            Some(at(friday + 1, 9, 0))
+        );
+
+        let weekdays: Cron = "30 9 * * 1-5".parse().unwrap();
+        assert_eq!(
+            weekdays.next_after(at(friday, 10, 0)),
+            Some(at(friday + 3, 9, 30))
+        );
+
+        // Either the day of the month or the day of the week will do.
+        let either: Cron = "0 0 1 * 6".parse().unwrap();
+        assert_eq!(
+            either.next_after(at(friday, 0, 0)),
+            Some(at(friday + 1, 0, 0))
+        );
+
+        let never: Cron = "0 0 30 2 *".parse().unwrap();
+        assert_eq!(never.next_after(at(friday, 0, 0)), None);
+    }
+
+    #[test]
+    fn discord_webhooks_are_posted_to_as_discord() {
+        let discord = Url::parse("https://discord.com/api/webhooks/1/x").unwrap();
+        let slack = Url::parse("https://hooks.slack.com/services/T0/B0/X").unwrap();
+        assert!(is_discord(&discord));
+        assert!(!is_discord(&slack));
+
+        let permalink = Url::parse("https://example.com/?item=0").unwrap();
+        assert_eq!(
+            slack_body("Is a < b?", Some(&permalink)),
+            json!({ "text": "Is a &lt; b?\n<https://example.com/?item=0|Permalink>" })
+        );
+    }
+}
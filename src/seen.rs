@@ -0,0 +1,203 @@
+//! Random suggestions that don't repeat until every one has been seen.
+//!
+//! Normally every random suggestion on `/` is chosen from the whole list, so
+//! refreshing the page often shows suggestions the reader has already seen.
+//! If the App is given an `Unseen` value as app data, the service instead
+//! remembers, in a cookie, which suggestions each browser has been shown, and
+//! chooses among the rest:
+//!
+//! ```
+//! # use things_to_check::{seen, view};
+//! # #[actix_web::main]
+//! # async fn main() -> std::result::Result<(), things_to_check::view::Error> {
+//! use actix_web::{web, App, HttpServer};
+//!
+//! let service = view::make_service()?;
+//! let unseen = web::Data::new(seen::Unseen::new("s3kr1t"));
+//! let app_factory = move ||
+//!     App::new()
+//!         .app_data(unseen.clone())
+//!         .configure(|cfg| service(cfg));
+//!
+//! HttpServer::new(app_factory);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Once every suggestion carrying the requested `tag` has been seen, the
+//! browser starts over. Only random suggestions are tracked; permalinks and
+//! the JSON API behave as before.
+//!
+//! The cookie records the suggestions seen in one list, by index, and is
+//! signed with the key given to `Unseen::new`, so that it can't be edited to
+//! name suggestions that don't exist. Asking for a suggestion from a different
+//! list starts that list over. Changing the key starts every browser over.
+
+use actix_web::cookie::{time, Cookie, SameSite};
+use actix_web::HttpRequest;
+use hmac::{Hmac, Mac};
+use rand::seq::SliceRandom;
+use rand::thread_rng;
+use sha2::Sha256;
+use std::collections::BTreeSet;
+
+use crate::things::{Thing, Things};
+
+/// The name of the cookie recording the suggestions a browser has seen.
+const COOKIE: &str = "seen";
+
+/// How long a browser remembers the suggestions it's seen, in days.
+const MAX_AGE_DAYS: i64 = 30;
+
+/// Enables unseen-only random suggestions, and holds the key their cookies are
+/// signed with.
+pub struct Unseen {
+    key: Vec<u8>,
+}
+
+impl Unseen {
+    /// Track seen suggestions in cookies signed with `key`, which should be
+    /// long, random, and kept secret.
+    pub fn new(key: impl AsRef<[u8]>) -> Self {
+        Unseen {
+            key: key.as_ref().to_owned(),
+        }
+    }
+
+    fn mac(&self, list: &str, seen: &str) -> Hmac<Sha256> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts keys of any size");
+        mac.update(list.as_bytes());
+        mac.update(b"\n");
+        mac.update(seen.as_bytes());
+        mac
+    }
+
+    /// The indices in `list` that the browser making `req` has seen. Missing,
+    /// malformed, and forged cookies, and cookies for other lists, count as
+    /// having seen nothing.
+    pub(crate) fn read(&self, req: &HttpRequest, list: &str) -> BTreeSet<usize> {
+        let cookie = match req.cookie(COOKIE) {
+            Some(cookie) => cookie,
+            None => return BTreeSet::new(),
+        };
+
+        let verified = cookie.value().split_once('.').filter(|(seen, signature)| {
+            let signature = hex::decode(signature).unwrap_or_default();
+            self.mac(list, seen).verify_slice(&signature).is_ok()
+        });
+
+        verified
+            .and_then(|(seen, _)| hex::decode(seen).ok())
+            .map(|bitmap| decode(&bitmap))
+            .unwrap_or_default()
+    }
+
+    /// A cookie recording that the browser has seen `seen` in `list`.
+    pub(crate) fn cookie(&self, list: &str, seen: &BTreeSet<usize>) -> Cookie<'static> {
+        let seen = hex::encode(encode(seen));
+        let signature = hex::encode(self.mac(list, &seen).finalize().into_bytes());
+
+        Cookie::build(COOKIE, format!("{}.{}", seen, signature))
+            .path("/")
+            .http_only(true)
+            .same_site(SameSite::Lax)
+            .max_age(time::Duration::days(MAX_AGE_DAYS))
+            .finish()
+    }
+}
+
+// Indices as a bitmap, one bit per index, least significant bit first.
+fn encode(seen: &BTreeSet<usize>) -> Vec<u8> {
+    let len = seen.iter().next_back().map_or(0, |last| last / 8 + 1);
+    let mut bitmap = vec![0; len];
+    for index in seen {
+        bitmap[index / 8] |= 1 << (index % 8);
+    }
+
+    bitmap
+}
+
+fn decode(bitmap: &[u8]) -> BTreeSet<usize> {
+    bitmap
+        .iter()
+        .enumerate()
+        .flat_map(|(byte, bits)| {
+            (0..8)
+                .filter(move |bit| bits & (1 << bit) != 0)
+                .map(move |bit| byte * 8 + bit)
+        })
+        .collect()
+}
+
+/// Choose a random thing carrying `tag` that hasn't been retired or `seen`,
+/// and add it to `seen`. If every such thing has been seen, they're forgotten,
+/// and the choice starts over.
+pub(crate) fn choose<'a>(
+    things: &'a Things,
+    tag: Option<&str>,
+    seen: &mut BTreeSet<usize>,
+) -> Option<&'a (usize, Thing)> {
+    let candidates: Vec<_> = things
+        .0
+        .iter()
+        .filter(|(_, thing)| !thing.retired && thing.has_tag(tag))
+        .collect();
+
+    let unseen: Vec<_> = candidates
+        .iter()
+        .filter(|(index, _)| !seen.contains(index))
+        .copied()
+        .collect();
+    let chosen = if unseen.is_empty() {
+        for (index, _) in &candidates {
+            seen.remove(index);
+        }
+        candidates.choose(&mut thread_rng())
+    } else {
+        unseen.choose(&mut thread_rng())
+    };
+
+    chosen.map(|&entry| {
+        seen.insert(entry.0);
+        entry
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::things::load_catalog;
+
+    #[test]
+    fn bitmaps_round_trip() {
+        let seen = BTreeSet::from([0, 3, 8, 21]);
+        assert_eq!(encode(&seen), [0b1001, 0b1, 0b10_0000]);
+        assert_eq!(decode(&encode(&seen)), seen);
+        assert!(encode(&BTreeSet::new()).is_empty());
+    }
+
+    #[test]
+    fn choices_cover_every_thing_before_repeating() {
+        let catalog = load_catalog(
+            "
+- Have you checked DNS?
+- Have you checked the MTU?
+- Have you checked the cache?
+- text: Have you checked the hub?
+  retired: true
+",
+        )
+        .unwrap();
+        let things = catalog.list(None).unwrap();
+
+        let mut seen = BTreeSet::new();
+        for _ in 0..3 {
+            choose(things, None, &mut seen).unwrap();
+        }
+        assert_eq!(seen, BTreeSet::from([0, 1, 2]));
+
+        choose(things, None, &mut seen).unwrap();
+        assert_eq!(seen.len(), 1);
+    }
+}
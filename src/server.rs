@@ -0,0 +1,442 @@
+//! Serve a configured service over HTTP.
+//!
+//! `Server` takes care of the `HttpServer` boilerplate around a service from
+//! the `view` module: it wraps the App in `request_id::RequestIds`, and
+//! optionally an `access_log::AccessLog`, serves the `404 Not Found` page for
+//! unknown paths, binds to the listen address (or Unix socket), and shuts down
+//! gracefully on `SIGINT` or `SIGTERM`. With the `tls` feature, it can also
+//! serve HTTPS itself, without a reverse proxy in front.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # use things_to_check::{server, twelve, view};
+//! # #[actix_web::main]
+//! # async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+//! use std::time::Duration;
+//!
+//! let service = view::make_service()?;
+//!
+//! server::Server::new(twelve::port(3000)?)?
+//!     .with_workers(4)
+//!     .with_shutdown_timeout(Duration::from_secs(10))
+//!     .run(service)
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! On the first `SIGINT` or `SIGTERM`, the server stops accepting connections
+//! and gives the requests in flight up to the shutdown timeout to finish. A
+//! second signal stops it at once.
+
+use actix_web::dev::ServerHandle;
+use actix_web::middleware::Condition;
+use actix_web::rt::{
+    self,
+    signal::unix::{signal, SignalKind},
+};
+use actix_web::{dev, web, App, HttpServer};
+use futures_util::future;
+#[cfg(feature = "tls")]
+use rustls_pemfile::Item;
+use std::fs;
+#[cfg(feature = "tls")]
+use std::fs::File;
+use std::io;
+#[cfg(feature = "tls")]
+use std::io::BufReader;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::access_log::AccessLog;
+use crate::request_id;
+#[cfg(feature = "otel")]
+use crate::trace;
+use crate::view;
+
+/// How long requests in flight get to finish after a shutdown signal, unless
+/// set with `Server::with_shutdown_timeout`.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// An HTTP server for a service, listening on one or more addresses, or on
+/// a Unix socket.
+#[derive(Clone, Debug)]
+pub struct Server {
+    listen: Listen,
+    workers: Option<usize>,
+    shutdown_timeout: Duration,
+    access_log: Option<AccessLog>,
+    #[cfg(feature = "otel")]
+    traced: bool,
+    #[cfg(feature = "tls")]
+    tls: Option<Tls>,
+}
+
+impl Server {
+    /// A server listening on every address `addr` resolves to, such as a
+    /// `twelve::PortAddr` or `"localhost:3000"`.
+    ///
+    /// This fails if `addr` can't be resolved. Nothing is bound until the
+    /// server is started.
+    pub fn new(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Ok(Self::listening(Listen::Tcp(
+            addr.to_socket_addrs()?.collect(),
+        )))
+    }
+
+    /// A server listening on the Unix socket at `path`, for a reverse proxy
+    /// on the same host to forward requests to.
+    ///
+    /// If a socket is left at `path` from an earlier run, it's replaced when
+    /// the server starts, unless something is still listening on it.
+    pub fn on_socket(path: impl Into<PathBuf>) -> Self {
+        Self::listening(Listen::Unix(path.into()))
+    }
+
+    fn listening(listen: Listen) -> Self {
+        Server {
+            listen,
+            workers: None,
+            shutdown_timeout: SHUTDOWN_TIMEOUT,
+            access_log: None,
+            #[cfg(feature = "otel")]
+            traced: false,
+            #[cfg(feature = "tls")]
+            tls: None,
+        }
+    }
+
+    /// Handle requests on `workers` threads, rather than one per CPU.
+    pub fn with_workers(self, workers: usize) -> Self {
+        Server {
+            workers: Some(workers),
+            ..self
+        }
+    }
+
+    /// Give requests in flight up to `timeout` to finish once the server is
+    /// told to stop, rather than thirty seconds. The timeout is rounded down
+    /// to whole seconds.
+    pub fn with_shutdown_timeout(self, timeout: Duration) -> Self {
+        Server {
+            shutdown_timeout: timeout,
+            ..self
+        }
+    }
+
+    /// Log every request to `log`.
+    pub fn with_access_log(self, log: AccessLog) -> Self {
+        Server {
+            access_log: Some(log),
+            ..self
+        }
+    }
+
+    /// Record a span for every request, as `trace::Tracing` does.
+    ///
+    /// This is only available with the `otel` feature.
+    #[cfg(feature = "otel")]
+    pub fn traced(self) -> Self {
+        Server {
+            traced: true,
+            ..self
+        }
+    }
+
+    /// Serve HTTPS, rather than HTTP, with the PEM-encoded certificate chain
+    /// in the file at `certificate` and the PEM-encoded private key in the
+    /// file at `key`.
+    ///
+    /// The files are read when the server starts. Servers listening on Unix
+    /// sockets can't serve HTTPS. This is only available with the `tls`
+    /// feature.
+    #[cfg(feature = "tls")]
+    pub fn with_tls(self, certificate: impl Into<PathBuf>, key: impl Into<PathBuf>) -> Self {
+        Server {
+            tls: Some(Tls {
+                certificate: certificate.into(),
+                key: key.into(),
+            }),
+            ..self
+        }
+    }
+
+    /// Bind to the server's addresses and start serving `service`.
+    ///
+    /// The returned `dev::Server` runs until it's stopped, through its
+    /// `handle()` or by a signal, and resolves once every worker has shut
+    /// down. This must be called from within an actix runtime, and fails if
+    /// any of the addresses, or the socket, can't be bound, or if the server
+    /// is to serve HTTPS and its certificate or key can't be read.
+    pub fn start<F>(self, service: F) -> io::Result<dev::Server>
+    where
+        F: Fn(&mut web::ServiceConfig) + Clone + Send + 'static,
+    {
+        #[cfg(feature = "otel")]
+        let traced = self.traced;
+        let access_log = self.access_log.clone();
+        let app_factory = move || {
+            let app = App::new();
+            #[cfg(feature = "otel")]
+            let app = app.wrap(Condition::new(traced, trace::Tracing));
+            let service = service.clone();
+            let logged = access_log.is_some();
+            app.wrap(request_id::RequestIds)
+                .wrap(Condition::new(
+                    logged,
+                    access_log.clone().unwrap_or_default(),
+                ))
+                .configure(move |cfg| service(cfg))
+                .default_service(web::to(view::missing))
+        };
+
+        let http_server = HttpServer::new(app_factory)
+            .shutdown_timeout(self.shutdown_timeout.as_secs())
+            .disable_signals();
+        let http_server = match self.workers {
+            Some(workers) => http_server.workers(workers),
+            None => http_server,
+        };
+        let http_server = match (&self.listen, self.tls_config()?) {
+            #[cfg(feature = "tls")]
+            (Listen::Tcp(addrs), Some(config)) => http_server.bind_rustls(&addrs[..], config)?,
+            #[cfg(feature = "tls")]
+            (Listen::Unix(_), Some(_)) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "HTTPS can't be served on a Unix socket",
+                ))
+            }
+            (Listen::Tcp(addrs), _) => http_server.bind(&addrs[..])?,
+            (Listen::Unix(path), _) => {
+                remove_stale_socket(path)?;
+                http_server.bind_uds(path)?
+            }
+        };
+        let server = http_server.run();
+        rt::spawn(stop_on_signal(server.handle()));
+
+        Ok(server)
+    }
+
+    /// Serve `service` until the server is stopped by a signal.
+    ///
+    /// See `start` for details.
+    pub async fn run<F>(self, service: F) -> io::Result<()>
+    where
+        F: Fn(&mut web::ServiceConfig) + Clone + Send + 'static,
+    {
+        self.start(service)?.await
+    }
+
+    #[cfg(feature = "tls")]
+    fn tls_config(&self) -> io::Result<Option<rustls::ServerConfig>> {
+        self.tls.as_ref().map(Tls::config).transpose()
+    }
+
+    #[cfg(not(feature = "tls"))]
+    fn tls_config(&self) -> io::Result<Option<std::convert::Infallible>> {
+        Ok(None)
+    }
+}
+
+// Where a server listens.
+#[derive(Clone, Debug)]
+enum Listen {
+    Tcp(Vec<SocketAddr>),
+    Unix(PathBuf),
+}
+
+// Removes a socket left at `path` by a server that has since stopped, which
+// would otherwise keep this one from binding there. Sockets something is still
+// listening on, and anything other than a socket, are left for binding to
+// fail on.
+fn remove_stale_socket(path: &Path) -> io::Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() && UnixStream::connect(path).is_err() => {
+            fs::remove_file(path)
+        }
+        _ => Ok(()),
+    }
+}
+
+// The files a server serving HTTPS reads its certificate chain and key from.
+#[cfg(feature = "tls")]
+#[derive(Clone, Debug)]
+struct Tls {
+    certificate: PathBuf,
+    key: PathBuf,
+}
+
+#[cfg(feature = "tls")]
+impl Tls {
+    fn config(&self) -> io::Result<rustls::ServerConfig> {
+        let certificates: Vec<_> = read_pem(&self.certificate)?
+            .into_iter()
+            .filter_map(|item| match item {
+                Item::X509Certificate(der) => Some(rustls::Certificate(der)),
+                _ => None,
+            })
+            .collect();
+        if certificates.is_empty() {
+            return Err(invalid(&self.certificate, "no certificates found"));
+        }
+        let key = read_pem(&self.key)?
+            .into_iter()
+            .find_map(|item| match item {
+                Item::RSAKey(der) | Item::PKCS8Key(der) | Item::ECKey(der) => {
+                    Some(rustls::PrivateKey(der))
+                }
+                _ => None,
+            })
+            .ok_or_else(|| invalid(&self.key, "no private key found"))?;
+
+        rustls::ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(certificates, key)
+            .map_err(|err| invalid(&self.key, err))
+    }
+}
+
+#[cfg(feature = "tls")]
+fn read_pem(path: &Path) -> io::Result<Vec<Item>> {
+    File::open(path)
+        .map(BufReader::new)
+        .and_then(|mut file| rustls_pemfile::read_all(&mut file))
+        .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path.display(), err)))
+}
+
+#[cfg(feature = "tls")]
+fn invalid(path: &Path, err: impl std::fmt::Display) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {}", path.display(), err),
+    )
+}
+
+// Stops the server gracefully on the first SIGINT or SIGTERM, and at once on
+// the next. This runs until the runtime shuts down, and only fails if the
+// signal handlers can't be installed.
+async fn stop_on_signal(handle: ServerHandle) -> io::Result<()> {
+    let mut interrupts = signal(SignalKind::interrupt())?;
+    let mut terminations = signal(SignalKind::terminate())?;
+
+    let mut graceful = true;
+    loop {
+        future::select(Box::pin(interrupts.recv()), Box::pin(terminations.recv())).await;
+
+        if graceful {
+            eprintln!("Shutting down; signal again to stop at once");
+        }
+        rt::spawn(handle.stop(graceful));
+        graceful = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::os::unix::net::UnixListener;
+    use std::{env, process};
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn serves_until_stopped() {
+        // Find a free port to listen on.
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let service = view::make_service().unwrap();
+        let server = Server::new(addr)
+            .unwrap()
+            .with_workers(1)
+            .with_shutdown_timeout(Duration::from_secs(1))
+            .start(service)
+            .unwrap();
+        let handle = server.handle();
+        let running = rt::spawn(server);
+
+        let (health, missing) = web::block(move || {
+            let health = ureq::get(&format!("http://{}/healthz", addr)).call();
+            let missing = ureq::get(&format!("http://{}/no-such-page", addr)).call();
+            (health, missing)
+        })
+        .await
+        .unwrap();
+
+        let resp = health.unwrap();
+        assert_eq!(resp.status(), 200);
+        assert!(resp.header(request_id::HEADER.as_str()).is_some());
+
+        match missing {
+            Err(ureq::Error::Status(status, resp)) => {
+                assert_eq!(status, 404);
+                assert!(resp.into_string().unwrap().contains("Request ID"));
+            }
+            other => panic!("expected a 404, got {:?}", other),
+        }
+
+        handle.stop(true).await;
+        running.await.unwrap().unwrap();
+    }
+
+    #[actix_web::test]
+    async fn serves_on_unix_sockets() {
+        let path = env::temp_dir().join(format!("things-to-check-{}.sock", process::id()));
+        // A socket left over from an earlier run, which nothing listens on.
+        drop(UnixListener::bind(&path).unwrap());
+
+        let service = view::make_service().unwrap();
+        let server = Server::on_socket(&path)
+            .with_workers(1)
+            .start(service)
+            .unwrap();
+        let handle = server.handle();
+        let running = rt::spawn(server);
+
+        let socket = path.clone();
+        let resp = web::block(move || {
+            let mut stream = UnixStream::connect(socket)?;
+            stream.write_all(b"GET /healthz HTTP/1.0\r\nHost: localhost\r\n\r\n")?;
+            let mut resp = String::new();
+            stream.read_to_string(&mut resp)?;
+            io::Result::Ok(resp)
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        assert!(resp.starts_with("HTTP/1.0 200 OK"));
+
+        handle.stop(true).await;
+        running.await.unwrap().unwrap();
+    }
+
+    #[cfg(feature = "tls")]
+    #[actix_web::test]
+    async fn tls_needs_a_certificate() {
+        let path = env::temp_dir().join(format!("things-to-check-tls-{}.pem", process::id()));
+        fs::write(&path, "not a certificate").unwrap();
+
+        let service = view::make_service().unwrap();
+        let started = Server::new("127.0.0.1:0")
+            .unwrap()
+            .with_tls(&path, &path)
+            .start(service);
+        fs::remove_file(&path).unwrap();
+
+        let err = match started {
+            Ok(_) => panic!("expected the certificate to be rejected"),
+            Err(err) => err,
+        };
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("no certificates found"));
+    }
+}
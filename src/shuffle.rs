@@ -0,0 +1,208 @@
+//! Random suggestions dealt from a shuffled deck, one browsing session at a
+//! time.
+//!
+//! Like `seen::Unseen`, this stops "That wasn't it" from repeating
+//! suggestions before every one has been shown, but it keeps far less in its
+//! cookie: rather than remembering each suggestion seen, the service shuffles
+//! the list into an order of its own for each browser, and remembers only the
+//! seed of the shuffle and how far through it the browser has got. If the App
+//! is given a `Shuffle` value as app data, random suggestions on `/` are dealt
+//! in that order:
+//!
+//! ```
+//! # use things_to_check::{shuffle, view};
+//! # #[actix_web::main]
+//! # async fn main() -> std::result::Result<(), things_to_check::view::Error> {
+//! use actix_web::{web, App, HttpServer};
+//!
+//! let service = view::make_service()?;
+//! let shuffle = web::Data::new(shuffle::Shuffle::new("s3kr1t"));
+//! let app_factory = move ||
+//!     App::new()
+//!         .app_data(shuffle.clone())
+//!         .configure(|cfg| service(cfg));
+//!
+//! HttpServer::new(app_factory);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Once the whole deck has been dealt, it's shuffled again. The cookie lasts
+//! as long as the browser's session, and is signed with the key given to
+//! `Shuffle::new`, so that it can't be edited to deal a chosen order. Asking
+//! for a different list or tag starts a new deck. If both this and
+//! `seen::Unseen` are given, this takes precedence.
+
+use actix_web::cookie::{Cookie, SameSite};
+use actix_web::HttpRequest;
+use hmac::{Hmac, Mac};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{thread_rng, Rng, SeedableRng};
+use sha2::Sha256;
+
+use crate::things::{Thing, Things};
+
+/// The name of the cookie recording a browser's place in its deck.
+const COOKIE: &str = "shuffle";
+
+/// Enables shuffled random suggestions, and holds the key their cookies are
+/// signed with.
+pub struct Shuffle {
+    key: Vec<u8>,
+}
+
+/// A browser's deck: the seed its order is shuffled with, and how many
+/// suggestions have been dealt from it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Deck {
+    seed: u64,
+    dealt: usize,
+}
+
+impl Deck {
+    fn new() -> Self {
+        Deck {
+            seed: thread_rng().gen(),
+            dealt: 0,
+        }
+    }
+}
+
+impl Shuffle {
+    /// Track decks in cookies signed with `key`, which should be long, random,
+    /// and kept secret.
+    pub fn new(key: impl AsRef<[u8]>) -> Self {
+        Shuffle {
+            key: key.as_ref().to_owned(),
+        }
+    }
+
+    fn mac(&self, list: &str, tag: Option<&str>, deck: &str) -> Hmac<Sha256> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts keys of any size");
+        mac.update(list.as_bytes());
+        mac.update(b"\n");
+        mac.update(tag.unwrap_or_default().as_bytes());
+        mac.update(b"\n");
+        mac.update(deck.as_bytes());
+        mac
+    }
+
+    /// The deck the browser making `req` is dealing from, for `tag` in
+    /// `list`. Missing, malformed, and forged cookies, and cookies for other
+    /// lists or tags, start a new deck.
+    pub(crate) fn read(&self, req: &HttpRequest, list: &str, tag: Option<&str>) -> Deck {
+        let cookie = match req.cookie(COOKIE) {
+            Some(cookie) => cookie,
+            None => return Deck::new(),
+        };
+
+        let verified = cookie.value().rsplit_once('.').filter(|(deck, signature)| {
+            let signature = hex::decode(signature).unwrap_or_default();
+            self.mac(list, tag, deck).verify_slice(&signature).is_ok()
+        });
+
+        verified
+            .and_then(|(deck, _)| {
+                let (seed, dealt) = deck.split_once('.')?;
+                Some(Deck {
+                    seed: u64::from_str_radix(seed, 16).ok()?,
+                    dealt: dealt.parse().ok()?,
+                })
+            })
+            .unwrap_or_else(Deck::new)
+    }
+
+    /// A cookie recording that the browser has got to `deck` for `tag` in
+    /// `list`.
+    pub(crate) fn cookie(&self, list: &str, tag: Option<&str>, deck: &Deck) -> Cookie<'static> {
+        let value = format!("{:016x}.{}", deck.seed, deck.dealt);
+        let signature = hex::encode(self.mac(list, tag, &value).finalize().into_bytes());
+
+        // No expiry, so the deck lasts for the browser's session.
+        Cookie::build(COOKIE, format!("{}.{}", value, signature))
+            .path("/")
+            .http_only(true)
+            .same_site(SameSite::Lax)
+            .finish()
+    }
+}
+
+/// Deal the next thing carrying `tag` that hasn't been retired from `deck`,
+/// shuffling a new deck if every such thing has been dealt.
+pub(crate) fn deal<'a>(
+    things: &'a Things,
+    tag: Option<&str>,
+    deck: &mut Deck,
+) -> Option<&'a (usize, Thing)> {
+    let mut candidates: Vec<_> = things
+        .0
+        .iter()
+        .filter(|(_, thing)| !thing.retired && thing.has_tag(tag))
+        .collect();
+    if candidates.is_empty() {
+        return None;
+    }
+
+    if deck.dealt >= candidates.len() {
+        *deck = Deck::new();
+    }
+    candidates.shuffle(&mut StdRng::seed_from_u64(deck.seed));
+
+    let dealt = candidates[deck.dealt];
+    deck.dealt += 1;
+    Some(dealt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::things::load_catalog;
+    use actix_web::test::TestRequest;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn decks_deal_every_thing_before_repeating() {
+        let catalog = load_catalog(
+            "
+- Have you checked DNS?
+- Have you checked the MTU?
+- Have you checked the cache?
+- text: Have you checked the hub?
+  retired: true
+",
+        )
+        .unwrap();
+        let things = catalog.list(None).unwrap();
+
+        let mut deck = Deck::new();
+        let dealt: BTreeSet<_> = (0..3)
+            .map(|_| deal(things, None, &mut deck).unwrap().0)
+            .collect();
+        assert_eq!(dealt, BTreeSet::from([0, 1, 2]));
+
+        deal(things, None, &mut deck).unwrap();
+        assert_eq!(deck.dealt, 1);
+    }
+
+    #[test]
+    fn cookies_are_signed() {
+        let shuffle = Shuffle::new("s3kr1t");
+        let deck = Deck { seed: 42, dealt: 3 };
+        let cookie = shuffle.cookie("general", None, &deck);
+
+        let req = TestRequest::default()
+            .cookie(cookie.clone())
+            .to_http_request();
+        assert_eq!(shuffle.read(&req, "general", None), deck);
+        assert_ne!(shuffle.read(&req, "general", Some("dns")), deck);
+        assert_ne!(Shuffle::new("other").read(&req, "general", None), deck);
+
+        let forged = cookie.value().replacen(".3.", ".0.", 1);
+        let req = TestRequest::default()
+            .cookie(Cookie::new(COOKIE, forged))
+            .to_http_request();
+        assert_ne!(shuffle.read(&req, "general", None), deck);
+    }
+}
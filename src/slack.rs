@@ -0,0 +1,1157 @@
+//! A Slack app, for asking for suggestions from Slack.
+//!
+//! The app is off unless the App is given a `Slack` value as app data, which
+//! holds the signing secret Slack signs its requests with:
+//!
+//! ```
+//! # use things_to_check::{slack, view};
+//! # #[actix_web::main]
+//! # async fn main() -> std::result::Result<(), things_to_check::view::Error> {
+//! use actix_web::{web, App, HttpServer};
+//!
+//! let service = view::make_service()?;
+//! let slack = web::Data::new(slack::Slack::new("s3kr1t"));
+//! let app_factory = move ||
+//!     App::new()
+//!         .app_data(slack.clone())
+//!         .configure(|cfg| service(cfg));
+//!
+//! HttpServer::new(app_factory);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Endpoints
+//!
+//! * `/slack/command` (`POST`): a slash command, such as `/troubleshoot`.
+//!
+//!   Point a slash command's request URL here. The command posts a random
+//!   suggestion to the channel, with a "Permalink" button linking to the
+//!   suggestion on the web, for sharing outside Slack, and a "Give me
+//!   another" button.
+//!
+//!   The command's text can hold these words:
+//!
+//!   * `quietly`: show the suggestion only to the person who asked, rather
+//!     than posting it to the channel.
+//!   * `aloud`: post the suggestion to the channel, even if the app answers
+//!     quietly by default.
+//!
+//!   Any other text asks for a particular suggestion or searches for one. A
+//!   single word that names a suggestion, by ID, index, or alias, as for the
+//!   `item` parameter to `/`, shows that suggestion, as in `/troubleshoot 42`;
+//!   other words show a random suggestion among those matching them, as for
+//!   `/search`, as in `/troubleshoot dns`. "Give me another" keeps to the same
+//!   search.
+//!
+//! * `/slack/interact` (`POST`): interactivity requests.
+//!
+//!   Point the app's interactivity request URL here. Pressing "Give me
+//!   another" replaces the message with a new suggestion, chosen in the same
+//!   way as the first, by way of the `response_url` Slack sends with the
+//!   press. Other interactions, such as following a "Permalink", are
+//!   ignored.
+//!
+//! Requests must carry a valid `X-Slack-Signature` for the signing secret, and
+//! an `X-Slack-Request-Timestamp` within five minutes of the current time, or
+//! are rejected with `401 Unauthorized`.
+//!
+//! * `/slack/events` (`POST`): Events API requests.
+//!
+//!   Point the app's event subscriptions request URL here, and subscribe to
+//!   the `app_mention` bot event. Mentioning the app, as in `@troubleshoot
+//!   dns`, replies in a thread with a suggestion, chosen from the rest of the
+//!   message's text as for the slash command. Replies are posted with the
+//!   workspace's bot token, from `Slack::with_bot_token` or from installing
+//!   the app; events in workspaces without one are ignored, as are other
+//!   events and Slack's retries of events it already sent.
+//!
+//!   Subscribing to the `link_shared` bot event, for the service's domain,
+//!   also previews permalinks pasted into Slack: links to a suggestion's page
+//!   under `/check/{slug}` unfurl to show the suggestion, translated if they
+//!   carry a `lang` parameter. Older links to `/` with an `item` parameter,
+//!   which redirect to those pages, unfurl the same way.
+//!
+//! An app given its client ID and secret, with `Slack::installable`, can also
+//! be installed in any workspace, rather than only the one it was created in:
+//!
+//! * `/slack/oauth/start` (`GET`): starts installing the app.
+//!
+//!   Link an "Add to Slack" button here. Redirects to Slack, which asks the
+//!   person installing the app to approve it, and then sends them on to
+//!   `/slack/oauth/callback`. Add that URL to the app's redirect URLs.
+//!
+//! * `/slack/oauth/callback` (`GET`): finishes installing the app.
+//!
+//!   Exchanges the code Slack sends for the workspace's bot token, and keeps
+//!   the token in the app's `Tokens`. Requests whose `state` doesn't match the
+//!   one given to the same browser by `/slack/oauth/start` are rejected with
+//!   `400 Bad Request`, so that nobody can be tricked into finishing an
+//!   installation they didn't start.
+//!
+//! Suggestions come from the default list, unless the app is given lists for
+//! particular workspaces with `Slack::with_team_lists`, so that one service can
+//! give different organizations different advice. Each workspace's commands,
+//! buttons, and mentions then choose from its own list, by the `team_id` Slack
+//! sends, and its permalinks link to that list.
+//!
+//! Permalinks are made from the address Slack's requests arrive at, unless the
+//! app is given the service's public URL with `Slack::linking_to`, as it
+//! should be behind a proxy. Unfurling then previews links to that URL.
+//!
+//! All of these endpoints return `404 Not Found` if the app is off, and the
+//! OAuth endpoints also return it if the app isn't installable. Every request
+//! comes from Slack, or from someone following a link on its way to or from
+//! Slack, so these endpoints aren't rate limited.
+
+use actix_web::cookie::{time, Cookie, SameSite};
+use actix_web::http::header;
+use actix_web::{error, get, post, web, HttpRequest, HttpResponse};
+use hmac::{Hmac, Mac};
+use rand::seq::SliceRandom;
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::iter;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+use url::Url;
+
+use crate::access_log;
+use crate::counts::{self, Channel};
+use crate::things::{Catalog, Store, Thing, Things};
+use crate::view::{self, ItemQuery};
+
+/// How far, in seconds, a request's timestamp may be from the current time.
+/// Older requests are refused, so that captured requests can't be replayed.
+const MAX_SKEW: u64 = 5 * 60;
+
+/// The `action_id` of the "Give me another" button.
+const ANOTHER: &str = "another";
+
+/// The `action_id` of the "Permalink" button.
+const PERMALINK: &str = "permalink";
+
+/// The only host Slack sends response URLs for.
+const RESPONSE_HOST: &str = "hooks.slack.com";
+
+/// Where people installing the app approve it.
+const AUTHORIZE_URL: &str = "https://slack.com/oauth/v2/authorize";
+
+/// Where codes from installations are exchanged for bot tokens.
+const ACCESS_URL: &str = "https://slack.com/api/oauth.v2.access";
+
+/// The bot token scopes the app asks for when it's installed.
+const SCOPES: &str = "commands,app_mentions:read,chat:write,links:read,links:write";
+
+/// Where replies to mentions are posted.
+const POST_MESSAGE_URL: &str = "https://slack.com/api/chat.postMessage";
+
+/// Where previews of shared permalinks are posted.
+const UNFURL_URL: &str = "https://slack.com/api/chat.unfurl";
+
+/// The name of the cookie holding an installation's `state`, between
+/// `/slack/oauth/start` and `/slack/oauth/callback`.
+const STATE_COOKIE: &str = "slack_oauth_state";
+
+/// How long someone has to approve an installation, in minutes.
+const STATE_MAX_AGE_MINUTES: i64 = 10;
+
+/// Enables the Slack app, and holds the secret Slack signs requests with.
+pub struct Slack {
+    signing_secret: String,
+    quiet: bool,
+    bot_token: Option<String>,
+    install: Option<Install>,
+    index: Option<Url>,
+    team_lists: BTreeMap<String, String>,
+}
+
+// The app's OAuth credentials, and the tokens of the workspaces it's been
+// installed in.
+struct Install {
+    client_id: String,
+    client_secret: String,
+    tokens: Tokens,
+}
+
+/// The bot tokens of the workspaces the Slack app is installed in, by team ID.
+pub struct Tokens {
+    path: Option<PathBuf>,
+    teams: RwLock<BTreeMap<String, String>>,
+}
+
+impl Tokens {
+    /// Keep tokens in memory only. Installations are forgotten when the app
+    /// restarts.
+    pub fn in_memory() -> Self {
+        Tokens {
+            path: None,
+            teams: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// Keep tokens in the JSON file at `path`, which is created on the first
+    /// installation if it doesn't exist yet. The file holds secrets, so keep
+    /// it private.
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let teams = match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(err),
+        };
+
+        Ok(Tokens {
+            path: Some(path),
+            teams: RwLock::new(teams),
+        })
+    }
+
+    /// The bot token for the workspace `team`, if the app is installed there.
+    pub fn get(&self, team: &str) -> Option<String> {
+        self.teams
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .get(team)
+            .cloned()
+    }
+
+    fn insert(&self, team: &str, token: &str) -> io::Result<()> {
+        let mut teams = self.teams.write().unwrap_or_else(|err| err.into_inner());
+        teams.insert(team.to_owned(), token.to_owned());
+
+        match &self.path {
+            // Write the whole file aside and then move it into place, so that
+            // a failed write can't lose the tokens already saved. Only this
+            // user may read the file, as it holds every workspace's token.
+            Some(path) => {
+                let json = serde_json::to_string_pretty(&*teams)?;
+                let saving = path.with_extension("tmp");
+                OpenOptions::new()
+                    .write(true)
+                    .create(true)
+                    .truncate(true)
+                    .mode(0o600)
+                    .open(&saving)?
+                    .write_all(json.as_bytes())?;
+                fs::rename(&saving, path)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl Slack {
+    /// Enable the Slack app, for requests signed with `signing_secret`, from
+    /// the app's Basic Information page.
+    pub fn new(signing_secret: impl Into<String>) -> Self {
+        Slack {
+            signing_secret: signing_secret.into(),
+            quiet: false,
+            bot_token: None,
+            install: None,
+            index: None,
+            team_lists: BTreeMap::new(),
+        }
+    }
+
+    /// Link to suggestions' permalinks on the index page at `index`, the
+    /// service's public URL, rather than at the address requests arrive at.
+    pub fn linking_to(self, index: Url) -> Self {
+        Slack {
+            index: Some(index),
+            ..self
+        }
+    }
+
+    // The index page permalinks are made from.
+    fn index(&self, req: &HttpRequest) -> error::Result<Url> {
+        match &self.index {
+            Some(index) => Ok(index.clone()),
+            None => Ok(req.url_for("index", iter::empty::<&str>())?),
+        }
+    }
+
+    // The permalink to `thing` on the list `list`.
+    fn permalink(
+        &self,
+        req: &HttpRequest,
+        list: Option<&str>,
+        thing: &Thing,
+    ) -> error::Result<Url> {
+        let query = ItemQuery {
+            list: list.map(str::to_owned),
+            ..Default::default()
+        };
+        Ok(view::permalink(&self.index(req)?, &query, thing)?)
+    }
+
+    /// Choose suggestions for each workspace in `team_lists`, a map from team
+    /// IDs, such as `T0123ABCD`, to the names of lists in the catalog.
+    /// Workspaces not in the map get the default list.
+    pub fn with_team_lists(self, team_lists: BTreeMap<String, String>) -> Self {
+        Slack { team_lists, ..self }
+    }
+
+    // The list the workspace `team` chooses suggestions from, or `None` for
+    // the default list.
+    fn list(&self, team: Option<&str>) -> Option<&str> {
+        team.and_then(|team| self.team_lists.get(team))
+            .map(String::as_str)
+    }
+
+    /// Reply to mentions with `bot_token`, from the app's OAuth & Permissions
+    /// page, in workspaces the app hasn't been installed in over OAuth.
+    pub fn with_bot_token(self, bot_token: impl Into<String>) -> Self {
+        Slack {
+            bot_token: Some(bot_token.into()),
+            ..self
+        }
+    }
+
+    // The token to post to the workspace `team` with, if any.
+    fn token(&self, team: &str) -> Option<String> {
+        self.tokens()
+            .and_then(|tokens| tokens.get(team))
+            .or_else(|| self.bot_token.clone())
+    }
+
+    /// Let the app be installed in any workspace, using the `client_id` and
+    /// `client_secret` from the app's Basic Information page, and keeping
+    /// each workspace's bot token in `tokens`.
+    pub fn installable(
+        self,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+        tokens: Tokens,
+    ) -> Self {
+        Slack {
+            install: Some(Install {
+                client_id: client_id.into(),
+                client_secret: client_secret.into(),
+                tokens,
+            }),
+            ..self
+        }
+    }
+
+    /// The bot tokens of the workspaces the app has been installed in, if it's
+    /// installable.
+    pub fn tokens(&self) -> Option<&Tokens> {
+        self.install.as_ref().map(|install| &install.tokens)
+    }
+
+    /// Show suggestions only to the person who asked for them, unless the
+    /// command asks for them `aloud`.
+    pub fn quietly(self) -> Self {
+        Slack {
+            quiet: true,
+            ..self
+        }
+    }
+
+    // Slack signs requests with an HMAC of the version, the timestamp, and
+    // the body, keyed with the signing secret, in the X-Slack-Signature
+    // header.
+    fn verify(&self, req: &HttpRequest, body: &[u8]) -> error::Result<()> {
+        let header = |name| {
+            req.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+        };
+        let timestamp = header("X-Slack-Request-Timestamp");
+        let signature = header("X-Slack-Signature")
+            .and_then(|value| value.strip_prefix("v0="))
+            .and_then(|value| hex::decode(value).ok());
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs());
+        let fresh = timestamp
+            .and_then(|timestamp| timestamp.parse::<u64>().ok())
+            .is_some_and(|timestamp| now.abs_diff(timestamp) <= MAX_SKEW);
+
+        match (timestamp, signature) {
+            (Some(timestamp), Some(signature)) if fresh => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(self.signing_secret.as_bytes())
+                    .expect("HMAC accepts keys of any size");
+                mac.update(b"v0:");
+                mac.update(timestamp.as_bytes());
+                mac.update(b":");
+                mac.update(body);
+                mac.verify_slice(&signature)
+                    .map_err(|_| error::ErrorUnauthorized("Unauthorized"))
+            }
+            _ => Err(error::ErrorUnauthorized("Unauthorized")),
+        }
+    }
+}
+
+fn slack(req: &HttpRequest) -> error::Result<&Slack> {
+    req.app_data::<web::Data<Slack>>()
+        .map(|slack| slack.get_ref())
+        .ok_or_else(|| view::not_found(req))
+}
+
+// Slack's mrkdwn treats these characters as markup, so text has to escape
+// them.
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// The suggestions a message asks for: a particular `item`, or a random one,
+/// among those matching the search `q` if given. Messages' "Give me another"
+/// buttons carry these, URL-encoded, as their value. Other chat integrations
+/// with slash commands ask in the same way.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+pub(crate) struct Ask {
+    item: Option<String>,
+    q: Option<String>,
+}
+
+impl Ask {
+    // What a command's `text` asks for in `things`: a single word naming a
+    // suggestion asks for that suggestion, and other words search.
+    pub(crate) fn from_text(things: Option<&Things>, text: &str) -> Self {
+        let names_item = !text.contains(char::is_whitespace)
+            && things
+                .is_some_and(|things| things.aliased(text).or_else(|| things.find(text)).is_some());
+
+        match text {
+            "" => Ask::default(),
+            _ if names_item => Ask {
+                item: Some(text.to_owned()),
+                q: None,
+            },
+            _ => Ask {
+                item: None,
+                q: Some(text.to_owned()),
+            },
+        }
+    }
+
+    pub(crate) fn choose<'a>(&self, things: &'a Things) -> Option<&'a (usize, Thing)> {
+        match (&self.item, &self.q) {
+            (Some(item), _) => things.aliased(item).or_else(|| things.find(item)),
+            (None, Some(q)) => things.search(q).choose(&mut thread_rng()).copied(),
+            (None, None) => things.choose(None, None),
+        }
+    }
+
+    fn another(&self) -> Self {
+        Ask {
+            item: None,
+            q: self.q.clone(),
+        }
+    }
+}
+
+// A message showing a suggestion chosen by `ask` from the list `list`, with a
+// button for another.
+fn message(
+    req: &HttpRequest,
+    store: &Store,
+    list: Option<&str>,
+    ask: &Ask,
+) -> error::Result<Value> {
+    let catalog = store.current();
+    let chosen = catalog
+        .named_list(list)
+        .and_then(|(name, things)| Some((name, ask.choose(things)?)));
+    let (name, (number, thing)) = match chosen {
+        Some(chosen) => chosen,
+        None => {
+            let text = match (&ask.item, &ask.q) {
+                (None, Some(q)) => format!("Sorry, no suggestions match {}.", escape(q)),
+                _ => "Sorry, there are no suggestions to show.".to_owned(),
+            };
+            return Ok(json!({
+                "response_type": "ephemeral",
+                "text": text,
+            }));
+        }
+    };
+
+    counts::served(req, name, &thing.item(*number), Channel::Slack);
+    access_log::chose(req, name, &thing.item(*number));
+
+    let url = slack(req)?.permalink(req, list, thing)?;
+    let text = escape(&thing.text());
+    let another = serde_urlencoded::to_string(ask.another())?;
+
+    Ok(json!({
+        "response_type": "in_channel",
+        "text": text,
+        "blocks": [
+            {
+                "type": "section",
+                "text": { "type": "mrkdwn", "text": text },
+            },
+            {
+                "type": "actions",
+                "elements": [
+                    {
+                        "type": "button",
+                        "text": { "type": "plain_text", "text": "Permalink" },
+                        "action_id": PERMALINK,
+                        "url": url.as_str(),
+                    },
+                    {
+                        "type": "button",
+                        "text": { "type": "plain_text", "text": "Give me another" },
+                        "action_id": ANOTHER,
+                        "value": another,
+                    },
+                ],
+            },
+        ],
+    }))
+}
+
+#[derive(Deserialize)]
+struct CommandForm {
+    #[serde(default)]
+    text: String,
+    team_id: Option<String>,
+}
+
+// What a slash command's text asks for: who sees the answer, and the rest
+// of the text, which names or searches for a suggestion.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Command {
+    pub(crate) quiet: bool,
+    pub(crate) text: String,
+}
+
+impl Command {
+    pub(crate) fn parse(text: &str, quiet: bool) -> Self {
+        let mut parsed = Command {
+            quiet,
+            text: String::new(),
+        };
+        let mut words = vec![];
+        for word in text.split_whitespace() {
+            match word.to_lowercase().as_str() {
+                "quietly" => parsed.quiet = true,
+                "aloud" => parsed.quiet = false,
+                _ => words.push(word),
+            }
+        }
+        parsed.text = words.join(" ");
+
+        parsed
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/slack/command",
+    tag = "slack",
+    request_body(content = String, description = "A Slack slash command invocation", content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 200, description = "A message to post, as JSON"),
+        (status = 400, description = "The request was malformed"),
+        (status = 401, description = "The request's signature was missing, stale, or incorrect"),
+        (status = 404, description = "The Slack app is off"),
+    ),
+)]
+#[post("/slack/command")]
+pub(crate) async fn command(
+    req: HttpRequest,
+    store: web::Data<Store>,
+    body: web::Bytes,
+) -> error::Result<HttpResponse> {
+    let slack = slack(&req)?;
+    slack.verify(&req, &body)?;
+
+    let form: CommandForm = serde_urlencoded::from_bytes(&body).map_err(error::ErrorBadRequest)?;
+    let asked = Command::parse(&form.text, slack.quiet);
+    let list = slack.list(form.team_id.as_deref());
+    let ask = Ask::from_text(store.current().list(list), &asked.text);
+
+    let mut message = message(&req, &store, list, &ask)?;
+    if asked.quiet {
+        message["response_type"] = json!("ephemeral");
+    }
+
+    Ok(HttpResponse::Ok().json(message))
+}
+
+#[derive(Deserialize)]
+struct InteractionForm {
+    payload: String,
+}
+
+#[derive(Deserialize)]
+struct Interaction {
+    #[serde(rename = "type")]
+    kind: String,
+    response_url: Option<String>,
+    team: Option<InteractionTeam>,
+    #[serde(default)]
+    actions: Vec<Action>,
+}
+
+#[derive(Deserialize)]
+struct InteractionTeam {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct Action {
+    action_id: String,
+    value: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/slack/interact",
+    tag = "slack",
+    request_body(content = String, description = "A Slack interaction payload", content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 200, description = "The interaction was handled"),
+        (status = 400, description = "The payload was malformed"),
+        (status = 401, description = "The request's signature was missing, stale, or incorrect"),
+        (status = 404, description = "The Slack app is off"),
+        (status = 502, description = "The new suggestion could not be sent to Slack"),
+    ),
+)]
+#[post("/slack/interact")]
+pub(crate) async fn interact(
+    req: HttpRequest,
+    store: web::Data<Store>,
+    body: web::Bytes,
+) -> error::Result<HttpResponse> {
+    let slack = slack(&req)?;
+    slack.verify(&req, &body)?;
+
+    let form: InteractionForm =
+        serde_urlencoded::from_bytes(&body).map_err(error::ErrorBadRequest)?;
+    let interaction: Interaction =
+        serde_json::from_str(&form.payload).map_err(error::ErrorBadRequest)?;
+
+    let team = interaction.team.map(|team| team.id);
+    let pressed = interaction
+        .actions
+        .iter()
+        .find(|action| action.action_id == ANOTHER);
+    let (action, response_url) =
+        match (interaction.kind.as_str(), pressed, interaction.response_url) {
+            ("block_actions", Some(action), Some(response_url)) => (action, response_url),
+            _ => return Ok(HttpResponse::Ok().finish()),
+        };
+
+    // Only post to Slack, so that forged payloads can't aim requests from
+    // this service elsewhere.
+    let response_url = Url::parse(&response_url).map_err(error::ErrorBadRequest)?;
+    if response_url.scheme() != "https" || response_url.host_str() != Some(RESPONSE_HOST) {
+        return Err(error::ErrorBadRequest("Not a Slack response URL"));
+    }
+
+    let ask: Ask = serde_urlencoded::from_str(action.value.as_deref().unwrap_or_default())
+        .map_err(error::ErrorBadRequest)?;
+    let list = slack.list(team.as_deref());
+    let mut message = message(&req, &store, list, &ask)?;
+    message["replace_original"] = json!(true);
+
+    // Posting blocks, so keep it off the thread serving requests.
+    web::block(move || {
+        ureq::post(response_url.as_str())
+            .set("Content-Type", "application/json")
+            .send_string(&message.to_string())
+            .map_err(Box::new)
+    })
+    .await?
+    .map_err(|err| {
+        eprintln!("Unable to send a suggestion to Slack: {}", err);
+        error::ErrorBadGateway(err)
+    })?;
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Envelope {
+    UrlVerification {
+        challenge: String,
+    },
+    EventCallback {
+        team_id: String,
+        event: Event,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+struct Event {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    text: String,
+    channel: Option<String>,
+    ts: Option<String>,
+    thread_ts: Option<String>,
+    message_ts: Option<String>,
+    #[serde(default)]
+    links: Vec<Link>,
+}
+
+#[derive(Deserialize)]
+struct Link {
+    url: String,
+}
+
+// Mentions arrive as `<@U0123>` in a mention's text; whatever else it says
+// asks for a suggestion.
+fn without_mentions(text: &str) -> String {
+    text.split_whitespace()
+        .filter(|word| !(word.starts_with("<@") && word.ends_with('>')))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[utoipa::path(
+    post,
+    path = "/slack/events",
+    tag = "slack",
+    request_body(content = String, description = "A Slack Events API request", content_type = "application/json"),
+    responses(
+        (status = 200, description = "The event was received, or the URL verified"),
+        (status = 400, description = "The request was malformed"),
+        (status = 401, description = "The request's signature was missing, stale, or incorrect"),
+        (status = 404, description = "The Slack app is off"),
+    ),
+)]
+#[post("/slack/events")]
+pub(crate) async fn events(
+    req: HttpRequest,
+    store: web::Data<Store>,
+    body: web::Bytes,
+) -> error::Result<HttpResponse> {
+    let slack = slack(&req)?;
+    slack.verify(&req, &body)?;
+
+    let envelope: Envelope = serde_json::from_slice(&body).map_err(error::ErrorBadRequest)?;
+    let (team, event) = match envelope {
+        Envelope::UrlVerification { challenge } => {
+            return Ok(HttpResponse::Ok().json(json!({ "challenge": challenge })))
+        }
+        Envelope::EventCallback { team_id, event } => (team_id, event),
+        Envelope::Other => return Ok(HttpResponse::Ok().finish()),
+    };
+
+    // Slack resends events it thinks went unanswered; the first delivery
+    // already answered.
+    if req.headers().contains_key("X-Slack-Retry-Num") {
+        return Ok(HttpResponse::Ok().finish());
+    }
+
+    let call = match event.kind.as_str() {
+        "app_mention" => reply(&req, &store, slack.list(Some(&team)), event)?
+            .map(|reply| (POST_MESSAGE_URL, reply)),
+        "link_shared" => unfurl(&req, &store, event)?.map(|unfurl| (UNFURL_URL, unfurl)),
+        _ => None,
+    };
+    let (method, body) = match call {
+        Some(call) => call,
+        None => return Ok(HttpResponse::Ok().finish()),
+    };
+    let token = match slack.token(&team) {
+        Some(token) => token,
+        None => {
+            eprintln!("Unable to answer a Slack event: no token for {}", team);
+            return Ok(HttpResponse::Ok().finish());
+        }
+    };
+
+    // Slack wants an answer within three seconds, so answer the event first,
+    // and call Slack back afterwards.
+    actix_web::rt::spawn(call_api(method, token, body));
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+// A reply to the mention `event`, choosing from the list `list`, to post with
+// `chat.postMessage`.
+fn reply(
+    req: &HttpRequest,
+    store: &Store,
+    list: Option<&str>,
+    event: Event,
+) -> error::Result<Option<Value>> {
+    let (channel, ts) = match (event.channel, event.ts) {
+        (Some(channel), Some(ts)) => (channel, ts),
+        _ => return Ok(None),
+    };
+
+    let asked = Command::parse(&without_mentions(&event.text), false);
+    let ask = Ask::from_text(store.current().list(list), &asked.text);
+    let mut message = message(req, store, list, &ask)?;
+    message["channel"] = json!(channel);
+    message["thread_ts"] = json!(event.thread_ts.unwrap_or(ts));
+
+    Ok(Some(message))
+}
+
+// The suggestion `link` is a permalink to, if it's a permalink on the service
+// whose index page is at `index`.
+fn permalinked(catalog: &Catalog, index: &Url, link: &str) -> Option<Thing> {
+    let link = Url::parse(link).ok()?;
+    let (query, (_, thing)) = view::permalinked(catalog, index, &link)?;
+
+    Some(thing.translated(query.lang.as_deref()))
+}
+
+// Previews of the permalinks shared in `event`, to post with `chat.unfurl`.
+fn unfurl(req: &HttpRequest, store: &Store, event: Event) -> error::Result<Option<Value>> {
+    let index = slack(req)?.index(req)?;
+    let catalog = store.current();
+
+    let unfurls: serde_json::Map<_, _> = event
+        .links
+        .into_iter()
+        .filter_map(|link| {
+            let thing = permalinked(&catalog, &index, &link.url)?;
+            let preview = json!({
+                "blocks": [
+                    {
+                        "type": "section",
+                        "text": { "type": "mrkdwn", "text": escape(&thing.text()) },
+                    },
+                ],
+            });
+            Some((link.url, preview))
+        })
+        .collect();
+
+    let unfurl = match (event.channel, event.message_ts) {
+        (Some(channel), Some(ts)) if !unfurls.is_empty() => Some(json!({
+            "channel": channel,
+            "ts": ts,
+            "unfurls": unfurls,
+        })),
+        _ => None,
+    };
+
+    Ok(unfurl)
+}
+
+// Call the Web API method at `url` with `body`, as the bot with `token`.
+async fn call_api(url: &'static str, token: String, body: Value) {
+    // Calling Slack blocks, so keep it off the thread serving requests.
+    let called = web::block(move || {
+        let response = ureq::post(url)
+            .set("Authorization", &format!("Bearer {}", token))
+            .set("Content-Type", "application/json; charset=utf-8")
+            .send_string(&body.to_string())
+            .map_err(|err| err.to_string())?
+            .into_string()
+            .map_err(|err| err.to_string())?;
+        let response: Value = serde_json::from_str(&response).map_err(|err| err.to_string())?;
+        match response["ok"].as_bool() {
+            Some(true) => Ok(()),
+            _ => Err(response["error"]
+                .as_str()
+                .unwrap_or("unknown error")
+                .to_owned()),
+        }
+    })
+    .await;
+
+    match called {
+        Ok(Ok(())) => {}
+        Ok(Err(err)) => eprintln!("Unable to call {}: {}", url, err),
+        Err(err) => eprintln!("Unable to call {}: {}", url, err),
+    }
+}
+
+fn install(req: &HttpRequest) -> error::Result<&Install> {
+    slack(req)?
+        .install
+        .as_ref()
+        .ok_or_else(|| view::not_found(req))
+}
+
+#[utoipa::path(
+    get,
+    path = "/slack/oauth/start",
+    tag = "slack",
+    responses(
+        (status = 302, description = "Sends the browser to Slack to approve the installation"),
+        (status = 404, description = "The Slack app is off, or isn't installable"),
+    ),
+)]
+#[get("/slack/oauth/start")]
+pub(crate) async fn oauth_start(req: HttpRequest) -> error::Result<HttpResponse> {
+    let install = install(&req)?;
+
+    let state = hex::encode(thread_rng().gen::<[u8; 16]>());
+    let redirect_uri = req.url_for("slack_oauth_callback", iter::empty::<&str>())?;
+
+    let mut authorize = Url::parse(AUTHORIZE_URL).expect("the authorize URL is valid");
+    authorize
+        .query_pairs_mut()
+        .append_pair("client_id", &install.client_id)
+        .append_pair("scope", SCOPES)
+        .append_pair("state", &state)
+        .append_pair("redirect_uri", redirect_uri.as_str());
+
+    let cookie = Cookie::build(STATE_COOKIE, state)
+        .path("/slack/oauth")
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        .max_age(time::Duration::minutes(STATE_MAX_AGE_MINUTES))
+        .finish();
+
+    Ok(HttpResponse::Found()
+        .insert_header((header::LOCATION, authorize.as_str()))
+        .insert_header(("Cache-Control", "no-store"))
+        .cookie(cookie)
+        .finish())
+}
+
+#[derive(Deserialize)]
+struct CallbackQuery {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Access {
+    ok: bool,
+    error: Option<String>,
+    access_token: Option<String>,
+    team: Option<Team>,
+}
+
+#[derive(Deserialize)]
+struct Team {
+    id: String,
+    name: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/slack/oauth/callback",
+    tag = "slack",
+    params(
+        ("code" = Option<String>, Query, description = "The code to exchange for a bot token"),
+        ("state" = Option<String>, Query, description = "The state given to Slack by `/slack/oauth/start`"),
+        ("error" = Option<String>, Query, description = "Why the installation wasn't approved, if it wasn't"),
+    ),
+    responses(
+        (status = 200, description = "The app was installed", content_type = "text/plain"),
+        (status = 400, description = "The installation was refused, or wasn't started by this browser"),
+        (status = 404, description = "The Slack app is off, or isn't installable"),
+        (status = 502, description = "Slack did not issue a bot token"),
+    ),
+)]
+#[get("/slack/oauth/callback", name = "slack_oauth_callback")]
+pub(crate) async fn oauth_callback(
+    req: HttpRequest,
+    query: web::Query<CallbackQuery>,
+) -> error::Result<HttpResponse> {
+    let install = install(&req)?;
+
+    let expected = req.cookie(STATE_COOKIE);
+    let started_here = match (&query.state, &expected) {
+        (Some(state), Some(expected)) => state == expected.value(),
+        _ => false,
+    };
+    if !started_here {
+        return Err(error::ErrorBadRequest("Installation not started here"));
+    }
+    if let Some(err) = &query.error {
+        return Err(error::ErrorBadRequest(format!(
+            "Installation not approved: {}",
+            err
+        )));
+    }
+    let code = query
+        .code
+        .clone()
+        .ok_or_else(|| error::ErrorBadRequest("Missing code"))?;
+
+    let redirect_uri = req.url_for("slack_oauth_callback", iter::empty::<&str>())?;
+    let (client_id, client_secret) = (install.client_id.clone(), install.client_secret.clone());
+    // Exchanging the code blocks, so keep it off the thread serving requests.
+    let access = web::block(move || {
+        ureq::post(ACCESS_URL)
+            .send_form(&[
+                ("client_id", &client_id),
+                ("client_secret", &client_secret),
+                ("code", &code),
+                ("redirect_uri", redirect_uri.as_str()),
+            ])
+            .map_err(|err| err.to_string())?
+            .into_string()
+            .map_err(|err| err.to_string())
+            .and_then(|json| serde_json::from_str::<Access>(&json).map_err(|err| err.to_string()))
+    })
+    .await?;
+
+    let installed = match access {
+        Ok(Access {
+            ok: true,
+            access_token: Some(token),
+            team: Some(team),
+            ..
+        }) => Ok((token, team)),
+        Ok(Access { error, .. }) => Err(error.unwrap_or_else(|| "no bot token".to_owned())),
+        Err(err) => Err(err),
+    };
+    let (token, team) = installed.map_err(|err| {
+        eprintln!("Unable to install the Slack app: {}", err);
+        error::ErrorBadGateway(err)
+    })?;
+
+    install.tokens.insert(&team.id, &token).map_err(|err| {
+        eprintln!("Unable to save a Slack token: {}", err);
+        error::ErrorInternalServerError(err)
+    })?;
+
+    let mut used = Cookie::build(STATE_COOKIE, "")
+        .path("/slack/oauth")
+        .finish();
+    used.make_removal();
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; charset=utf-8")
+        .insert_header(("Cache-Control", "no-store"))
+        .cookie(used)
+        .body(format!("Installed in {}.", team.name)))
+}
+
+pub(crate) fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(command)
+        .service(interact)
+        .service(events)
+        .service(oauth_start)
+        .service(oauth_callback);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::things::load_catalog;
+    use std::os::unix::fs::PermissionsExt;
+    use std::{env, process};
+
+    fn command(quiet: bool, text: &str) -> Command {
+        Command {
+            quiet,
+            text: text.to_owned(),
+        }
+    }
+
+    #[test]
+    fn commands_choose_who_sees_suggestions() {
+        assert_eq!(Command::parse("", false), command(false, ""));
+        assert_eq!(Command::parse("Quietly", false), command(true, ""));
+        assert_eq!(Command::parse("", true), command(true, ""));
+        assert_eq!(Command::parse("aloud", true), command(false, ""));
+        assert_eq!(
+            Command::parse("quietly  dns  cache", false),
+            command(true, "dns cache")
+        );
+    }
+
+    #[test]
+    fn commands_name_or_search_for_suggestions() {
+        let catalog = load_catalog(
+            "
+- Have you checked DNS?
+- text: Have you checked the cache?
+  id: cache
+",
+        )
+        .unwrap();
+        let things = catalog.list(None);
+
+        let item = |item: &str| Ask {
+            item: Some(item.to_owned()),
+            q: None,
+        };
+        let search = |q: &str| Ask {
+            item: None,
+            q: Some(q.to_owned()),
+        };
+        assert_eq!(Ask::from_text(things, ""), Ask::default());
+        assert_eq!(Ask::from_text(things, "0"), item("0"));
+        assert_eq!(Ask::from_text(things, "cache"), item("cache"));
+        assert_eq!(Ask::from_text(things, "dns"), search("dns"));
+        assert_eq!(Ask::from_text(things, "42"), search("42"));
+        assert_eq!(Ask::from_text(things, "the cache"), search("the cache"));
+    }
+
+    #[test]
+    fn mentions_are_not_asked_for() {
+        assert_eq!(without_mentions("<@U0123> dns"), "dns");
+        assert_eq!(
+            without_mentions("have you <@U0123>  checked"),
+            "have you checked"
+        );
+        assert_eq!(without_mentions("<@U0123>"), "");
+    }
+
+    #[test]
+    fn permalinks_name_suggestions() {
+        let catalog = load_catalog(
+            "
+- Have you checked DNS?
+- text: Have you checked the cache?
+  id: cache
+",
+        )
+        .unwrap();
+        let index = Url::parse("https://example.com/").unwrap();
+
+        let text = |link| permalinked(&catalog, &index, link).map(|thing| thing.markdown);
+        assert_eq!(
+            text("https://example.com/?item=0").as_deref(),
+            Some("Have you checked DNS?")
+        );
+        assert_eq!(
+            text("https://example.com/?item=cache&lang=fr").as_deref(),
+            Some("Have you checked the cache?")
+        );
+        assert_eq!(
+            text("https://example.com/check/have-you-checked-the-cache").as_deref(),
+            Some("Have you checked the cache?")
+        );
+        assert_eq!(text("https://example.com/"), None);
+        assert_eq!(text("https://example.com/?item=2"), None);
+        assert_eq!(text("https://example.com/check/have-you-checked"), None);
+        assert_eq!(text("https://example.com/feed.xml?item=0"), None);
+    }
+
+    #[test]
+    fn tokens_are_saved() {
+        let path = env::temp_dir().join(format!("things-to-check-slack-{}.json", process::id()));
+        let tokens = Tokens::open(&path).unwrap();
+        assert_eq!(tokens.get("T1"), None);
+        tokens.insert("T1", "xoxb-1").unwrap();
+        tokens.insert("T2", "xoxb-2").unwrap();
+
+        let mode = fs::metadata(&path).map(|metadata| metadata.permissions().mode());
+        let reopened = Tokens::open(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(mode.unwrap() & 0o777, 0o600);
+        let reopened = reopened.unwrap();
+        assert_eq!(reopened.get("T1").as_deref(), Some("xoxb-1"));
+        assert_eq!(reopened.get("T2").as_deref(), Some("xoxb-2"));
+    }
+}
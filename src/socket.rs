@@ -0,0 +1,189 @@
+//! A WebSocket endpoint, for interactive frontends and chat bridges.
+//!
+//! Clients that want suggestions one after another, with low latency, can
+//! hold a socket open and ask for each in turn, rather than making a request
+//! each time. It is mounted by every `make_*service(…)` function.
+//!
+//! # Endpoints
+//!
+//! * `/ws` (`GET`): a WebSocket that answers every text message with a
+//!   suggestion.
+//!
+//!   Takes the same URL parameters as `/api/v1/suggestion`. A `next` message
+//!   asks for the suggestion a request to `/api/v1/suggestion` with those
+//!   parameters would return: without an `item`, one chosen at random from
+//!   those that haven't been retired. A message holding a JSON object with
+//!   `item`, `list`, `tag`, and `lang` fields, all optional, asks for a
+//!   suggestion in the same way, with those parameters in place of the URL
+//!   parameters.
+//!
+//!   Each answer is a text message holding the suggestion as JSON, as from
+//!   `/api/v1/suggestion`, or a JSON object with an `error` field if there is
+//!   no such suggestion or the message can't be understood. Binary and
+//!   fragmented messages close the socket.
+//!
+//! Every message counts against the service's rate limit, if any, as a request
+//! would; messages over the limit are answered with an error.
+
+use actix_codec::{Decoder, Encoder};
+use actix_http::ws::{self, CloseCode, CloseReason, Codec, Frame, Message};
+use actix_web::body::BodyStream;
+use actix_web::{error, get, web, HttpRequest, HttpResponse};
+use bytes::{Bytes, BytesMut};
+use futures_util::{stream, StreamExt};
+use serde::Serialize;
+
+use crate::api::Suggestion;
+use crate::limit::Limited;
+use crate::things::Store;
+use crate::view::{ItemQuery, Urls};
+
+#[derive(Serialize)]
+struct Failure {
+    error: String,
+}
+
+struct Session {
+    req: HttpRequest,
+    store: web::Data<Store>,
+    query: ItemQuery,
+    payload: web::Payload,
+    codec: Codec,
+    incoming: BytesMut,
+    closed: bool,
+}
+
+impl Session {
+    // The suggestion asked for by `query`, as JSON.
+    fn suggestion(&self, query: &ItemQuery) -> error::Result<String> {
+        Limited::check(&self.req)?;
+
+        let catalog = self.store.current();
+        let (list, things) = catalog
+            .named_list(query.list.as_deref())
+            .ok_or_else(|| error::ErrorNotFound("Not found"))?;
+
+        let item = query.item.as_deref();
+        let tag = query.tag.as_deref();
+        let chosen = match item.and_then(|item| things.aliased(item)) {
+            Some(aliased) => Some(aliased).filter(|(_, thing)| thing.has_tag(tag)),
+            None => things.choose(item, tag),
+        };
+        let (index, thing) = chosen.ok_or_else(|| error::ErrorNotFound("Not found"))?;
+
+        let id = thing.item(*index);
+        let url = self.req.suggestion(query, thing)?;
+        let thing = thing.translated(query.lang.as_deref());
+        let suggestion = Suggestion::new(list, id, url.into(), thing, things.position(*index));
+
+        serde_json::to_string(&suggestion).map_err(error::ErrorInternalServerError)
+    }
+
+    // The answer to a text message.
+    fn answer(&self, text: &[u8]) -> String {
+        let query = if text == b"next" {
+            Ok(self.query.clone())
+        } else {
+            serde_json::from_slice(text).map_err(error::ErrorBadRequest)
+        };
+
+        query
+            .and_then(|query| self.suggestion(&query))
+            .unwrap_or_else(|err| {
+                let failure = Failure {
+                    error: err.to_string(),
+                };
+                serde_json::to_string(&failure).expect("failures serialize")
+            })
+    }
+
+    fn close(&mut self, code: CloseCode) -> Message {
+        self.closed = true;
+        Message::Close(Some(CloseReason::from(code)))
+    }
+
+    // The reply to `frame`, if it needs one.
+    fn reply(&mut self, frame: Frame) -> Option<Message> {
+        match frame {
+            Frame::Text(text) => Some(Message::Text(self.answer(&text).into())),
+            Frame::Ping(message) => Some(Message::Pong(message)),
+            Frame::Pong(_) => None,
+            Frame::Close(reason) => {
+                self.closed = true;
+                Some(Message::Close(reason))
+            }
+            Frame::Binary(_) | Frame::Continuation(_) => Some(self.close(CloseCode::Unsupported)),
+        }
+    }
+
+    // The next message to send, read from the client's messages, or `None`
+    // once the socket is closed.
+    async fn next(&mut self) -> Option<Message> {
+        while !self.closed {
+            match self.codec.decode(&mut self.incoming) {
+                Ok(Some(frame)) => {
+                    if let Some(message) = self.reply(frame) {
+                        return Some(message);
+                    }
+                }
+                Ok(None) => match self.payload.next().await {
+                    Some(Ok(chunk)) => self.incoming.extend_from_slice(&chunk),
+                    _ => return None,
+                },
+                Err(_) => return Some(self.close(CloseCode::Protocol)),
+            }
+        }
+
+        None
+    }
+
+    fn encode(&mut self, message: Message) -> Result<Bytes, ws::ProtocolError> {
+        let mut outgoing = BytesMut::new();
+        self.codec.encode(message, &mut outgoing)?;
+        Ok(outgoing.freeze())
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/ws",
+    tag = "streams",
+    params(ItemQuery),
+    responses(
+        (status = 101, description = "The socket is open"),
+        (status = 400, description = "The request isn't a WebSocket handshake"),
+    ),
+)]
+#[get("/ws")]
+pub(crate) async fn socket(
+    _: Limited,
+    req: HttpRequest,
+    store: web::Data<Store>,
+    query: web::Query<ItemQuery>,
+    payload: web::Payload,
+) -> error::Result<HttpResponse> {
+    let mut handshake = ws::handshake(req.head())?;
+
+    let session = Session {
+        req,
+        store,
+        query: query.into_inner(),
+        payload,
+        codec: Codec::new(),
+        incoming: BytesMut::new(),
+        closed: false,
+    };
+    let outgoing = stream::unfold(session, |mut session| async move {
+        let message = session.next().await?;
+        let bytes = session.encode(message);
+        Some((bytes, session))
+    });
+
+    let response = handshake.message_body(BodyStream::new(outgoing))?;
+
+    Ok(HttpResponse::from(response).map_into_boxed_body())
+}
+
+pub(crate) fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(socket);
+}
@@ -0,0 +1,273 @@
+//! Suggestions stored in an SQLite database.
+//!
+//! This module is only available with the `sqlite` feature. A service created
+//! with `view::make_sqlite_service(…)` serves suggestions from an SQLite
+//! database file, and comes with a `Database` handle that can add and edit
+//! suggestions, and attach metadata to them, while the service is running.
+//!
+//! The `item` parameter's contract still holds: every suggestion is numbered
+//! when it's inserted, numbers are never reused, and suggestions can be edited
+//! but never removed. A new, empty database is seeded with the built-in
+//! suggestions, so that existing links continue to work.
+//!
+//! The database holds a single list of suggestions, which is served as the
+//! `general` list. The `view::Options` passed to `make_sqlite_service(…)` apply
+//! as they do to suggestions from any other source: suggestions are checked for
+//! problems when the database is opened, and excluded suggestions stay hidden,
+//! and Markdown extensions enabled, as the database changes.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # use things_to_check::view;
+//! # fn main() -> std::result::Result<(), things_to_check::view::Error> {
+//! let options = view::Options::default();
+//! let (service, database) = view::make_sqlite_service("things-to-check.db", &options)?;
+//!
+//! let item = database.insert("Have you checked the database?")?;
+//! database.set_metadata(item, "author", "ojacobson")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use actix_web::web;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+use crate::things::{load_catalog, Catalog, Store, Thing, Things, THINGS};
+use crate::view::{Error, Options};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS things (
+        item INTEGER PRIMARY KEY,
+        markdown TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS metadata (
+        item INTEGER NOT NULL REFERENCES things (item),
+        key TEXT NOT NULL,
+        value TEXT NOT NULL,
+        PRIMARY KEY (item, key)
+    );
+";
+
+/// A handle on the database backing a running service.
+///
+/// Changes made through this handle are written to the database, then served
+/// immediately.
+pub struct Database {
+    conn: Mutex<Connection>,
+    store: web::Data<Store>,
+    options: Options,
+}
+
+impl Database {
+    pub(crate) fn open(path: impl AsRef<Path>, options: &Options) -> Result<Self, Error> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+
+        let count: usize = conn.query_row("SELECT COUNT(*) FROM things", [], |row| row.get(0))?;
+        if count == 0 {
+            seed(&conn)?;
+        }
+
+        let catalog = Catalog::from(read_things(&conn)?);
+        catalog.check(options.strictness)?;
+
+        Ok(Database {
+            conn: Mutex::new(conn),
+            store: web::Data::new(Store::new(prepared(catalog, options))),
+            options: options.clone(),
+        })
+    }
+
+    pub(crate) fn store(&self) -> web::Data<Store> {
+        self.store.clone()
+    }
+
+    /// Add a new suggestion, returning its `item` number.
+    pub fn insert(&self, markdown: &str) -> Result<usize, Error> {
+        let conn = self.lock();
+        let item: usize = conn.query_row(
+            "INSERT INTO things (item, markdown)
+                SELECT COALESCE(MAX(item) + 1, 0), ? FROM things
+                RETURNING item",
+            params![markdown],
+            |row| row.get(0),
+        )?;
+
+        self.refresh(&conn)?;
+
+        Ok(item)
+    }
+
+    /// Replace the text of an existing suggestion.
+    ///
+    /// Returns `false`, and changes nothing, if there is no such suggestion.
+    pub fn update(&self, item: usize, markdown: &str) -> Result<bool, Error> {
+        let conn = self.lock();
+        let changed = conn.execute(
+            "UPDATE things SET markdown = ? WHERE item = ?",
+            params![markdown, item],
+        )?;
+
+        self.refresh(&conn)?;
+
+        Ok(changed > 0)
+    }
+
+    /// Set a metadata field on an existing suggestion, replacing any previous
+    /// value for the same key.
+    pub fn set_metadata(&self, item: usize, key: &str, value: &str) -> Result<(), Error> {
+        self.lock().execute(
+            "INSERT INTO metadata (item, key, value) VALUES (?, ?, ?)
+                ON CONFLICT (item, key) DO UPDATE SET value = excluded.value",
+            params![item, key, value],
+        )?;
+
+        Ok(())
+    }
+
+    /// Look up a single metadata field on a suggestion.
+    pub fn metadata(&self, item: usize, key: &str) -> Result<Option<String>, Error> {
+        let value = self
+            .lock()
+            .query_row(
+                "SELECT value FROM metadata WHERE item = ? AND key = ?",
+                params![item, key],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        Ok(value)
+    }
+
+    /// Look up all of the metadata attached to a suggestion.
+    pub fn all_metadata(&self, item: usize) -> Result<BTreeMap<String, String>, Error> {
+        let conn = self.lock();
+        let mut stmt = conn.prepare("SELECT key, value FROM metadata WHERE item = ?")?;
+        let metadata = stmt
+            .query_map(params![item], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+
+        Ok(metadata)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn refresh(&self, conn: &Connection) -> Result<(), Error> {
+        let catalog = Catalog::from(read_things(conn)?);
+        self.store.replace(prepared(catalog, &self.options));
+
+        Ok(())
+    }
+}
+
+// Applies the service's options to suggestions read from the database.
+fn prepared(mut catalog: Catalog, options: &Options) -> Catalog {
+    catalog.exclude(&options.exclude);
+    catalog.render(options.markdown);
+    catalog
+}
+
+fn seed(conn: &Connection) -> Result<(), Error> {
+    let catalog = load_catalog(THINGS)?;
+    let things = catalog.list(None).map_or(&[][..], |things| &things.0);
+
+    let mut stmt = conn.prepare("INSERT INTO things (item, markdown) VALUES (?, ?)")?;
+    for (item, thing) in things {
+        stmt.execute(params![item, &thing.markdown])?;
+    }
+
+    Ok(())
+}
+
+fn read_things(conn: &Connection) -> rusqlite::Result<Things> {
+    let mut stmt = conn.prepare("SELECT item, markdown FROM things ORDER BY item")?;
+    let things = stmt
+        .query_map([], |row| {
+            let markdown: String = row.get(1)?;
+            Ok((row.get(0)?, Thing::from(markdown)))
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+
+    Ok(Things(things))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeds_empty_database() {
+        let database = Database::open(":memory:", &Options::default()).unwrap();
+        let seeded = load_catalog(THINGS).unwrap();
+
+        assert_eq!(
+            database.store().current().list(None).unwrap().0.len(),
+            seeded.list(None).unwrap().0.len()
+        );
+    }
+
+    #[test]
+    fn insert_appends() {
+        let database = Database::open(":memory:", &Options::default()).unwrap();
+        let before = database.store().current().list(None).unwrap().0.len();
+
+        let item = database.insert("Have you checked the database?").unwrap();
+
+        let catalog = database.store().current();
+        let things = catalog.list(None).unwrap();
+        assert_eq!(item, before);
+        assert_eq!(things.0[item].1.markdown, "Have you checked the database?");
+    }
+
+    #[test]
+    fn update_preserves_item() {
+        let database = Database::open(":memory:", &Options::default()).unwrap();
+
+        assert!(database.update(0, "Have you checked ownership?").unwrap());
+        assert!(!database
+            .update(100_000, "Have you checked nothing?")
+            .unwrap());
+
+        let catalog = database.store().current();
+        let things = catalog.list(None).unwrap();
+        assert_eq!(things.0[0].0, 0);
+        assert_eq!(things.0[0].1.markdown, "Have you checked ownership?");
+    }
+
+    #[test]
+    fn metadata_round_trips() {
+        let database = Database::open(":memory:", &Options::default()).unwrap();
+
+        database.set_metadata(0, "author", "owen").unwrap();
+        database.set_metadata(0, "author", "ojacobson").unwrap();
+
+        assert_eq!(
+            database.metadata(0, "author").unwrap().as_deref(),
+            Some("ojacobson")
+        );
+        assert_eq!(database.all_metadata(0).unwrap().len(), 1);
+        assert_eq!(database.metadata(1, "author").unwrap(), None);
+    }
+
+    #[test]
+    fn changes_keep_exclusions() {
+        let options = Options {
+            exclude: vec!["0".into()],
+            ..Options::default()
+        };
+        let database = Database::open(":memory:", &options).unwrap();
+
+        database.update(1, "Have you checked ownership?").unwrap();
+
+        let catalog = database.store().current();
+        let things = catalog.list(None).unwrap();
+        assert!(things.find("0").is_none());
+        assert!(things.find("1").is_some());
+    }
+}
@@ -0,0 +1,160 @@
+//! A stream of suggestions, for wallboards and status displays.
+//!
+//! This endpoint pushes a new suggestion to the client every so often, so that
+//! a display can show a rotating tip without polling. It is mounted by every
+//! `make_*service(…)` function.
+//!
+//! # Endpoints
+//!
+//! * `/stream` (`GET`): a [Server-Sent Events][1] stream of suggestions.
+//!
+//!   Takes the same `list`, `tag`, and `lang` URL parameters as `/`, and an
+//!   `interval` parameter giving the number of seconds between suggestions,
+//!   which defaults to 60 and is clamped between 5 and 3600. A suggestion is
+//!   sent as soon as the stream opens, and then once per interval, each
+//!   chosen at random from those that haven't been retired. Each is a
+//!   `suggestion` event, whose data is the suggestion as JSON, as from
+//!   `/api/v1/suggestion`.
+//!
+//!   Suggestions are chosen from the current suggestions each time, so
+//!   streams pick up changes to them. If there are no suggestions to choose
+//!   from when one is due, a comment is sent instead; if the list goes away,
+//!   the stream ends.
+//!
+//! Unknown lists, and tags no suggestion carries, return `404 Not Found` when
+//! the stream is opened. Opening a stream counts against the service's rate
+//! limit, if any, but the suggestions sent on it don't.
+//!
+//! [1]: https://html.spec.whatwg.org/multipage/server-sent-events.html
+
+use actix_web::rt::time;
+use actix_web::{error, get, web, HttpRequest, HttpResponse};
+use futures_util::stream;
+use serde::Deserialize;
+use std::time::Duration;
+use utoipa::IntoParams;
+
+use crate::api::Suggestion;
+use crate::limit::Limited;
+use crate::things::{Store, Thing, Things};
+use crate::view::{self, ItemQuery, Urls};
+
+/// The seconds between suggestions, if the client doesn't say.
+const DEFAULT_INTERVAL: u64 = 60;
+
+/// The fewest seconds between suggestions a client can ask for.
+const MIN_INTERVAL: u64 = 5;
+
+/// The most seconds between suggestions a client can ask for.
+const MAX_INTERVAL: u64 = 3600;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct StreamQuery {
+    list: Option<String>,
+    tag: Option<String>,
+    lang: Option<String>,
+    interval: Option<u64>,
+}
+
+impl StreamQuery {
+    fn item_query(&self) -> ItemQuery {
+        ItemQuery {
+            item: None,
+            list: self.list.clone(),
+            tag: self.tag.clone(),
+            lang: self.lang.clone(),
+        }
+    }
+}
+
+// A `suggestion` event carrying the thing at `entry` in `list`, whose things
+// are `things`.
+fn suggestion_event(
+    req: &HttpRequest,
+    query: &ItemQuery,
+    list: &str,
+    things: &Things,
+    entry: &(usize, Thing),
+) -> error::Result<web::Bytes> {
+    let (index, thing) = entry;
+    let id = thing.item(*index);
+    let url = req.suggestion(query, thing)?;
+    let thing = thing.translated(query.lang.as_deref());
+    let suggestion = Suggestion::new(list, id, url.into(), thing, things.position(*index));
+
+    // Serialized JSON never contains a newline, so it fits in one data field.
+    let data = serde_json::to_string(&suggestion).map_err(error::ErrorInternalServerError)?;
+
+    Ok(format!("event: suggestion\ndata: {}\n\n", data).into())
+}
+
+// The next event for the stream, or `None` if the list has gone away.
+fn next_event(
+    req: &HttpRequest,
+    store: &Store,
+    query: &ItemQuery,
+) -> Option<error::Result<web::Bytes>> {
+    let catalog = store.current();
+    let (list, things) = catalog.named_list(query.list.as_deref())?;
+
+    let event = match things.choose(None, query.tag.as_deref()) {
+        Some(entry) => suggestion_event(req, query, list, things, entry),
+        None => Ok(web::Bytes::from_static(b": no suggestions\n\n")),
+    };
+
+    Some(event)
+}
+
+#[utoipa::path(
+    get,
+    path = "/stream",
+    tag = "streams",
+    params(StreamQuery),
+    responses(
+        (status = 200, description = "A stream of suggestions", content_type = "text/event-stream"),
+        (status = 404, description = "No such list or tagged suggestion"),
+    ),
+)]
+#[get("/stream")]
+pub(crate) async fn suggestions(
+    _: Limited,
+    req: HttpRequest,
+    store: web::Data<Store>,
+    query: web::Query<StreamQuery>,
+) -> error::Result<HttpResponse> {
+    let found = store
+        .current()
+        .list(query.list.as_deref())
+        .and_then(|things| things.choose(None, query.tag.as_deref()))
+        .is_some();
+    if !found {
+        return Err(view::not_found(&req));
+    }
+
+    let period = query
+        .interval
+        .unwrap_or(DEFAULT_INTERVAL)
+        .clamp(MIN_INTERVAL, MAX_INTERVAL);
+    // The first tick completes immediately, so the first suggestion is sent
+    // as soon as the stream opens.
+    let ticks = time::interval(Duration::from_secs(period));
+    let item_query = query.item_query();
+
+    let events = stream::unfold(ticks, move |mut ticks| {
+        let (req, store, item_query) = (req.clone(), store.clone(), item_query.clone());
+        async move {
+            ticks.tick().await;
+            next_event(&req, &store, &item_query).map(|event| (event, ticks))
+        }
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-store"))
+        .streaming(events))
+}
+
+pub(crate) fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(suggestions);
+}
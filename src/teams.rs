@@ -0,0 +1,212 @@
+//! A Microsoft Teams outgoing webhook, for asking for suggestions from Teams.
+//!
+//! The webhook is off unless the App is given a `Teams` value as app data,
+//! which holds the security token Teams signs its requests with:
+//!
+//! ```
+//! # use things_to_check::{teams, view};
+//! # #[actix_web::main]
+//! # async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+//! use actix_web::{web, App, HttpServer};
+//!
+//! let service = view::make_service()?;
+//! let teams = web::Data::new(teams::Teams::new("czNrcjF0")?);
+//! let app_factory = move ||
+//!     App::new()
+//!         .app_data(teams.clone())
+//!         .configure(|cfg| service(cfg));
+//!
+//! HttpServer::new(app_factory);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Endpoints
+//!
+//! * `/teams/troubleshoot` (`POST`): an outgoing webhook.
+//!
+//!   Point an outgoing webhook's callback URL here. Mentioning the webhook, as
+//!   in `@troubleshoot`, replies with a random suggestion, as an Adaptive Card
+//!   with a link to its permalink. If the rest of the message names a
+//!   suggestion, as for the `item` parameter to `/`, as in `@troubleshoot 42`,
+//!   the reply shows that suggestion instead.
+//!
+//! Requests must carry an `Authorization: HMAC …` header holding the
+//! signature Teams makes of the body with the security token, or are rejected
+//! with `401 Unauthorized`. The endpoint returns `404 Not Found` if the
+//! webhook is off. Every request comes from Teams, on behalf of everyone in
+//! the team, so it isn't rate limited.
+
+use actix_web::{error, post, web, HttpRequest, HttpResponse};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use thiserror::Error;
+
+use crate::things::Store;
+use crate::view::{self, ItemQuery, Urls};
+
+/// A security token was not base64.
+#[derive(Error, Debug)]
+#[error("Invalid Teams security token: expected base64")]
+pub struct InvalidSecurityToken;
+
+/// Enables the Teams webhook, and holds the key Teams signs requests with.
+pub struct Teams {
+    key: Vec<u8>,
+}
+
+impl Teams {
+    /// Enable the Teams webhook, for requests signed with `security_token`,
+    /// shown when the outgoing webhook is created.
+    pub fn new(security_token: &str) -> Result<Self, InvalidSecurityToken> {
+        let key = BASE64
+            .decode(security_token.trim())
+            .map_err(|_| InvalidSecurityToken)?;
+
+        Ok(Teams { key })
+    }
+
+    // Teams signs the body with an HMAC keyed with the decoded security
+    // token, in the Authorization header.
+    fn verify(&self, req: &HttpRequest, body: &[u8]) -> error::Result<()> {
+        let signature = req
+            .headers()
+            .get("Authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("HMAC "))
+            .and_then(|value| BASE64.decode(value).ok());
+
+        match signature {
+            Some(signature) => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(&self.key)
+                    .expect("HMAC accepts keys of any size");
+                mac.update(body);
+                mac.verify_slice(&signature)
+                    .map_err(|_| error::ErrorUnauthorized("Unauthorized"))
+            }
+            None => Err(error::ErrorUnauthorized("Unauthorized")),
+        }
+    }
+}
+
+fn teams(req: &HttpRequest) -> error::Result<&Teams> {
+    req.app_data::<web::Data<Teams>>()
+        .map(|teams| teams.get_ref())
+        .ok_or_else(|| view::not_found(req))
+}
+
+// Mentions arrive as `<at>Name</at>` in a message's text; whatever else it
+// says may name a suggestion.
+fn without_mentions(text: &str) -> String {
+    let mut rest = text;
+    let mut kept = String::new();
+    while let Some((before, after)) = rest.split_once("<at>") {
+        kept.push_str(before);
+        rest = after.split_once("</at>").map_or("", |(_, after)| after);
+    }
+    kept.push_str(rest);
+
+    kept.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[derive(Deserialize)]
+struct Activity {
+    #[serde(default)]
+    text: String,
+}
+
+// A reply showing a suggestion, the one `text` names if it names one, as an
+// Adaptive Card.
+fn reply(req: &HttpRequest, store: &Store, text: &str) -> error::Result<Value> {
+    let catalog = store.current();
+    let chosen = catalog.list(None).and_then(|things| {
+        things
+            .aliased(text)
+            .or_else(|| things.find(text))
+            .or_else(|| things.choose(None, None))
+    });
+    let (_, thing) = match chosen {
+        Some(chosen) => chosen,
+        None => {
+            return Ok(json!({
+                "type": "message",
+                "text": "Sorry, there are no suggestions to show.",
+            }))
+        }
+    };
+
+    let url = req.suggestion(&ItemQuery::default(), thing)?;
+
+    Ok(json!({
+        "type": "message",
+        "attachments": [
+            {
+                "contentType": "application/vnd.microsoft.card.adaptive",
+                "content": {
+                    "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+                    "type": "AdaptiveCard",
+                    "version": "1.4",
+                    "body": [
+                        { "type": "TextBlock", "text": thing.text(), "wrap": true },
+                    ],
+                    "actions": [
+                        { "type": "Action.OpenUrl", "title": "Permalink", "url": url.as_str() },
+                    ],
+                },
+            },
+        ],
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/teams/troubleshoot",
+    tag = "teams",
+    request_body(content = String, description = "A Teams message activity", content_type = "application/json"),
+    responses(
+        (status = 200, description = "A reply, as a JSON activity"),
+        (status = 400, description = "The activity was malformed"),
+        (status = 401, description = "The request's signature was missing or incorrect"),
+        (status = 404, description = "The Teams webhook is off"),
+    ),
+)]
+#[post("/teams/troubleshoot")]
+pub(crate) async fn troubleshoot(
+    req: HttpRequest,
+    store: web::Data<Store>,
+    body: web::Bytes,
+) -> error::Result<HttpResponse> {
+    teams(&req)?.verify(&req, &body)?;
+
+    let activity: Activity = serde_json::from_slice(&body).map_err(error::ErrorBadRequest)?;
+    let reply = reply(&req, &store, &without_mentions(&activity.text))?;
+
+    Ok(HttpResponse::Ok().json(reply))
+}
+
+pub(crate) fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(troubleshoot);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mentions_are_not_asked_for() {
+        assert_eq!(without_mentions("<at>troubleshoot</at> 42\n"), "42");
+        assert_eq!(without_mentions("is it <at>Bot</at>  dns"), "is it dns");
+        assert_eq!(without_mentions("<at>troubleshoot</at>"), "");
+        assert_eq!(without_mentions("<at>unclosed"), "");
+    }
+
+    #[test]
+    fn security_tokens_are_base64() {
+        assert!(Teams::new("czNrcjF0").is_ok());
+        assert!(Teams::new("not base64!").is_err());
+    }
+}
@@ -0,0 +1,224 @@
+//! A Telegram bot webhook, for asking for suggestions from Telegram.
+//!
+//! The webhook is off unless the App is given a `Telegram` value as app data,
+//! which holds the secret Telegram presents with each update:
+//!
+//! ```
+//! # use things_to_check::{telegram, view};
+//! # #[actix_web::main]
+//! # async fn main() -> std::result::Result<(), things_to_check::view::Error> {
+//! use actix_web::{web, App, HttpServer};
+//!
+//! let service = view::make_service()?;
+//! let telegram = web::Data::new(telegram::Telegram::new("s3kr1t"));
+//! let app_factory = move ||
+//!     App::new()
+//!         .app_data(telegram.clone())
+//!         .configure(|cfg| service(cfg));
+//!
+//! HttpServer::new(app_factory);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Endpoints
+//!
+//! * `/telegram/webhook` (`POST`): a bot's webhook.
+//!
+//!   Set the bot's webhook to this URL, with the secret as its `secret_token`,
+//!   using the Bot API's `setWebhook` method. Sending the bot
+//!   `/troubleshoot` replies with a random suggestion, with a link to its
+//!   permalink. Its text is read as for the Slack app's command: a single
+//!   word naming a suggestion, as in `/troubleshoot 42`, replies with that
+//!   suggestion, and other words reply with a random suggestion matching
+//!   them. Other updates are accepted and ignored.
+//!
+//! Requests without the secret in their `X-Telegram-Bot-Api-Secret-Token`
+//! header are rejected with `401 Unauthorized`. Replies are returned in the
+//! response, rather than sent through the Bot API, so the bot's own token is
+//! never needed, and stays out of URLs and logs. The endpoint returns `404 Not Found` if the webhook is off. Every
+//! request comes from Telegram, on behalf of everyone talking to the bot, so
+//! it isn't rate limited.
+
+use actix_web::{error, post, web, HttpRequest, HttpResponse};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::moderation::constant_time_eq;
+use crate::slack::Ask;
+use crate::things::Store;
+use crate::view::{self, ItemQuery, Urls};
+
+/// The command the bot answers.
+const COMMAND: &str = "/troubleshoot";
+
+/// Telegram's header carrying the secret given to `setWebhook`.
+const SECRET_HEADER: &str = "X-Telegram-Bot-Api-Secret-Token";
+
+/// Enables the Telegram webhook, and holds the secret that authenticates it.
+pub struct Telegram {
+    secret: String,
+}
+
+impl Telegram {
+    /// Enable the Telegram webhook, for requests carrying `secret`, the
+    /// `secret_token` the bot's webhook was set with. Telegram allows 1 to 256
+    /// letters, digits, `_`, and `-`.
+    pub fn new(secret: impl Into<String>) -> Self {
+        Telegram {
+            secret: secret.into(),
+        }
+    }
+}
+
+fn telegram(req: &HttpRequest) -> error::Result<&Telegram> {
+    req.app_data::<web::Data<Telegram>>()
+        .map(|telegram| telegram.get_ref())
+        .ok_or_else(|| view::not_found(req))
+}
+
+#[derive(Deserialize)]
+struct Update {
+    message: Option<Message>,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    chat: Chat,
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct Chat {
+    id: i64,
+}
+
+// What a message asks of the bot, if it's a command: the rest of its text. In
+// groups, commands may name the bot they're for, as in `/troubleshoot@bot`.
+fn asked(text: &str) -> Option<&str> {
+    let text = text.trim_start();
+    let (command, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    let command = command
+        .split_once('@')
+        .map_or(command, |(command, _)| command);
+
+    (command == COMMAND).then(|| rest.trim())
+}
+
+// Text escaped for Telegram's MarkdownV2, which reserves these characters
+// everywhere outside code and links.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "_*[]()~`>#+-=|{}.!\\".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// A link's URL escaped for MarkdownV2, which reserves fewer characters there.
+fn escape_url(url: &str) -> String {
+    url.replace('\\', "\\\\").replace(')', "\\)")
+}
+
+// The text of a reply showing a suggestion chosen by the command's `text`.
+fn reply(req: &HttpRequest, store: &Store, text: &str) -> error::Result<String> {
+    let catalog = store.current();
+    let things = catalog.list(None);
+    let ask = Ask::from_text(things, text);
+    let (_, thing) = match things.and_then(|things| ask.choose(things)) {
+        Some(chosen) => chosen,
+        None => return Ok(escape("Sorry, there are no suggestions to show.")),
+    };
+
+    let url = req.suggestion(&ItemQuery::default(), thing)?;
+
+    Ok(format!(
+        "{}\n\n[Permalink]({})",
+        escape(&thing.text()),
+        escape_url(url.as_str())
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/telegram/webhook",
+    tag = "telegram",
+    params(("X-Telegram-Bot-Api-Secret-Token" = String, Header, description = "The webhook's secret token")),
+    request_body(content = String, description = "A Telegram update", content_type = "application/json"),
+    responses(
+        (status = 200, description = "A `sendMessage` call answering the update, as JSON, or nothing"),
+        (status = 400, description = "The update was malformed"),
+        (status = 401, description = "The secret token was missing or incorrect"),
+        (status = 404, description = "The Telegram webhook is off"),
+    ),
+)]
+#[post("/telegram/webhook")]
+pub(crate) async fn webhook(
+    req: HttpRequest,
+    store: web::Data<Store>,
+    update: web::Json<Update>,
+) -> error::Result<HttpResponse> {
+    let telegram = telegram(&req)?;
+    let secret = req
+        .headers()
+        .get(SECRET_HEADER)
+        .map_or(&b""[..], |value| value.as_bytes());
+    if !constant_time_eq(secret, telegram.secret.as_bytes()) {
+        return Err(error::ErrorUnauthorized("Unauthorized"));
+    }
+
+    let message = match &update.message {
+        Some(message) => message,
+        None => return Ok(HttpResponse::Ok().finish()),
+    };
+    let text = match asked(&message.text) {
+        Some(text) => text,
+        None => return Ok(HttpResponse::Ok().finish()),
+    };
+
+    // Telegram makes a Bot API call returned in the response to a webhook, so
+    // replying takes no request of our own.
+    let reply: Value = json!({
+        "method": "sendMessage",
+        "chat_id": message.chat.id,
+        "text": reply(&req, &store, text)?,
+        "parse_mode": "MarkdownV2",
+    });
+
+    Ok(HttpResponse::Ok().json(reply))
+}
+
+pub(crate) fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(webhook);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_may_name_the_bot() {
+        assert_eq!(asked("/troubleshoot"), Some(""));
+        assert_eq!(asked("/troubleshoot 42"), Some("42"));
+        assert_eq!(
+            asked("/troubleshoot@things_bot is it dns\n"),
+            Some("is it dns")
+        );
+        assert_eq!(asked("/troubleshooting"), None);
+        assert_eq!(asked("have you tried /troubleshoot"), None);
+    }
+
+    #[test]
+    fn markdown_is_escaped() {
+        assert_eq!(escape("Is it DNS?"), "Is it DNS?");
+        assert_eq!(escape("1.1.1.1 (maybe)!"), "1\\.1\\.1\\.1 \\(maybe\\)\\!");
+        assert_eq!(
+            escape_url("https://example.com/?item=a)"),
+            "https://example.com/?item=a\\)"
+        );
+    }
+}
@@ -0,0 +1,239 @@
+//! Page templates supplied at runtime, overriding the built-in ones.
+//!
+//! The HTML pages are rendered from templates compiled into the service. If
+//! the App is given a `Templates` value as app data, read from a directory,
+//! the templates in that directory are used instead, so that a deployment can
+//! change how its pages look without rebuilding the crate:
+//!
+//! ```no_run
+//! # use things_to_check::{templates, view};
+//! # #[actix_web::main]
+//! # async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+//! use actix_web::{web, App, HttpServer};
+//!
+//! let service = view::make_service()?;
+//! let templates = web::Data::new(templates::Templates::open("/etc/things-to-check/templates")?);
+//! let app_factory = move ||
+//!     App::new()
+//!         .app_data(templates.clone())
+//!         .configure(|cfg| service(cfg));
+//!
+//! HttpServer::new(app_factory);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Each page whose template is missing from the directory keeps its built-in
+//! template. Overrides are [Jinja][1]-style templates, and may `include` or
+//! `extend` other templates in the same directory. Their output is
+//! HTML-escaped, so mark HTML that's meant to be rendered with the `safe`
+//! filter. The pages, and what their templates are given, are:
+//!
+//! * `index.html`, for `/`:
+//!
+//!   * `suggestion`: the suggestion being shown, as an object with the same
+//!     fields as `/api/v1/suggestion` returns. Its `html` renders the
+//!     suggestion, and its `url` is its permalink.
+//!   * `related`: up to three suggestions related to it, in the same format.
+//!   * `query`: the page's `item`, `list`, `tag`, and `lang` parameters, each
+//!     possibly `none`.
+//!   * `locale`: the language the built-in page's fixed text would be in,
+//!     such as `fr`: the `lang` parameter's, if the service has text in that
+//!     language, or else the best match for the `Accept-Language` header.
+//!   * `theme`: how the pages are branded, from the `theme` module.
+//!   * `urls`: an object holding the URLs of the page for `another` random
+//!     suggestion, the `search` page, the page listing `all` the
+//!     suggestions, the suggestion's `og_image`, and its `oembed`
+//!     description, `shares`, a list of links sharing the
+//!     suggestion elsewhere, each with a `name` and a `url`, and `refresh`,
+//!     which for pages asked to refresh holds the `seconds` to wait and the
+//!     `url` to move on to, and is otherwise `none`. It also holds the URLs
+//!     of the built-in page's `stylesheet` and `script`, for overrides that
+//!     only change the page's markup.
+//!
+//! * `search.html`, for `/search`:
+//!
+//!   * `q`: the words searched for.
+//!   * `lang`: the language of the page.
+//!   * `query`: the page's `list` and `lang` parameters, each possibly `none`.
+//!   * `results`: the suggestions found, in the same format as `suggestion`
+//!     above.
+//!   * `locale`: the language of the page's fixed text, as above.
+//!   * `theme`: how the pages are branded.
+//!   * `urls`: an object holding the URLs of the page for `another` random
+//!     suggestion, of the `search` page, of the page listing `all` the
+//!     suggestions, and of the built-in page's `stylesheet`.
+//!
+//! * `all.html`, for `/all` and `/category/{name}`:
+//!
+//!   * `lang`: the language of the page.
+//!   * `query`: the page's `list`, `tag`, and `lang` parameters, each possibly
+//!     `none`. On a category's page, `tag` is the category.
+//!   * `category`: the category whose suggestions are listed, or `none` on
+//!     `/all`.
+//!   * `suggestions`: every suggestion in the list, in order, in the same
+//!     format as `suggestion` above.
+//!   * `locale`: the language of the page's fixed text, as above.
+//!   * `theme`: how the pages are branded.
+//!   * `urls`: an object holding the URLs of the page for `another` random
+//!     suggestion, of the `search` page, of the `categories` page, and of the
+//!     built-in page's `stylesheet` and `script`.
+//!
+//! * `categories.html`, for `/categories`:
+//!
+//!   * `lang`: the language of the page.
+//!   * `query`: the page's `list` and `lang` parameters, each possibly `none`.
+//!   * `categories`: the list's categories, in order by name, each with its
+//!     `name`, the `count` of suggestions in it, and the `url` of its page.
+//!   * `locale`: the language of the page's fixed text, as above.
+//!   * `theme`: how the pages are branded.
+//!   * `urls`: an object holding the URLs of the page for `another` random
+//!     suggestion, of the page listing `all` the suggestions, and of the
+//!     built-in page's `stylesheet`.
+//!
+//! * `error.html`, for `404 Not Found` responses outside the API, such as for
+//!   unknown lists and suggestions:
+//!
+//!   * `status`: the response's status code, `404`.
+//!   * `request_id`: the request's ID, or `none` if requests have no IDs; see
+//!     the `request_id` module.
+//!   * `locale`: the language of the page's fixed text, as above.
+//!   * `theme`: how the pages are branded.
+//!   * `urls`: an object holding the URLs of the page for `another` random
+//!     suggestion, and of the built-in page's `stylesheet`.
+//!
+//! * `admin.html`, for the operators' dashboard at `/admin`; see the `admin`
+//!   module:
+//!
+//!   * `source`: where the suggestions come from.
+//!   * `failure`: why the last attempt to refresh them failed, or `none`.
+//!   * `loaded`: when they were last loaded, as an HTTP date.
+//!   * `lists`: each list's `name`, and its numbers of `live` and `retired`
+//!     suggestions.
+//!   * `pending`: the number of submissions awaiting moderation.
+//!   * `most_served`: the most-served suggestions, most-served first, each
+//!     with its `list`, its `item`, its `text`, or `none` if it's no longer
+//!     listed, and how often it was served on the `web` and in `slack`; or
+//!     `none` if suggestions aren't counted.
+//!   * `errors`: the last few server errors, newest first, each with the
+//!     time it happened `at`, its `request_id`, its `request`, and its
+//!     `message`.
+//!   * `locale`: the language of the page's fixed text, as above.
+//!   * `theme`: how the pages are branded.
+//!   * `urls`: an object holding the URL of the built-in page's `stylesheet`.
+//!
+//! Templates are read once, when they're opened; restart the service to pick
+//! up changes. Mistakes in a page's template are reported by
+//! `Templates::open`, and errors rendering one return `500 Internal Server
+//! Error`.
+//!
+//! [1]: https://jinja.palletsprojects.com/en/stable/templates/
+
+use actix_web::{error, web, HttpRequest};
+use askama::Template;
+use minijinja::{path_loader, Environment};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use crate::request_id;
+
+/// The templates a directory can override.
+const PAGES: [&str; 6] = [
+    "index.html",
+    "search.html",
+    "all.html",
+    "categories.html",
+    "error.html",
+    "admin.html",
+];
+
+/// Errors that can arise opening a directory of templates.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// Indicates that the directory could not be read.
+    #[error("Unable to read templates from {}: {source}", path.display())]
+    ReadError { path: PathBuf, source: io::Error },
+    /// Indicates that a template in the directory was invalid.
+    #[error("Invalid template: {0}")]
+    TemplateError(#[from] minijinja::Error),
+}
+
+/// Overrides the built-in page templates with those in a directory.
+pub struct Templates {
+    env: Environment<'static>,
+    overridden: BTreeSet<&'static str>,
+}
+
+impl Templates {
+    /// Read templates from the directory at `dir`.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, Error> {
+        let dir = dir.as_ref();
+        let names = fs::read_dir(dir)
+            .and_then(|entries| {
+                entries
+                    .map(|entry| Ok(entry?.file_name()))
+                    .collect::<io::Result<Vec<_>>>()
+            })
+            .map_err(|source| Error::ReadError {
+                path: dir.to_owned(),
+                source,
+            })?;
+
+        let mut env = Environment::new();
+        env.set_loader(path_loader(dir));
+
+        let mut overridden = BTreeSet::new();
+        for page in PAGES {
+            if names.iter().any(|name| name == page) {
+                // Load each override now, so that mistakes show up at startup
+                // rather than on the first request.
+                env.get_template(page)?;
+                overridden.insert(page);
+            }
+        }
+
+        Ok(Templates { env, overridden })
+    }
+}
+
+/// Render the page `name` with `built_in`, its built-in template, or with the
+/// override the App was given for it, if any. `context` makes what the
+/// override is given; it's only called for overrides.
+pub(crate) fn render<T, C>(
+    req: &HttpRequest,
+    name: &str,
+    built_in: &T,
+    context: impl FnOnce() -> error::Result<C>,
+) -> error::Result<String>
+where
+    T: Template,
+    C: Serialize,
+{
+    let templates = req
+        .app_data::<web::Data<Templates>>()
+        .filter(|templates| templates.overridden.contains(name));
+
+    match templates {
+        Some(templates) => {
+            let context = context()?;
+            templates
+                .env
+                .get_template(name)
+                .and_then(|template| template.render(context))
+                .map_err(|err| {
+                    eprintln!(
+                        "{}Unable to render template {}: {}",
+                        request_id::prefix(req),
+                        name,
+                        err
+                    );
+                    error::ErrorInternalServerError(err)
+                })
+        }
+        None => built_in.render().map_err(error::ErrorInternalServerError),
+    }
+}
@@ -0,0 +1,98 @@
+//! Branding for the HTML pages.
+//!
+//! An organization running its own instance can give the pages its name,
+//! colors, and logo, through `view::Options`:
+//!
+//! ```
+//! # use things_to_check::{theme, view};
+//! # fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+//! let options = view::Options {
+//!     theme: theme::Theme {
+//!         title: Some("Example Corp troubleshooting".to_owned()),
+//!         accent: Some("#c0392b".to_owned()),
+//!         footer: Some("Ask in #ops if you're stuck.".to_owned()),
+//!         ..Default::default()
+//!     },
+//!     ..Default::default()
+//! };
+//! let service = view::make_service_with(&options)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Anything left unset keeps the pages' usual look. Templates overriding the
+//! pages, from the `templates` module, are given the theme as `theme`, with
+//! the same fields, and the `config` module reads it from the `[theme]`
+//! section of a configuration file, again with the same fields.
+
+use serde::{de, Deserialize, Deserializer, Serialize};
+use thiserror::Error;
+use url::Url;
+
+/// How the HTML pages are branded.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    /// The site's name, shown atop each page and in page titles.
+    pub title: Option<String>,
+    /// The color of links, as a CSS color such as `#c0392b`.
+    #[serde(deserialize_with = "deserialize_color")]
+    pub accent: Option<String>,
+    /// The color of links in the dark color scheme, as a CSS color. Defaults
+    /// to the usual dark scheme's links, rather than to `accent`, which may
+    /// not stand out against a dark background.
+    #[serde(deserialize_with = "deserialize_color")]
+    pub dark_accent: Option<String>,
+    /// The address of an image to show atop each page, beside the title.
+    pub logo: Option<Url>,
+    /// The address of an image to use as the site's icon, in browser tabs and
+    /// bookmarks, in place of the built-in icon. See the `pwa` module.
+    pub icon: Option<Url>,
+    /// Text to show at the foot of each page, such as who to ask for help.
+    pub footer: Option<String>,
+}
+
+/// Indicates that an accent color was not a plain CSS color.
+#[derive(Error, Debug)]
+#[error("Not a CSS color: {0:?}")]
+pub struct InvalidColor(pub String);
+
+/// Check that `color` is a CSS color, such as `#c0392b`, `teal`, or
+/// `rgb(192 57 43)`, that can be placed in a stylesheet as it is. Colors are
+/// only checked for characters that could escape the stylesheet, not for
+/// being colors CSS knows.
+pub fn color(color: &str) -> Result<String, InvalidColor> {
+    let plain = !color.is_empty()
+        && color
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "#(),.% -".contains(c));
+
+    if plain {
+        Ok(color.to_owned())
+    } else {
+        Err(InvalidColor(color.to_owned()))
+    }
+}
+
+// A color, checked as `color` checks it, if one is given.
+fn deserialize_color<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|given| color(&given).map_err(de::Error::custom))
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_stay_in_stylesheets() {
+        assert_eq!(color("#c0392b").unwrap(), "#c0392b");
+        assert_eq!(color("rgb(192, 57, 43)").unwrap(), "rgb(192, 57, 43)");
+        assert!(color("red; } body { display: none").is_err());
+        assert!(color("</style>").is_err());
+        assert!(color("").is_err());
+    }
+}
@@ -0,0 +1,2169 @@
+//! The suggestions themselves, and the machinery for loading them.
+//!
+//! Suggestions are stored as a YAML list of Markdown strings. Each one is
+//! rendered to HTML once, when it's loaded, and kept alongside its index in the
+//! list, which serves as its permalink. Besides CommonMark, the Markdown may
+//! use strikethrough, tables, footnotes, and smart punctuation, unless
+//! `view::Options::markdown` turns them off. Fenced code blocks are
+//! highlighted; see the `highlight` module for the languages it knows. Emoji
+//! shortcodes, such as `:bulb:`, are replaced with the emoji they name, as
+//! chat services do. Each is also given a slug made from its text, such as
+//! `have-you-checked-dns`, for a permalink people can read.
+//!
+//! Files on disk may also be written in TOML or JSON, with the same structure,
+//! and are recognized by their extension (`.toml` or `.json`; anything else is
+//! read as YAML). TOML files can't hold a bare list, so they must use named
+//! lists.
+//!
+//! A file can also hold several named lists, as a YAML mapping from list names
+//! to lists. Each list is numbered independently, and the first list in the
+//! file is served when no list is requested. A file holding a single list is
+//! treated as though that list were named `general`.
+//!
+//! Each entry in a list is either a Markdown string, or a mapping with the
+//! Markdown under `text` and any of the following optional fields:
+//!
+//! * `id`: a stable, non-numeric name for the entry. Permalinks to entries
+//!   with an `id` use it instead of the entry's index, so that they survive the
+//!   list being reordered. IDs must be unique within a list.
+//!
+//! * `tags`: a list of short labels, such as `dns` or `hardware`, used to
+//!   filter suggestions. Pages link to up to three related suggestions, those
+//!   sharing the most tags, or failing that the most distinctive words.
+//!
+//! * `author`: who suggested the entry.
+//!
+//! * `added`: when the entry was added, as a date (`YYYY-MM-DD`).
+//!
+//! * `links`: a list of URLs with further reading.
+//!
+//! * `context`: the kind of system the entry applies to, such as `network`,
+//!   `database`, `ci`, or `humans`.
+//!
+//! * `translations`: a mapping from language codes, such as `fr` or `pt-BR`,
+//!   to translations of the entry's Markdown. The entry's `text` is assumed to
+//!   be in English.
+//!
+//! * `retired`: if `true`, the entry is never chosen at random, but links to it
+//!   still work. Retire entries instead of removing them.
+
+use indexmap::IndexMap;
+use pulldown_cmark::{html, CodeBlockKind, Event, Options, Parser, Tag};
+use rand::seq::SliceRandom;
+use rand::thread_rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::SystemTime;
+use url::Url;
+use utoipa::ToSchema;
+
+use crate::emoji::emojify;
+use crate::highlight;
+use crate::view::{Error, Markdown, ParseError, Problem, ProblemKind, Strictness};
+
+pub(crate) const THINGS: &str = include_str!("things-to-check.yml");
+
+/// The longest a thing's Markdown may be, in characters.
+pub(crate) const MAX_LENGTH: usize = 500;
+
+/// The index of the first thing approved by moderators in each list. Sources
+/// number their things from zero, so approved things are numbered from here to
+/// keep a source that grows from handing their indices to other things.
+pub(crate) const FIRST_APPROVED_INDEX: usize = 1_000_000;
+
+/// The language of things' untranslated text.
+pub(crate) const DEFAULT_LANG: &str = "en";
+
+// The parser options enabling the extensions in `extensions`.
+fn options(extensions: Markdown) -> Options {
+    let mut options = Options::empty();
+    options.set(Options::ENABLE_STRIKETHROUGH, extensions.strikethrough);
+    options.set(Options::ENABLE_TABLES, extensions.tables);
+    options.set(Options::ENABLE_FOOTNOTES, extensions.footnotes);
+    options.set(
+        Options::ENABLE_SMART_PUNCTUATION,
+        extensions.smart_punctuation,
+    );
+    options
+}
+
+// The parser options for reading Markdown rather than rendering it: every
+// extension, so that their syntax isn't mistaken for text, but punctuation as
+// it was written.
+fn reading_options() -> Options {
+    options(Markdown {
+        smart_punctuation: false,
+        ..Markdown::default()
+    })
+}
+
+fn render(markdown: &str, extensions: Markdown) -> String {
+    let parser = Parser::new_ext(markdown, options(extensions));
+
+    let mut html = String::new();
+    html::push_html(&mut html, highlighted(parser));
+
+    html
+}
+
+// `events`, with fenced code blocks in languages `highlight` knows replaced
+// by their highlighted HTML.
+fn highlighted<'a>(events: impl Iterator<Item = Event<'a>>) -> impl Iterator<Item = Event<'a>> {
+    let mut block: Option<(String, String)> = None;
+    events.flat_map(move |event| {
+        let lang = match &event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) => {
+                info.split_whitespace().next().map(str::to_owned)
+            }
+            _ => None,
+        };
+        let replaced = match (&mut block, event) {
+            (None, event) => match lang {
+                Some(lang) => {
+                    block = Some((lang, String::new()));
+                    vec![]
+                }
+                None => vec![event],
+            },
+            (Some((_, code)), Event::Text(text)) => {
+                code.push_str(&text);
+                vec![]
+            }
+            (Some(_), Event::End(Tag::CodeBlock(kind))) => {
+                let (lang, code) = block.take().expect("a code block is open");
+                match highlight::highlight(&lang, &code) {
+                    Some(html) => vec![Event::Html(
+                        format!(
+                            "<pre><code class=\"language-{}\">{}</code></pre>\n",
+                            escape_html_attribute(&lang),
+                            html
+                        )
+                        .into(),
+                    )],
+                    None => vec![
+                        Event::Start(Tag::CodeBlock(kind.clone())),
+                        Event::Text(code.into()),
+                        Event::End(Tag::CodeBlock(kind)),
+                    ],
+                }
+            }
+            // Code blocks hold nothing but text.
+            (Some(_), event) => vec![event],
+        };
+        replaced
+    })
+}
+
+fn escape_html_attribute(text: &str) -> String {
+    let mut escaped = String::new();
+    // Writing to a String never fails.
+    let _ = pulldown_cmark::escape::escape_html(&mut escaped, text);
+    escaped
+}
+
+// The text of `markdown`, without formatting.
+fn plain(markdown: &str) -> String {
+    Parser::new_ext(markdown, reading_options())
+        .filter_map(|event| match event {
+            Event::Text(text) | Event::Code(text) => Some(text.into_string()),
+            Event::SoftBreak | Event::HardBreak => Some(" ".to_owned()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[derive(Clone)]
+pub(crate) struct Thing {
+    pub(crate) id: Option<String>,
+    pub(crate) markdown: String,
+    pub(crate) html: String,
+    pub(crate) tags: Vec<String>,
+    pub(crate) author: Option<String>,
+    pub(crate) added: Option<String>,
+    pub(crate) links: Vec<String>,
+    pub(crate) context: Option<String>,
+    pub(crate) retired: bool,
+    pub(crate) aliases: Vec<String>,
+    pub(crate) lang: String,
+    pub(crate) translations: BTreeMap<String, Rendered>,
+    pub(crate) details: Option<Rendered>,
+    /// The indices of the things in the same list most like this one, best
+    /// first. Worked out when the list is stored; see `Things::relate`.
+    pub(crate) related: Vec<usize>,
+    /// The name of this thing in its readable permalink, unique within its
+    /// list. Made unique when the list is stored; see `Things::slug`.
+    pub(crate) slug: String,
+}
+
+/// Markdown, and the HTML it renders to.
+#[derive(Clone, Serialize, ToSchema)]
+pub(crate) struct Rendered {
+    pub(crate) markdown: String,
+    pub(crate) html: String,
+}
+
+impl From<String> for Rendered {
+    fn from(markdown: String) -> Self {
+        let html = render(&markdown, Markdown::default());
+        Rendered { markdown, html }
+    }
+}
+
+impl Rendered {
+    fn render(&mut self, extensions: Markdown) {
+        self.html = render(&self.markdown, extensions);
+    }
+}
+
+impl Thing {
+    /// This thing, with its text in the language `lang` if there's a
+    /// translation for it, and in English otherwise.
+    ///
+    /// A regional language such as `fr-CA` falls back to the general language
+    /// (`fr`) before falling back to English.
+    pub(crate) fn translated(&self, lang: Option<&str>) -> Thing {
+        let translation = lang.and_then(|lang| {
+            self.translations.get_key_value(lang).or_else(|| {
+                let (general, _) = lang.split_once('-')?;
+                self.translations.get_key_value(general)
+            })
+        });
+
+        match translation {
+            Some((lang, translation)) => Thing {
+                markdown: translation.markdown.clone(),
+                html: translation.html.clone(),
+                lang: lang.clone(),
+                ..self.clone()
+            },
+            None => self.clone(),
+        }
+    }
+
+    /// Render this thing's Markdown again, with only the extensions in
+    /// `extensions`.
+    pub(crate) fn render(&mut self, extensions: Markdown) {
+        self.html = render(&self.markdown, extensions);
+        for translation in self.translations.values_mut() {
+            translation.render(extensions);
+        }
+        if let Some(details) = &mut self.details {
+            details.render(extensions);
+        }
+    }
+
+    /// Whether this thing links to, or embeds, anything but a web page, such
+    /// as a `javascript:` URL, or a relative URL that could mean anything.
+    pub(crate) fn links_elsewhere(&self) -> bool {
+        Parser::new_ext(&self.markdown, reading_options()).any(|event| match event {
+            Event::Start(Tag::Link(_, url, _) | Tag::Image(_, url, _)) => {
+                !matches!(Url::parse(&url), Ok(url) if matches!(url.scheme(), "http" | "https"))
+            }
+            _ => false,
+        })
+    }
+
+    /// Find problems with this thing on its own: Markdown that renders to
+    /// nothing, that contains raw HTML, or that is too long.
+    pub(crate) fn problems(&self) -> Vec<ProblemKind> {
+        let mut problems = vec![];
+        if self.html.trim().is_empty() {
+            problems.push(ProblemKind::Empty);
+        }
+        if Parser::new_ext(&self.markdown, reading_options())
+            .any(|event| matches!(event, Event::Html(_)))
+        {
+            problems.push(ProblemKind::RawHtml);
+        }
+        let length = self.markdown.chars().count();
+        if length > MAX_LENGTH {
+            problems.push(ProblemKind::TooLong { length });
+        }
+
+        problems
+    }
+
+    /// The value of the `item` parameter that refers to this thing, given its
+    /// index: its ID, if it has one, or the index otherwise.
+    pub(crate) fn item(&self, index: usize) -> String {
+        match &self.id {
+            Some(id) => id.clone(),
+            None => index.to_string(),
+        }
+    }
+
+    /// This thing's text, without Markdown formatting, for places that can't
+    /// show HTML.
+    pub(crate) fn text(&self) -> String {
+        plain(&self.markdown)
+    }
+
+    /// The date this thing was added, if it gives one in the `YYYY-MM-DD`
+    /// format.
+    pub(crate) fn date(&self) -> Option<&str> {
+        let added = self.added.as_deref()?;
+        let well_formed = added.len() == 10
+            && added.char_indices().all(|(i, c)| match i {
+                4 | 7 => c == '-',
+                _ => c.is_ascii_digit(),
+            });
+
+        Some(added).filter(|_| well_formed)
+    }
+
+    /// True if this thing carries `tag`, or if there is no tag to look for.
+    pub(crate) fn has_tag(&self, tag: Option<&str>) -> bool {
+        match tag {
+            Some(tag) => self.tags.iter().any(|t| t == tag),
+            None => true,
+        }
+    }
+}
+
+impl From<String> for Thing {
+    fn from(markdown: String) -> Self {
+        Thing::from(RawThing::Text(markdown))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawThing {
+    Text(String),
+    Detailed(Box<RawDetails>),
+}
+
+#[derive(Deserialize, Default)]
+struct RawDetails {
+    text: String,
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    author: Option<String>,
+    #[serde(default)]
+    added: Option<String>,
+    #[serde(default)]
+    links: Vec<String>,
+    #[serde(default)]
+    context: Option<String>,
+    #[serde(default)]
+    retired: bool,
+    #[serde(default)]
+    aliases: Vec<RawItem>,
+    #[serde(default)]
+    translations: BTreeMap<String, String>,
+    #[serde(default)]
+    details: Option<String>,
+}
+
+// Aliases are most often old indices, which data files will naturally write as
+// numbers rather than strings.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawItem {
+    Index(usize),
+    Id(String),
+}
+
+impl From<RawItem> for String {
+    fn from(raw: RawItem) -> Self {
+        match raw {
+            RawItem::Index(index) => index.to_string(),
+            RawItem::Id(id) => id,
+        }
+    }
+}
+
+impl From<RawThing> for Thing {
+    fn from(raw: RawThing) -> Self {
+        let RawDetails {
+            text,
+            id,
+            tags,
+            author,
+            added,
+            links,
+            context,
+            retired,
+            aliases,
+            translations,
+            details,
+        } = match raw {
+            RawThing::Text(text) => RawDetails {
+                text,
+                ..RawDetails::default()
+            },
+            RawThing::Detailed(details) => *details,
+        };
+        let markdown = emojify(&text);
+        let html = render(&markdown, Markdown::default());
+        let slug = slugify(&markdown);
+        let translations = translations
+            .into_iter()
+            .map(|(lang, markdown)| (lang, Rendered::from(emojify(&markdown))))
+            .collect();
+
+        Thing {
+            id,
+            markdown,
+            html,
+            tags,
+            author,
+            added,
+            links,
+            context,
+            retired,
+            aliases: aliases.into_iter().map(String::from).collect(),
+            lang: DEFAULT_LANG.to_owned(),
+            translations,
+            details: details.map(|details| Rendered::from(emojify(&details))),
+            related: vec![],
+            slug,
+        }
+    }
+}
+
+/// The most related things kept for each thing.
+const MAX_RELATED: usize = 3;
+
+/// Words too common in English to relate things, even in lists where few
+/// things use them.
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "at", "be", "by", "for", "from", "if", "in", "into", "is", "it",
+    "its", "not", "of", "on", "or", "out", "re", "s", "t", "that", "the", "this", "to", "was",
+    "which", "with", "you", "your",
+];
+
+/// How much two things' shared words must weigh for them to be related
+/// without sharing a tag. One word shared by fewer than about a third of a
+/// list's things is enough.
+const RELATED_THRESHOLD: f64 = 1.0;
+
+/// The longest a slug made from a thing's text may be, in characters. Longer
+/// texts are cut at the last whole word that fits.
+const MAX_SLUG_LENGTH: usize = 60;
+
+/// A thing's place in its list, counting from 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Position {
+    pub(crate) number: usize,
+    pub(crate) total: usize,
+}
+
+#[derive(Clone)]
+pub(crate) struct Things(pub(crate) Vec<(usize, Thing)>);
+
+impl Things {
+    /// Find a thing by the value of an `item` parameter: by ID, if any thing
+    /// has that ID, or by index otherwise.
+    pub(crate) fn find(&self, item: &str) -> Option<&(usize, Thing)> {
+        self.0
+            .iter()
+            .find(|(_, thing)| thing.id.as_deref() == Some(item))
+            .or_else(|| {
+                // Things are kept in order by index, but excluded things leave
+                // gaps, so an index isn't necessarily a position.
+                let index = item.parse::<usize>().ok()?;
+                self.0
+                    .binary_search_by_key(&index, |(index, _)| *index)
+                    .ok()
+                    .map(|position| &self.0[position])
+            })
+    }
+
+    /// Choose the thing requested by an `item` parameter, or a random thing
+    /// that hasn't been retired if there's no `item`, among the things
+    /// carrying `tag`.
+    pub(crate) fn choose(&self, item: Option<&str>, tag: Option<&str>) -> Option<&(usize, Thing)> {
+        match item {
+            Some(item) => self.find(item).filter(|(_, thing)| thing.has_tag(tag)),
+            None => self.sample(1, tag).pop(),
+        }
+    }
+
+    /// Choose up to `count` different random things that haven't been
+    /// retired, among the things carrying `tag`, in random order.
+    pub(crate) fn sample(&self, count: usize, tag: Option<&str>) -> Vec<&(usize, Thing)> {
+        self.0
+            .iter()
+            .filter(|(_, thing)| !thing.retired && thing.has_tag(tag))
+            .collect::<Vec<_>>()
+            .choose_multiple(&mut thread_rng(), count)
+            .copied()
+            .collect()
+    }
+
+    /// Find the things that haven't been retired matching the search `terms`,
+    /// best matches first.
+    ///
+    /// Every word in `terms` must match a word in a thing's text or details,
+    /// ignoring case. A word matches if it appears within a word of the text,
+    /// or, for words of four or more letters, if it's one typo away from a
+    /// word of the text; the former ranks higher.
+    pub(crate) fn search(&self, terms: &str) -> Vec<&(usize, Thing)> {
+        let terms: Vec<_> = words(terms).collect();
+        if terms.is_empty() {
+            return vec![];
+        }
+
+        let mut matches: Vec<_> = self
+            .0
+            .iter()
+            .filter(|(_, thing)| !thing.retired)
+            .filter_map(|entry| {
+                let (_, thing) = entry;
+                let text = match &thing.details {
+                    Some(details) => format!("{} {}", thing.markdown, details.markdown),
+                    None => thing.markdown.clone(),
+                };
+                let text: Vec<_> = words(&text).collect();
+
+                terms
+                    .iter()
+                    .map(|term| text.iter().map(|word| score(term, word)).max())
+                    .try_fold(0, |total, score| match score {
+                        Some(score) if score > 0 => Some(total + score),
+                        _ => None,
+                    })
+                    .map(|score| (score, entry))
+            })
+            .collect();
+        // Stable, so equally good matches stay in index order.
+        matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+
+        matches.into_iter().map(|(_, entry)| entry).collect()
+    }
+
+    /// The things carrying `tag` that haven't been retired, most recently
+    /// added first: by date for things with one, and then by index, as new
+    /// things go at the end.
+    pub(crate) fn newest(&self, tag: Option<&str>) -> Vec<&(usize, Thing)> {
+        let mut newest: Vec<_> = self
+            .0
+            .iter()
+            .filter(|(_, thing)| !thing.retired && thing.has_tag(tag))
+            .collect();
+        newest.sort_by(|(a_index, a), (b_index, b)| (b.date(), b_index).cmp(&(a.date(), a_index)));
+
+        newest
+    }
+
+    /// Find the thing that an `item` parameter used to refer to, if some thing
+    /// lists it among its aliases.
+    pub(crate) fn aliased(&self, item: &str) -> Option<&(usize, Thing)> {
+        self.0
+            .iter()
+            .find(|(_, thing)| thing.aliases.iter().any(|alias| alias == item))
+    }
+
+    /// Every tag carried by a thing, in order by name, with the number of
+    /// things carrying it, retired things included.
+    pub(crate) fn tags(&self) -> Vec<(&str, usize)> {
+        let mut tags = BTreeMap::new();
+        for tag in self.0.iter().flat_map(|(_, thing)| &thing.tags) {
+            *tags.entry(tag.as_str()).or_insert(0) += 1;
+        }
+
+        tags.into_iter().collect()
+    }
+
+    /// Find the thing whose slug is `slug`.
+    pub(crate) fn slugged(&self, slug: &str) -> Option<&(usize, Thing)> {
+        self.0.iter().find(|(_, thing)| thing.slug == slug)
+    }
+
+    /// The things related to `thing` that haven't been retired, best first.
+    pub(crate) fn related(&self, thing: &Thing) -> Vec<&(usize, Thing)> {
+        thing
+            .related
+            .iter()
+            .filter_map(|index| self.find(&index.to_string()))
+            .filter(|(_, related)| !related.retired)
+            .collect()
+    }
+
+    /// Work out which things are related to each thing, for `related`: those
+    /// sharing the most tags, and then the most distinctive words. Words
+    /// that most things share, such as "checked", count for little, and
+    /// `STOP_WORDS` for nothing.
+    fn relate(&mut self) {
+        let vocabularies: Vec<HashSet<String>> = self
+            .0
+            .iter()
+            .map(|(_, thing)| {
+                words(&thing.markdown)
+                    .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+                    .collect()
+            })
+            .collect();
+        let mut frequencies: HashMap<&str, usize> = HashMap::new();
+        for word in vocabularies.iter().flatten() {
+            *frequencies.entry(word).or_default() += 1;
+        }
+        let count = self.0.len() as f64;
+        let weight = |word: &str| (count / frequencies[word] as f64).ln();
+
+        let related: Vec<Vec<usize>> = self
+            .0
+            .iter()
+            .zip(&vocabularies)
+            .map(|((index, thing), vocabulary)| {
+                let mut scores: Vec<_> = self
+                    .0
+                    .iter()
+                    .zip(&vocabularies)
+                    .filter(|((other, candidate), _)| other != index && !candidate.retired)
+                    .map(|((other, candidate), candidate_vocabulary)| {
+                        let tags = thing
+                            .tags
+                            .iter()
+                            .filter(|tag| candidate.tags.contains(tag))
+                            .count();
+                        let words: f64 = vocabulary
+                            .intersection(candidate_vocabulary)
+                            .map(|word| weight(word))
+                            .sum();
+                        (tags, words, *other)
+                    })
+                    .filter(|&(tags, words, _)| tags > 0 || words > RELATED_THRESHOLD)
+                    .collect();
+                // Stable, so equally related things stay in index order.
+                scores.sort_by(|(a_tags, a_words, _), (b_tags, b_words, _)| {
+                    (b_tags, b_words)
+                        .partial_cmp(&(a_tags, a_words))
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                });
+
+                scores
+                    .into_iter()
+                    .take(MAX_RELATED)
+                    .map(|(_, _, other)| other)
+                    .collect()
+            })
+            .collect();
+
+        for ((_, thing), related) in self.0.iter_mut().zip(related) {
+            thing.related = related;
+        }
+    }
+
+    /// Make every thing's slug unique, for `slugged`. Where things' texts
+    /// make the same slug, the first keeps it and the rest are numbered, as
+    /// in `have-you-checked-dns-2`; things whose texts make no slug at all
+    /// use their `item`.
+    fn slug(&mut self) {
+        let mut taken = HashSet::new();
+        for (index, thing) in &mut self.0 {
+            let base = match slugify(&thing.markdown) {
+                slug if slug.is_empty() => thing.item(*index),
+                slug => slug,
+            };
+            let mut slug = base.clone();
+            let mut count = 1;
+            while taken.contains(&slug) {
+                count += 1;
+                slug = format!("{}-{}", base, count);
+            }
+            taken.insert(slug.clone());
+            thing.slug = slug;
+        }
+    }
+
+    /// Where the thing at `index` falls in the list, as in "#42 of 217".
+    /// Things are numbered by position, so excluded things leave no gaps.
+    pub(crate) fn position(&self, index: usize) -> Position {
+        Position {
+            number: self.0.partition_point(|(other, _)| *other < index) + 1,
+            total: self.0.len(),
+        }
+    }
+
+    // IDs share the `item` parameter with indices, so an ID that looks like an
+    // index would be ambiguous, and so would two things with the same ID.
+    // Aliases share it too, so an alias can't name a thing that still exists,
+    // or two different things.
+    fn check_ids(&self) -> Result<(), String> {
+        let mut seen = HashSet::new();
+        for id in self.0.iter().filter_map(|(_, thing)| thing.id.as_deref()) {
+            if id.parse::<usize>().is_ok() {
+                return Err(format!("numeric id {:?}", id));
+            }
+            if !seen.insert(id) {
+                return Err(format!("duplicate id {:?}", id));
+            }
+        }
+
+        let items: HashSet<_> = self
+            .0
+            .iter()
+            .map(|(index, thing)| thing.item(*index))
+            .collect();
+        let mut aliases = HashSet::new();
+        for alias in self.0.iter().flat_map(|(_, thing)| &thing.aliases) {
+            if items.contains(alias) {
+                return Err(format!("alias {:?} names an existing item", alias));
+            }
+            if !aliases.insert(alias) {
+                return Err(format!("duplicate alias {:?}", alias));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl From<Vec<RawThing>> for Things {
+    fn from(raw_things: Vec<RawThing>) -> Self {
+        Things(
+            raw_things
+                .into_iter()
+                .map(Thing::from)
+                .enumerate()
+                .collect(),
+        )
+    }
+}
+
+/// The name of the list in a file that contains only one list.
+pub(crate) const DEFAULT_LIST: &str = "general";
+
+/// Every list of things being served, by name, in the order they appear in
+/// the source.
+///
+/// Alongside the lists, the catalog remembers one past the highest index each
+/// list had before things were excluded from it, so that things added later
+/// never take over an excluded thing's index; see `Catalog::push`.
+#[derive(Clone)]
+pub(crate) struct Catalog(pub(crate) IndexMap<String, Things>, BTreeMap<String, usize>);
+
+impl Catalog {
+    /// Find a list by name, or the default list if no name is given.
+    pub(crate) fn list(&self, name: Option<&str>) -> Option<&Things> {
+        self.named_list(name).map(|(_, things)| things)
+    }
+
+    /// Find a list and its name, by name, or the default list if no name is
+    /// given.
+    pub(crate) fn named_list(&self, name: Option<&str>) -> Option<(&str, &Things)> {
+        let (name, things) = match name {
+            Some(name) => self.0.get_key_value(name)?,
+            None => self.0.first()?,
+        };
+
+        Some((name.as_str(), things))
+    }
+
+    /// Add the lists in `other` to this catalog. Lists with the same name are
+    /// joined together, with the things from `other` numbered after the things
+    /// already in this catalog.
+    fn merge(&mut self, other: Catalog) {
+        for (name, Things(things)) in other.0 {
+            let list = self.0.entry(name).or_insert_with(|| Things(vec![]));
+            let start = list.0.len();
+            list.0.extend(
+                things
+                    .into_iter()
+                    .enumerate()
+                    .map(|(offset, (_, thing))| (start + offset, thing)),
+            );
+        }
+    }
+
+    /// Find things that repeat an earlier thing in the same list, either
+    /// exactly, or after ignoring differences in case and whitespace.
+    fn duplicates(&self) -> Vec<Problem> {
+        let mut duplicates = vec![];
+        for (name, Things(things)) in &self.0 {
+            let mut exact = HashMap::new();
+            let mut near = HashMap::new();
+            for (index, thing) in things {
+                let duplicate = if let Some(&original) = exact.get(thing.markdown.as_str()) {
+                    Some(ProblemKind::Duplicate {
+                        original,
+                        exact: true,
+                    })
+                } else {
+                    near.get(&normalize(&thing.markdown))
+                        .map(|&original| ProblemKind::Duplicate {
+                            original,
+                            exact: false,
+                        })
+                };
+                if let Some(kind) = duplicate {
+                    duplicates.push(Problem {
+                        list: name.clone(),
+                        item: *index,
+                        kind,
+                    });
+                }
+
+                exact.entry(thing.markdown.as_str()).or_insert(*index);
+                near.entry(normalize(&thing.markdown)).or_insert(*index);
+            }
+        }
+
+        duplicates
+    }
+
+    /// Find every problem with the things in this catalog.
+    pub(crate) fn problems(&self) -> Vec<Problem> {
+        let mut problems: Vec<_> = self
+            .0
+            .iter()
+            .flat_map(|(name, Things(things))| {
+                things.iter().flat_map(move |(index, thing)| {
+                    thing.problems().into_iter().map(move |kind| Problem {
+                        list: name.clone(),
+                        item: *index,
+                        kind,
+                    })
+                })
+            })
+            .collect();
+        problems.extend(self.duplicates());
+
+        problems
+    }
+
+    /// Check this catalog for problems, either reporting them on stderr or
+    /// failing, depending on `strictness`.
+    pub(crate) fn check(&self, strictness: Strictness) -> Result<(), Error> {
+        let problems = self.problems();
+        if problems.is_empty() {
+            return Ok(());
+        }
+
+        match strictness {
+            Strictness::Warn => {
+                for problem in problems {
+                    eprintln!("Warning: {}", problem);
+                }
+                Ok(())
+            }
+            Strictness::Fail => Err(Error::ValidationError(problems)),
+        }
+    }
+
+    /// Remove excluded things, keeping the indices of the rest unchanged so
+    /// that links to them stay valid.
+    ///
+    /// Each entry in `exclude` names a thing by ID or index, like the `item`
+    /// parameter, and applies to every list; entries of the form `list:item`
+    /// apply only to the named list.
+    pub(crate) fn exclude(&mut self, exclude: &[String]) {
+        let used: Vec<_> = self
+            .0
+            .keys()
+            .map(|name| (name.clone(), self.next_index(name)))
+            .collect();
+        self.1.extend(used);
+
+        for (name, Things(things)) in &mut self.0 {
+            things.retain(|(index, thing)| {
+                let matches =
+                    |item: &str| thing.id.as_deref() == Some(item) || item.parse() == Ok(*index);
+                !exclude.iter().any(|entry| match entry.split_once(':') {
+                    Some((list, item)) => list == name && matches(item),
+                    None => matches(entry),
+                })
+            });
+        }
+    }
+
+    /// The index for the next thing added to the list `name`: one past every
+    /// index the list has used, including those of excluded things, and those
+    /// that aliases keep pointing at moved things.
+    fn next_index(&self, name: &str) -> usize {
+        let things = self.0.get(name).map_or(&[][..], |Things(things)| things);
+        let indices = things.iter().map(|(index, _)| *index);
+        let aliases = things
+            .iter()
+            .flat_map(|(_, thing)| &thing.aliases)
+            .filter_map(|alias| alias.parse::<usize>().ok());
+
+        indices
+            .chain(aliases)
+            .map(|index| index + 1)
+            .chain(self.1.get(name).copied())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Add an approved `thing` at the end of the list `name`, creating the list
+    /// if need be, and return its index: `FIRST_APPROVED_INDEX`, or one past
+    /// every index the list has used if that's higher.
+    pub(crate) fn push(&mut self, name: &str, thing: Thing) -> usize {
+        let index = self.next_index(name).max(FIRST_APPROVED_INDEX);
+        self.insert(name, index, thing);
+
+        index
+    }
+
+    /// Add `thing` to the list `name` at `index`, creating the list if need be,
+    /// unless the list already has a thing at that index.
+    fn insert(&mut self, name: &str, index: usize, thing: Thing) {
+        let Things(things) = self
+            .0
+            .entry(name.to_owned())
+            .or_insert_with(|| Things(vec![]));
+        if let Err(position) = things.binary_search_by_key(&index, |(index, _)| *index) {
+            things.insert(position, (index, thing));
+        }
+    }
+
+    /// Render every thing's Markdown again, with only the extensions in
+    /// `extensions`. Things are rendered with every extension when they're
+    /// loaded.
+    pub(crate) fn render(&mut self, extensions: Markdown) {
+        if extensions == Markdown::default() {
+            return;
+        }
+        for Things(things) in self.0.values_mut() {
+            for (_, thing) in things {
+                thing.render(extensions);
+            }
+        }
+    }
+
+    /// Work out what's derived from every list's things: which are related,
+    /// and their slugs. See `Things::relate` and `Things::slug`.
+    fn prepare(&mut self) {
+        for things in self.0.values_mut() {
+            things.relate();
+            things.slug();
+        }
+    }
+
+    fn check_ids(&self) -> Result<(), Error> {
+        for (name, things) in &self.0 {
+            things.check_ids().map_err(|reason| Error::IdError {
+                list: name.clone(),
+                reason,
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+// The lowercased words of `text`, ignoring punctuation and Markdown syntax.
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+// The slug for a thing whose Markdown is `markdown`: the ASCII letters and
+// digits of its words, lowercased and joined with hyphens, and cut short at
+// `MAX_SLUG_LENGTH`. Other characters are dropped, so that slugs never need
+// escaping in URLs.
+fn slugify(markdown: &str) -> String {
+    let mut slug = String::new();
+    for word in words(&plain(markdown)) {
+        let word: String = word.chars().filter(char::is_ascii_alphanumeric).collect();
+        if word.is_empty() {
+            continue;
+        }
+        if !slug.is_empty() && slug.len() + 1 + word.len() > MAX_SLUG_LENGTH {
+            break;
+        }
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.push_str(&word);
+    }
+    slug.truncate(MAX_SLUG_LENGTH);
+
+    slug
+}
+
+// How well a search term matches one word of a thing's text: 2 if the word
+// contains the term, 1 if it's one typo away, and 0 otherwise.
+fn score(term: &str, word: &str) -> usize {
+    if word.contains(term) {
+        2
+    } else if term.chars().count() >= 4 && edit_distance(term, word) <= 1 {
+        1
+    } else {
+        0
+    }
+}
+
+// The Levenshtein distance between `a` and `b`: how many characters must be
+// inserted, deleted, or replaced to turn one into the other.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<_> = b.chars().collect();
+    let mut previous: Vec<_> = (0..=b.len()).collect();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let replace = previous[j] + usize::from(a != *b);
+            let insert = current[j] + 1;
+            let delete = previous[j + 1] + 1;
+            current.push(replace.min(insert).min(delete));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+// Two things whose Markdown normalizes to the same string are near-duplicates.
+fn normalize(markdown: &str) -> String {
+    markdown
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+impl From<Things> for Catalog {
+    fn from(things: Things) -> Self {
+        Catalog(
+            IndexMap::from([(DEFAULT_LIST.to_owned(), things)]),
+            BTreeMap::new(),
+        )
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawCatalog {
+    List(Vec<RawThing>),
+    Lists(IndexMap<String, Vec<RawThing>>),
+}
+
+impl From<RawCatalog> for Catalog {
+    fn from(raw: RawCatalog) -> Self {
+        match raw {
+            RawCatalog::List(list) => Catalog::from(Things::from(list)),
+            RawCatalog::Lists(lists) => Catalog(
+                lists
+                    .into_iter()
+                    .map(|(name, list)| (name, Things::from(list)))
+                    .collect(),
+                BTreeMap::new(),
+            ),
+        }
+    }
+}
+
+/// The formats suggestions can be written in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// YAML, the format of the built-in suggestions.
+    Yaml,
+    /// TOML.
+    Toml,
+    /// JSON.
+    Json,
+}
+
+impl Format {
+    /// Recognize the format of a file by its extension.
+    fn of(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()? {
+            "yml" | "yaml" => Some(Format::Yaml),
+            "toml" => Some(Format::Toml),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+
+    fn parse(self, src: &str) -> Result<Catalog, ParseError> {
+        let raw: RawCatalog = match self {
+            Format::Yaml => serde_yaml::from_str(src)?,
+            Format::Toml => toml::from_str(src).map_err(Box::new)?,
+            Format::Json => serde_json::from_str(src)?,
+        };
+
+        Ok(Catalog::from(raw))
+    }
+}
+
+pub(crate) fn load_catalog(src: &str) -> Result<Catalog, Error> {
+    let raw: RawCatalog = serde_yaml::from_str(src)?;
+    let catalog = Catalog::from(raw);
+    catalog.check_ids()?;
+
+    Ok(catalog)
+}
+
+/// Parse a catalog from a document produced by a `DataProvider`.
+pub(crate) fn parse_catalog(src: &str, format: Format) -> Result<Catalog, Error> {
+    let catalog = format.parse(src)?;
+    catalog.check_ids()?;
+
+    Ok(catalog)
+}
+
+/// Read a catalog from `path`, which may be either a single file, or a
+/// directory of files.
+///
+/// When reading a directory, every `*.yml`, `*.yaml`, `*.toml`, and `*.json`
+/// file in the directory is read, in order by file name, and their lists are
+/// merged together. Files later in the
+/// order are numbered after files earlier in the order, so adding a file can
+/// renumber the things in files that come after it; use IDs for things that
+/// need stable links.
+pub(crate) fn read_catalog(path: impl AsRef<Path>) -> Result<Catalog, Error> {
+    let path = path.as_ref();
+    let catalog = if path.is_dir() {
+        let mut catalog = Catalog(IndexMap::new(), BTreeMap::new());
+        for file in catalog_files(path)? {
+            catalog.merge(read_catalog_file(&file)?);
+        }
+        catalog
+    } else {
+        read_catalog_file(path)?
+    };
+
+    catalog.check_ids()?;
+
+    Ok(catalog)
+}
+
+fn read_catalog_file(path: &Path) -> Result<Catalog, Error> {
+    let src = fs::read_to_string(path)?;
+    let format = Format::of(path).unwrap_or(Format::Yaml);
+
+    format.parse(&src).map_err(|source| Error::FileError {
+        path: path.to_owned(),
+        source,
+    })
+}
+
+/// Fetch a catalog from an `http` or `https` URL.
+///
+/// The format is recognized by the extension of the URL's path, as for files.
+pub(crate) fn fetch_catalog(url: &Url) -> Result<Catalog, Error> {
+    let (catalog, _) = fetch_catalog_if_changed(url, &Validators::default())?
+        .expect("fetches without validators are unconditional");
+
+    Ok(catalog)
+}
+
+/// What a server told us about the version of a document it sent, so that
+/// later requests can ask for the document only if it has changed since.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Validators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl Validators {
+    fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+}
+
+/// Fetch a catalog from an `http` or `https` URL, unless the server reports
+/// that it hasn't changed since the version described by `validators`.
+///
+/// Returns the catalog and the validators for the version fetched, or `None`
+/// if it's unchanged. If `validators` is empty, the request is unconditional,
+/// and this never returns `None`.
+pub(crate) fn fetch_catalog_if_changed(
+    url: &Url,
+    validators: &Validators,
+) -> Result<Option<(Catalog, Validators)>, Error> {
+    let mut request = ureq::get(url.as_str());
+    if let Some(etag) = &validators.etag {
+        request = request.set("If-None-Match", etag);
+    }
+    if let Some(last_modified) = &validators.last_modified {
+        request = request.set("If-Modified-Since", last_modified);
+    }
+
+    let response = request.call().map_err(Box::new)?;
+    if response.status() == 304 && !validators.is_empty() {
+        return Ok(None);
+    }
+    let fetched = Validators {
+        etag: response.header("ETag").map(str::to_owned),
+        last_modified: response.header("Last-Modified").map(str::to_owned),
+    };
+    let src = response.into_string()?;
+    let format = Format::of(Path::new(url.path())).unwrap_or(Format::Yaml);
+
+    let catalog = format.parse(&src).map_err(|source| Error::UrlError {
+        url: url.clone(),
+        source,
+    })?;
+    catalog.check_ids()?;
+
+    Ok(Some((catalog, fetched)))
+}
+
+/// The files that make up the catalog in directory `dir`, in the order they're
+/// read.
+pub(crate) fn catalog_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && Format::of(&path).is_some() {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+/// Holds the current catalog of things, and allows it to be replaced while the
+/// service is running.
+///
+/// Readers take a reference-counted snapshot via `current()`, so replacing the
+/// catalog never disturbs a request that's already working with the old one.
+///
+/// The store also remembers the things moderators have approved, which aren't
+/// part of any source, so that they survive the catalog being replaced; whether
+/// the last attempt to refresh the catalog from its source failed, for
+/// readiness checks; and when the catalog was last replaced, for the admin
+/// dashboard.
+pub(crate) struct Store {
+    catalog: RwLock<Arc<Catalog>>,
+    approved: Mutex<Vec<(String, usize, Thing)>>,
+    failure: Mutex<Option<String>>,
+    loaded: Mutex<SystemTime>,
+}
+
+impl Store {
+    pub(crate) fn new(mut catalog: Catalog) -> Self {
+        catalog.prepare();
+        Store {
+            catalog: RwLock::new(Arc::new(catalog)),
+            approved: Mutex::new(vec![]),
+            failure: Mutex::new(None),
+            loaded: Mutex::new(SystemTime::now()),
+        }
+    }
+
+    pub(crate) fn current(&self) -> Arc<Catalog> {
+        // A poisoned lock can only result from a panic while swapping one Arc
+        // for another, which leaves the guarded value intact either way.
+        let catalog = self.catalog.read().unwrap_or_else(|err| err.into_inner());
+        Arc::clone(&catalog)
+    }
+
+    pub(crate) fn replace(&self, mut catalog: Catalog) {
+        let mut current = self.catalog.write().unwrap_or_else(|err| err.into_inner());
+        let approved = self.approved.lock().unwrap_or_else(|err| err.into_inner());
+        for (name, index, thing) in approved.iter() {
+            catalog.insert(name, *index, thing.clone());
+        }
+        catalog.prepare();
+        *current = Arc::new(catalog);
+        *self.loaded.lock().unwrap_or_else(|err| err.into_inner()) = SystemTime::now();
+    }
+
+    /// When the catalog was last loaded from its source.
+    pub(crate) fn loaded(&self) -> SystemTime {
+        *self.loaded.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Note the outcome of an attempt to refresh the catalog from its source,
+    /// and pass it on.
+    pub(crate) fn record<T, E: fmt::Display>(&self, result: Result<T, E>) -> Result<T, E> {
+        let mut failure = self.failure.lock().unwrap_or_else(|err| err.into_inner());
+        *failure = result.as_ref().err().map(ToString::to_string);
+        result
+    }
+
+    /// Why the last attempt to refresh the catalog failed, if it did.
+    pub(crate) fn failure(&self) -> Option<String> {
+        let failure = self.failure.lock().unwrap_or_else(|err| err.into_inner());
+        failure.clone()
+    }
+
+    /// Add a thing approved by moderators to the list `name`, or to the first
+    /// list if there's no such list, and return the list's name and the
+    /// thing's index. The thing is kept when the catalog is replaced.
+    pub(crate) fn approve(&self, name: Option<&str>, thing: Thing) -> (String, usize) {
+        let mut current = self.catalog.write().unwrap_or_else(|err| err.into_inner());
+        let mut catalog = Catalog::clone(&current);
+
+        let name = name
+            .filter(|name| catalog.0.contains_key(*name))
+            .or_else(|| catalog.0.keys().next().map(String::as_str))
+            .unwrap_or(DEFAULT_LIST)
+            .to_owned();
+        let index = catalog.push(&name, thing.clone());
+        self.approved
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .push((name.clone(), index, thing));
+
+        catalog.prepare();
+        *current = Arc::new(catalog);
+
+        (name, index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::process;
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn single_list_is_default() {
+        let catalog = load_catalog("- Have you checked DNS?").unwrap();
+
+        let default = catalog.list(None).unwrap();
+        let general = catalog.list(Some(DEFAULT_LIST)).unwrap();
+        assert_eq!(default.0[0].1.markdown, "Have you checked DNS?");
+        assert_eq!(general.0[0].1.markdown, "Have you checked DNS?");
+    }
+
+    #[test]
+    fn named_lists_are_numbered_independently() {
+        let catalog = load_catalog(
+            "
+networking:
+  - Have you checked DNS?
+  - Have you checked the MTU?
+kubernetes:
+  - Have you checked the pod's events?
+",
+        )
+        .unwrap();
+
+        let networking = catalog.list(Some("networking")).unwrap();
+        let kubernetes = catalog.list(Some("kubernetes")).unwrap();
+        assert_eq!(networking.0[1].0, 1);
+        assert_eq!(kubernetes.0[0].0, 0);
+        assert_eq!(
+            kubernetes.0[0].1.markdown,
+            "Have you checked the pod's events?"
+        );
+    }
+
+    #[test]
+    fn first_named_list_is_default() {
+        let catalog = load_catalog(
+            "
+networking:
+  - Have you checked DNS?
+general:
+  - Have you checked permissions?
+",
+        )
+        .unwrap();
+
+        let default = catalog.list(None).unwrap();
+        assert_eq!(default.0[0].1.markdown, "Have you checked DNS?");
+        assert!(catalog.list(Some("kubernetes")).is_none());
+    }
+
+    #[test]
+    fn entries_may_carry_tags() {
+        let catalog = load_catalog(
+            "
+- Have you checked permissions?
+- text: Have you checked DNS?
+  tags: [dns, networking]
+",
+        )
+        .unwrap();
+
+        let things = catalog.list(None).unwrap();
+        let (_, plain) = &things.0[0];
+        let (_, tagged) = &things.0[1];
+        assert_eq!(tagged.markdown, "Have you checked DNS?");
+        assert!(tagged.has_tag(Some("dns")));
+        assert!(!tagged.has_tag(Some("hardware")));
+        assert!(!plain.has_tag(Some("dns")));
+        assert!(plain.has_tag(None));
+    }
+
+    #[test]
+    fn tags_are_counted() {
+        let catalog = load_catalog(
+            "
+- Have you checked permissions?
+- text: Have you checked DNS?
+  tags: [dns, networking]
+- text: Have you checked the MTU?
+  tags: [networking]
+",
+        )
+        .unwrap();
+
+        let things = catalog.list(None).unwrap();
+        assert_eq!(things.tags(), [("dns", 1), ("networking", 2)]);
+    }
+
+    #[test]
+    fn samples_are_distinct() {
+        let catalog = load_catalog(
+            "
+- Have you checked DNS?
+- Have you checked the MTU?
+- text: Have you checked the hub?
+  retired: true
+",
+        )
+        .unwrap();
+        let things = catalog.list(None).unwrap();
+
+        let mut indices: Vec<_> = things
+            .sample(5, None)
+            .iter()
+            .map(|(index, _)| *index)
+            .collect();
+        indices.sort_unstable();
+        assert_eq!(indices, [0, 1]);
+        assert_eq!(things.sample(1, None).len(), 1);
+    }
+
+    #[test]
+    fn entries_may_be_translated() {
+        let catalog = load_catalog(
+            "
+- text: Have you checked the logs?
+  translations:
+    fr: Avez-vous vérifié les journaux?
+    pt-BR: Você verificou os logs?
+",
+        )
+        .unwrap();
+
+        let (_, thing) = &catalog.list(None).unwrap().0[0];
+        let text = |lang| thing.translated(lang).markdown;
+        assert_eq!(text(None), "Have you checked the logs?");
+        assert_eq!(text(Some("fr")), "Avez-vous vérifié les journaux?");
+        assert_eq!(text(Some("fr-CA")), "Avez-vous vérifié les journaux?");
+        assert_eq!(text(Some("pt-BR")), "Você verificou os logs?");
+        assert_eq!(text(Some("pt")), "Have you checked the logs?");
+        assert_eq!(text(Some("de")), "Have you checked the logs?");
+        assert_eq!(thing.translated(Some("fr-CA")).lang, "fr");
+        assert_eq!(thing.translated(Some("de")).lang, "en");
+    }
+
+    #[test]
+    fn entries_may_be_retired() {
+        let catalog = load_catalog(
+            "
+- Have you checked permissions?
+- text: Have you checked the token ring?
+  retired: true
+",
+        )
+        .unwrap();
+
+        let things = catalog.list(None).unwrap();
+        assert!(!things.0[0].1.retired);
+        assert!(things.0[1].1.retired);
+        assert_eq!(things.find("1").unwrap().0, 1);
+    }
+
+    #[test]
+    fn builtin_things_have_no_problems() {
+        let catalog = load_catalog(THINGS).unwrap();
+
+        assert!(catalog.problems().is_empty());
+    }
+
+    #[test]
+    fn duplicates_are_detected() {
+        let catalog = load_catalog(
+            "
+- Have you checked DNS?
+- Have you checked permissions?
+- Have you checked DNS?
+- have you  checked dns?
+",
+        )
+        .unwrap();
+
+        let duplicates = catalog.problems();
+        assert_eq!(duplicates.len(), 2);
+        assert_eq!(duplicates[0].item, 2);
+        assert_eq!(
+            duplicates[0].kind,
+            ProblemKind::Duplicate {
+                original: 0,
+                exact: true
+            }
+        );
+        assert_eq!(duplicates[1].item, 3);
+        assert_eq!(
+            duplicates[1].kind,
+            ProblemKind::Duplicate {
+                original: 0,
+                exact: false
+            }
+        );
+
+        assert!(catalog.check(Strictness::Warn).is_ok());
+        assert!(catalog.check(Strictness::Fail).is_err());
+    }
+
+    #[test]
+    fn duplicates_in_different_lists_are_allowed() {
+        let catalog = load_catalog(
+            "
+networking:
+  - Have you checked DNS?
+kubernetes:
+  - Have you checked DNS?
+",
+        )
+        .unwrap();
+
+        assert!(catalog.problems().is_empty());
+    }
+
+    #[test]
+    fn invalid_things_are_detected() {
+        let long = format!("- Have you checked{}?", " the logs".repeat(100));
+        let catalog = load_catalog(&format!(
+            "
+- Have you checked permissions?
+- '   '
+- Have you checked <script>alert('the logs')</script>?
+{}
+",
+            long
+        ))
+        .unwrap();
+
+        let problems: Vec<_> = catalog
+            .problems()
+            .into_iter()
+            .map(|problem| (problem.item, problem.kind))
+            .collect();
+        assert_eq!(
+            problems,
+            [
+                (1, ProblemKind::Empty),
+                (2, ProblemKind::RawHtml),
+                (3, ProblemKind::TooLong { length: 917 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn items_are_found_by_id_or_index() {
+        let catalog = load_catalog(
+            "
+- Have you checked permissions?
+- text: Have you checked DNS?
+  id: dns
+",
+        )
+        .unwrap();
+
+        let things = catalog.list(None).unwrap();
+        assert_eq!(things.find("dns").unwrap().0, 1);
+        assert_eq!(things.find("1").unwrap().0, 1);
+        assert_eq!(things.find("0").unwrap().0, 0);
+        assert!(things.find("2").is_none());
+        assert!(things.find("cabling").is_none());
+
+        assert_eq!(things.0[0].1.item(0), "0");
+        assert_eq!(things.0[1].1.item(1), "dns");
+    }
+
+    #[test]
+    fn excluded_things_are_removed() {
+        let mut catalog = load_catalog(
+            "
+general:
+  - Have you checked permissions?
+  - text: Have you checked DNS?
+    id: dns
+  - Have you checked the cables?
+kubernetes:
+  - Have you checked the pod logs?
+  - Have you checked the node's disk?
+",
+        )
+        .unwrap();
+
+        catalog.exclude(&["dns".to_owned(), "kubernetes:0".to_owned()]);
+
+        let general = catalog.list(Some("general")).unwrap();
+        assert_eq!(general.0.len(), 2);
+        assert!(general.find("dns").is_none());
+        assert!(general.find("1").is_none());
+        assert_eq!(
+            general.find("2").unwrap().1.markdown,
+            "Have you checked the cables?"
+        );
+
+        let kubernetes = catalog.list(Some("kubernetes")).unwrap();
+        assert!(kubernetes.find("0").is_none());
+        assert!(kubernetes.find("1").is_some());
+    }
+
+    #[test]
+    fn related_things_share_tags_or_words() {
+        let catalog = load_catalog(
+            "
+- Have you checked for an expired certificate?
+- Have you checked the cables?
+- text: Have you checked the MTU?
+  tags: [network]
+- Have you checked for an expired root certificate?
+- text: Have you checked DNS?
+  tags: [network]
+- text: Have you checked for an expired certificate chain?
+  retired: true
+",
+        )
+        .unwrap();
+        let store = Store::new(catalog);
+        let catalog = store.current();
+        let things = catalog.list(None).unwrap();
+
+        let related = |index: usize| -> Vec<usize> {
+            let (_, thing) = things.find(&index.to_string()).unwrap();
+            things
+                .related(thing)
+                .iter()
+                .map(|(index, _)| *index)
+                .collect()
+        };
+        assert_eq!(related(0), [3]);
+        assert_eq!(related(1), [] as [usize; 0]);
+        assert_eq!(related(2), [4]);
+        assert_eq!(related(5), [0, 3]);
+    }
+
+    #[test]
+    fn positions_skip_excluded_things() {
+        let mut catalog = load_catalog(
+            "
+- Have you checked permissions?
+- Have you checked DNS?
+- Have you checked the cables?
+",
+        )
+        .unwrap();
+        catalog.exclude(&["1".to_owned()]);
+
+        let things = catalog.list(None).unwrap();
+        assert_eq!(
+            things.position(0),
+            Position {
+                number: 1,
+                total: 2
+            }
+        );
+        assert_eq!(
+            things.position(2),
+            Position {
+                number: 2,
+                total: 2
+            }
+        );
+    }
+
+    #[test]
+    fn slugs_are_readable_and_unique() {
+        let catalog = load_catalog(
+            "
+- Have you checked `resolv.conf`?
+- Have you checked DNS?
+- Have you checked *DNS*?
+- \"???\"
+- Have you checked whether the certificate on the load balancer in front of the service has expired?
+",
+        )
+        .unwrap();
+        let store = Store::new(catalog);
+        let catalog = store.current();
+        let things = catalog.list(None).unwrap();
+
+        let slugs: Vec<_> = things.0.iter().map(|(_, thing)| &thing.slug).collect();
+        assert_eq!(
+            slugs,
+            [
+                "have-you-checked-resolv-conf",
+                "have-you-checked-dns",
+                "have-you-checked-dns-2",
+                "3",
+                "have-you-checked-whether-the-certificate-on-the-load",
+            ]
+        );
+        assert_eq!(things.slugged("have-you-checked-dns-2").unwrap().0, 2);
+        assert!(things.slugged("have-you-checked").is_none());
+    }
+
+    #[test]
+    fn aliases_find_moved_things() {
+        let catalog = load_catalog(
+            "
+- text: Have you checked permissions?
+  id: permissions
+  aliases: [0, perms]
+- Have you checked DNS?
+",
+        )
+        .unwrap();
+
+        let things = catalog.list(None).unwrap();
+        assert_eq!(things.aliased("0").unwrap().1.item(0), "permissions");
+        assert_eq!(things.aliased("perms").unwrap().1.item(0), "permissions");
+        assert!(things.aliased("1").is_none());
+        assert!(things.aliased("permissions").is_none());
+    }
+
+    #[test]
+    fn pushed_things_skip_used_indices() {
+        let mut catalog = load_catalog(
+            "
+- Have you checked permissions?
+- Have you checked the logs?
+",
+        )
+        .unwrap();
+        assert_eq!(
+            catalog.push(
+                DEFAULT_LIST,
+                Thing::from("Have you checked NTP?".to_owned())
+            ),
+            FIRST_APPROVED_INDEX
+        );
+
+        catalog.exclude(&[FIRST_APPROVED_INDEX.to_string()]);
+        assert_eq!(
+            catalog.push(
+                DEFAULT_LIST,
+                Thing::from("Have you checked TLS?".to_owned())
+            ),
+            FIRST_APPROVED_INDEX + 1
+        );
+        assert_eq!(
+            catalog.push("new", Thing::from("Have you checked disks?".to_owned())),
+            FIRST_APPROVED_INDEX
+        );
+
+        let mut catalog = load_catalog(
+            "
+- Have you checked permissions?
+- text: Have you checked DNS?
+  aliases: [1000000]
+",
+        )
+        .unwrap();
+        assert_eq!(
+            catalog.push(
+                DEFAULT_LIST,
+                Thing::from("Have you checked NTP?".to_owned())
+            ),
+            FIRST_APPROVED_INDEX + 1
+        );
+    }
+
+    #[test]
+    fn approved_things_survive_replacement() {
+        let store = Store::new(load_catalog("- Have you checked permissions?").unwrap());
+        let (name, index) = store.approve(
+            Some("missing"),
+            Thing::from("Have you checked NTP?".to_owned()),
+        );
+        assert_eq!(name, DEFAULT_LIST);
+
+        store.replace(
+            load_catalog(
+                "
+- Have you checked permissions?
+- Have you checked the logs?
+",
+            )
+            .unwrap(),
+        );
+
+        let catalog = store.current();
+        let things = catalog.list(None).unwrap();
+        assert_eq!(things.0.len(), 3);
+        assert_eq!(
+            things.find(&index.to_string()).unwrap().1.markdown,
+            "Have you checked NTP?"
+        );
+    }
+
+    #[test]
+    fn aliases_must_be_unambiguous() {
+        assert!(load_catalog(
+            "
+- Have you checked permissions?
+- text: Have you checked DNS?
+  aliases: [0]
+",
+        )
+        .is_err());
+        assert!(load_catalog(
+            "
+- text: Have you checked permissions?
+  id: permissions
+- text: Have you checked DNS?
+  aliases: [permissions]
+",
+        )
+        .is_err());
+        assert!(load_catalog(
+            "
+- text: Have you checked permissions?
+  aliases: [old]
+- text: Have you checked DNS?
+  aliases: [old]
+",
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn markdown_may_use_extensions() {
+        let mut catalog = load_catalog(
+            r#"
+- Have you checked the ~~cache~~ "CDN"?
+- text: Have you checked the MTU?[^1]
+  details: |
+    | Link | MTU |
+    | --- | --- |
+    | Ethernet | 1500 |
+
+    [^1]: Maximum transmission unit.
+"#,
+        )
+        .unwrap();
+
+        let things = catalog.list(None).unwrap();
+        let (_, cdn) = things.find("0").unwrap();
+        assert!(cdn.html.contains("<del>cache</del>"));
+        assert!(cdn.html.contains("“CDN”"));
+        assert_eq!(cdn.text(), r#"Have you checked the cache "CDN"?"#);
+        let (_, mtu) = things.find("1").unwrap();
+        assert!(mtu.html.contains("footnote-reference"));
+        assert!(mtu.details.as_ref().unwrap().html.contains("<table>"));
+
+        catalog.render(Markdown {
+            strikethrough: false,
+            tables: false,
+            footnotes: true,
+            smart_punctuation: false,
+        });
+        let things = catalog.list(None).unwrap();
+        let (_, cdn) = things.find("0").unwrap();
+        assert!(cdn.html.contains("~~cache~~ &quot;CDN&quot;"));
+        let (_, mtu) = things.find("1").unwrap();
+        assert!(!mtu.details.as_ref().unwrap().html.contains("<table>"));
+    }
+
+    #[test]
+    fn code_blocks_are_highlighted() {
+        let catalog = load_catalog(
+            r#"
+- |
+  Have you checked the resolver?
+
+  ```sh
+  dig +short example.com # "A" record
+  ```
+- |
+  Have you checked the build?
+
+  ```cobol
+  DISPLAY "HI".
+  ```
+"#,
+        )
+        .unwrap();
+        let things = catalog.list(None).unwrap();
+
+        let (_, sh) = things.find("0").unwrap();
+        assert!(sh.html.contains(
+            r#"<pre><code class="language-sh">dig +short example.com <span class="hl-comment"># &quot;A&quot; record</span>"#
+        ));
+        let (_, cobol) = things.find("1").unwrap();
+        assert!(cobol
+            .html
+            .contains(r#"<pre><code class="language-cobol">DISPLAY &quot;HI&quot;."#));
+    }
+
+    #[test]
+    fn shortcodes_become_emoji() {
+        let catalog = load_catalog(
+            "
+- text: \":bulb: Have you checked `:bulb:`?\"
+  details: \"Look for :fire: in the logs.\"
+",
+        )
+        .unwrap();
+        let things = catalog.list(None).unwrap();
+
+        let (_, thing) = things.find("0").unwrap();
+        assert_eq!(thing.text(), "💡 Have you checked :bulb:?");
+        assert!(thing.details.as_ref().unwrap().html.contains("🔥"));
+        assert_eq!(thing.slug, "have-you-checked-bulb");
+    }
+
+    #[test]
+    fn entries_may_carry_details() {
+        let catalog = load_catalog(
+            "
+- text: Have you checked the logs?
+  details: Most services log *why* they failed.
+- Have you checked DNS?
+",
+        )
+        .unwrap();
+
+        let things = catalog.list(None).unwrap();
+        let details = things.0[0].1.details.as_ref().unwrap();
+        assert_eq!(details.markdown, "Most services log *why* they failed.");
+        assert_eq!(
+            details.html,
+            "<p>Most services log <em>why</em> they failed.</p>\n"
+        );
+        assert!(things.0[1].1.details.is_none());
+    }
+
+    #[test]
+    fn edit_distance_counts_typos() {
+        assert_eq!(edit_distance("dns", "dns"), 0);
+        assert_eq!(edit_distance("cache", "cahce"), 2);
+        assert_eq!(edit_distance("certificate", "certificat"), 1);
+        assert_eq!(edit_distance("", "dns"), 3);
+    }
+
+    #[test]
+    fn search_ranks_matches() {
+        let catalog = load_catalog(
+            "
+- Have you checked the logs?
+- Have you checked DNS?
+- text: Have you checked the firewall?
+  details: Firewalls can silently drop DNS queries.
+- text: Have you checked the DNS cache?
+  retired: true
+",
+        )
+        .unwrap();
+        let things = catalog.list(None).unwrap();
+        let found = |terms| {
+            things
+                .search(terms)
+                .into_iter()
+                .map(|(index, _)| *index)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(found("dns"), [1, 2]);
+        assert_eq!(found("DNS firewall"), [2]);
+        assert_eq!(found("firewal"), [2]);
+        assert_eq!(found("lgos"), Vec::<usize>::new());
+        assert_eq!(found("logz"), [0]);
+        assert_eq!(found("  "), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn text_drops_formatting() {
+        let catalog = load_catalog("- Have you checked `resolv.conf`?\n").unwrap();
+        let (_, thing) = &catalog.list(None).unwrap().0[0];
+
+        assert_eq!(thing.text(), "Have you checked resolv.conf?");
+    }
+
+    #[test]
+    fn newest_things_come_first() {
+        let catalog = load_catalog(
+            "
+- Have you checked the logs?
+- text: Have you checked for a leap second?
+  added: 2016-12-31
+- text: Have you checked DNS?
+  added: last tuesday
+- text: Have you checked the cache?
+  added: 2017-01-01
+- text: Have you checked the power?
+  added: 2020-02-02
+  retired: true
+",
+        )
+        .unwrap();
+        let things = catalog.list(None).unwrap();
+        let newest: Vec<_> = things
+            .newest(None)
+            .into_iter()
+            .map(|(index, _)| *index)
+            .collect();
+
+        assert_eq!(newest, [3, 1, 2, 0]);
+    }
+
+    #[test]
+    fn entries_may_carry_metadata() {
+        let catalog = load_catalog(
+            "
+- text: Have you checked for a leap second?
+  author: Owen
+  added: 2016-12-31
+  context: network
+  links:
+    - https://en.wikipedia.org/wiki/Leap_second
+",
+        )
+        .unwrap();
+
+        let (_, thing) = &catalog.list(None).unwrap().0[0];
+        assert_eq!(thing.author.as_deref(), Some("Owen"));
+        assert_eq!(thing.added.as_deref(), Some("2016-12-31"));
+        assert_eq!(thing.context.as_deref(), Some("network"));
+        assert_eq!(thing.links, ["https://en.wikipedia.org/wiki/Leap_second"]);
+    }
+
+    #[test]
+    fn ids_must_be_unique() {
+        let result = load_catalog(
+            "
+- text: Have you checked DNS?
+  id: dns
+- text: Have you checked resolv.conf?
+  id: dns
+",
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn directories_are_merged_in_name_order() {
+        let dir = env::temp_dir().join(format!("things-to-check-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("b.yml"), "- Have you checked DNS?").unwrap();
+        fs::write(dir.join("a.yml"), "- Have you checked permissions?").unwrap();
+        fs::write(
+            dir.join("c.yml"),
+            "networking:\n  - Have you checked the MTU?",
+        )
+        .unwrap();
+        fs::write(dir.join("README"), "Not a suggestion.").unwrap();
+
+        let catalog = read_catalog(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+        let catalog = catalog.unwrap();
+
+        let general = catalog.list(Some(DEFAULT_LIST)).unwrap();
+        let networking = catalog.list(Some("networking")).unwrap();
+        assert_eq!(general.0.len(), 2);
+        assert_eq!(general.0[0].1.markdown, "Have you checked permissions?");
+        assert_eq!(general.0[1].0, 1);
+        assert_eq!(general.0[1].1.markdown, "Have you checked DNS?");
+        assert_eq!(networking.0[0].1.markdown, "Have you checked the MTU?");
+    }
+
+    #[test]
+    fn other_formats_are_recognized() {
+        let toml = Format::Toml
+            .parse(
+                r#"
+[[networking]]
+text = "Have you checked DNS?"
+tags = ["dns"]
+
+[[general]]
+text = "Have you checked permissions?"
+"#,
+            )
+            .unwrap();
+        let json = Format::Json
+            .parse(r#"["Have you checked DNS?", {"text": "Have you checked the MTU?"}]"#)
+            .unwrap();
+
+        let networking = toml.list(None).unwrap();
+        assert_eq!(networking.0[0].1.markdown, "Have you checked DNS?");
+        assert!(networking.0[0].1.has_tag(Some("dns")));
+        assert!(toml.list(Some(DEFAULT_LIST)).is_some());
+        assert_eq!(
+            json.list(None).unwrap().0[1].1.markdown,
+            "Have you checked the MTU?"
+        );
+    }
+
+    #[test]
+    fn ids_must_not_be_numeric() {
+        let result = load_catalog(
+            "
+- Have you checked permissions?
+- text: Have you checked DNS?
+  id: '0'
+",
+        );
+
+        assert!(result.is_err());
+    }
+
+    // Serves `responses`, one per connection, in order, and returns the
+    // requests received.
+    fn serve(responses: Vec<&'static str>) -> (Url, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!(
+            "http://{}/things.yml",
+            listener.local_addr().unwrap()
+        ))
+        .unwrap();
+
+        let server = thread::spawn(move || {
+            responses
+                .into_iter()
+                .map(|response| {
+                    let (mut stream, _) = listener.accept().unwrap();
+                    let mut request = String::new();
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    loop {
+                        let mut line = String::new();
+                        reader.read_line(&mut line).unwrap();
+                        if line.trim().is_empty() {
+                            break;
+                        }
+                        request.push_str(&line);
+                    }
+                    stream.write_all(response.as_bytes()).unwrap();
+                    request
+                })
+                .collect()
+        });
+
+        (url, server)
+    }
+
+    #[test]
+    fn unchanged_catalogs_are_not_fetched_again() {
+        let (url, server) = serve(vec![
+            "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: 23\r\nConnection: close\r\n\r\n- Have you checked DNS?",
+            "HTTP/1.1 304 Not Modified\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n",
+        ]);
+
+        let (catalog, validators) = fetch_catalog_if_changed(&url, &Validators::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(catalog.list(None).unwrap().0.len(), 1);
+        assert_eq!(validators.etag.as_deref(), Some("\"v1\""));
+
+        assert!(fetch_catalog_if_changed(&url, &validators)
+            .unwrap()
+            .is_none());
+
+        let requests = server.join().unwrap();
+        assert!(!requests[0].to_lowercase().contains("if-none-match"));
+        assert!(requests[1].to_lowercase().contains("if-none-match: \"v1\""));
+    }
+}
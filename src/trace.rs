@@ -0,0 +1,312 @@
+//! Distributed tracing, with OpenTelemetry.
+//!
+//! This module is only available with the `otel` feature. It provides
+//! `Tracing`, a middleware that records a span for each request the App
+//! serves, and `install`, which sends those spans to a collector over
+//! [OTLP][1], so that requests to this service show up in the same tracing
+//! backend as the rest of a deployment:
+//!
+//! ```no_run
+//! # use things_to_check::{trace, view};
+//! # #[actix_web::main]
+//! # async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+//! use actix_web::{App, HttpServer};
+//! use url::Url;
+//!
+//! let service = view::make_service()?;
+//! let provider = trace::install(&Url::parse("http://localhost:4318/")?)?;
+//! let app_factory = move ||
+//!     App::new()
+//!         .wrap(trace::Tracing)
+//!         .configure(|cfg| service(cfg));
+//!
+//! HttpServer::new(app_factory).bind("localhost:3000")?.run().await?;
+//! provider.shutdown()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Requests carrying a [W3C `traceparent` header][2] are recorded as part of
+//! the trace it names, as children of the caller's span; others start a trace
+//! of their own. Each span is named for the request's method and the route it
+//! matched, such as `GET /check/{slug}`, and records the path, the route, the
+//! `User-Agent`, and the response's status code. Responses with `5xx` status
+//! codes mark their spans as errors.
+//!
+//! `Tracing` records spans with the global tracer provider, which discards
+//! them until one is installed; `install` installs one. Services using some
+//! other exporter can install their own provider, and a
+//! `TraceContextPropagator`, with `opentelemetry::global` instead.
+//!
+//! [1]: https://opentelemetry.io/docs/specs/otlp/
+//! [2]: https://www.w3.org/TR/trace-context/
+
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{self, HeaderMap};
+use async_trait::async_trait;
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use opentelemetry::context::FutureExt;
+use opentelemetry::propagation::Extractor;
+use opentelemetry::trace::{SpanKind, Status, TraceContextExt, Tracer};
+use opentelemetry::{global, KeyValue};
+use opentelemetry_http::{Bytes, HttpClient, HttpError, Request, Response};
+use opentelemetry_otlp::{ExporterBuildError, SpanExporter, WithExportConfig, WithHttpConfig};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
+use std::env;
+use std::io::Read;
+use std::time::Duration;
+use thiserror::Error;
+use url::Url;
+
+/// The name spans are recorded under, and the service's name unless
+/// `OTEL_SERVICE_NAME` says otherwise.
+const NAME: &str = env!("CARGO_PKG_NAME");
+
+/// How long to wait for the collector to accept a batch of spans.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Errors that can arise installing an OTLP exporter.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// Indicates that the exporter could not be set up, such as for an
+    /// endpoint that isn't a valid URI.
+    #[error("Unable to export traces: {0}")]
+    ExporterError(#[from] ExporterBuildError),
+}
+
+/// Send spans to the OTLP collector at `endpoint`, such as
+/// `http://localhost:4318/`, over HTTP, and read `traceparent` headers.
+///
+/// Spans are sent in batches, from a thread of their own, to `v1/traces`
+/// under `endpoint`. The service is named by the `OTEL_SERVICE_NAME`
+/// environment variable, if set, and is otherwise `things-to-check`. Shut
+/// the returned provider down when the service stops, so that the last spans
+/// are sent.
+pub fn install(endpoint: &Url) -> Result<SdkTracerProvider, Error> {
+    let mut traces = endpoint.clone();
+    // Every URL with a host has a path to add to.
+    if let Ok(mut path) = traces.path_segments_mut() {
+        path.pop_if_empty().extend(["v1", "traces"]);
+    }
+
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_http_client(Client::default())
+        .with_endpoint(traces.as_str())
+        .with_timeout(TIMEOUT)
+        .build()?;
+    let resource = match env::var_os("OTEL_SERVICE_NAME") {
+        Some(_) => Resource::builder().build(),
+        None => Resource::builder().with_service_name(NAME).build(),
+    };
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(resource)
+        .build();
+
+    global::set_tracer_provider(provider.clone());
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    Ok(provider)
+}
+
+// Sends spans to the collector with the same HTTP client as the rest of the
+// service. The batch exporter sends from its own thread, so blocking is fine.
+#[derive(Debug)]
+struct Client(ureq::Agent);
+
+impl Default for Client {
+    fn default() -> Self {
+        Client(ureq::AgentBuilder::new().timeout(TIMEOUT).build())
+    }
+}
+
+#[async_trait]
+impl HttpClient for Client {
+    async fn send_bytes(&self, request: Request<Bytes>) -> Result<Response<Bytes>, HttpError> {
+        let (parts, body) = request.into_parts();
+        let mut call = self
+            .0
+            .request(parts.method.as_str(), &parts.uri.to_string());
+        for (name, value) in &parts.headers {
+            call = call.set(name.as_str(), value.to_str()?);
+        }
+
+        // The exporter reports unsuccessful statuses itself.
+        let response = match call.send_bytes(&body) {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+            Err(err) => return Err(Box::new(err)),
+        };
+        let status = response.status();
+        let mut body = vec![];
+        response.into_reader().read_to_end(&mut body)?;
+
+        Ok(Response::builder().status(status).body(body.into())?)
+    }
+}
+
+// Reads trace context from a request's headers.
+struct Headers<'a>(&'a HeaderMap);
+
+impl Extractor for Headers<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|name| name.as_str()).collect()
+    }
+}
+
+/// Records a span for each request. Wrap an App in it to trace every
+/// request the App serves.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Tracing;
+
+impl<S, B> Transform<S, ServiceRequest> for Tracing
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = TracingService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(TracingService { service }))
+    }
+}
+
+/// The service `Tracing` wraps an App's service in.
+pub struct TracingService<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for TracingService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let parent = global::get_text_map_propagator(|propagator| {
+            propagator.extract(&Headers(req.headers()))
+        });
+        let method = req.method().to_string();
+        let mut attributes = vec![
+            KeyValue::new("http.request.method", method.clone()),
+            KeyValue::new("url.path", req.path().to_owned()),
+        ];
+        if let Some(agent) = req
+            .headers()
+            .get(header::USER_AGENT)
+            .and_then(|agent| agent.to_str().ok())
+        {
+            attributes.push(KeyValue::new("user_agent.original", agent.to_owned()));
+        }
+
+        let tracer = global::tracer(NAME);
+        let span = tracer
+            .span_builder(method.clone())
+            .with_kind(SpanKind::Server)
+            .with_attributes(attributes)
+            .start_with_context(&tracer, &parent);
+        let cx = parent.with_span(span);
+        let response = self.service.call(req).with_context(cx.clone());
+
+        Box::pin(async move {
+            let response = response.await;
+            let span = cx.span();
+            // Routes are only matched once the App has handled the request.
+            let (route, status) = match &response {
+                Ok(response) => (response.request().match_pattern(), response.status()),
+                Err(err) => (None, err.as_response_error().status_code()),
+            };
+            if let Some(route) = route {
+                span.update_name(format!("{} {}", method, route));
+                span.set_attribute(KeyValue::new("http.route", route));
+            }
+            span.set_attribute(KeyValue::new(
+                "http.response.status_code",
+                i64::from(status.as_u16()),
+            ));
+            if status.is_server_error() {
+                span.set_status(Status::error(status.to_string()));
+            }
+            span.end();
+
+            response
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, App};
+    use opentelemetry::trace::{SpanId, TraceId};
+    use opentelemetry_sdk::error::OTelSdkResult;
+    use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::view;
+
+    #[derive(Clone, Debug, Default)]
+    struct Collected(Arc<Mutex<Vec<SpanData>>>);
+
+    impl SpanExporter for Collected {
+        async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+            self.0.lock().unwrap().extend(batch);
+            Ok(())
+        }
+    }
+
+    #[actix_web::test]
+    async fn requests_continue_their_callers_traces() {
+        let collected = Collected::default();
+        global::set_tracer_provider(
+            SdkTracerProvider::builder()
+                .with_simple_exporter(collected.clone())
+                .build(),
+        );
+        global::set_text_map_propagator(TraceContextPropagator::new());
+
+        let service = view::make_service().unwrap();
+        let app = test::init_service(App::new().wrap(Tracing).configure(|cfg| service(cfg))).await;
+
+        let req = test::TestRequest::get()
+            .uri("/check/have-you-checked-permissions")
+            .insert_header((
+                "traceparent",
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+
+        let spans = collected.0.lock().unwrap();
+        let span = spans.last().unwrap();
+        assert_eq!(span.name, "GET /check/{slug}");
+        assert_eq!(span.span_kind, SpanKind::Server);
+        assert_eq!(
+            span.span_context.trace_id(),
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap()
+        );
+        assert_eq!(
+            span.parent_span_id,
+            SpanId::from_hex("00f067aa0ba902b7").unwrap()
+        );
+        assert!(span
+            .attributes
+            .contains(&KeyValue::new("http.response.status_code", 200)));
+    }
+}
@@ -5,18 +5,35 @@
 //! for reading configuration data from the environment, via `std::env`, and
 //! converting it to useful types.
 //!
+//! To read the same settings from a file as well, with the environment still
+//! overriding it, see the `config` module.
+//!
 //! [1]: https://12factor.net/
 //! [2]: https://12factor.net/config
 
 #[cfg(test)]
 use proptest_derive::Arbitrary;
+use std::collections::BTreeMap;
 use std::env;
 use std::io;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs};
 use std::num;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
 use thiserror::Error;
+use url::Url;
+
+use crate::github::{InvalidRepository, Repository};
+#[cfg(feature = "irc")]
+use crate::irc::Irc;
+#[cfg(feature = "matrix")]
+use crate::matrix::Matrix;
+use crate::schedule::{InvalidCron, Schedule};
+use crate::theme::{self, InvalidColor, Theme};
+use crate::view::Markdown;
 
-/// Errors that can arise when reading a port number from the environment.
+/// Errors that can arise when reading configuration from the environment.
 ///
 /// For convenience when returning errors into `main`, this type can be
 /// converted to std::io::Error.
@@ -42,6 +59,61 @@ pub enum Error {
         #[from]
         source: num::ParseIntError,
     },
+    /// THINGS_TO_CHECK_SOURCE was set to something that looks like a URL, but
+    /// isn't a valid one.
+    ///
+    /// Rather than treating the value as a file path, which would almost
+    /// certainly fail later with a more confusing error, we report it.
+    #[error("THINGS_TO_CHECK_SOURCE must be a path or a valid URL ({source})")]
+    InvalidUrl {
+        #[from]
+        source: url::ParseError,
+    },
+    /// THINGS_TO_CHECK_SOURCE was set to a `github:` source, but didn't name
+    /// a file in a repository.
+    #[error("THINGS_TO_CHECK_SOURCE must name a file in a GitHub repository ({source})")]
+    InvalidRepository {
+        #[from]
+        source: InvalidRepository,
+    },
+    /// THINGS_TO_CHECK_REFRESH was set, but not to a whole number of seconds.
+    #[error("THINGS_TO_CHECK_REFRESH must be a number of seconds, not {0:?}")]
+    InvalidRefresh(String),
+    /// THINGS_TO_CHECK_SCHEDULE was set, but not to a crontab-style schedule.
+    #[error("THINGS_TO_CHECK_SCHEDULE must be a crontab schedule ({source})")]
+    InvalidSchedule {
+        #[from]
+        source: InvalidCron,
+    },
+    /// A variable that should hold URLs held something else.
+    #[error("{name} must hold URLs, not {value:?}")]
+    NotAUrl { name: &'static str, value: String },
+    /// IRC_SERVERS held something other than host names with optional ports.
+    #[error("IRC_SERVERS must hold host names, with optional ports, not {0:?}")]
+    InvalidServer(String),
+    /// SLACK_TEAM_LISTS held something other than team IDs paired with list
+    /// names.
+    #[error("SLACK_TEAM_LISTS must pair team IDs with list names, as TEAM=list, not {0:?}")]
+    InvalidTeamList(String),
+    /// TWITCH_COOLDOWN was set, but not to a whole number of seconds.
+    #[error("TWITCH_COOLDOWN must be a number of seconds, not {0:?}")]
+    InvalidCooldown(String),
+    /// A variable that should hold a CSS color held something else.
+    #[error("{name} must be a CSS color ({source})")]
+    InvalidColor {
+        name: &'static str,
+        source: InvalidColor,
+    },
+    /// THINGS_TO_CHECK_MARKDOWN named something other than a Markdown
+    /// extension.
+    #[error("THINGS_TO_CHECK_MARKDOWN must list Markdown extensions, not {0:?}")]
+    InvalidMarkdownExtension(String),
+    /// A variable that must be set alongside another was unset.
+    #[error("{name} must be set along with {with}")]
+    Missing {
+        name: &'static str,
+        with: &'static str,
+    },
 }
 
 /// A listen address consisting of only a port number.
@@ -64,6 +136,12 @@ fn v6(port_addr: &PortAddr) -> SocketAddr {
     SocketAddr::new(IpAddr::from(Ipv6Addr::UNSPECIFIED), port_addr.port)
 }
 
+impl From<u16> for PortAddr {
+    fn from(port: u16) -> Self {
+        PortAddr { port }
+    }
+}
+
 impl ToSocketAddrs for PortAddr {
     type Iter = std::vec::IntoIter<SocketAddr>;
 
@@ -104,6 +182,504 @@ pub fn port(default_port: u16) -> Result<PortAddr, Error> {
     Ok(PortAddr { port })
 }
 
+/// Where to load suggestions from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// A file or directory on disk.
+    Path(PathBuf),
+    /// An `http` or `https` URL.
+    Url(Url),
+    /// A file in a GitHub repository.
+    GitHub(Repository),
+}
+
+/// Query the environment for a source of suggestions.
+///
+/// This will read the THINGS_TO_CHECK_SOURCE environment variable. If it's
+/// unset, this returns `None`, and the app should use its built-in
+/// suggestions. Values starting with `http://` or `https://` are URLs, values
+/// of the form `github:owner/repository/path[@branch]` are files in GitHub
+/// repositories, and anything else is a path. If the variable holds an invalid
+/// URL or repository, this will return an error.
+///
+/// # Examples
+///
+/// ```
+/// use things_to_check::twelve;
+///
+/// match twelve::source()? {
+///     Some(twelve::Source::Path(path)) => println!("Loading {}", path.display()),
+///     Some(twelve::Source::Url(url)) => println!("Fetching {}", url),
+///     Some(twelve::Source::GitHub(repository)) => println!("Fetching {}", repository),
+///     None => println!("Using built-in suggestions"),
+/// }
+/// # Ok::<(), twelve::Error>(())
+/// ```
+pub fn source() -> Result<Option<Source>, Error> {
+    let source = match env::var_os("THINGS_TO_CHECK_SOURCE") {
+        Some(source) => source,
+        None => return Ok(None),
+    };
+
+    match source.to_str() {
+        Some(source) => Ok(Some(source.parse()?)),
+        None => Ok(Some(Source::Path(PathBuf::from(source)))),
+    }
+}
+
+/// Reads a source in the form `source()` reads THINGS_TO_CHECK_SOURCE in, for
+/// sources named elsewhere, such as on the command line.
+impl FromStr for Source {
+    type Err = Error;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let source = match source {
+            url if url.starts_with("http://") || url.starts_with("https://") => {
+                Source::Url(Url::parse(url)?)
+            }
+            source if source.starts_with("github:") => {
+                Source::GitHub(source["github:".len()..].parse()?)
+            }
+            _ => Source::Path(PathBuf::from(source)),
+        };
+
+        Ok(source)
+    }
+}
+
+/// Query the environment for how often to check remote suggestions for
+/// changes.
+///
+/// This will read the THINGS_TO_CHECK_REFRESH environment variable, as a
+/// number of seconds. If it's unset, this returns `default_refresh`. If it's set
+/// to anything other than a whole number, this will return an error.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use things_to_check::twelve;
+///
+/// let refresh = twelve::refresh(Duration::from_secs(300))?;
+/// # Ok::<(), twelve::Error>(())
+/// ```
+pub fn refresh(default_refresh: Duration) -> Result<Duration, Error> {
+    let refresh = match env::var_os("THINGS_TO_CHECK_REFRESH") {
+        Some(refresh) => refresh,
+        None => return Ok(default_refresh),
+    };
+
+    refresh
+        .to_str()
+        .and_then(|refresh| refresh.parse().ok())
+        .map(Duration::from_secs)
+        .ok_or_else(|| Error::InvalidRefresh(refresh.to_string_lossy().into_owned()))
+}
+
+/// Query the environment for suggestions to hide.
+///
+/// This will read the THINGS_TO_CHECK_EXCLUDE environment variable, a
+/// comma-separated list of entries in the form accepted by
+/// `view::Options::exclude`. Whitespace around entries, and empty entries, are
+/// ignored. If it's unset, or isn't unicode, nothing is hidden.
+///
+/// # Examples
+///
+/// ```
+/// use things_to_check::{twelve, view};
+///
+/// let options = view::Options {
+///     exclude: twelve::exclude(),
+///     ..Default::default()
+/// };
+/// ```
+pub fn exclude() -> Vec<String> {
+    list("THINGS_TO_CHECK_EXCLUDE")
+}
+
+/// Query the environment for the extensions to Markdown suggestions may use.
+///
+/// This will read the THINGS_TO_CHECK_MARKDOWN environment variable, a list
+/// read in the same way as `exclude()`, of the extensions to allow:
+/// `strikethrough`, `tables`, `footnotes`, and `smart-punctuation`. If it's
+/// unset, all of them are allowed; if it's empty, none are. If it names
+/// anything else, this will return an error.
+///
+/// # Examples
+///
+/// ```
+/// use things_to_check::{twelve, view};
+///
+/// let options = view::Options {
+///     markdown: twelve::markdown()?,
+///     ..Default::default()
+/// };
+/// # Ok::<(), twelve::Error>(())
+/// ```
+pub fn markdown() -> Result<Markdown, Error> {
+    if env::var_os("THINGS_TO_CHECK_MARKDOWN").is_none() {
+        return Ok(Markdown::default());
+    }
+
+    let mut markdown = Markdown {
+        strikethrough: false,
+        tables: false,
+        footnotes: false,
+        smart_punctuation: false,
+    };
+    for extension in list("THINGS_TO_CHECK_MARKDOWN") {
+        match extension.as_str() {
+            "strikethrough" => markdown.strikethrough = true,
+            "tables" => markdown.tables = true,
+            "footnotes" => markdown.footnotes = true,
+            "smart-punctuation" => markdown.smart_punctuation = true,
+            _ => return Err(Error::InvalidMarkdownExtension(extension)),
+        }
+    }
+
+    Ok(markdown)
+}
+
+/// Query the environment for the origins allowed to call the API from a
+/// browser.
+///
+/// This will read the THINGS_TO_CHECK_CORS_ORIGINS environment variable, a
+/// comma-separated list of origins in the form accepted by `api::Cors::new`,
+/// such as `https://status.example.com`, or `*` for any origin. It's read in
+/// the same way as `exclude()`; if it's unset, CORS should stay off.
+///
+/// # Examples
+///
+/// ```
+/// use actix_web::web;
+/// use things_to_check::{api, twelve};
+///
+/// let origins = twelve::cors_origins();
+/// let cors = (!origins.is_empty()).then(|| web::Data::new(api::Cors::new(origins)));
+/// ```
+pub fn cors_origins() -> Vec<String> {
+    list("THINGS_TO_CHECK_CORS_ORIGINS")
+}
+
+/// The schedule posts suggestions on if THINGS_TO_CHECK_SCHEDULE is unset.
+pub const DEFAULT_SCHEDULE: &str = "0 9 * * *";
+
+/// Query the environment for the service's public URL.
+///
+/// This will read the THINGS_TO_CHECK_URL environment variable, the URL
+/// people reach the index page at, such as `https://troubleshoot.example.com/`.
+/// Integrations link to suggestions' permalinks under it, rather than under
+/// the address requests arrived at, which can differ behind a proxy. If it's
+/// unset, this returns `None`. Invalid URLs are reported as errors.
+pub fn public_url() -> Result<Option<Url>, Error> {
+    match env::var("THINGS_TO_CHECK_URL") {
+        Ok(index) => match Url::parse(&index) {
+            Ok(index) => Ok(Some(index)),
+            Err(_) => Err(Error::NotAUrl {
+                name: "THINGS_TO_CHECK_URL",
+                value: index,
+            }),
+        },
+        Err(_) => Ok(None),
+    }
+}
+
+/// Query the environment for how to brand the HTML pages.
+///
+/// This will read the THINGS_TO_CHECK_TITLE, THINGS_TO_CHECK_ACCENT,
+/// THINGS_TO_CHECK_DARK_ACCENT, THINGS_TO_CHECK_LOGO_URL,
+/// THINGS_TO_CHECK_ICON_URL, and THINGS_TO_CHECK_FOOTER environment variables
+/// into the matching fields of a `theme::Theme`; unset variables leave their
+/// fields unset. Colors that aren't plain CSS colors, and logo and icon URLs
+/// that aren't URLs, are reported as errors.
+pub fn theme() -> Result<Theme, Error> {
+    let color = |name| match env::var(name) {
+        Ok(color) => theme::color(&color)
+            .map(Some)
+            .map_err(|source| Error::InvalidColor { name, source }),
+        Err(_) => Ok(None),
+    };
+    let url = |name| match env::var(name) {
+        Ok(value) => Url::parse(&value)
+            .map(Some)
+            .map_err(|_| Error::NotAUrl { name, value }),
+        Err(_) => Ok(None),
+    };
+
+    Ok(Theme {
+        title: env::var("THINGS_TO_CHECK_TITLE").ok(),
+        accent: color("THINGS_TO_CHECK_ACCENT")?,
+        dark_accent: color("THINGS_TO_CHECK_DARK_ACCENT")?,
+        logo: url("THINGS_TO_CHECK_LOGO_URL")?,
+        icon: url("THINGS_TO_CHECK_ICON_URL")?,
+        footer: env::var("THINGS_TO_CHECK_FOOTER").ok(),
+    })
+}
+
+/// Query the environment for the suggestion lists Slack workspaces choose from.
+///
+/// This will read the SLACK_TEAM_LISTS environment variable, a comma-separated
+/// list read in the same way as `exclude()`, of team IDs each paired with the
+/// name of a list, such as `T0123ABCD=ops, T0456EFGH=support`. If it's unset,
+/// this returns no lists, and every workspace gets the default list. Entries
+/// without a team ID or a list name are reported as errors. See
+/// `slack::Slack::with_team_lists`.
+pub fn slack_team_lists() -> Result<BTreeMap<String, String>, Error> {
+    list("SLACK_TEAM_LISTS")
+        .into_iter()
+        .map(|entry| match entry.split_once('=') {
+            Some((team, list)) if !team.trim().is_empty() && !list.trim().is_empty() => {
+                Ok((team.trim().to_owned(), list.trim().to_owned()))
+            }
+            _ => Err(Error::InvalidTeamList(entry)),
+        })
+        .collect()
+}
+
+/// Query the environment for webhooks to post suggestions to as JSON.
+///
+/// This will read the THINGS_TO_CHECK_NOTIFY_URLS environment variable, a
+/// comma-separated list of webhook URLs read in the same way as `exclude()`.
+/// If it's unset, this returns no webhooks. Invalid URLs are reported as
+/// errors. See the `notify` module.
+///
+/// # Examples
+///
+/// ```
+/// use actix_web::web;
+/// use things_to_check::{notify, twelve};
+///
+/// let webhooks = twelve::notify_urls()?;
+/// let notifier = (!webhooks.is_empty()).then(|| web::Data::new(notify::Notifier::new(webhooks)));
+/// # Ok::<(), twelve::Error>(())
+/// ```
+pub fn notify_urls() -> Result<Vec<Url>, Error> {
+    list("THINGS_TO_CHECK_NOTIFY_URLS")
+        .into_iter()
+        .map(|webhook| {
+            Url::parse(&webhook).map_err(|_| Error::NotAUrl {
+                name: "THINGS_TO_CHECK_NOTIFY_URLS",
+                value: webhook,
+            })
+        })
+        .collect()
+}
+
+/// Query the environment for when and where to post suggestions to chat.
+///
+/// This will read the THINGS_TO_CHECK_WEBHOOKS environment variable, a
+/// comma-separated list of chat webhook URLs read in the same way as
+/// `exclude()`, and the webhooks to post suggestions to as JSON, as for
+/// `notify_urls()`; if neither is set, this returns `None`, and nothing
+/// should be posted. The
+/// schedule is read from THINGS_TO_CHECK_SCHEDULE, as for
+/// `schedule::Cron`, or is `DEFAULT_SCHEDULE`, every day at 09:00 UTC, if
+/// that's unset. If THINGS_TO_CHECK_URL is set, posts link to suggestions'
+/// permalinks on the index page at that URL. Invalid schedules and URLs are
+/// reported as errors.
+///
+/// # Examples
+///
+/// ```
+/// use things_to_check::{twelve, view};
+///
+/// let options = view::Options {
+///     schedule: twelve::schedule()?,
+///     ..Default::default()
+/// };
+/// # Ok::<(), twelve::Error>(())
+/// ```
+pub fn schedule() -> Result<Option<Schedule>, Error> {
+    let url = |name, value: String| Url::parse(&value).map_err(|_| Error::NotAUrl { name, value });
+
+    let webhooks = list("THINGS_TO_CHECK_WEBHOOKS")
+        .into_iter()
+        .map(|webhook| url("THINGS_TO_CHECK_WEBHOOKS", webhook))
+        .collect::<Result<Vec<_>, _>>()?;
+    let notify = notify_urls()?;
+    if webhooks.is_empty() && notify.is_empty() {
+        return Ok(None);
+    }
+
+    let when = env::var("THINGS_TO_CHECK_SCHEDULE")
+        .as_deref()
+        .unwrap_or(DEFAULT_SCHEDULE)
+        .parse()?;
+    let schedule = Schedule::new(when, webhooks).notifying(notify);
+
+    match public_url()? {
+        Some(index) => Ok(Some(schedule.linking_to(index))),
+        None => Ok(Some(schedule)),
+    }
+}
+
+/// Query the environment for a Matrix bot to run.
+///
+/// This will read the MATRIX_HOMESERVER environment variable, the URL of the
+/// homeserver to log in to; if it's unset, this returns `None`, and no bot
+/// should run. The bot logs in as MATRIX_USER with MATRIX_PASSWORD, which
+/// must both be set, and joins the rooms in MATRIX_ROOMS, a comma-separated
+/// list of room IDs and aliases read in the same way as `exclude()`. If
+/// THINGS_TO_CHECK_URL is set, replies link to suggestions' permalinks on the
+/// index page at that URL.
+///
+/// This is only available with the `matrix` feature.
+#[cfg(feature = "matrix")]
+pub fn matrix() -> Result<Option<Matrix>, Error> {
+    let url = |name, value: String| Url::parse(&value).map_err(|_| Error::NotAUrl { name, value });
+    let required = |name| {
+        env::var(name).map_err(|_| Error::Missing {
+            name,
+            with: "MATRIX_HOMESERVER",
+        })
+    };
+
+    let homeserver = match env::var("MATRIX_HOMESERVER") {
+        Ok(homeserver) => url("MATRIX_HOMESERVER", homeserver)?,
+        Err(_) => return Ok(None),
+    };
+    let matrix = Matrix::new(
+        homeserver,
+        required("MATRIX_USER")?,
+        required("MATRIX_PASSWORD")?,
+    )
+    .joining(list("MATRIX_ROOMS"));
+
+    match public_url()? {
+        Some(index) => Ok(Some(matrix.linking_to(index))),
+        None => Ok(Some(matrix)),
+    }
+}
+
+/// The nickname IRC bots use if IRC_NICK is unset.
+#[cfg(feature = "irc")]
+pub const DEFAULT_IRC_NICK: &str = "troubleshoot";
+
+/// Query the environment for IRC servers to run bots on.
+///
+/// This will read the IRC_SERVERS environment variable, a comma-separated
+/// list of servers read in the same way as `exclude()`, each a host name
+/// optionally followed by a colon and a port, such as `irc.libera.chat:6697`;
+/// if it's unset, this returns no servers. On each server, the bot uses the
+/// nickname in IRC_NICK, or `DEFAULT_IRC_NICK` if that's unset, sends the
+/// server password in IRC_PASSWORD, if set, and joins the channels in
+/// IRC_CHANNELS, another comma-separated list. If THINGS_TO_CHECK_URL is set,
+/// replies end with suggestions' permalinks on the index page at that URL.
+///
+/// This is only available with the `irc` feature.
+#[cfg(feature = "irc")]
+pub fn irc() -> Result<Vec<Irc>, Error> {
+    let nickname = env::var("IRC_NICK").unwrap_or_else(|_| DEFAULT_IRC_NICK.to_owned());
+    let password = env::var("IRC_PASSWORD").ok();
+    let channels = list("IRC_CHANNELS");
+    let index = public_url()?;
+
+    list("IRC_SERVERS")
+        .into_iter()
+        .map(|server| {
+            let irc = match server.rsplit_once(':') {
+                Some((host, port)) => match port.parse() {
+                    Ok(port) if !host.is_empty() => Irc::new(host, &nickname).on_port(port),
+                    _ => return Err(Error::InvalidServer(server)),
+                },
+                None => Irc::new(server, &nickname),
+            };
+            let irc = irc.joining(channels.clone());
+            let irc = match &password {
+                Some(password) => irc.with_password(password),
+                None => irc,
+            };
+            Ok(match &index {
+                Some(index) => irc.linking_to(index.clone()),
+                None => irc,
+            })
+        })
+        .collect()
+}
+
+/// The cooldown Twitch bots use if TWITCH_COOLDOWN is unset.
+#[cfg(feature = "irc")]
+pub const DEFAULT_TWITCH_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Query the environment for a Twitch chat bot to run.
+///
+/// This will read the TWITCH_OAUTH_TOKEN environment variable, the bot
+/// account's OAuth token; if it's unset, this returns `None`, and no bot
+/// should run. The bot logs in as TWITCH_NICK, which must also be set, and
+/// joins the channels in TWITCH_CHANNELS, a comma-separated list read in the
+/// same way as `exclude()`, with or without their leading `#`. It answers at
+/// most once in each channel every TWITCH_COOLDOWN seconds, or
+/// `DEFAULT_TWITCH_COOLDOWN` if that's unset. If THINGS_TO_CHECK_URL is set,
+/// replies end with suggestions' permalinks on the index page at that URL.
+///
+/// This is only available with the `irc` feature.
+#[cfg(feature = "irc")]
+pub fn twitch() -> Result<Option<Irc>, Error> {
+    let token = match env::var("TWITCH_OAUTH_TOKEN") {
+        Ok(token) => token,
+        Err(_) => return Ok(None),
+    };
+    let nickname = env::var("TWITCH_NICK").map_err(|_| Error::Missing {
+        name: "TWITCH_NICK",
+        with: "TWITCH_OAUTH_TOKEN",
+    })?;
+    let cooldown = match env::var("TWITCH_COOLDOWN") {
+        Ok(cooldown) => match cooldown.parse() {
+            Ok(secs) => Duration::from_secs(secs),
+            Err(_) => return Err(Error::InvalidCooldown(cooldown)),
+        },
+        Err(_) => DEFAULT_TWITCH_COOLDOWN,
+    };
+    let channels = list("TWITCH_CHANNELS")
+        .into_iter()
+        .map(|channel| format!("#{}", channel.trim_start_matches('#').to_lowercase()))
+        .collect();
+
+    let twitch = Irc::twitch(nickname, &token)
+        .joining(channels)
+        .cooling_down(cooldown);
+
+    match public_url()? {
+        Some(index) => Ok(Some(twitch.linking_to(index))),
+        None => Ok(Some(twitch)),
+    }
+}
+
+/// Query the environment for an OpenTelemetry collector to send traces to.
+///
+/// This will read the OTEL_EXPORTER_OTLP_ENDPOINT environment variable, the
+/// base URL of a collector accepting OTLP over HTTP, such as
+/// `http://localhost:4318/`; if it's unset, this returns `None`, and requests
+/// should not be traced. See `trace::install`.
+///
+/// This is only available with the `otel` feature.
+#[cfg(feature = "otel")]
+pub fn otlp_endpoint() -> Result<Option<Url>, Error> {
+    match env::var("OTEL_EXPORTER_OTLP_ENDPOINT") {
+        Ok(endpoint) => match Url::parse(&endpoint) {
+            Ok(endpoint) => Ok(Some(endpoint)),
+            Err(_) => Err(Error::NotAUrl {
+                name: "OTEL_EXPORTER_OTLP_ENDPOINT",
+                value: endpoint,
+            }),
+        },
+        Err(_) => Ok(None),
+    }
+}
+
+// A comma-separated list of entries in the environment variable `name`.
+fn list(name: &str) -> Vec<String> {
+    env::var(name)
+        .map(|list| {
+            list.split(',')
+                .map(str::trim)
+                .filter(|entry| !entry.is_empty())
+                .map(str::to_owned)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
     use lazy_static::lazy_static;
@@ -163,7 +739,7 @@ mod tests {
         // tests through a mutex.
         //
         // Huge hack.
-        static ref ENV_MUTEX: Mutex<Runner> = Mutex::new(Runner::default());
+        static ref ENV_MUTEX: Mutex<Runner> = Mutex::new(Runner);
     }
 
     // Runs a body with ENV_MUTEX locked. Easier to write.
@@ -194,7 +770,7 @@ mod tests {
             prop_assume!(env_port.parse::<u16>().is_err());
 
             env_locked(|| {
-                env::set_var("PORT", env_port.to_string());
+                env::set_var("PORT", &env_port);
 
                 let port_result = port(default_port);
 
@@ -214,6 +790,342 @@ mod tests {
         }
     }
 
+    #[test]
+    fn source_unset() {
+        env_locked(|| {
+            env::remove_var("THINGS_TO_CHECK_SOURCE");
+
+            assert_eq!(source().unwrap(), None);
+        })
+    }
+
+    #[test]
+    fn source_path() {
+        env_locked(|| {
+            env::set_var("THINGS_TO_CHECK_SOURCE", "/etc/things-to-check.yml");
+
+            assert_eq!(
+                source().unwrap(),
+                Some(Source::Path(PathBuf::from("/etc/things-to-check.yml")))
+            );
+        })
+    }
+
+    #[test]
+    fn source_url() {
+        env_locked(|| {
+            env::set_var("THINGS_TO_CHECK_SOURCE", "https://example.com/things.yml");
+
+            assert_eq!(
+                source().unwrap(),
+                Some(Source::Url(
+                    Url::parse("https://example.com/things.yml").unwrap()
+                ))
+            );
+        })
+    }
+
+    #[test]
+    fn source_github() {
+        env_locked(|| {
+            env::set_var(
+                "THINGS_TO_CHECK_SOURCE",
+                "github:ojacobson/things-to-check/src/things-to-check.yml",
+            );
+
+            assert_eq!(
+                source().unwrap(),
+                Some(Source::GitHub(Repository::new(
+                    "ojacobson",
+                    "things-to-check",
+                    "src/things-to-check.yml",
+                    "main"
+                )))
+            );
+        })
+    }
+
+    #[test]
+    fn source_invalid_url() {
+        env_locked(|| {
+            env::set_var("THINGS_TO_CHECK_SOURCE", "https://");
+
+            assert!(source().is_err());
+        })
+    }
+
+    #[test]
+    fn refresh_uses_default() {
+        env_locked(|| {
+            env::remove_var("THINGS_TO_CHECK_REFRESH");
+
+            assert_eq!(
+                refresh(Duration::from_secs(300)).unwrap(),
+                Duration::from_secs(300)
+            );
+        })
+    }
+
+    #[test]
+    fn refresh_reads_seconds() {
+        env_locked(|| {
+            env::set_var("THINGS_TO_CHECK_REFRESH", "60");
+
+            assert_eq!(
+                refresh(Duration::from_secs(300)).unwrap(),
+                Duration::from_secs(60)
+            );
+
+            env::set_var("THINGS_TO_CHECK_REFRESH", "soon");
+
+            assert!(refresh(Duration::from_secs(300)).is_err());
+        })
+    }
+
+    #[test]
+    fn schedule_needs_webhooks() {
+        env_locked(|| {
+            env::remove_var("THINGS_TO_CHECK_WEBHOOKS");
+            env::set_var("THINGS_TO_CHECK_SCHEDULE", "0 9 * * *");
+
+            assert!(schedule().unwrap().is_none());
+        })
+    }
+
+    #[test]
+    fn schedule_reads_webhooks() {
+        env_locked(|| {
+            env::set_var(
+                "THINGS_TO_CHECK_WEBHOOKS",
+                "https://hooks.slack.com/services/T0/B0/X, https://discord.com/api/webhooks/1/x",
+            );
+            env::set_var("THINGS_TO_CHECK_SCHEDULE", "30 8 * * 1-5");
+
+            assert!(schedule().unwrap().is_some());
+
+            env::set_var("THINGS_TO_CHECK_SCHEDULE", "every morning");
+
+            assert!(schedule().is_err());
+
+            env::remove_var("THINGS_TO_CHECK_SCHEDULE");
+            env::set_var("THINGS_TO_CHECK_WEBHOOKS", "hooks.slack.com");
+
+            assert!(schedule().is_err());
+
+            env::remove_var("THINGS_TO_CHECK_WEBHOOKS");
+        })
+    }
+
+    #[cfg(feature = "matrix")]
+    #[test]
+    fn matrix_needs_credentials() {
+        env_locked(|| {
+            env::remove_var("MATRIX_HOMESERVER");
+
+            assert!(matrix().unwrap().is_none());
+
+            env::set_var("MATRIX_HOMESERVER", "https://matrix.example.com");
+            env::set_var("MATRIX_USER", "troubleshoot");
+            env::remove_var("MATRIX_PASSWORD");
+
+            assert!(matrix().is_err());
+
+            env::set_var("MATRIX_PASSWORD", "s3kr1t");
+
+            assert!(matrix().unwrap().is_some());
+
+            env::remove_var("MATRIX_HOMESERVER");
+            env::remove_var("MATRIX_USER");
+            env::remove_var("MATRIX_PASSWORD");
+        })
+    }
+
+    #[cfg(feature = "irc")]
+    #[test]
+    fn irc_reads_servers() {
+        env_locked(|| {
+            env::remove_var("IRC_SERVERS");
+
+            assert!(irc().unwrap().is_empty());
+
+            env::set_var("IRC_SERVERS", "irc.libera.chat, irc.example.com:6697");
+            env::set_var("IRC_CHANNELS", "#ops");
+
+            assert_eq!(irc().unwrap().len(), 2);
+
+            env::set_var("IRC_SERVERS", "irc.example.com:ircs");
+
+            assert!(irc().is_err());
+
+            env::remove_var("IRC_SERVERS");
+            env::remove_var("IRC_CHANNELS");
+        })
+    }
+
+    #[cfg(feature = "irc")]
+    #[test]
+    fn twitch_needs_a_nickname() {
+        env_locked(|| {
+            env::remove_var("TWITCH_OAUTH_TOKEN");
+
+            assert!(twitch().unwrap().is_none());
+
+            env::set_var("TWITCH_OAUTH_TOKEN", "oauth:abc123");
+            env::remove_var("TWITCH_NICK");
+
+            assert!(twitch().is_err());
+
+            env::set_var("TWITCH_NICK", "troublebot");
+            env::set_var("TWITCH_COOLDOWN", "a while");
+
+            assert!(twitch().is_err());
+
+            env::set_var("TWITCH_COOLDOWN", "60");
+
+            assert!(twitch().unwrap().is_some());
+
+            env::remove_var("TWITCH_OAUTH_TOKEN");
+            env::remove_var("TWITCH_NICK");
+            env::remove_var("TWITCH_COOLDOWN");
+        })
+    }
+
+    #[test]
+    fn notify_urls_list() {
+        env_locked(|| {
+            env::remove_var("THINGS_TO_CHECK_WEBHOOKS");
+            env::set_var(
+                "THINGS_TO_CHECK_NOTIFY_URLS",
+                "https://example.com/hooks/a, https://example.com/hooks/b",
+            );
+
+            assert_eq!(notify_urls().unwrap().len(), 2);
+            assert!(schedule().unwrap().is_some());
+
+            env::set_var("THINGS_TO_CHECK_NOTIFY_URLS", "example.com/hooks");
+
+            assert!(notify_urls().is_err());
+
+            env::remove_var("THINGS_TO_CHECK_NOTIFY_URLS");
+        })
+    }
+
+    #[test]
+    fn theme_reads_branding() {
+        env_locked(|| {
+            env::set_var("THINGS_TO_CHECK_TITLE", "Example Corp");
+            env::set_var("THINGS_TO_CHECK_ACCENT", "#c0392b");
+            env::remove_var("THINGS_TO_CHECK_DARK_ACCENT");
+            env::remove_var("THINGS_TO_CHECK_LOGO_URL");
+            env::remove_var("THINGS_TO_CHECK_ICON_URL");
+            env::remove_var("THINGS_TO_CHECK_FOOTER");
+
+            let read = theme().unwrap();
+            assert_eq!(read.title.as_deref(), Some("Example Corp"));
+            assert_eq!(read.accent.as_deref(), Some("#c0392b"));
+            assert_eq!(read.dark_accent, None);
+
+            env::set_var("THINGS_TO_CHECK_ACCENT", "red; } body { display: none");
+
+            assert!(theme().is_err());
+
+            env::remove_var("THINGS_TO_CHECK_ACCENT");
+            env::set_var("THINGS_TO_CHECK_LOGO_URL", "logo.png");
+
+            assert!(theme().is_err());
+
+            env::remove_var("THINGS_TO_CHECK_LOGO_URL");
+            env::set_var("THINGS_TO_CHECK_ICON_URL", "https://example.com/icon.png");
+
+            assert_eq!(
+                theme().unwrap().icon.map(String::from).as_deref(),
+                Some("https://example.com/icon.png")
+            );
+
+            env::remove_var("THINGS_TO_CHECK_TITLE");
+            env::remove_var("THINGS_TO_CHECK_ICON_URL");
+        })
+    }
+
+    #[test]
+    fn slack_team_lists_pair_teams_with_lists() {
+        env_locked(|| {
+            env::remove_var("SLACK_TEAM_LISTS");
+
+            assert!(slack_team_lists().unwrap().is_empty());
+
+            env::set_var("SLACK_TEAM_LISTS", "T0123=ops, T0456 = support");
+
+            let lists = slack_team_lists().unwrap();
+            assert_eq!(lists.get("T0123").map(String::as_str), Some("ops"));
+            assert_eq!(lists.get("T0456").map(String::as_str), Some("support"));
+
+            env::set_var("SLACK_TEAM_LISTS", "T0123");
+
+            assert!(slack_team_lists().is_err());
+
+            env::remove_var("SLACK_TEAM_LISTS");
+        })
+    }
+
+    #[test]
+    fn exclude_unset() {
+        env_locked(|| {
+            env::remove_var("THINGS_TO_CHECK_EXCLUDE");
+
+            assert!(exclude().is_empty());
+        })
+    }
+
+    #[test]
+    fn exclude_list() {
+        env_locked(|| {
+            env::set_var("THINGS_TO_CHECK_EXCLUDE", "dns, kubernetes:3,,");
+
+            assert_eq!(exclude(), vec!["dns", "kubernetes:3"]);
+        })
+    }
+
+    #[test]
+    fn markdown_extensions() {
+        env_locked(|| {
+            env::remove_var("THINGS_TO_CHECK_MARKDOWN");
+
+            assert_eq!(markdown().unwrap(), Markdown::default());
+
+            env::set_var("THINGS_TO_CHECK_MARKDOWN", "tables, footnotes");
+
+            let extensions = markdown().unwrap();
+            assert!(extensions.tables && extensions.footnotes);
+            assert!(!extensions.strikethrough && !extensions.smart_punctuation);
+
+            env::set_var("THINGS_TO_CHECK_MARKDOWN", "");
+
+            assert!(!markdown().unwrap().tables);
+
+            env::set_var("THINGS_TO_CHECK_MARKDOWN", "emoji");
+
+            assert!(markdown().is_err());
+
+            env::remove_var("THINGS_TO_CHECK_MARKDOWN");
+        })
+    }
+
+    #[test]
+    fn cors_origins_list() {
+        env_locked(|| {
+            env::set_var(
+                "THINGS_TO_CHECK_CORS_ORIGINS",
+                "https://status.example.com, http://localhost:8080",
+            );
+
+            assert_eq!(
+                cors_origins(),
+                vec!["https://status.example.com", "http://localhost:8080"]
+            );
+        })
+    }
+
     #[test]
     fn port_non_unicode() {
         let non_unicode = OsStr::from_bytes(&[0xF5u8]);
@@ -26,39 +26,246 @@
 //!
 //! * `/` (`GET`): an HTML page suggesting one thing to check.
 //!
-//!   Takes an optional `item` URL parameter, which must be an integer between 0
-//!   and the number of options available (not provided). If `item` is provided,
-//!   this endpoint returns a fixed result (the `item`th suggestion in the
-//!   backing data); otherwise, it returns a randomly-selected result, for
-//!   fortuitous suggesting.
+//!   Takes an optional `item` URL parameter, which must be either the `id` of a
+//!   suggestion, or an integer between 0 and the number of options available
+//!   (not provided). If `item` is provided, this endpoint redirects, with
+//!   `302 Found`, to the permalink of a fixed result (the suggestion with that
+//!   `id`, or the `item`th suggestion in the backing data) under `/check`;
+//!   otherwise, it returns a randomly-selected result, for fortuitous
+//!   suggesting. Retired suggestions are never selected at random, but are
+//!   still returned, marked as retired, when requested by `item`.
 //!
-//!   The returned page is always `text/html` on success. Invalid `item` indices
-//!   will return an error.
+//!   Takes an optional `list` URL parameter, naming the list of suggestions to
+//!   choose from. If `list` is not provided, the first list is used. The `item`
+//!   parameter is an index into the chosen list.
+//!
+//!   Takes an optional `lang` URL parameter, holding a language code such as
+//!   `fr` or `pt-BR`. Suggestions with a translation into that language are
+//!   shown in that language; others are shown in English.
+//!
+//!   Takes an optional `tag` URL parameter. If `tag` is provided, only
+//!   suggestions carrying that tag are chosen, and an `item` without that tag
+//!   is treated as missing.
+//!
+//!   Suggestions may list `aliases`: the old `item` values of suggestions that
+//!   have since been given an `id` or moved. Requests for an alias are
+//!   redirected to the suggestion's permalink too. These redirects are never
+//!   permanent, as permalinks change with suggestions' text, and a cached
+//!   redirect would outlive them.
+//!
+//!   The returned page is always `text/html` on success. Invalid `item` indices,
+//!   unknown `list` names, and tags matching no suggestions will return an
+//!   error. Randomly-selected pages are never cached.
+//!
+//! * `/check/{slug}` (`GET`): the HTML page for one suggestion, its
+//!   permalink. The slug is made from the suggestion's text, such as
+//!   `have-you-checked-dns`; see the `things` module. Takes the same `list`,
+//!   `tag`, and `lang` parameters as `/`.
+//!
+//!   Pages carry a strong `ETag`, and requests whose `If-None-Match` header
+//!   matches it return `304 Not Modified`, so that browsers and proxies can
+//!   cache permalinks. A slug changes if its suggestion's text does; links
+//!   that must never break should use `/?item=` with the suggestion's `id`.
+//!
+//! * `/search` (`GET`): an HTML page listing the suggestions matching a
+//!   search.
+//!
+//!   Takes a `q` URL parameter holding the words to search for, and the `list`
+//!   and `lang` parameters described above. Matches are found and ranked as
+//!   for `/api/v1/search`; see the `api` module. Every suggestion page links
+//!   here through a search box.
+//!
+//! * `/all` (`GET`): an HTML page listing every suggestion in a list, in
+//!   order, for readers who would rather scan them all than trust to chance.
+//!
+//!   Takes the same `list`, `tag`, and `lang` parameters as `/`. Each
+//!   suggestion is anchored by its slug, as in `/all#have-you-checked-dns`,
+//!   and links to its permalink. Retired suggestions are listed, marked as
+//!   retired. With scripts, a box above the list hides the suggestions that
+//!   don't match what's typed into it. The page carries an `ETag`, as
+//!   permalinks do.
+//!
+//! * `/categories` (`GET`): an HTML page listing the tags carried by the
+//!   suggestions in a list, as categories to browse, with the number of
+//!   suggestions in each. Takes the same `list` and `lang` parameters as `/`.
+//!
+//! * `/category/{name}` (`GET`): the `/all` page for the suggestions tagged
+//!   `name`, such as `/category/dns`. Takes the same `list` and `lang`
+//!   parameters as `/`; categories no suggestion is tagged with return `404
+//!   Not Found`.
+//!
+//! * `/healthz` (`GET`): a health check for load balancers and orchestrators.
+//!
+//!   Returns `200 OK` with the JSON body `{"status":"ok"}` while there are
+//!   suggestions to serve, and `503 Service Unavailable` with `{"status":
+//!   "unavailable"}` otherwise, such as when every suggestion has been removed.
+//!   Never picks a suggestion.
+//!
+//! * `/readyz` (`GET`): a readiness check, for orchestrators deciding whether
+//!   to route traffic here.
+//!
+//!   Returns `200 OK` with the JSON body `{"status":"ready"}` while there are
+//!   suggestions to serve and the last attempt to refresh them from their
+//!   source, if any, succeeded. Otherwise, returns `503 Service Unavailable`
+//!   with `{"status":"unavailable"}` and a `reason`: `no-suggestions`, or
+//!   `source-failed` for a file that couldn't be reloaded, a URL or GitHub
+//!   repository that couldn't be fetched, or a provider that failed. The
+//!   replica recovers as soon as a refresh succeeds. Services backed by SQLite
+//!   serve suggestions from memory once loaded, so are ready while they have
+//!   suggestions.
+//!
+//! * `/sitemap.xml` and `/robots.txt`: every suggestion's permalink, and
+//!   rules for crawlers, for search engines. See the `crawlers` module.
+//!
+//! * `/oembed`, `/badge.svg`, `/badge/…`, `/qr/…`, and `/og/…`: suggestions
+//!   for embedding in other sites, link previews, and printing. See the
+//!   `embed` module.
+//!
+//! * `/feed.xml` and `/feed.json`: feeds of suggestions. See the `feed`
+//!   module.
+//!
+//! * `/poster.pdf`: every suggestion, as a poster to print. See the `poster`
+//!   module.
+//!
+//! * `/stream`: a stream of suggestions, for wallboards. See the `stream`
+//!   module.
+//!
+//! * `/ws`: a WebSocket answering requests for suggestions, for interactive
+//!   frontends. See the `socket` module.
+//!
+//! * `/graphql`: a GraphQL endpoint, with the `graphql` feature. See the
+//!   `graphql` module.
+//!
+//! * `/api/v1/…`: suggestions as JSON, for bots and scripts. See the `api`
+//!   module.
+//!
+//! * `/suggest` and `/admin/pending/…`: submission and moderation of new
+//!   suggestions. See the `moderation` module.
+//!
+//! * `/admin`: a dashboard for operators, for moderators. See the `admin`
+//!   module.
+//!
+//! * `/notify/webhooks`: posts a suggestion as JSON to configured webhooks,
+//!   for moderators. See the `notify` module.
+//!
+//! * `/slack/…`: a Slack app, if enabled. See the `slack` module.
+//!
+//! * `/discord/interactions`: a Discord app, if enabled. See the `discord`
+//!   module.
+//!
+//! * `/teams/troubleshoot`: a Microsoft Teams outgoing webhook, if enabled.
+//!   See the `teams` module.
+//!
+//! * `/mattermost/troubleshoot`: a Mattermost slash command, if enabled. See
+//!   the `mattermost` module.
+//!
+//! * `/telegram/webhook`: a Telegram bot's webhook, if enabled. See the
+//!   `telegram` module.
+//!
+//! * `/zulip/troubleshoot`: a Zulip outgoing webhook, if enabled. See the
+//!   `zulip` module.
+//!
+//! * `/rocketchat/troubleshoot`: a Rocket.Chat outgoing webhook, if enabled.
+//!   See the `rocketchat` module.
+//!
+//! * `/webex/webhook`: a Webex bot's webhook, if enabled. See the `webex`
+//!   module.
+//!
+//! * `/hooks/github`: refreshes suggestions fetched from GitHub, for services
+//!   created with `make_github_service(…)`. See the `github` module.
 //!
 //! # Data
 //!
-//! This module creates a data item in the configured application, consisting of
-//! a list of strings loaded from a YAML constant. The data comes from a file in
-//! this module parsed at compile time — our target deployment environments
-//! don't support modifying it without triggering a rebuild anyways. It's parsed
-//! on startup, however, and invalid data can cause `make_service` to fail.
+//! This module serves a catalog of suggestions, which may be split into several
+//! named lists, such as `networking` or `kubernetes`, so that one instance can
+//! serve advice for several domains. See the `list` parameter, above.
+//!
+//! `make_service` serves the built-in suggestions, from a YAML file in this
+//! module compiled into the crate. They're parsed on startup, however, and
+//! invalid data can cause `make_service` to fail. Operators who want to change
+//! the suggestions without rebuilding can serve them from elsewhere instead:
+//! from a file or directory on disk, with `make_service_from_path(…)` or
+//! `make_reloadable_service(…)`; from a URL, with `make_service_from_url(…)` or
+//! `make_polling_service(…)`; from a GitHub repository, with
+//! `make_github_service(…)`; from any `DataProvider`, with
+//! `make_service_from_provider(…)`; or, with the `sqlite` feature, from an
+//! SQLite database, with `make_sqlite_service(…)`. Services made with
+//! `make_service_from_path(…)` and `make_service_from_url(…)` read suggestions
+//! once, when they're set up; the others can refresh them while they run.
 //!
-//! When adding suggestions, add them at the end. This will ensure that existing
-//! links to existing items are not invalidated or changed - the `item`
-//! parameter to the `/` endpoint is a literal index into this list.
+//! Each suggestion's permalink is `/check/{slug}`, with a slug made from its
+//! text. The `item` parameter is an index into the suggestion's list, unless
+//! the suggestion has an `id`, and `/?item=` links redirect to the permalink of
+//! whichever suggestion is at that index. When adding suggestions, add them at
+//! the end, so that existing `item` links keep naming the same suggestions.
 
-use actix_web::{error, get, web, HttpRequest, Responder};
+use actix_web::http::header::{self, ContentType, EntityTag, IfNoneMatch, TryIntoHeaderValue};
+use actix_web::http::StatusCode;
+use actix_web::{error, get, rt, web, HttpMessage, HttpRequest, HttpResponse, Responder};
 use askama::Template;
-use pulldown_cmark::{html, Options, Parser};
-use rand::seq::SliceRandom;
-use rand::thread_rng;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use serde_urlencoded::ser;
+use sha2::{Digest, Sha256};
+use std::any::type_name;
+use std::fmt;
+use std::io;
 use std::iter;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use thiserror::Error;
+use url::Url;
+use utoipa::IntoParams;
+
+use crate::access_log;
+use crate::admin::{self, Errors};
+use crate::api;
+use crate::assets;
+use crate::counts::{self, Channel};
+use crate::crawlers::{self, Robots};
+use crate::discord;
+use crate::embed;
+use crate::feed;
+use crate::github::{self, Hook, Repository};
+#[cfg(feature = "graphql")]
+use crate::graphql;
+#[cfg(feature = "irc")]
+use crate::irc::{self, Irc};
+use crate::limit::{Limited, Limiter, RateLimit};
+use crate::locale::Locale;
+#[cfg(feature = "matrix")]
+use crate::matrix::{Bot, Matrix};
+use crate::mattermost;
+use crate::moderation::{self, Queue};
+use crate::notify;
+use crate::poster;
+use crate::provider::{self, DataProvider, Refresher};
+use crate::pwa;
+use crate::reload::Reloader;
+use crate::remote::Poller;
+use crate::request_id::{self, RequestId};
+use crate::rocketchat;
+use crate::schedule::{Schedule, Scheduler};
+use crate::seen::{self, Unseen};
+use crate::shuffle::{self, Shuffle};
+use crate::slack;
+use crate::socket;
+#[cfg(feature = "sqlite")]
+use crate::sqlite::Database;
+use crate::stream;
+use crate::teams;
+use crate::telegram;
+use crate::templates;
+use crate::theme::Theme;
+use crate::things::{
+    fetch_catalog, fetch_catalog_if_changed, load_catalog, read_catalog, Catalog, Position, Store,
+    Thing, Things, DEFAULT_LANG, MAX_LENGTH, THINGS,
+};
+use crate::webex;
+use crate::zulip;
 
 #[derive(Error, Debug)]
-enum UrlError {
+pub(crate) enum UrlError {
     #[error("Unable to generate URL: {0}")]
     UrlGenerationError(#[from] error::UrlGenerationError),
     #[error("Unable to generate URL: {0}")]
@@ -71,18 +278,54 @@ impl From<UrlError> for error::Error {
     }
 }
 
-trait Urls {
+pub(crate) trait Urls {
     fn index(&self, query: &ItemQuery) -> Result<url::Url, UrlError>;
 
-    // Askama always passes parameters from templates to functions as borrows,
-    // regardless of type; receiving a reference to a usize is silly, but as a
-    // result, necessary.
-    fn suggestion(&self, idx: &usize) -> Result<url::Url, UrlError> {
-        self.index(&ItemQuery::from(*idx))
+    fn search(&self) -> Result<url::Url, UrlError>;
+
+    fn all(&self, query: &ItemQuery) -> Result<url::Url, UrlError>;
+
+    fn categories(&self, query: &ItemQuery) -> Result<url::Url, UrlError>;
+
+    fn category(&self, query: &ItemQuery, name: &str) -> Result<url::Url, UrlError>;
+
+    fn suggestion(&self, query: &ItemQuery, thing: &Thing) -> Result<url::Url, UrlError> {
+        permalink(&self.index(&ItemQuery::default())?, query, thing)
     }
 
-    fn new_suggestion(&self) -> Result<url::Url, UrlError> {
-        self.index(&ItemQuery::default())
+    fn new_suggestion(&self, query: &ItemQuery) -> Result<url::Url, UrlError> {
+        self.index(&ItemQuery {
+            item: None,
+            ..query.clone()
+        })
+    }
+
+    fn oembed(&self, url: &Url) -> Result<url::Url, UrlError>;
+
+    fn og_image(&self, query: &ItemQuery, item: &str) -> Result<url::Url, UrlError>;
+
+    fn widget(&self, query: &ItemQuery) -> Result<url::Url, UrlError>;
+
+    fn manifest(&self) -> Result<url::Url, UrlError>;
+
+    /// The URL of the built-in asset `name`, such as `index.css`, which must
+    /// exist.
+    fn asset(&self, name: &str) -> Result<url::Url, UrlError>;
+
+    fn icon(&self, size: u32) -> Result<url::Url, UrlError>;
+
+    fn favicon(&self) -> Result<url::Url, UrlError>;
+
+    fn touch_icon(&self) -> Result<url::Url, UrlError>;
+
+    fn svg_icon(&self) -> Result<url::Url, UrlError>;
+
+    fn mask_icon(&self) -> Result<url::Url, UrlError>;
+
+    fn service_worker(&self) -> Result<url::Url, UrlError>;
+
+    fn suggestion_oembed(&self, query: &ItemQuery, thing: &Thing) -> Result<url::Url, UrlError> {
+        self.oembed(&self.suggestion(query, thing)?)
     }
 }
 
@@ -91,21 +334,686 @@ impl Urls for HttpRequest {
         let mut url = self.url_for("index", iter::empty::<&str>())?;
 
         let query = serde_urlencoded::to_string(query)?;
-        url.set_query(Some(&query));
+        url.set_query(Some(query.as_str()).filter(|query| !query.is_empty()));
+
+        Ok(url)
+    }
+
+    fn search(&self) -> Result<url::Url, UrlError> {
+        Ok(self.url_for("search", iter::empty::<&str>())?)
+    }
+
+    fn all(&self, query: &ItemQuery) -> Result<url::Url, UrlError> {
+        let mut url = self.url_for("all", iter::empty::<&str>())?;
+
+        let query = serde_urlencoded::to_string(ItemQuery {
+            item: None,
+            ..query.clone()
+        })?;
+        url.set_query(Some(query.as_str()).filter(|query| !query.is_empty()));
+
+        Ok(url)
+    }
+
+    fn categories(&self, query: &ItemQuery) -> Result<url::Url, UrlError> {
+        let mut url = self.url_for("categories", iter::empty::<&str>())?;
+
+        let query = serde_urlencoded::to_string(ItemQuery {
+            item: None,
+            tag: None,
+            ..query.clone()
+        })?;
+        url.set_query(Some(query.as_str()).filter(|query| !query.is_empty()));
+
+        Ok(url)
+    }
+
+    fn category(&self, query: &ItemQuery, name: &str) -> Result<url::Url, UrlError> {
+        let mut url = self.url_for("category", [name])?;
+
+        let query = serde_urlencoded::to_string(ItemQuery {
+            item: None,
+            tag: None,
+            ..query.clone()
+        })?;
+        url.set_query(Some(query.as_str()).filter(|query| !query.is_empty()));
+
+        Ok(url)
+    }
+
+    fn og_image(&self, query: &ItemQuery, item: &str) -> Result<url::Url, UrlError> {
+        let mut url = self.url_for("og_image", [item])?;
+
+        let query = serde_urlencoded::to_string(ItemQuery {
+            item: None,
+            ..query.clone()
+        })?;
+        url.set_query(Some(query.as_str()).filter(|query| !query.is_empty()));
 
         Ok(url)
     }
+
+    fn widget(&self, query: &ItemQuery) -> Result<url::Url, UrlError> {
+        let mut url = self.url_for("widget", iter::empty::<&str>())?;
+
+        let query = serde_urlencoded::to_string(ItemQuery {
+            item: None,
+            ..query.clone()
+        })?;
+        url.set_query(Some(query.as_str()).filter(|query| !query.is_empty()));
+
+        Ok(url)
+    }
+
+    fn manifest(&self) -> Result<url::Url, UrlError> {
+        Ok(self.url_for("manifest", iter::empty::<&str>())?)
+    }
+
+    fn asset(&self, name: &str) -> Result<url::Url, UrlError> {
+        let name = assets::fingerprinted(name).expect("pages link only built-in assets");
+        Ok(self.url_for("asset", [name])?)
+    }
+
+    fn icon(&self, size: u32) -> Result<url::Url, UrlError> {
+        Ok(self.url_for("icon", [size.to_string()])?)
+    }
+
+    fn favicon(&self) -> Result<url::Url, UrlError> {
+        Ok(self.url_for("favicon", iter::empty::<&str>())?)
+    }
+
+    fn touch_icon(&self) -> Result<url::Url, UrlError> {
+        Ok(self.url_for("touch_icon", iter::empty::<&str>())?)
+    }
+
+    fn svg_icon(&self) -> Result<url::Url, UrlError> {
+        Ok(self.url_for("svg_icon", iter::empty::<&str>())?)
+    }
+
+    fn mask_icon(&self) -> Result<url::Url, UrlError> {
+        Ok(self.url_for("mask_icon", iter::empty::<&str>())?)
+    }
+
+    fn service_worker(&self) -> Result<url::Url, UrlError> {
+        Ok(self.url_for("service_worker", iter::empty::<&str>())?)
+    }
+
+    fn oembed(&self, url: &Url) -> Result<url::Url, UrlError> {
+        let mut oembed = self.url_for("oembed", iter::empty::<&str>())?;
+        oembed
+            .query_pairs_mut()
+            .append_pair("url", url.as_str())
+            .append_pair("format", "json");
+
+        Ok(oembed)
+    }
+}
+
+/// The permalink to `thing` on the service whose index page is at
+/// `index_url`: `check/` and its slug, with the rest of `query`.
+pub(crate) fn permalink(
+    index_url: &Url,
+    query: &ItemQuery,
+    thing: &Thing,
+) -> Result<Url, UrlError> {
+    let mut url = index_url.clone();
+    // Every URL with a host has a path to add to.
+    if let Ok(mut path) = url.path_segments_mut() {
+        path.pop_if_empty().push("check").push(&thing.slug);
+    }
+
+    let query = serde_urlencoded::to_string(ItemQuery {
+        item: None,
+        ..query.clone()
+    })?;
+    url.set_query(Some(query.as_str()).filter(|query| !query.is_empty()));
+
+    Ok(url)
+}
+
+/// The thing `link` is a permalink to, on the service whose index page is at
+/// `index_url`, and the rest of its query. Both permalinks under `check/` and
+/// links naming an `item` on the index page are recognized; only their paths
+/// are compared.
+pub(crate) fn permalinked<'a>(
+    catalog: &'a Catalog,
+    index_url: &Url,
+    link: &Url,
+) -> Option<(ItemQuery, &'a (usize, Thing))> {
+    let query: ItemQuery = serde_urlencoded::from_str(link.query().unwrap_or_default()).ok()?;
+    let things = catalog.list(query.list.as_deref())?;
+
+    let entry = if link.path() == index_url.path() {
+        let item = query.item.as_deref()?;
+        things.aliased(item).or_else(|| things.find(item))?
+    } else {
+        let slug = link
+            .path()
+            .strip_prefix(index_url.path())?
+            .strip_prefix("check/")?;
+        things.slugged(slug)?
+    };
+
+    Some(entry)
+        .filter(|(_, thing)| thing.has_tag(query.tag.as_deref()))
+        .map(|entry| (query, entry))
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct ItemQuery {
+    pub(crate) item: Option<String>,
+    pub(crate) list: Option<String>,
+    pub(crate) tag: Option<String>,
+    pub(crate) lang: Option<String>,
+}
+
+/// Respond with `body`, which must be the same on every request for the same
+/// URL, tagged with a strong ETag. If the request's `If-None-Match` header
+/// already matches the tag, respond with `304 Not Modified` instead.
+pub(crate) fn fixed(
+    req: &HttpRequest,
+    content_type: impl TryIntoHeaderValue,
+    body: impl Into<web::Bytes>,
+) -> HttpResponse {
+    let body = body.into();
+    let etag = EntityTag::new_strong(hex::encode(Sha256::digest(&body)));
+    let cached = match req.get_header::<IfNoneMatch>() {
+        Some(IfNoneMatch::Any) => true,
+        Some(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&etag)),
+        None => false,
+    };
+
+    // The suggestions can change while the service runs, so caches must check
+    // back before reusing a response.
+    let mut response = if cached {
+        HttpResponse::NotModified()
+    } else {
+        HttpResponse::Ok()
+    };
+    response
+        .insert_header(header::ETag(etag))
+        .insert_header(("Cache-Control", "no-cache"));
+
+    if cached {
+        response.finish()
+    } else {
+        response.content_type(content_type).body(body)
+    }
+}
+
+#[derive(Template)]
+#[template(path = "error.html")]
+struct ErrorPage {
+    req: HttpRequest,
+    another: String,
+    request_id: Option<RequestId>,
+    theme: Theme,
+    locale: Locale,
+}
+
+/// A `404 Not Found` response to `req`, pointing the way to a random
+/// suggestion. Requests to the API, under `/api/`, get a JSON object with the
+/// `error`, the URL of `another` suggestion, and the `request_id`, if
+/// requests have IDs; others get a page in the site's style, from the
+/// `error.html` template.
+fn not_found_response(req: &HttpRequest) -> HttpResponse {
+    let another = req
+        .new_suggestion(&ItemQuery::default())
+        .map(String::from)
+        .unwrap_or_else(|_| "/".to_owned());
+    let request_id = request_id::of(req);
+    if req.path().starts_with("/api/") {
+        return HttpResponse::NotFound().json(json!({
+            "error": "Not found",
+            "another": another,
+            "request_id": request_id.as_ref().map(RequestId::as_str),
+        }));
+    }
+
+    let page = ErrorPage {
+        req: req.clone(),
+        another,
+        request_id,
+        theme: req
+            .app_data::<web::Data<Theme>>()
+            .map(|theme| theme.get_ref().clone())
+            .unwrap_or_default(),
+        locale: Locale::negotiate(req, None),
+    };
+    let body = templates::render(req, "error.html", &page, || {
+        Ok(json!({
+            "status": 404,
+            "request_id": page.request_id.as_ref().map(RequestId::as_str),
+            "locale": page.locale.lang(),
+            "theme": page.theme,
+            "urls": {
+                "another": page.another,
+                "stylesheet": page.req.asset("search.css")?.as_str(),
+            },
+        }))
+    });
+
+    match body {
+        Ok(body) => HttpResponse::NotFound()
+            .content_type(ContentType::html())
+            .insert_header((header::VARY, "Accept-Language"))
+            .body(body),
+        // The error page is no place to report another error.
+        Err(_) => HttpResponse::NotFound()
+            .content_type(ContentType::plaintext())
+            .body("Not found"),
+    }
+}
+
+/// The error for requests naming something that isn't there, such as an
+/// unknown list or item, responding as `not_found_response` describes.
+pub(crate) fn not_found(req: &HttpRequest) -> error::Error {
+    error::InternalError::from_response("Not found", not_found_response(req)).into()
+}
+
+/// Respond to requests for paths no endpoint serves, with the same `404 Not
+/// Found` page as for unknown lists and items. A `ServiceConfig` can't set the
+/// App's default service, so Apps wanting this page for every path should set
+/// it themselves:
+///
+/// ```
+/// # use things_to_check::view;
+/// # fn main() -> std::result::Result<(), view::Error> {
+/// use actix_web::{web, App};
+///
+/// let service = view::make_service()?;
+/// let app = App::new()
+///     .configure(|cfg| service(cfg))
+///     .default_service(web::to(view::missing));
+/// # Ok(())
+/// # }
+/// ```
+pub async fn missing(req: HttpRequest) -> HttpResponse {
+    not_found_response(&req)
+}
+
+/// The most results returned for one search.
+pub(crate) const MAX_RESULTS: usize = 20;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct SearchQuery {
+    #[serde(default)]
+    pub(crate) q: String,
+    pub(crate) list: Option<String>,
+    pub(crate) lang: Option<String>,
+}
+
+impl SearchQuery {
+    pub(crate) fn item_query(&self) -> ItemQuery {
+        ItemQuery {
+            item: None,
+            list: self.list.clone(),
+            tag: None,
+            lang: self.lang.clone(),
+        }
+    }
+}
+
+struct Found {
+    id: String,
+    url: String,
+    thing: Thing,
+    position: Position,
+}
+
+impl Found {
+    // The thing found, as the API returns it.
+    fn suggestion(&self, list: &str) -> api::Suggestion {
+        api::Suggestion::new(
+            list,
+            self.id.clone(),
+            self.url.clone(),
+            self.thing.clone(),
+            self.position,
+        )
+    }
+}
+
+#[derive(Template)]
+#[template(path = "search.html")]
+struct SearchResults {
+    q: String,
+    lang: String,
+    query: ItemQuery,
+    req: HttpRequest,
+    results: Vec<Found>,
+    theme: web::Data<Theme>,
+    locale: Locale,
 }
 
-#[derive(Serialize, Deserialize, Default)]
-struct ItemQuery {
-    item: Option<usize>,
+#[utoipa::path(
+    get,
+    path = "/search",
+    tag = "html",
+    params(SearchQuery),
+    responses(
+        (status = 200, description = "The suggestions matching the search", content_type = "text/html"),
+        (status = 404, description = "No such list"),
+    ),
+)]
+#[get("/search", name = "search")]
+pub(crate) async fn search(
+    _: Limited,
+    req: HttpRequest,
+    data: web::Data<Store>,
+    theme: web::Data<Theme>,
+    query: web::Query<SearchQuery>,
+) -> error::Result<impl Responder> {
+    let catalog = data.current();
+    let (list, things) = match catalog.named_list(query.list.as_deref()) {
+        Some(found) => found,
+        None => return Err(not_found(&req)),
+    };
+
+    let item_query = query.item_query();
+    let results = things
+        .search(&query.q)
+        .into_iter()
+        .take(MAX_RESULTS)
+        .map(|(number, thing)| {
+            let id = thing.item(*number);
+            let url = req.suggestion(&item_query, thing)?;
+            Ok(Found {
+                id,
+                url: url.into(),
+                thing: thing.translated(query.lang.as_deref()),
+                position: things.position(*number),
+            })
+        })
+        .collect::<error::Result<_>>()?;
+
+    let query = query.into_inner();
+    let locale = Locale::negotiate(&req, query.lang.as_deref());
+    let page = SearchResults {
+        lang: query
+            .lang
+            .clone()
+            .unwrap_or_else(|| DEFAULT_LANG.to_owned()),
+        query: query.item_query(),
+        q: query.q,
+        req,
+        results,
+        theme,
+        locale,
+    };
+    let body = templates::render(&page.req, "search.html", &page, || {
+        let results: Vec<_> = page
+            .results
+            .iter()
+            .map(|found| found.suggestion(list))
+            .collect();
+        Ok(json!({
+            "q": page.q,
+            "lang": page.lang,
+            "query": page.query,
+            "results": results,
+            "locale": page.locale.lang(),
+            "theme": page.theme.get_ref(),
+            "urls": {
+                "another": page.req.new_suggestion(&page.query)?.as_str(),
+                "search": page.req.search()?.as_str(),
+                "all": page.req.all(&page.query)?.as_str(),
+                "stylesheet": page.req.asset("search.css")?.as_str(),
+            },
+        }))
+    })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .insert_header((header::VARY, "Accept-Language"))
+        .body(body))
+}
+
+#[derive(Template)]
+#[template(path = "all.html")]
+struct AllSuggestions {
+    lang: String,
+    query: ItemQuery,
+    category: Option<String>,
+    req: HttpRequest,
+    suggestions: Vec<Found>,
+    theme: web::Data<Theme>,
+    locale: Locale,
+}
+
+#[utoipa::path(
+    get,
+    path = "/all",
+    tag = "html",
+    params(ItemQuery),
+    responses(
+        (status = 200, description = "Every suggestion in the list", content_type = "text/html"),
+        (status = 304, description = "The page matches the If-None-Match header"),
+        (status = 404, description = "No such list, or no tagged suggestions"),
+    ),
+)]
+#[get("/all", name = "all")]
+pub(crate) async fn all(
+    _: Limited,
+    req: HttpRequest,
+    data: web::Data<Store>,
+    theme: web::Data<Theme>,
+    query: web::Query<ItemQuery>,
+) -> error::Result<HttpResponse> {
+    let query = ItemQuery {
+        item: None,
+        ..query.into_inner()
+    };
+
+    listing(&req, &data, theme, query, None)
 }
 
-impl From<usize> for ItemQuery {
-    fn from(idx: usize) -> Self {
-        ItemQuery { item: Some(idx) }
+#[utoipa::path(
+    get,
+    path = "/category/{name}",
+    tag = "html",
+    params(
+        ("name" = String, Path, description = "The category's tag"),
+        ItemQuery,
+    ),
+    responses(
+        (status = 200, description = "Every suggestion in the category", content_type = "text/html"),
+        (status = 304, description = "The page matches the If-None-Match header"),
+        (status = 404, description = "No such list or category"),
+    ),
+)]
+#[get("/category/{name}", name = "category")]
+pub(crate) async fn category_page(
+    _: Limited,
+    req: HttpRequest,
+    data: web::Data<Store>,
+    theme: web::Data<Theme>,
+    name: web::Path<String>,
+    query: web::Query<ItemQuery>,
+) -> error::Result<HttpResponse> {
+    let name = name.into_inner();
+    let query = ItemQuery {
+        item: None,
+        tag: Some(name.clone()),
+        ..query.into_inner()
+    };
+
+    listing(&req, &data, theme, query, Some(name))
+}
+
+// The page listing every suggestion matching `query`, which must not name an
+// `item`, titled for its `category` if it has one.
+fn listing(
+    req: &HttpRequest,
+    data: &Store,
+    theme: web::Data<Theme>,
+    query: ItemQuery,
+    category: Option<String>,
+) -> error::Result<HttpResponse> {
+    let catalog = data.current();
+    let (list, things) = catalog
+        .named_list(query.list.as_deref())
+        .ok_or_else(|| not_found(req))?;
+
+    let suggestions: Vec<_> = things
+        .0
+        .iter()
+        .filter(|(_, thing)| thing.has_tag(query.tag.as_deref()))
+        .map(|(number, thing)| {
+            let id = thing.item(*number);
+            let url = req.suggestion(&query, thing)?;
+            Ok(Found {
+                id,
+                url: url.into(),
+                thing: thing.translated(query.lang.as_deref()),
+                position: things.position(*number),
+            })
+        })
+        .collect::<error::Result<_>>()?;
+    if suggestions.is_empty() && query.tag.is_some() {
+        return Err(not_found(req));
     }
+
+    let locale = Locale::negotiate(req, query.lang.as_deref());
+    let page = AllSuggestions {
+        lang: query
+            .lang
+            .clone()
+            .unwrap_or_else(|| DEFAULT_LANG.to_owned()),
+        query,
+        category,
+        req: req.clone(),
+        suggestions,
+        theme,
+        locale,
+    };
+    let body = templates::render(req, "all.html", &page, || {
+        let suggestions: Vec<_> = page
+            .suggestions
+            .iter()
+            .map(|found| found.suggestion(list))
+            .collect();
+        Ok(json!({
+            "lang": page.lang,
+            "query": page.query,
+            "category": page.category,
+            "suggestions": suggestions,
+            "locale": page.locale.lang(),
+            "theme": page.theme.get_ref(),
+            "urls": {
+                "another": req.new_suggestion(&page.query)?.as_str(),
+                "search": req.search()?.as_str(),
+                "categories": req.categories(&page.query)?.as_str(),
+                "stylesheet": req.asset("search.css")?.as_str(),
+                "script": req.asset("all.js")?.as_str(),
+            },
+        }))
+    })?;
+
+    // The page's text follows Accept-Language, so caches must too.
+    let mut page = fixed(req, ContentType::html(), body);
+    page.headers_mut().insert(
+        header::VARY,
+        header::HeaderValue::from_static("Accept-Language"),
+    );
+    Ok(page)
+}
+
+/// A tag, as a category of suggestions to browse.
+#[derive(Serialize)]
+struct Category {
+    name: String,
+    count: usize,
+    url: String,
+}
+
+#[derive(Template)]
+#[template(path = "categories.html")]
+struct Categories {
+    lang: String,
+    query: ItemQuery,
+    req: HttpRequest,
+    categories: Vec<Category>,
+    theme: web::Data<Theme>,
+    locale: Locale,
+}
+
+#[utoipa::path(
+    get,
+    path = "/categories",
+    tag = "html",
+    params(ItemQuery),
+    responses(
+        (status = 200, description = "The list's categories", content_type = "text/html"),
+        (status = 304, description = "The page matches the If-None-Match header"),
+        (status = 404, description = "No such list"),
+    ),
+)]
+#[get("/categories", name = "categories")]
+pub(crate) async fn category_index(
+    _: Limited,
+    req: HttpRequest,
+    data: web::Data<Store>,
+    theme: web::Data<Theme>,
+    query: web::Query<ItemQuery>,
+) -> error::Result<HttpResponse> {
+    let catalog = data.current();
+    let things = catalog
+        .list(query.list.as_deref())
+        .ok_or_else(|| not_found(&req))?;
+
+    let query = ItemQuery {
+        item: None,
+        tag: None,
+        ..query.into_inner()
+    };
+    let categories = things
+        .tags()
+        .into_iter()
+        .map(|(name, count)| {
+            Ok(Category {
+                name: name.to_owned(),
+                count,
+                url: req.category(&query, name)?.into(),
+            })
+        })
+        .collect::<error::Result<_>>()?;
+
+    let locale = Locale::negotiate(&req, query.lang.as_deref());
+    let page = Categories {
+        lang: query
+            .lang
+            .clone()
+            .unwrap_or_else(|| DEFAULT_LANG.to_owned()),
+        query,
+        req: req.clone(),
+        categories,
+        theme,
+        locale,
+    };
+    let body = templates::render(&req, "categories.html", &page, || {
+        Ok(json!({
+            "lang": page.lang,
+            "query": page.query,
+            "categories": page.categories,
+            "locale": page.locale.lang(),
+            "theme": page.theme.get_ref(),
+            "urls": {
+                "another": req.new_suggestion(&page.query)?.as_str(),
+                "all": req.all(&page.query)?.as_str(),
+                "stylesheet": req.asset("search.css")?.as_str(),
+            },
+        }))
+    })?;
+
+    let mut page = fixed(&req, ContentType::html(), body);
+    page.headers_mut().insert(
+        header::VARY,
+        header::HeaderValue::from_static("Accept-Language"),
+    );
+    Ok(page)
 }
 
 #[derive(Template)]
@@ -113,66 +1021,401 @@ impl From<usize> for ItemQuery {
 struct Suggestion {
     thing: Thing,
     req: HttpRequest,
-    index: usize,
+    query: ItemQuery,
+    item: String,
+    position: Position,
+    theme: web::Data<Theme>,
+    locale: Locale,
+    shares: Vec<Share>,
+    related: Vec<Found>,
+    refresh: Option<Refresh>,
+}
+
+impl Suggestion {
+    // The suggestion's place in its list, as in "#42 of 217".
+    fn numbered(&self) -> String {
+        let number = self.position.number.to_string();
+        let total = self.position.total.to_string();
+        self.locale
+            .text_with_all("position", &[("number", &number), ("total", &total)])
+    }
 }
 
+/// Where to share suggestions on Mastodon, which asks readers for their own
+/// server.
+const MASTODON_SHARE: &str = "https://s2f.kytta.dev/";
+
+/// Where to share suggestions on Twitter.
+const TWITTER_SHARE: &str = "https://twitter.com/intent/tweet";
+
+/// The shortest interval, in seconds, a page may be asked to refresh at, so
+/// that a mistyped `refresh` can't hammer the service.
+const MIN_REFRESH: u64 = 5;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct RefreshQuery {
+    refresh: Option<u64>,
+}
+
+/// Where and when a page in kiosk mode moves on to another suggestion.
+#[derive(Serialize)]
+struct Refresh {
+    seconds: u64,
+    url: String,
+}
+
+/// A link sharing a suggestion somewhere.
+#[derive(Serialize)]
+struct Share {
+    name: String,
+    url: String,
+}
+
+// Links sharing the suggestion `text`, at `permalink`, on social sites and by
+// email.
+fn shares(locale: &Locale, text: &str, permalink: &Url) -> Vec<Share> {
+    let post = format!("{} {}", text, permalink);
+    let mastodon = Url::parse_with_params(MASTODON_SHARE, [("text", &post)])
+        .expect("Mastodon share URL is valid");
+    let twitter =
+        Url::parse_with_params(TWITTER_SHARE, [("text", text), ("url", permalink.as_str())])
+            .expect("Twitter share URL is valid");
+    // Mail clients read `+` literally, so spaces must be percent-encoded;
+    // form encoding has already encoded any literal `+`.
+    let email =
+        serde_urlencoded::to_string([("subject", locale.text("suggestion-title")), ("body", post)])
+            .expect("strings are URL-encodable")
+            .replace('+', "%20");
+
+    vec![
+        Share {
+            name: "Mastodon".to_owned(),
+            url: mastodon.into(),
+        },
+        Share {
+            name: "Twitter".to_owned(),
+            url: twitter.into(),
+        },
+        Share {
+            name: locale.text("email"),
+            url: format!("mailto:?{}", email),
+        },
+    ]
+}
+
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "html",
+    params(ItemQuery, RefreshQuery),
+    responses(
+        (status = 200, description = "A random suggestion", content_type = "text/html"),
+        (status = 302, description = "Redirects an item, or an alias, to the suggestion's permalink"),
+        (status = 404, description = "No such list, item, or tagged suggestion"),
+    ),
+)]
 #[get("/")]
-async fn index(
+pub(crate) async fn index(
+    _: Limited,
     req: HttpRequest,
-    data: web::Data<Things>,
+    data: web::Data<Store>,
+    theme: web::Data<Theme>,
     query: web::Query<ItemQuery>,
+    refresh: web::Query<RefreshQuery>,
 ) -> error::Result<impl Responder> {
-    let thing = match query.item {
-        Some(index) => data.0.get(index),
-        None => data.0.choose(&mut thread_rng()),
+    let catalog = data.current();
+    let (list, data) = match catalog.named_list(query.list.as_deref()) {
+        Some(found) => found,
+        None => return Err(not_found(&req)),
     };
 
-    let (index, thing) = match thing {
-        Some(thing) => thing.to_owned(),
-        None => return Err(error::ErrorNotFound("Not found")),
+    // Fixed items are served from their permalinks, so that each suggestion
+    // has one URL. Aliases take priority over indices, as an aliased index
+    // may have been reused by a different thing since.
+    if let Some(item) = query.item.as_deref() {
+        let (_, thing) = data
+            .aliased(item)
+            .or_else(|| data.find(item))
+            .ok_or_else(|| not_found(&req))?;
+        let mut location = req.suggestion(&query, thing)?;
+        if let Some(seconds) = refresh.refresh {
+            location
+                .query_pairs_mut()
+                .append_pair("refresh", &seconds.to_string());
+        }
+        return Ok(HttpResponse::Found()
+            .insert_header((header::LOCATION, location.as_str()))
+            .finish());
+    }
+
+    let shuffle = req.app_data::<web::Data<Shuffle>>();
+    let unseen = req.app_data::<web::Data<Unseen>>();
+    let tag = query.tag.as_deref();
+    let (thing, cookie) = match (shuffle, unseen) {
+        (Some(shuffle), _) => {
+            let mut deck = shuffle.read(&req, list, tag);
+            let thing = shuffle::deal(data, tag, &mut deck);
+            (thing, Some(shuffle.cookie(list, tag, &deck)))
+        }
+        (None, Some(unseen)) => {
+            let mut seen = unseen.read(&req, list);
+            let thing = seen::choose(data, tag, &mut seen);
+            (thing, Some(unseen.cookie(list, &seen)))
+        }
+        (None, None) => (data.choose(None, tag), None),
     };
+    let entry = thing.ok_or_else(|| not_found(&req))?;
+
+    let body = page(
+        &req,
+        list,
+        data,
+        theme,
+        query.into_inner(),
+        refresh.refresh,
+        entry,
+    )?;
+    served(&req, list, entry);
 
-    let response = Suggestion { thing, req, index };
-    let response = response
-        .customize()
+    let mut page = HttpResponse::Ok();
+    page.content_type(ContentType::html())
         .insert_header(("Cache-Control", "no-store"));
+    if let Some(cookie) = cookie {
+        page.insert_header((header::SET_COOKIE, cookie.to_string()));
+    }
 
-    Ok(response)
+    Ok(page.body(body))
 }
 
-const THINGS: &str = include_str!("things-to-check.yml");
+#[utoipa::path(
+    get,
+    path = "/check/{slug}",
+    tag = "html",
+    params(
+        ("slug" = String, Path, description = "The suggestion's slug"),
+        ItemQuery,
+        RefreshQuery,
+    ),
+    responses(
+        (status = 200, description = "The suggestion", content_type = "text/html"),
+        (status = 304, description = "The page matches the If-None-Match header"),
+        (status = 404, description = "No such list, slug, or tagged suggestion"),
+    ),
+)]
+#[get("/check/{slug}")]
+pub(crate) async fn check(
+    _: Limited,
+    req: HttpRequest,
+    data: web::Data<Store>,
+    theme: web::Data<Theme>,
+    slug: web::Path<String>,
+    query: web::Query<ItemQuery>,
+    refresh: web::Query<RefreshQuery>,
+) -> error::Result<HttpResponse> {
+    let catalog = data.current();
+    let (list, data) = catalog
+        .named_list(query.list.as_deref())
+        .ok_or_else(|| not_found(&req))?;
+    let entry = data
+        .slugged(&slug)
+        .filter(|(_, thing)| thing.has_tag(query.tag.as_deref()))
+        .ok_or_else(|| not_found(&req))?;
+
+    let body = page(
+        &req,
+        list,
+        data,
+        theme,
+        query.into_inner(),
+        refresh.refresh,
+        entry,
+    )?;
 
-#[derive(Clone)]
-struct Thing {
-    markdown: String,
-    html: String,
+    // The page's text follows Accept-Language, so caches must too.
+    let mut page = fixed(&req, ContentType::html(), body);
+    page.headers_mut().insert(
+        header::VARY,
+        header::HeaderValue::from_static("Accept-Language"),
+    );
+    // Revalidating a cached page isn't reading it again.
+    if page.status() == StatusCode::OK {
+        served(&req, list, entry);
+    }
+    Ok(page)
 }
 
-impl From<String> for Thing {
-    fn from(markdown: String) -> Self {
-        let options = Options::empty();
-        let parser = Parser::new_ext(&markdown, options);
+// Notes that the thing at `entry` in `list` was shown to a reader.
+fn served(req: &HttpRequest, list: &str, (number, thing): &(usize, Thing)) {
+    let item = thing.item(*number);
+    counts::served(req, list, &item, Channel::Web);
+    access_log::chose(req, list, &item);
+}
 
-        let mut html = String::new();
-        html::push_html(&mut html, parser);
+// The page suggesting the thing at `entry` in `list`, whose things are
+// `things`, refreshing every `refresh` seconds if asked to.
+fn page(
+    req: &HttpRequest,
+    list: &str,
+    things: &Things,
+    theme: web::Data<Theme>,
+    query: ItemQuery,
+    refresh: Option<u64>,
+    entry: &(usize, Thing),
+) -> error::Result<String> {
+    let (number, thing) = entry;
+    let thing = thing.translated(query.lang.as_deref());
+    let item = thing.item(*number);
+    let locale = Locale::negotiate(req, query.lang.as_deref());
+    let shares = shares(&locale, &thing.text(), &req.suggestion(&query, &thing)?);
+    let related = things
+        .related(&thing)
+        .into_iter()
+        .map(|(number, related)| {
+            let id = related.item(*number);
+            let url = req.suggestion(&query, related)?;
+            Ok(Found {
+                id,
+                url: url.into(),
+                thing: related.translated(query.lang.as_deref()),
+                position: things.position(*number),
+            })
+        })
+        .collect::<error::Result<_>>()?;
+    let refresh = match refresh {
+        Some(seconds) => {
+            let seconds = seconds.max(MIN_REFRESH);
+            let mut url = req.new_suggestion(&query)?;
+            url.query_pairs_mut()
+                .append_pair("refresh", &seconds.to_string());
+            Some(Refresh {
+                seconds,
+                url: url.into(),
+            })
+        }
+        None => None,
+    };
+    let response = Suggestion {
+        thing,
+        req: req.clone(),
+        query,
+        item,
+        position: things.position(*number),
+        theme,
+        locale,
+        shares,
+        related,
+        refresh,
+    };
 
-        Thing { markdown, html }
-    }
+    templates::render(req, "index.html", &response, || {
+        let Suggestion {
+            thing,
+            req,
+            query,
+            item,
+            position,
+            theme,
+            locale,
+            shares,
+            related,
+            refresh,
+        } = &response;
+        let url = req.suggestion(query, thing)?;
+        let suggestion =
+            api::Suggestion::new(list, item.clone(), url.into(), thing.clone(), *position);
+        let related: Vec<_> = related.iter().map(|found| found.suggestion(list)).collect();
+        Ok(json!({
+            "suggestion": suggestion,
+            "related": related,
+            "query": query,
+            "locale": locale.lang(),
+            "theme": theme.get_ref(),
+            "urls": {
+                "another": req.new_suggestion(query)?.as_str(),
+                "search": req.search()?.as_str(),
+                "all": req.all(query)?.as_str(),
+                "og_image": req.og_image(query, item)?.as_str(),
+                "oembed": req.suggestion_oembed(query, thing)?.as_str(),
+                "shares": shares,
+                "refresh": refresh,
+                "stylesheet": req.asset("index.css")?.as_str(),
+                "script": req.asset("index.js")?.as_str(),
+            },
+        }))
+    })
 }
 
-#[derive(Clone)]
-struct Things(Vec<(usize, Thing)>);
+#[derive(Serialize)]
+struct Health {
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<&'static str>,
+}
 
-fn load_things(src: &str) -> serde_yaml::Result<Things> {
-    let raw_things: Vec<String> = serde_yaml::from_str(src)?;
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "operations",
+    responses(
+        (status = 200, description = "There are suggestions to serve", content_type = "application/json"),
+        (status = 503, description = "There are no suggestions to serve", content_type = "application/json"),
+    ),
+)]
+#[get("/healthz")]
+pub(crate) async fn healthz(data: web::Data<Store>) -> HttpResponse {
+    let catalog = data.current();
+    let ready = catalog.0.values().any(|Things(things)| !things.is_empty());
 
-    Ok(Things(
-        raw_things
-            .into_iter()
-            .map(Thing::from)
-            .enumerate()
-            .collect(),
-    ))
+    let (mut response, status) = if ready {
+        (HttpResponse::Ok(), "ok")
+    } else {
+        (HttpResponse::ServiceUnavailable(), "unavailable")
+    };
+    response
+        .insert_header(("Cache-Control", "no-store"))
+        .json(Health {
+            status,
+            reason: None,
+        })
+}
+
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "operations",
+    responses(
+        (status = 200, description = "The suggestions are loaded, and their source is reachable", content_type = "application/json"),
+        (status = 503, description = "There are no suggestions to serve, or their source could not be refreshed", content_type = "application/json"),
+    ),
+)]
+#[get("/readyz")]
+pub(crate) async fn readyz(data: web::Data<Store>) -> HttpResponse {
+    let catalog = data.current();
+    let reason = if catalog.0.values().all(|Things(things)| things.is_empty()) {
+        Some("no-suggestions")
+    } else if data.failure().is_some() {
+        Some("source-failed")
+    } else {
+        None
+    };
+
+    let (mut response, status) = match reason {
+        None => (HttpResponse::Ok(), "ready"),
+        Some(_) => (HttpResponse::ServiceUnavailable(), "unavailable"),
+    };
+    response
+        .insert_header(("Cache-Control", "no-store"))
+        .json(Health { status, reason })
+}
+
+/// A description of where a service's suggestions come from, for operators.
+pub(crate) struct Source(pub(crate) String);
+
+impl Source {
+    fn new(description: impl fmt::Display) -> Self {
+        Source(description.to_string())
+    }
 }
 
 /// Errors that can arise initializing the service.
@@ -182,6 +1425,187 @@ pub enum Error {
     /// fixable by recompiling the program with correct YAML.
     #[error("Unable to load Things To Check YAML: {0}")]
     DeserializeError(#[from] serde_yaml::Error),
+    /// Indicates that the suggestions file could not be read from disk.
+    #[error("Unable to read Things To Check file: {0}")]
+    ReadError(#[from] io::Error),
+    /// Indicates that a suggestions file on disk was invalid in some way.
+    #[error("Unable to load Things To Check file {}: {source}", path.display())]
+    FileError { path: PathBuf, source: ParseError },
+    /// Indicates that suggestions could not be fetched from a URL.
+    #[error("Unable to fetch Things To Check: {0}")]
+    FetchError(#[from] Box<ureq::Error>),
+    /// Indicates that suggestions fetched from a URL were invalid in some way.
+    #[error("Unable to load Things To Check from {url}: {source}")]
+    UrlError { url: Url, source: ParseError },
+    /// Indicates that a `DataProvider` could not provide suggestions.
+    #[error("Unable to load Things To Check: {0}")]
+    ProviderError(#[source] Box<dyn std::error::Error + Send + Sync>),
+    /// Indicates that the suggestions provided by a `DataProvider` were
+    /// invalid in some way.
+    #[error("Unable to load Things To Check: {0}")]
+    DocumentError(#[from] ParseError),
+    /// Indicates that the suggestion IDs in a list were ambiguous.
+    #[error("Invalid suggestion IDs in list {list:?}: {reason}")]
+    IdError { list: String, reason: String },
+    /// Indicates that some suggestions had problems, and that problems were
+    /// not allowed. See `Strictness`.
+    #[error("Invalid suggestions: {}", display_all(.0))]
+    ValidationError(Vec<Problem>),
+    /// Indicates that the suggestions database could not be read or written.
+    #[cfg(feature = "sqlite")]
+    #[error("Unable to access Things To Check database: {0}")]
+    DatabaseError(#[from] rusqlite::Error),
+}
+
+/// Errors that can arise parsing a suggestions file, in any of the supported
+/// formats.
+#[derive(Error, Debug)]
+pub enum ParseError {
+    /// The file was read as YAML, and was invalid.
+    #[error("invalid YAML: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+    /// The file was read as TOML, and was invalid.
+    #[error("invalid TOML: {0}")]
+    Toml(#[from] Box<toml::de::Error>),
+    /// The file was read as JSON, and was invalid.
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// A problem with one of the suggestions being loaded.
+#[derive(Debug)]
+pub struct Problem {
+    /// The name of the list the suggestion is in.
+    pub list: String,
+    /// The index of the suggestion in its list.
+    pub item: usize,
+    /// What's wrong with it.
+    pub kind: ProblemKind,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "item {} in list {:?} {}",
+            self.item, self.list, self.kind
+        )
+    }
+}
+
+/// The problems a suggestion can have.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ProblemKind {
+    /// The suggestion repeats the suggestion at index `original` in the same
+    /// list, either exactly, or differing only by case or whitespace.
+    Duplicate { original: usize, exact: bool },
+    /// The suggestion's Markdown renders to no HTML at all.
+    Empty,
+    /// The suggestion's Markdown includes raw HTML, which would be passed
+    /// through to the page unescaped.
+    RawHtml,
+    /// The suggestion's Markdown is `length` characters long, which is longer
+    /// than suggestions are allowed to be.
+    TooLong { length: usize },
+}
+
+impl fmt::Display for ProblemKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProblemKind::Duplicate {
+                original,
+                exact: true,
+            } => write!(f, "duplicates item {}", original),
+            ProblemKind::Duplicate {
+                original,
+                exact: false,
+            } => write!(f, "nearly duplicates item {}", original),
+            ProblemKind::Empty => write!(f, "is empty"),
+            ProblemKind::RawHtml => write!(f, "contains raw HTML"),
+            ProblemKind::TooLong { length } => write!(
+                f,
+                "is {} characters long (the limit is {})",
+                length, MAX_LENGTH
+            ),
+        }
+    }
+}
+
+fn display_all(items: &[impl fmt::Display]) -> String {
+    items
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// How to treat problems found in suggestions loaded from a file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strictness {
+    /// Report problems on stderr, and serve the suggestions anyway.
+    #[default]
+    Warn,
+    /// Refuse to serve suggestions with problems.
+    Fail,
+}
+
+/// Which extensions to CommonMark suggestions may use. All of them are on by
+/// default.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Markdown {
+    /// `~~Strikethrough~~`.
+    pub strikethrough: bool,
+    /// Tables, as on GitHub.
+    pub tables: bool,
+    /// Footnotes, referred to as `[^1]` and written as `[^1]: …`.
+    pub footnotes: bool,
+    /// Curly quotes, dashes, and ellipses in place of their plain forms, such
+    /// as `"`, `--`, and `...`.
+    pub smart_punctuation: bool,
+}
+
+impl Default for Markdown {
+    fn default() -> Self {
+        Markdown {
+            strikethrough: true,
+            tables: true,
+            footnotes: true,
+            smart_punctuation: true,
+        }
+    }
+}
+
+/// Options controlling how suggestions are loaded and served.
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    /// How to treat suggestions with problems. See `ProblemKind` for the
+    /// problems that are checked for.
+    pub strictness: Strictness,
+    /// Suggestions to hide. Each entry is an ID or index, as accepted by the
+    /// `item` parameter, and hides matching suggestions in every list; an
+    /// entry of the form `list:item` hides a suggestion in that list only.
+    /// Hidden suggestions are never served, but the remaining suggestions
+    /// keep their indices.
+    pub exclude: Vec<String>,
+    /// How often each client may ask for suggestions, or `None` for no limit.
+    /// See the `limit` module.
+    pub rate_limit: Option<RateLimit>,
+    /// What `/robots.txt` asks of crawlers. See the `crawlers` module.
+    pub robots: Robots,
+    /// When and where to post suggestions to chat, or `None` not to. See the
+    /// `schedule` module.
+    pub schedule: Option<Schedule>,
+    /// A Matrix bot to answer commands with, or `None` not to. See the
+    /// `matrix` module.
+    #[cfg(feature = "matrix")]
+    pub matrix: Option<Matrix>,
+    /// IRC servers to answer commands on. See the `irc` module.
+    #[cfg(feature = "irc")]
+    pub irc: Vec<Irc>,
+    /// How the HTML pages are branded. See the `theme` module.
+    pub theme: Theme,
+    /// Which extensions to Markdown suggestions may use.
+    pub markdown: Markdown,
 }
 
 /// Set up an instance of this service.
@@ -189,9 +1613,318 @@ pub enum Error {
 /// The returned function will configure any actix-web App with the necessary
 /// state to tell people how to troubleshoot problems.
 pub fn make_service() -> Result<impl Fn(&mut web::ServiceConfig) + Clone, Error> {
-    let things = load_things(THINGS)?;
+    make_service_with(&Options::default())
+}
+
+/// Set up an instance of this service, serving the built-in suggestions as
+/// adjusted by `options`.
+///
+/// # Examples
+///
+/// ```
+/// # use things_to_check::view;
+/// # fn main() -> std::result::Result<(), things_to_check::view::Error> {
+/// let options = view::Options {
+///     exclude: vec!["0".to_owned()],
+///     ..Default::default()
+/// };
+/// let service = view::make_service_with(&options)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn make_service_with(
+    options: &Options,
+) -> Result<impl Fn(&mut web::ServiceConfig) + Clone, Error> {
+    let mut catalog = load_catalog(THINGS)?;
+    catalog.check(options.strictness)?;
+    catalog.exclude(&options.exclude);
+    catalog.render(options.markdown);
+
+    Ok(service(catalog, Source::new("built-in"), options))
+}
+
+/// Set up an instance of this service, reading suggestions from a file.
+///
+/// The file at `path` must contain a YAML list of Markdown strings, in the
+/// same format as the built-in suggestions, or a mapping of list names to such
+/// lists. Files ending in `.toml` or `.json` are read as TOML or JSON instead.
+/// If `path` is a directory, every such file in it is read, in order by name,
+/// and the lists they contain are merged together. Suggestions are read once,
+/// when this function is called; missing or invalid files will cause this to
+/// fail, as will suggestions with problems if `options` calls for it.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use things_to_check::view;
+/// # fn main() -> std::result::Result<(), things_to_check::view::Error> {
+/// let options = view::Options::default();
+/// let service = view::make_service_from_path("/etc/things-to-check.yml", &options)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn make_service_from_path(
+    path: impl AsRef<Path>,
+    options: &Options,
+) -> Result<impl Fn(&mut web::ServiceConfig) + Clone, Error> {
+    let path = path.as_ref();
+    let mut catalog = read_catalog(path)?;
+    catalog.check(options.strictness)?;
+    catalog.exclude(&options.exclude);
+    catalog.render(options.markdown);
+
+    Ok(service(catalog, Source::new(path.display()), options))
+}
+
+/// Set up an instance of this service, fetching suggestions from a URL.
+///
+/// The document at `url` must be in one of the formats accepted by
+/// `make_service_from_path(…)`, and is recognized by the extension of the
+/// URL's path in the same way. It's fetched once, when this function is
+/// called; unreachable or invalid documents will cause this to fail, as will
+/// suggestions with problems if `options` calls for it.
+pub fn make_service_from_url(
+    url: &Url,
+    options: &Options,
+) -> Result<impl Fn(&mut web::ServiceConfig) + Clone, Error> {
+    let mut catalog = fetch_catalog(url)?;
+    catalog.check(options.strictness)?;
+    catalog.exclude(&options.exclude);
+    catalog.render(options.markdown);
+
+    Ok(service(catalog, Source::new(url), options))
+}
+
+/// Set up an instance of this service, fetching suggestions from a URL that
+/// can be polled for changes later.
+///
+/// This behaves like `make_service_from_url(…)`, but also returns a `Poller`
+/// which can fetch the suggestions again, if they've changed, while the
+/// service is running. See the `remote` module for details.
+pub fn make_polling_service(
+    url: &Url,
+    options: &Options,
+) -> Result<(impl Fn(&mut web::ServiceConfig) + Clone, Poller), Error> {
+    let (mut catalog, validators) = fetch_catalog_if_changed(url, &Default::default())?
+        .expect("fetches without validators are unconditional");
+    catalog.check(options.strictness)?;
+    catalog.exclude(&options.exclude);
+    catalog.render(options.markdown);
+
+    let store = web::Data::new(Store::new(catalog));
+    let poller = Poller::new(url.clone(), options.clone(), store.clone(), validators);
+
+    Ok((shared_service(store, Source::new(url), options), poller))
+}
+
+/// Set up an instance of this service, reading suggestions from a file that
+/// can be reloaded later.
+///
+/// This behaves like `make_service_from_path(…)`, but also returns a
+/// `Reloader` which can swap in the file's current contents while the service
+/// is running. See the `reload` module for details.
+pub fn make_reloadable_service(
+    path: impl AsRef<Path>,
+    options: &Options,
+) -> Result<(impl Fn(&mut web::ServiceConfig) + Clone, Reloader), Error> {
+    let path = path.as_ref().to_owned();
+    let mut catalog = read_catalog(&path)?;
+    catalog.check(options.strictness)?;
+    catalog.exclude(&options.exclude);
+    catalog.render(options.markdown);
+
+    let store = web::Data::new(Store::new(catalog));
+    let source = Source::new(path.display());
+    let reloader = Reloader::new(path, options.clone(), store.clone());
+
+    Ok((shared_service(store, source, options), reloader))
+}
+
+/// Set up an instance of this service, serving suggestions from a
+/// `DataProvider`.
+///
+/// The provider is asked for suggestions when this function is called;
+/// failures will cause this to fail, as will suggestions with problems if
+/// `options` calls for it. The returned `Refresher` asks the provider again,
+/// and serves what it provides. See the `provider` module for details.
+pub async fn make_service_from_provider<P: DataProvider>(
+    provider: P,
+    options: &Options,
+) -> Result<(impl Fn(&mut web::ServiceConfig) + Clone, Refresher<P>), Error> {
+    let catalog = provider::load(&provider, options).await?;
+
+    let store = web::Data::new(Store::new(catalog));
+    let refresher = Refresher::new(provider, options.clone(), store.clone());
+
+    let source = Source::new(format!("provider:{}", type_name::<P>()));
+
+    Ok((shared_service(store, source, options), refresher))
+}
+
+/// Set up an instance of this service, fetching suggestions from a GitHub
+/// repository, and refreshing them when GitHub reports a push.
+///
+/// The file in `repository` must be in one of the formats accepted by
+/// `make_service_from_path(…)`. It's fetched when this function is called,
+/// and again whenever a push to its branch is delivered to the
+/// `/hooks/github` endpoint, which checks deliveries' signatures against
+/// `secret` if one is given. Without a secret, deliveries aren't trusted to
+/// name the commit to fetch. See the `github` module for details.
+pub fn make_github_service(
+    repository: &Repository,
+    secret: Option<&str>,
+    options: &Options,
+) -> Result<impl Fn(&mut web::ServiceConfig) + Clone, Error> {
+    let mut catalog = repository.fetch(repository.branch())?;
+    catalog.check(options.strictness)?;
+    catalog.exclude(&options.exclude);
+    catalog.render(options.markdown);
+
+    let store = web::Data::new(Store::new(catalog));
+    let hook = web::Data::new(Hook::new(
+        repository.clone(),
+        secret.map(str::to_owned),
+        options.clone(),
+        store.clone(),
+    ));
+    let source = Source::new(format!("github:{}", repository));
+    let service = shared_service(store, source, options);
 
     Ok(move |cfg: &mut web::ServiceConfig| {
-        cfg.app_data(web::Data::new(things.clone())).service(index);
+        service(cfg);
+        cfg.app_data(hook.clone()).service(github::push);
     })
 }
+
+/// Set up an instance of this service, serving suggestions from an SQLite
+/// database.
+///
+/// The database at `path` is created if it doesn't exist, and seeded with the
+/// built-in suggestions if it's empty. The returned `Database` can be used to
+/// change the suggestions while the service is running. See the `sqlite`
+/// module for details.
+///
+/// Suggestions with problems cause this to fail if `options` calls for it.
+///
+/// Only available with the `sqlite` feature.
+#[cfg(feature = "sqlite")]
+pub fn make_sqlite_service(
+    path: impl AsRef<Path>,
+    options: &Options,
+) -> Result<(impl Fn(&mut web::ServiceConfig) + Clone, Database), Error> {
+    let path = path.as_ref();
+    let database = Database::open(path, options)?;
+    let source = Source::new(format!("sqlite:{}", path.display()));
+
+    Ok((shared_service(database.store(), source, options), database))
+}
+
+fn service(
+    catalog: Catalog,
+    source: Source,
+    options: &Options,
+) -> impl Fn(&mut web::ServiceConfig) + Clone {
+    shared_service(web::Data::new(Store::new(catalog)), source, options)
+}
+
+fn shared_service(
+    store: web::Data<Store>,
+    source: Source,
+    options: &Options,
+) -> impl Fn(&mut web::ServiceConfig) + Clone {
+    let queue = web::Data::new(Queue::default());
+    let errors = web::Data::new(Errors::default());
+    let source = web::Data::new(source);
+    let robots = web::Data::new(options.robots.clone());
+    let theme = web::Data::new(options.theme.clone());
+    let markdown = web::Data::new(options.markdown);
+    #[cfg(feature = "graphql")]
+    let schema = web::Data::new(graphql::schema(store.clone()));
+    let limiter = options
+        .rate_limit
+        .clone()
+        .map(|limit| web::Data::new(Limiter::new(limit)));
+    let scheduler = options
+        .schedule
+        .clone()
+        .map(|schedule| Scheduler::new(schedule, store.clone()));
+    let scheduler = Arc::new(Mutex::new(scheduler));
+    #[cfg(feature = "matrix")]
+    let bot = options
+        .matrix
+        .clone()
+        .map(|matrix| Bot::new(matrix, store.clone()));
+    #[cfg(feature = "matrix")]
+    let bot = Arc::new(Mutex::new(bot));
+    #[cfg(feature = "irc")]
+    let irc_bots: Vec<_> = options
+        .irc
+        .iter()
+        .map(|irc| irc::Bot::new(irc.clone(), store.clone()))
+        .collect();
+    #[cfg(feature = "irc")]
+    let irc_bots = Arc::new(Mutex::new(irc_bots));
+
+    move |cfg: &mut web::ServiceConfig| {
+        // The service is configured once for each worker; only the first
+        // runs the schedule, so that each post is made once.
+        let scheduler = scheduler
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .take();
+        if let Some(scheduler) = scheduler {
+            rt::spawn(scheduler.run());
+        }
+        // Likewise, only the first logs in to Matrix.
+        #[cfg(feature = "matrix")]
+        if let Some(bot) = bot.lock().unwrap_or_else(|err| err.into_inner()).take() {
+            rt::spawn(bot.run());
+        }
+        // And to each IRC server.
+        #[cfg(feature = "irc")]
+        for bot in std::mem::take(&mut *irc_bots.lock().unwrap_or_else(|err| err.into_inner())) {
+            rt::spawn(bot.run());
+        }
+        if let Some(limiter) = &limiter {
+            cfg.app_data(limiter.clone());
+        }
+        cfg.app_data(store.clone())
+            .app_data(queue.clone())
+            .app_data(errors.clone())
+            .app_data(source.clone())
+            .app_data(robots.clone())
+            .app_data(theme.clone())
+            .app_data(markdown.clone())
+            .service(index)
+            .service(check)
+            .service(search)
+            .service(all)
+            .service(category_index)
+            .service(category_page)
+            .service(healthz)
+            .service(readyz)
+            .configure(api::configure)
+            .configure(feed::configure)
+            .configure(poster::configure)
+            .configure(crawlers::configure)
+            .configure(embed::configure)
+            .configure(pwa::configure)
+            .configure(assets::configure)
+            .configure(stream::configure)
+            .configure(socket::configure)
+            .configure(moderation::configure)
+            .configure(admin::configure)
+            .configure(notify::configure)
+            .configure(slack::configure)
+            .configure(discord::configure)
+            .configure(teams::configure)
+            .configure(mattermost::configure)
+            .configure(telegram::configure)
+            .configure(zulip::configure)
+            .configure(rocketchat::configure)
+            .configure(webex::configure);
+
+        #[cfg(feature = "graphql")]
+        cfg.app_data(schema.clone()).configure(graphql::configure);
+    }
+}
@@ -0,0 +1,285 @@
+//! A Webex bot, for asking for suggestions from Webex.
+//!
+//! The bot is off unless the App is given a `Webex` value as app data, which
+//! holds the bot's access token and the secret its webhook is signed with:
+//!
+//! ```
+//! # use things_to_check::{webex, view};
+//! # #[actix_web::main]
+//! # async fn main() -> std::result::Result<(), things_to_check::view::Error> {
+//! use actix_web::{web, App, HttpServer};
+//!
+//! let service = view::make_service()?;
+//! let webex = web::Data::new(webex::Webex::new("bot-token", "s3kr1t"));
+//! let app_factory = move ||
+//!     App::new()
+//!         .app_data(webex.clone())
+//!         .configure(|cfg| service(cfg));
+//!
+//! HttpServer::new(app_factory);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Endpoints
+//!
+//! * `/webex/webhook` (`POST`): a bot's webhook.
+//!
+//!   Create a webhook for the bot's `messages` resource and `created` event,
+//!   with this URL as its target and a secret. Webex only tells the webhook a
+//!   message was sent, so the bot fetches each message sent to it, and posts
+//!   a random suggestion in reply, with a link to its permalink. The message's
+//!   text is read as for the Slack app's command: a single word naming a
+//!   suggestion, as in `@troubleshoot 42`, replies with that suggestion, and
+//!   other words reply with a random suggestion matching them. Messages from
+//!   bots, including this one, and other events are ignored.
+//!
+//! Requests must carry an `X-Spark-Signature` header holding the signature
+//! Webex makes of the body with the webhook's secret, or are rejected with
+//! `401 Unauthorized`. The endpoint returns `404 Not Found` if the bot is
+//! off. Every request comes from Webex, on behalf of everyone talking to the
+//! bot, so it isn't rate limited.
+
+use actix_web::{error, post, rt, web, HttpRequest, HttpResponse};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use serde_json::json;
+use sha1::Sha1;
+use url::Url;
+
+use crate::schedule::permalink;
+use crate::slack::Ask;
+use crate::things::Store;
+use crate::view::{self, ItemQuery, Urls};
+
+/// Where the Webex API lives.
+const API: &str = "https://webexapis.com/v1";
+
+/// Enables the Webex bot, and holds the bot's token and webhook secret.
+pub struct Webex {
+    token: String,
+    secret: String,
+}
+
+impl Webex {
+    /// Enable the Webex bot, posting as the bot with access token `token`, for
+    /// webhooks signed with `secret`.
+    pub fn new(token: impl Into<String>, secret: impl Into<String>) -> Self {
+        Webex {
+            token: token.into(),
+            secret: secret.into(),
+        }
+    }
+
+    // Webex signs the body with an HMAC keyed with the webhook's secret, in
+    // hex, in the X-Spark-Signature header.
+    fn verify(&self, req: &HttpRequest, body: &[u8]) -> error::Result<()> {
+        let signature = req
+            .headers()
+            .get("X-Spark-Signature")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| hex::decode(value).ok());
+
+        match signature {
+            Some(signature) => {
+                let mut mac = Hmac::<Sha1>::new_from_slice(self.secret.as_bytes())
+                    .expect("HMAC accepts keys of any size");
+                mac.update(body);
+                mac.verify_slice(&signature)
+                    .map_err(|_| error::ErrorUnauthorized("Unauthorized"))
+            }
+            None => Err(error::ErrorUnauthorized("Unauthorized")),
+        }
+    }
+}
+
+fn webex(req: &HttpRequest) -> error::Result<&Webex> {
+    req.app_data::<web::Data<Webex>>()
+        .map(|webex| webex.get_ref())
+        .ok_or_else(|| view::not_found(req))
+}
+
+#[derive(Deserialize)]
+struct Notification {
+    resource: String,
+    event: String,
+    data: Created,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Created {
+    id: String,
+    room_id: String,
+    #[serde(default)]
+    person_email: String,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    #[serde(default)]
+    text: String,
+    html: Option<String>,
+}
+
+impl Message {
+    // What the message asks of the bot. Mentions of the bot in group rooms are
+    // `<spark-mention>` elements in the message's HTML; whatever else it says
+    // is the question.
+    fn asked(&self) -> String {
+        let html = match &self.html {
+            Some(html) => html,
+            None => return self.text.trim().to_owned(),
+        };
+
+        let mut rest = html.as_str();
+        let mut kept = String::new();
+        while let Some((before, after)) = rest.split_once("<spark-mention") {
+            kept.push_str(before);
+            rest = after
+                .split_once("</spark-mention>")
+                .map_or("", |(_, after)| after);
+        }
+        kept.push_str(rest);
+
+        // Drop whatever other markup is left, keeping the text between tags.
+        let mut text = String::new();
+        let mut in_tag = false;
+        for c in kept.chars() {
+            match c {
+                '<' => in_tag = true,
+                '>' => {
+                    in_tag = false;
+                    text.push(' ');
+                }
+                _ if !in_tag => text.push(c),
+                _ => {}
+            }
+        }
+
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+// The Markdown of a reply showing a suggestion chosen by `text`, linking to
+// its permalink on the index page at `index`.
+fn reply(store: &Store, index: &Url, text: &str) -> String {
+    let catalog = store.current();
+    let things = catalog.list(None);
+    let ask = Ask::from_text(things, text);
+    let (_, thing) = match things.and_then(|things| ask.choose(things)) {
+        Some(chosen) => chosen,
+        None => return "Sorry, there are no suggestions to show.".to_owned(),
+    };
+
+    match permalink(index, thing) {
+        Some(permalink) => format!("{}\n\n[Permalink]({})", thing.markdown, permalink),
+        None => thing.markdown.clone(),
+    }
+}
+
+// Fetch the message `created` announces, and reply to it in its room, as the
+// bot with `token`.
+async fn answer(store: web::Data<Store>, index: Url, token: String, created: Created) {
+    // Calling Webex blocks, so keep it off the thread serving requests.
+    let answered = web::block(move || {
+        let auth = format!("Bearer {}", token);
+        let message = ureq::get(&format!("{}/messages/{}", API, created.id))
+            .set("Authorization", &auth)
+            .call()
+            .map_err(|err| err.to_string())?
+            .into_string()
+            .map_err(|err| err.to_string())?;
+        let message: Message = serde_json::from_str(&message).map_err(|err| err.to_string())?;
+
+        let body = json!({
+            "roomId": created.room_id,
+            "markdown": reply(&store, &index, &message.asked()),
+        });
+        ureq::post(&format!("{}/messages", API))
+            .set("Authorization", &auth)
+            .set("Content-Type", "application/json; charset=utf-8")
+            .send_string(&body.to_string())
+            .map_err(|err| err.to_string())?;
+
+        Ok::<_, String>(())
+    })
+    .await;
+
+    match answered {
+        Ok(Ok(())) => {}
+        Ok(Err(err)) => eprintln!("Unable to answer Webex message: {}", err),
+        Err(err) => eprintln!("Unable to answer Webex message: {}", err),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/webex/webhook",
+    tag = "webex",
+    request_body(content = String, description = "A Webex webhook notification", content_type = "application/json"),
+    responses(
+        (status = 200, description = "The notification was accepted"),
+        (status = 400, description = "The notification was malformed"),
+        (status = 401, description = "The request's signature was missing or incorrect"),
+        (status = 404, description = "The Webex bot is off"),
+    ),
+)]
+#[post("/webex/webhook")]
+pub(crate) async fn webhook(
+    req: HttpRequest,
+    store: web::Data<Store>,
+    body: web::Bytes,
+) -> error::Result<HttpResponse> {
+    let webex = webex(&req)?;
+    webex.verify(&req, &body)?;
+
+    let notification: Notification =
+        serde_json::from_slice(&body).map_err(error::ErrorBadRequest)?;
+    // Bots' addresses are all on webex.bot, so this also skips the bot's own
+    // replies.
+    if notification.resource != "messages"
+        || notification.event != "created"
+        || notification.data.person_email.ends_with("@webex.bot")
+    {
+        return Ok(HttpResponse::Ok().finish());
+    }
+
+    let index = req.index(&ItemQuery::default())?;
+    // Answer Webex first, and fetch the message and reply afterwards.
+    rt::spawn(answer(store, index, webex.token.clone(), notification.data));
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub(crate) fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(webhook);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(text: &str, html: Option<&str>) -> Message {
+        Message {
+            text: text.to_owned(),
+            html: html.map(str::to_owned),
+        }
+    }
+
+    #[test]
+    fn mentions_are_not_asked_for() {
+        let mentioned = message(
+            "Troubleshoot 42",
+            Some(
+                r#"<p><spark-mention data-object-type="person" data-object-id="Y2lz">Troubleshoot</spark-mention> 42</p>"#,
+            ),
+        );
+        assert_eq!(mentioned.asked(), "42");
+        assert_eq!(message(" is it dns\n", None).asked(), "is it dns");
+        assert_eq!(
+            message("", Some("<p>is it <b>dns</b></p>")).asked(),
+            "is it dns"
+        );
+    }
+}
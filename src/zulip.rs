@@ -0,0 +1,164 @@
+//! A Zulip outgoing webhook, for asking for suggestions from Zulip.
+//!
+//! The webhook is off unless the App is given a `Zulip` value as app data,
+//! which holds the token Zulip sends with each request:
+//!
+//! ```
+//! # use things_to_check::{zulip, view};
+//! # #[actix_web::main]
+//! # async fn main() -> std::result::Result<(), things_to_check::view::Error> {
+//! use actix_web::{web, App, HttpServer};
+//!
+//! let service = view::make_service()?;
+//! let zulip = web::Data::new(zulip::Zulip::new("s3kr1t"));
+//! let app_factory = move ||
+//!     App::new()
+//!         .app_data(zulip.clone())
+//!         .configure(|cfg| service(cfg));
+//!
+//! HttpServer::new(app_factory);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Endpoints
+//!
+//! * `/zulip/troubleshoot` (`POST`): an outgoing webhook.
+//!
+//!   Create an outgoing webhook bot with this endpoint URL, in Zulip's own
+//!   format. Mentioning the bot, or messaging it directly, replies with a
+//!   random suggestion, with a link to its permalink. The rest of the message
+//!   is read as for the Slack app's command: a single word naming a
+//!   suggestion, as in `@**troubleshoot** 42`, replies with that suggestion,
+//!   and other words reply with a random suggestion matching them.
+//!
+//! Requests must carry the bot's token in their `token` field, or are
+//! rejected with `401 Unauthorized`. The endpoint returns `404 Not Found` if
+//! the webhook is off. Every request comes from Zulip, on behalf of everyone
+//! in the organization, so it isn't rate limited.
+
+use actix_web::{error, post, web, HttpRequest, HttpResponse};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::moderation::constant_time_eq;
+use crate::slack::Ask;
+use crate::things::Store;
+use crate::view::{self, ItemQuery, Urls};
+
+/// Enables the Zulip webhook, and holds the token Zulip sends.
+pub struct Zulip {
+    token: String,
+}
+
+impl Zulip {
+    /// Enable the Zulip webhook, for requests carrying `token`, shown in the
+    /// bot's `zuliprc` when it is created.
+    pub fn new(token: impl Into<String>) -> Self {
+        Zulip {
+            token: token.into(),
+        }
+    }
+}
+
+fn zulip(req: &HttpRequest) -> error::Result<&Zulip> {
+    req.app_data::<web::Data<Zulip>>()
+        .map(|zulip| zulip.get_ref())
+        .ok_or_else(|| view::not_found(req))
+}
+
+#[derive(Deserialize)]
+struct Outgoing {
+    #[serde(default)]
+    token: String,
+    #[serde(default)]
+    data: String,
+}
+
+// Mentions arrive as `@**Name**`, or `@_**Name**` for silent mentions, in a
+// message's text; whatever else it says is the question.
+fn without_mentions(text: &str) -> String {
+    let mut rest = text;
+    let mut kept = String::new();
+    while let Some(start) = [rest.find("@**"), rest.find("@_**")]
+        .into_iter()
+        .flatten()
+        .min()
+    {
+        kept.push_str(&rest[..start]);
+        let name = rest[start + 1..]
+            .trim_start_matches('_')
+            .trim_start_matches("**");
+        rest = name.split_once("**").map_or("", |(_, after)| after);
+    }
+    kept.push_str(rest);
+
+    kept.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// A reply showing a suggestion chosen by `text`. Zulip renders Markdown, so
+// suggestions keep their formatting.
+fn reply(req: &HttpRequest, store: &Store, text: &str) -> error::Result<Value> {
+    let catalog = store.current();
+    let things = catalog.list(None);
+    let ask = Ask::from_text(things, text);
+    let (_, thing) = match things.and_then(|things| ask.choose(things)) {
+        Some(chosen) => chosen,
+        None => return Ok(json!({ "content": "Sorry, there are no suggestions to show." })),
+    };
+
+    let url = req.suggestion(&ItemQuery::default(), thing)?;
+
+    Ok(json!({
+        "content": format!("{}\n\n[Permalink]({})", thing.markdown, url),
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/zulip/troubleshoot",
+    tag = "zulip",
+    request_body(content = String, description = "A Zulip outgoing webhook message", content_type = "application/json"),
+    responses(
+        (status = 200, description = "A reply, as JSON"),
+        (status = 400, description = "The message was malformed"),
+        (status = 401, description = "The request's token was missing or incorrect"),
+        (status = 404, description = "The Zulip webhook is off"),
+    ),
+)]
+#[post("/zulip/troubleshoot")]
+pub(crate) async fn troubleshoot(
+    req: HttpRequest,
+    store: web::Data<Store>,
+    outgoing: web::Json<Outgoing>,
+) -> error::Result<HttpResponse> {
+    let zulip = zulip(&req)?;
+    if !constant_time_eq(outgoing.token.as_bytes(), zulip.token.as_bytes()) {
+        return Err(error::ErrorUnauthorized("Unauthorized"));
+    }
+
+    let reply = reply(&req, &store, &without_mentions(&outgoing.data))?;
+
+    Ok(HttpResponse::Ok().json(reply))
+}
+
+pub(crate) fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(troubleshoot);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mentions_are_not_asked_for() {
+        assert_eq!(without_mentions("@**troubleshoot** 42\n"), "42");
+        assert_eq!(without_mentions("is it @_**Bot Name**  dns"), "is it dns");
+        assert_eq!(without_mentions("@**troubleshoot**"), "");
+        assert_eq!(without_mentions("@**unclosed"), "");
+        assert_eq!(
+            without_mentions("mail me@example.com"),
+            "mail me@example.com"
+        );
+    }
+}
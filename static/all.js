@@ -0,0 +1,27 @@
+// Hide the suggestions that don't contain every word typed into the filter
+// box. Without scripts, the box stays hidden and every suggestion is shown.
+(function () {
+    var filter = document.querySelector("input.filter");
+    var items = Array.prototype.slice.call(document.querySelectorAll("ol.all > li"));
+    var none = document.querySelector("p.none");
+
+    var apply = function () {
+        var words = filter.value.toLowerCase().split(/\s+/).filter(Boolean);
+        var shown = 0;
+        items.forEach(function (item) {
+            var text = item.textContent.toLowerCase();
+            item.hidden = !words.every(function (word) {
+                return text.indexOf(word) !== -1;
+            });
+            if (!item.hidden) {
+                shown += 1;
+            }
+        });
+        none.hidden = shown > 0;
+    };
+
+    filter.addEventListener("input", apply);
+    filter.hidden = false;
+    // Browsers may restore the box's text when going back to the page.
+    apply();
+})();
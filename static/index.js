@@ -0,0 +1,122 @@
+// Installed copies of the page show the last suggestion when offline. The
+// page gives the service worker's address, which depends on where the
+// service is mounted.
+(function () {
+    var script = document.currentScript;
+    if ("serviceWorker" in navigator && script && script.dataset.serviceWorker) {
+        navigator.serviceWorker.register(script.dataset.serviceWorker);
+    }
+})();
+
+// Without scripts, the permalink is shown for copying by hand; with
+// them, the button copies it.
+(function () {
+    var copy = document.querySelector("button.copy");
+    var permalink = document.querySelector("p.permalink input");
+    var label = copy.textContent;
+    var copied = function () {
+        copy.textContent = copy.dataset.copied;
+        setTimeout(function () {
+            copy.textContent = label;
+        }, 2000);
+    };
+
+    copy.addEventListener("click", function () {
+        if (navigator.clipboard) {
+            navigator.clipboard.writeText(permalink.value).then(copied);
+        } else {
+            permalink.select();
+            if (document.execCommand("copy")) {
+                copied();
+            }
+        }
+    });
+    permalink.addEventListener("focus", function () {
+        permalink.select();
+    });
+    copy.hidden = false;
+})();
+
+// Remember the suggestions this browser has been shown, newest first,
+// and list the others so the reader can go back to one they skipped.
+(function () {
+    var recent = document.querySelector("details.recent");
+    var permalink = document.querySelector("p.permalink input").value;
+    var seen = [];
+    try {
+        seen = JSON.parse(localStorage.getItem("recent")) || [];
+    } catch (e) {}
+    if (!Array.isArray(seen)) {
+        seen = [];
+    }
+
+    var others = seen.filter(function (entry) {
+        return entry && entry.url !== permalink;
+    });
+    var list = recent.querySelector("ol");
+    others.forEach(function (entry) {
+        var link = document.createElement("a");
+        link.href = entry.url;
+        link.textContent = entry.text;
+        var item = document.createElement("li");
+        item.appendChild(link);
+        list.appendChild(item);
+    });
+    recent.hidden = others.length === 0;
+
+    seen = [{ url: permalink, text: recent.dataset.text }].concat(others).slice(0, 10);
+    try {
+        localStorage.setItem("recent", JSON.stringify(seen));
+    } catch (e) {}
+})();
+
+// Space or n asks for another suggestion, and c copies the permalink,
+// unless the reader is typing or using a control.
+(function () {
+    var another = document.querySelector("a.another");
+    var copy = document.querySelector("button.copy");
+
+    document.addEventListener("keydown", function (event) {
+        var target = event.target;
+        var busy = event.defaultPrevented || event.altKey || event.ctrlKey || event.metaKey
+            || target.isContentEditable
+            || /^(INPUT|TEXTAREA|SELECT|BUTTON|A|SUMMARY)$/.test(target.tagName);
+        if (busy) {
+            return;
+        }
+
+        if (event.key === " " || event.key === "n") {
+            event.preventDefault();
+            window.location.href = another.href;
+        } else if (event.key === "c") {
+            event.preventDefault();
+            copy.click();
+        }
+    });
+    document.querySelector("p.shortcuts").hidden = false;
+})();
+
+// The toggle flips between light and dark, starting from whichever
+// is showing, and remembers the choice for later visits.
+(function () {
+    var toggle = document.querySelector("button.theme");
+    var root = document.documentElement;
+    var prefersDark = window.matchMedia("(prefers-color-scheme: dark)");
+    var dark = function () {
+        return root.dataset.theme ? root.dataset.theme === "dark" : prefersDark.matches;
+    };
+    var label = function () {
+        toggle.textContent = dark() ? toggle.dataset.light : toggle.dataset.dark;
+    };
+
+    toggle.addEventListener("click", function () {
+        root.dataset.theme = dark() ? "light" : "dark";
+        try {
+            localStorage.setItem("theme", root.dataset.theme);
+        } catch (e) {}
+        label();
+    });
+    prefersDark.addEventListener("change", label);
+    label();
+    toggle.hidden = false;
+})();
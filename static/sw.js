@@ -0,0 +1,55 @@
+// Keeps the last suggestion seen, and the styles and scripts it uses, so that
+// the installed app has something to show when offline. Everything else goes
+// straight to the network.
+const CACHE = "things-to-check";
+const INDEX = new URL("./", self.location).pathname;
+const ASSETS = INDEX + "static/";
+
+self.addEventListener("install", () => self.skipWaiting());
+
+self.addEventListener("activate", (event) => {
+    event.waitUntil(self.clients.claim());
+});
+
+self.addEventListener("fetch", (event) => {
+    const request = event.request;
+    const url = new URL(request.url);
+    if (request.method !== "GET") {
+        return;
+    }
+
+    // Assets' names change with their contents, so a cached copy is never
+    // stale.
+    if (url.pathname.startsWith(ASSETS)) {
+        event.respondWith(
+            caches.match(request).then((cached) =>
+                cached || fetch(request).then((response) => {
+                    if (response.ok) {
+                        const copy = response.clone();
+                        event.waitUntil(caches.open(CACHE).then((cache) => cache.put(request, copy)));
+                    }
+                    return response;
+                })
+            )
+        );
+        return;
+    }
+
+    if (request.mode !== "navigate" || url.pathname !== INDEX) {
+        return;
+    }
+
+    event.respondWith(
+        fetch(request)
+            .then((response) => {
+                if (response.ok) {
+                    const copy = response.clone();
+                    event.waitUntil(caches.open(CACHE).then((cache) => cache.put(INDEX, copy)));
+                }
+                return response;
+            })
+            .catch(() =>
+                caches.match(INDEX).then((cached) => cached || Response.error())
+            )
+    );
+});